@@ -0,0 +1,344 @@
+//! Tic-Tac-Toe rules of timestampvm, shared by the VM and its frontends.
+//!
+//! The board is packed into a `u32`, two bits per cell, where cell `i`
+//! occupies bits `2i` and `2i + 1`. A cell holds 0 when empty, otherwise
+//! the player (1 or 2) that marked it.
+//!
+//! The crate only depends on `serde`, so it also builds for `wasm32`, where
+//! [`wasm`] exports board decoding, legal moves and win detection to
+//! JavaScript through `wasm-bindgen`:
+//!
+//! ```text
+//! cargo rustc -p tictactoe-game --release --target wasm32-unknown-unknown --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/tictactoe_game.wasm
+//! ```
+//!
+//! # Features
+//!
+//! * `std` (default): implements `std::error::Error` for [`MoveError`].
+//!   Without it the crate is `#![no_std]`, and only needs `alloc`.
+//! * `serde` (default): derives `Serialize` and `Deserialize` for
+//!   [`GameResult`] and [`GameStatus`].
+//! * `schemars`: derives `JsonSchema` for the same types, needs `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(clippy::pedantic)]
+
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of cells on the board.
+pub const CELLS: u8 = 9;
+
+/// Number of cells on each side of the board.
+pub const SIDE: u8 = 3;
+
+/// Returns the mark held by the given cell (0 if empty).
+#[must_use]
+#[allow(clippy::cast_possible_truncation)] // masked to two bits
+pub fn cell(board: u32, index: u8) -> u8 {
+    ((board >> (2 * u32::from(index))) & 0b11) as u8
+}
+
+/// Lines of three cells, any of which held by a single player wins the game.
+pub const LINES: [[u8; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [6, 4, 2],
+];
+
+/// Bits of the cells making up each of the [`LINES`].
+const LINE_MASKS: [u32; 8] = line_masks(0b11);
+
+/// Each of the [`LINES`] held entirely by player 1, then by player 2.
+const PLAYER_LINES: [[u32; 8]; 2] = [line_masks(1), line_masks(2)];
+
+/// Low bit of every cell, all of which are set on a full board once each
+/// cell's high bit is folded into it.
+const CELL_LOW_BITS: u32 = 0x1_5555;
+
+/// Returns the board of each of the [`LINES`] with its cells holding `mark`.
+#[allow(clippy::cast_lossless)] // `u32::from` isn't const
+const fn line_masks(mark: u32) -> [u32; 8] {
+    let mut masks = [0; 8];
+    let mut i = 0;
+    while i < LINES.len() {
+        let mut j = 0;
+        while j < LINES[i].len() {
+            masks[i] |= mark << (2 * LINES[i][j] as u32);
+            j += 1;
+        }
+        i += 1;
+    }
+    masks
+}
+
+/// Returns the board with the given cell marked by `player`.
+#[must_use]
+pub fn place(board: u32, player: u8, index: u8) -> u32 {
+    let shift = 2 * u32::from(index);
+    (board & !(0b11 << shift)) | (u32::from(player) << shift)
+}
+
+/// Returns the player holding a complete line, if any.
+#[must_use]
+pub fn winner(board: u32) -> Option<u8> {
+    (1..=2).find(|player| {
+        PLAYER_LINES[usize::from(player - 1)]
+            .iter()
+            .zip(LINE_MASKS)
+            .any(|(line, mask)| board & mask == *line)
+    })
+}
+
+/// Returns "true" if every cell is marked.
+#[must_use]
+pub fn is_full(board: u32) -> bool {
+    ((board | (board >> 1)) & CELL_LOW_BITS) == CELL_LOW_BITS
+}
+
+/// Applies an action and returns the board play continues from, which is
+/// empty if the move won or drew the game.
+#[must_use]
+pub fn advance(board: u32, action: u8) -> u32 {
+    let (player, index) = decode_action(action);
+    let board = place(board, player, index);
+    if winner(board).is_some() || is_full(board) {
+        return 0;
+    }
+    board
+}
+
+/// Checks that `action` can be played on `board` when `next_player` is due to move.
+/// # Errors
+/// Fails if the cell is off the board or taken, or the player moved out of turn.
+pub fn check_move(board: u32, next_player: u8, action: u8) -> Result<(), MoveError> {
+    let (player, index) = decode_action(action);
+
+    if index >= CELLS {
+        return Err(MoveError::OffBoard(index));
+    }
+    if cell(board, index) != 0 {
+        return Err(MoveError::CellOccupied(index));
+    }
+
+    // Players must alternate within a game
+    if player != next_player {
+        return Err(MoveError::WrongTurn {
+            player,
+            expected: next_player,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the number of moves played on the board, which is also the turn
+/// (counting from 0) of the next move.
+#[must_use]
+pub fn turn(board: u32) -> u8 {
+    (0..CELLS).filter(|i| cell(board, *i) != 0).fold(0, |turn, _| turn + 1)
+}
+
+/// Returns the player due to move on a board of the game in progress: player 1
+/// opens every game, after which players alternate.
+#[must_use]
+pub fn to_move(board: u32) -> u8 {
+    if turn(board) % 2 == 0 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Returns the indices of all empty cells, in ascending order.
+#[must_use]
+pub fn empty_cells(board: u32) -> Vec<u8> {
+    (0..CELLS).filter(|i| cell(board, *i) == 0).collect()
+}
+
+/// Returns the actions the player due to move may play on the board, in
+/// ascending cell order, or none once the board is won or full.
+#[must_use]
+pub fn legal_moves(board: u32) -> Vec<u8> {
+    if winner(board).is_some() {
+        return Vec::new();
+    }
+    let player = to_move(board);
+    empty_cells(board)
+        .into_iter()
+        .map(|index| encode_action(player, index))
+        .collect()
+}
+
+/// Packs a player (1 or 2) and cell index into the action byte carried by blocks.
+#[must_use]
+pub fn encode_action(player: u8, index: u8) -> u8 {
+    ((player.saturating_sub(1) & 0b1) << 4) | (index & 0b1111)
+}
+
+/// Returns the symbol rendered for a mark: "X" for player 1, "O" for player 2
+/// and an empty string for an empty cell.
+#[must_use]
+pub fn symbol(mark: u8) -> &'static str {
+    match mark {
+        1 => "X",
+        2 => "O",
+        _ => "",
+    }
+}
+
+/// Decodes the board into rows of cell symbols (see [`symbol`]).
+#[must_use]
+pub fn grid(board: u32) -> [[&'static str; SIDE as usize]; SIDE as usize] {
+    let mut rows = [[""; SIDE as usize]; SIDE as usize];
+    for index in 0..CELLS {
+        let (row, col) = position(index);
+        rows[usize::from(row)][usize::from(col)] = symbol(cell(board, index));
+    }
+    rows
+}
+
+/// Unpacks an action byte into the player (1 or 2) and cell index it carries.
+/// Bit 4 selects the player and the low 4 bits the cell.
+#[must_use]
+pub fn decode_action(action: u8) -> (u8, u8) {
+    (((action & 0b0001_0000) >> 4) + 1, action & 0b1111)
+}
+
+/// Returns the player expected to move after `last_player`.
+/// Player 1 always opens a game, after which players alternate.
+#[must_use]
+pub fn next_player(last_player: Option<u8>) -> u8 {
+    match last_player {
+        Some(1) => 2,
+        _ => 1,
+    }
+}
+
+/// Returns the (row, column) position of a cell index.
+#[must_use]
+pub fn position(index: u8) -> (u8, u8) {
+    (index / SIDE, index % SIDE)
+}
+
+/// Outcome of a finished game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct GameResult {
+    /// Winning player (1 or 2), or `None` if the game was drawn
+    pub winner: Option<u8>,
+    /// Height of the block whose move ended the game, or after which the seat to
+    /// move forfeited it
+    pub height: u64,
+}
+
+/// Status of a game, as reported to clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "status"))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum GameStatus {
+    InProgress { next_player: u8 },
+    Won { player: u8, at_height: u64 },
+    Drawn { at_height: u64 },
+}
+
+impl From<GameResult> for GameStatus {
+    fn from(result: GameResult) -> Self {
+        match result.winner {
+            Some(player) => Self::Won {
+                player,
+                at_height: result.height,
+            },
+            None => Self::Drawn {
+                at_height: result.height,
+            },
+        }
+    }
+}
+
+/// Ways a move can break the rules, see [`check_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The action's cell index is past the last cell
+    OffBoard(u8),
+    /// The cell is already marked
+    CellOccupied(u8),
+    /// The player moved when the other player was due to
+    WrongTurn { player: u8, expected: u8 },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OffBoard(index) => write!(f, "cell index {index} is off the board"),
+            Self::CellOccupied(index) => write!(f, "cell {index} is already taken"),
+            Self::WrongTurn { player, expected } => {
+                write!(f, "player {player} moved out of turn, expected player {expected}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MoveError {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeSet, vec};
+
+    use super::{cell, decode_action, is_full, legal_moves, place, winner, CELLS, LINES};
+
+    /// Returns the player holding a complete line, checking the lines cell by cell.
+    fn winner_by_line(board: u32) -> Option<u8> {
+        LINES.iter().find_map(|[a, b, c]| {
+            let mark = cell(board, *a);
+            (mark != 0 && mark == cell(board, *b) && mark == cell(board, *c)).then_some(mark)
+        })
+    }
+
+    /// Returns every board a game can reach, playing from the empty board until it
+    /// is won or full.
+    fn reachable_boards() -> BTreeSet<u32> {
+        let mut boards = BTreeSet::new();
+        let mut unplayed = vec![0];
+        while let Some(board) = unplayed.pop() {
+            if !boards.insert(board) {
+                continue;
+            }
+            for action in legal_moves(board) {
+                let (player, index) = decode_action(action);
+                unplayed.push(place(board, player, index));
+            }
+        }
+        boards
+    }
+
+    #[test]
+    fn masks_match_per_line_check() {
+        let boards = reachable_boards();
+        assert_eq!(boards.len(), 5_478);
+        for board in boards {
+            let full = (0..CELLS).all(|index| cell(board, index) != 0);
+            assert_eq!(winner(board), winner_by_line(board), "winner of {board:#x}");
+            assert_eq!(is_full(board), full, "fullness of {board:#x}");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! JavaScript bindings of the rules, so web UIs validate moves with the
+//! exact logic the VM runs. Boards and actions are passed as numbers, in
+//! the same encoding the chain APIs return.
+
+use alloc::{string::ToString, vec::Vec};
+
+use wasm_bindgen::prelude::*;
+
+/// Returns the mark of each cell in index order: 0 if empty, otherwise the player.
+#[wasm_bindgen(js_name = decodeBoard)]
+#[must_use]
+pub fn decode_board(board: u32) -> Vec<u8> {
+    (0..super::CELLS)
+        .map(|index| super::cell(board, index))
+        .collect()
+}
+
+/// Returns the board with the given action played, empty if it ended the game.
+#[wasm_bindgen(js_name = advance)]
+#[must_use]
+pub fn advance(board: u32, action: u8) -> u32 {
+    super::advance(board, action)
+}
+
+/// Returns the actions the player due to move may play.
+#[wasm_bindgen(js_name = legalMoves)]
+#[must_use]
+pub fn legal_moves(board: u32) -> Vec<u8> {
+    super::legal_moves(board)
+}
+
+/// Returns the player due to move.
+#[wasm_bindgen(js_name = toMove)]
+#[must_use]
+pub fn to_move(board: u32) -> u8 {
+    super::to_move(board)
+}
+
+/// Returns the player holding a complete line, or 0 if there is none.
+#[wasm_bindgen(js_name = winner)]
+#[must_use]
+pub fn winner(board: u32) -> u8 {
+    super::winner(board).unwrap_or(0)
+}
+
+/// Returns whether every cell is marked.
+#[wasm_bindgen(js_name = isFull)]
+#[must_use]
+pub fn is_full(board: u32) -> bool {
+    super::is_full(board)
+}
+
+/// Throws the reason the action can't be played on the board, if any.
+/// # Errors
+/// Fails if the move breaks the rules, see [`super::check_move`].
+#[wasm_bindgen(js_name = checkMove)]
+pub fn check_move(board: u32, action: u8) -> Result<(), JsValue> {
+    if super::winner(board).is_some() {
+        return Err(JsValue::from_str("the game is over"));
+    }
+    super::check_move(board, super::to_move(board), action)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Packs a player (1 or 2) and cell index into an action.
+#[wasm_bindgen(js_name = encodeAction)]
+#[must_use]
+pub fn encode_action(player: u8, index: u8) -> u8 {
+    super::encode_action(player, index)
+}
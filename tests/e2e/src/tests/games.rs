@@ -0,0 +1,153 @@
+//! Plays complete games across the nodes of the network, and checks every
+//! validator ends up with the same chain state.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use timestampvm::{
+    api::chain_handlers::{GetWinnersArgs, ProposedMoveArgs, WaitForMoveArgs},
+    client::{self, TicTacToeClient},
+    game,
+};
+
+/// Cells marked in turn, starting with X, and the winner of the resulting game.
+pub const GAMES: [(&[u8], Option<u8>); 3] = [
+    // X takes the top row
+    (&[0, 3, 1, 4, 2], Some(1)),
+    // O takes the anti-diagonal
+    (&[0, 4, 1, 2, 8, 6], Some(2)),
+    (&[0, 1, 2, 4, 3, 5, 7, 6, 8], None),
+];
+
+/// How long a proposed move is waited on to be accepted.
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plays the given moves as the game in progress, proposing each move through the
+/// next node in turn and waiting for the node to accept it.
+pub async fn play(rpc_eps: &[String], chain_url_path: &str, moves: &[u8], winner: Option<u8>) {
+    let game_index = client::current_game(&rpc_eps[0], chain_url_path)
+        .await
+        .unwrap();
+    log::info!("playing game {game_index} with moves {moves:?}");
+
+    for (turn, (cell, ep)) in moves.iter().zip(rpc_eps.iter().cycle()).enumerate() {
+        let player = if turn % 2 == 0 { 1 } else { 2 };
+        let args = ProposedMoveArgs {
+            action: game::encode_action(player, *cell),
+            signature: None,
+            work: None,
+            max_fee: None,
+        };
+        let resp = client::propose_move(ep, chain_url_path, args).await.unwrap();
+        log::info!(
+            "{ep} proposed cell {cell} for player {player} in block {}",
+            resp.block_id
+        );
+
+        let deadline = Instant::now() + ACCEPT_TIMEOUT;
+        loop {
+            let waited = client::wait_for_move(
+                ep,
+                chain_url_path,
+                WaitForMoveArgs {
+                    game: game_index,
+                    last_seen_height: resp.height - 1,
+                    timeout_ms: 1_000,
+                },
+            )
+            .await
+            .unwrap();
+            if let Some(accepted) = waited.next_move {
+                assert_eq!(accepted.cell, *cell, "another move was accepted first");
+                assert_eq!(accepted.player, player);
+                break;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "move {cell} of game {game_index} wasn't accepted by {ep}"
+            );
+        }
+    }
+
+    let status = client::get_game_status(&rpc_eps[0], chain_url_path, game_index)
+        .await
+        .unwrap();
+    log::info!("game {game_index} ended: {:?}", status.status);
+    match winner {
+        Some(player) => assert!(
+            matches!(status.status, game::GameStatus::Won { player: p, .. } if p == player),
+            "expected player {player} to win game {game_index}, got {:?}",
+            status.status
+        ),
+        None => assert!(
+            matches!(status.status, game::GameStatus::Drawn { .. }),
+            "expected game {game_index} to be drawn, got {:?}",
+            status.status
+        ),
+    }
+}
+
+/// Asserts every node reports the same height, games, winners and move histories,
+/// once all of them caught up with the highest accepted block.
+pub async fn assert_consistent(rpc_eps: &[String], chain_url_path: &str) {
+    let timeout = Duration::from_secs(60);
+    let start = Instant::now();
+    loop {
+        let snapshots = {
+            let mut snapshots = Vec::with_capacity(rpc_eps.len());
+            for ep in rpc_eps {
+                snapshots.push(snapshot(ep, chain_url_path).await);
+            }
+            snapshots
+        };
+
+        let diverging: Vec<_> = rpc_eps
+            .iter()
+            .zip(snapshots.iter())
+            .filter(|(_, s)| **s != snapshots[0])
+            .map(|(ep, _)| ep.as_str())
+            .collect();
+        if diverging.is_empty() {
+            log::info!("all {} nodes agree on {}", rpc_eps.len(), snapshots[0]);
+            return;
+        }
+
+        assert!(
+            start.elapsed() < timeout,
+            "{diverging:?} diverge from {}: {snapshots:#?}",
+            rpc_eps[0]
+        );
+        log::info!("{diverging:?} not caught up with {} yet", rpc_eps[0]);
+        thread::sleep(Duration::from_secs(2));
+    }
+}
+
+/// Chain state reported by a node.
+async fn snapshot(ep: &str, chain_url_path: &str) -> Value {
+    let cli = TicTacToeClient::new(ep, chain_url_path);
+    let info = cli.get_node_info().await.unwrap();
+    let winners = cli
+        .get_winners(GetWinnersArgs {
+            offset: 0,
+            limit: None,
+        })
+        .await
+        .unwrap();
+    let board = cli.get_board().await.unwrap();
+
+    let mut histories = Vec::with_capacity(winners.total + 1);
+    for game_index in 0..=winners.total {
+        histories.push(cli.get_move_history(game_index).await.unwrap().moves);
+    }
+
+    json!({
+        "height": info.height,
+        "games": winners.total,
+        "winners": winners.results,
+        "board": board.board,
+        "histories": histories,
+    })
+}
@@ -1,3 +1,5 @@
+mod games;
+
 use std::{
     fs,
     path::Path,
@@ -80,6 +82,7 @@ async fn e2e() {
     // write some random genesis file
     let genesis = timestampvm::genesis::Genesis {
         data: random_manager::secure_string(10),
+        ..Default::default()
     };
     let genesis_file_path = random_manager::tmp_path(10, None).unwrap();
     genesis.sync(&genesis_file_path).unwrap();
@@ -227,62 +230,11 @@ async fn e2e() {
         thread::sleep(Duration::from_millis(300));
     }
 
-    let ep = rpc_eps[0].clone();
-
-    log::info!("get last_accepted from chain handlers");
-    let resp = timestampvm::client::last_accepted(&ep, &chain_url_path)
-        .await
-        .unwrap();
-    log::info!("last_accepted response from {}: {:?}", ep, resp);
-
-    let blk_id = resp.result.unwrap().id;
-
-    log::info!("getting block {blk_id}");
-    let resp = timestampvm::client::get_block(&ep, &chain_url_path, &blk_id)
-        .await
-        .unwrap();
-    log::info!("get_block response from {}: {:?}", ep, resp);
-    let height0 = resp.result.unwrap().block.height();
-    assert_eq!(height0, 0);
-
-    log::info!("propose block");
-    let resp = timestampvm::client::propose_block(&ep, &chain_url_path, vec![0, 1, 2])
-        .await
-        .unwrap();
-    log::info!("propose_block response from {}: {:?}", ep, resp);
-
-    // enough time for block builds
-    thread::sleep(Duration::from_secs(5));
-
-    log::info!("get last_accepted from chain handlers");
-    let resp = timestampvm::client::last_accepted(&ep, &chain_url_path)
-        .await
-        .unwrap();
-    log::info!("last_accepted response from {}: {:?}", ep, resp);
-
-    let blk_id = resp.result.unwrap().id;
-
-    log::info!("getting block {blk_id}");
-    let resp = timestampvm::client::get_block(&ep, &chain_url_path, &blk_id)
-        .await
-        .unwrap();
-    log::info!("get_block response from {}: {:?}", ep, resp);
-    let height1 = resp.result.unwrap().block.height();
-    assert_eq!(height0 + 1, height1);
-
-    // expects an error of
-    // "error":{"code":-32603,"message":"data 1048586-byte exceeds the limit 1048576-byte"}
-    log::info!("propose block beyond its limit");
-    let resp = timestampvm::client::propose_block(
-        &ep,
-        &chain_url_path,
-        vec![1; timestampvm::vm::PROPOSE_LIMIT_BYTES + 10],
-    )
-    .await
-    .unwrap();
-    assert!(resp.result.is_none());
-    assert!(resp.error.is_some());
-    log::info!("propose block response: {:?}", resp);
+    log::info!("play games across nodes");
+    for (moves, winner) in games::GAMES {
+        games::play(&rpc_eps, &chain_url_path, moves, winner).await;
+    }
+    games::assert_consistent(&rpc_eps, &chain_url_path).await;
 
     if crate::get_network_runner_enable_shutdown() {
         log::info!("shutdown is enabled... stopping...");
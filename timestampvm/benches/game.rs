@@ -0,0 +1,53 @@
+//! Benchmarks board evaluation, run once per accepted move.
+//!
+//! `cargo bench -p timestampvm --bench game`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use timestampvm::game;
+
+/// Returns every board with each cell empty or marked by either player.
+fn all_boards() -> Vec<u32> {
+    (0..3_u32.pow(u32::from(game::CELLS)))
+        .map(|mut n| {
+            (0..game::CELLS).fold(0, |board, index| {
+                let mark = n % 3;
+                n /= 3;
+                game::place(board, u8::try_from(mark).unwrap(), index)
+            })
+        })
+        .collect()
+}
+
+/// Checks each of the lines in turn, as boards were evaluated before the
+/// precomputed masks.
+fn winner_by_lines(board: u32) -> Option<u8> {
+    game::LINES.iter().find_map(|[a, b, c]| {
+        let mark = game::cell(board, *a);
+        let won = mark != 0 && mark == game::cell(board, *b) && mark == game::cell(board, *c);
+        won.then_some(mark)
+    })
+}
+
+fn is_full_by_cells(board: u32) -> bool {
+    (0..game::CELLS).all(|i| game::cell(board, i) != 0)
+}
+
+fn evaluate(c: &mut Criterion) {
+    let boards = all_boards();
+
+    c.bench_function("winner", |b| {
+        b.iter(|| boards.iter().filter_map(|board| game::winner(black_box(*board))).count());
+    });
+    c.bench_function("winner_by_lines", |b| {
+        b.iter(|| boards.iter().filter_map(|board| winner_by_lines(black_box(*board))).count());
+    });
+    c.bench_function("is_full", |b| {
+        b.iter(|| boards.iter().filter(|board| game::is_full(black_box(**board))).count());
+    });
+    c.bench_function("is_full_by_cells", |b| {
+        b.iter(|| boards.iter().filter(|board| is_full_by_cells(black_box(**board))).count());
+    });
+}
+
+criterion_group!(benches, evaluate);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+//! Benchmarks the paths every block or query goes through: block encoding,
+//! verification, board updates on accept and RPC dispatch.
+//!
+//! `cargo bench -p timestampvm --bench hot_paths`
+
+use avalanche_types::{choices::status::Status, ids};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use timestampvm::{
+    api::chain_handlers::{ChainHandler, ChainService},
+    block::Block,
+    game,
+    state::State,
+    vm::Vm,
+};
+use tokio::runtime::Runtime;
+
+/// Moves of a game won by player 1 along the top row.
+const WINNING_GAME: [(u8, u8); 5] = [(1, 0), (2, 3), (1, 1), (2, 4), (1, 2)];
+
+/// Returns the blocks carrying `moves`, chained on top of `parent_id`.
+fn chain(parent_id: ids::Id, moves: &[(u8, u8)]) -> Vec<Block> {
+    let mut parent_id = parent_id;
+    let mut blocks = Vec::with_capacity(moves.len());
+    for (height, (player, cell)) in (1..).zip(moves) {
+        let action = game::encode_action(*player, *cell);
+        let block =
+            Block::try_new(parent_id, height, action, Vec::new(), Status::Processing).unwrap();
+        parent_id = block.id();
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Returns a state with only the genesis block accepted.
+async fn genesis_state() -> (State, Block) {
+    let state = State::default();
+    let mut genesis = Block::try_new(ids::Id::empty(), 0, 0, Vec::new(), Status::Processing)
+        .unwrap();
+    genesis.set_state(state.clone());
+    genesis.accept().await.unwrap();
+    (state, genesis)
+}
+
+fn codec(c: &mut Criterion) {
+    let block = chain(ids::Id::empty(), &WINNING_GAME[..1]).remove(0);
+    let bytes = block.bytes().to_vec();
+
+    c.bench_function("block_encode", |b| {
+        b.iter(|| {
+            Block::try_new(
+                black_box(ids::Id::empty()),
+                1,
+                game::encode_action(1, 4),
+                Vec::new(),
+                Status::Processing,
+            )
+            .unwrap()
+        });
+    });
+    c.bench_function("block_decode", |b| {
+        b.iter(|| Block::from_slice(black_box(&bytes)).unwrap().id());
+    });
+}
+
+fn verification(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (mut state, genesis) = rt.block_on(genesis_state());
+    let mut block = chain(genesis.id(), &WINNING_GAME[..1]).remove(0);
+    block.set_state(state.clone());
+
+    c.bench_function("block_verify", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                block.verify().await.unwrap();
+                // Verified blocks are skipped, so forget it for the next iteration
+                state.remove_verified(&block.id()).await;
+            });
+        });
+    });
+}
+
+fn board_update(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let blocks = chain(ids::Id::empty(), &WINNING_GAME);
+
+    c.bench_function("update_board_winning_game", |b| {
+        b.iter_batched(
+            State::default,
+            |state| {
+                rt.block_on(async {
+                    for block in &blocks {
+                        state.update_board(block).await.unwrap();
+                    }
+                });
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn rpc_dispatch(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let handler = ChainHandler::new(ChainService::new(Vm::<()>::new())).handler;
+
+    for (name, request) in [
+        ("rpc_ping", r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#),
+        ("rpc_get_board", r#"{"jsonrpc":"2.0","id":1,"method":"getBoard"}"#),
+        (
+            "rpc_get_winner",
+            r#"{"jsonrpc":"2.0","id":1,"method":"getWinner","params":[{"req":0}]}"#,
+        ),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| rt.block_on(handler.handle_request(black_box(request))).unwrap());
+        });
+    }
+}
+
+criterion_group!(benches, codec, verification, board_update, rpc_dispatch);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+//! Embeds the git commit the VM is built from, as reported by `getNodeInfo`, and
+//! generates the gRPC chain service from `proto/`.
+
+use std::process::Command;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or_else(|| String::from("unknown"), |commit| commit.trim().to_string());
+
+    println!("cargo:rustc-env=TIMESTAMPVM_BUILD_COMMIT={commit}");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+
+    // Use the bundled protoc so builds don't depend on one being installed
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/tictactoe/v1/chain.proto"], &["proto"])?;
+    println!("cargo:rerun-if-changed=proto");
+
+    Ok(())
+}
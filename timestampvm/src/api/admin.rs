@@ -0,0 +1,335 @@
+//! Implements the admin RPC, to inspect, repair, export and import a node's state
+//! and adjust its logging.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/admin`, only if enabled in the config.
+
+use std::io;
+
+use avalanche_types::{ids, proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, IoHandler, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    logging,
+    mempool::EvictionStats,
+    state::{ConsistencyReport, StateArchive},
+    vm::Vm,
+};
+
+use super::{
+    chain_handlers::{create_jsonrpc_error, pending_moves, GetPendingMovesResponse},
+    de_request,
+};
+
+/// Defines the admin RPCs, all prefixed with `admin_`.
+#[rpc]
+pub trait AdminRpc {
+    /// Dumps the verified blocks, the mempool and the turn of the current game
+    #[rpc(name = "admin_dumpState")]
+    fn dump_state(&self) -> BoxFuture<Result<StateDump>>;
+
+    /// Drops a pending block whatever its move, re-chaining the moves after it
+    #[rpc(name = "admin_evictPendingBlock")]
+    fn evict_pending_block(&self, args: EvictPendingBlockArgs) -> BoxFuture<Result<EvictionStats>>;
+
+    /// Replays the database and compares it to the in-memory state
+    #[rpc(name = "admin_checkConsistency")]
+    fn check_consistency(&self) -> BoxFuture<Result<ConsistencyReport>>;
+
+    /// Exports the accepted blocks, board snapshots and game results as a single archive
+    #[rpc(name = "admin_exportState")]
+    fn export_state(&self) -> BoxFuture<Result<StateArchive>>;
+
+    /// Accepts the blocks of an archive exported by another node, which must have
+    /// accepted nothing but its genesis block
+    #[rpc(name = "admin_importState")]
+    fn import_state(&self, archive: Value) -> BoxFuture<Result<ImportStateResponse>>;
+
+    /// Returns the filter of the VM's logs
+    #[rpc(name = "admin_getLogFilter")]
+    fn get_log_filter(&self) -> BoxFuture<Result<LogFilterResponse>>;
+
+    /// Changes the filter of the VM's logs, e.g. `timestampvm=debug,info`
+    #[rpc(name = "admin_setLogFilter")]
+    fn set_log_filter(&self, args: SetLogFilterArgs) -> BoxFuture<Result<LogFilterResponse>>;
+}
+
+/// Verified block, as dumped by `admin_dumpState`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct VerifiedBlockEntry {
+    pub block_id: ids::Id,
+    pub parent_id: ids::Id,
+    pub height: u64,
+    pub player: u8,
+    pub cell: u8,
+}
+
+/// Turn of the current game, as accepted and as predicted once the pending moves are.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TurnState {
+    pub game: usize,
+    pub board: u32,
+    pub next_player: u8,
+    /// Addresses holding each player's seat, if taken
+    pub seats: [Option<ids::short::Id>; 2],
+    /// Board once the pending moves are accepted
+    pub predicted_board: u32,
+    /// Player due to move once the pending moves are accepted
+    pub predicted_next_player: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StateDump {
+    pub bootstrapped: bool,
+    pub preferred: ids::Id,
+    /// Height of the last accepted block
+    pub height: u64,
+    pub turn: TurnState,
+    /// Blocks verified but not yet accepted or rejected, by height
+    pub verified: Vec<VerifiedBlockEntry>,
+    pub mempool: GetPendingMovesResponse,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EvictPendingBlockArgs {
+    pub block_id: ids::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImportStateResponse {
+    /// Height of the last imported block
+    pub height: u64,
+    /// Id of the last imported block, now preferred
+    pub last_accepted: ids::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SetLogFilterArgs {
+    pub filter: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LogFilterResponse {
+    pub filter: String,
+    /// Filter replaced by `admin_setLogFilter`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+/// Implements the admin RPCs on top of the Vm.
+#[derive(Clone)]
+pub struct AdminService<A> {
+    pub vm: Vm<A>,
+}
+
+impl<A> AdminService<A> {
+    pub fn new(vm: Vm<A>) -> Self {
+        Self { vm }
+    }
+}
+
+impl<A> AdminRpc for AdminService<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn dump_state(&self) -> BoxFuture<Result<StateDump>> {
+        log::debug!("admin_dumpState called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let board = state.get_curr_game().await;
+                let next_player = state.get_next_player().await;
+                let mempool = vm.mempool.read().await;
+                let (predicted_board, predicted_next_player) =
+                    mempool.predict(board, next_player, None);
+
+                let turn = TurnState {
+                    game: state.get_curr_game_index().await,
+                    board,
+                    next_player,
+                    seats: [state.get_seat(1).await, state.get_seat(2).await],
+                    predicted_board,
+                    predicted_next_player,
+                };
+                let verified = state
+                    .get_verified_blocks()
+                    .await
+                    .iter()
+                    .map(|block| VerifiedBlockEntry {
+                        block_id: block.id(),
+                        parent_id: block.parent_id(),
+                        height: block.height(),
+                        player: block.get_player_id(),
+                        cell: block.get_move_index(),
+                    })
+                    .collect();
+
+                return Ok(StateDump {
+                    bootstrapped: vm_state.bootstrapped,
+                    preferred: vm_state.preferred,
+                    height: state.get_height().await,
+                    turn,
+                    verified,
+                    mempool: pending_moves(state, &mempool).await,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn evict_pending_block(&self, args: EvictPendingBlockArgs) -> BoxFuture<Result<EvictionStats>> {
+        log::debug!("admin_evictPendingBlock called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            vm.evict_pending_block(&args.block_id)
+                .await
+                .map_err(create_jsonrpc_error)
+        })
+    }
+
+    fn check_consistency(&self) -> BoxFuture<Result<ConsistencyReport>> {
+        log::debug!("admin_checkConsistency called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let report = state
+                    .check_consistency()
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+                if !report.consistent {
+                    log::warn!(
+                        "in-memory state differs from the database: {:?}",
+                        report.mismatches
+                    );
+                }
+                return Ok(report);
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn export_state(&self) -> BoxFuture<Result<StateArchive>> {
+        log::debug!("admin_exportState called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return state.export_archive().await.map_err(create_jsonrpc_error);
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn import_state(&self, archive: Value) -> BoxFuture<Result<ImportStateResponse>> {
+        log::debug!("admin_importState called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            // Ids only deserialize from borrowed strings, which a `Value` can't lend
+            let archive: StateArchive = serde_json::from_str(&archive.to_string())
+                .map_err(|e| Error::invalid_params(format!("invalid archive: {e}")))?;
+            let (last_accepted, height) = vm.import_state(&archive).await.map_err(|e| {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    Error::invalid_params(e.to_string())
+                } else {
+                    create_jsonrpc_error(e)
+                }
+            })?;
+            Ok(ImportStateResponse {
+                height,
+                last_accepted,
+            })
+        })
+    }
+
+    fn get_log_filter(&self) -> BoxFuture<Result<LogFilterResponse>> {
+        log::debug!("admin_getLogFilter called");
+
+        Box::pin(async move {
+            let filter = logging::filter().ok_or_else(|| Error {
+                code: ErrorCode::InternalError,
+                message: String::from("the VM's logger isn't installed"),
+                data: None,
+            })?;
+            Ok(LogFilterResponse {
+                filter,
+                previous: None,
+            })
+        })
+    }
+
+    fn set_log_filter(&self, args: SetLogFilterArgs) -> BoxFuture<Result<LogFilterResponse>> {
+        log::debug!("admin_setLogFilter called");
+
+        Box::pin(async move {
+            let previous = logging::set_filter(&args.filter).map_err(|e| {
+                if e.kind() == io::ErrorKind::InvalidInput {
+                    Error::invalid_params(e.to_string())
+                } else {
+                    create_jsonrpc_error(e)
+                }
+            })?;
+            Ok(LogFilterResponse {
+                filter: args.filter,
+                previous: Some(previous),
+            })
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminHandler {
+    pub handler: IoHandler,
+}
+
+impl AdminHandler {
+    #[must_use]
+    pub fn new<A>(service: AdminService<A>) -> Self
+    where
+        A: Send + Sync + Clone + 'static,
+    {
+        let mut handler = jsonrpc_core::IoHandler::new();
+        handler.extend_with(AdminRpc::to_delegate(service));
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl Handle for AdminHandler {
+    async fn request(
+        &self,
+        req: &Bytes,
+        _headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        match self.handler.handle_request(&de_request(req)?).await {
+            Some(resp) => Ok((Bytes::from(resp), Vec::new())),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to handle request",
+            )),
+        }
+    }
+}
@@ -1,99 +1,2130 @@
 //! Implements chain/VM specific handlers.
 //! To be served via `[HOST]/ext/bc/[CHAIN ID]/rpc`.
 
-use crate::{block::Block, vm::Vm};
-use avalanche_types::{ids, proto::http::Element, subnet::rpc::http::handle::Handle};
+use crate::{
+    block::{AccountAction, Block, Seek, SeriesAction, TeamAction, TournamentAction, Transfer},
+    game::{self, GameError, GameResult, GameStatus},
+    mempool::{rate_limit::RateLimited, EvictionStats, Mempool},
+    metrics::{LatencyStats, Metrics},
+    state::{
+        leaderboard::LeaderboardOrder,
+        seat_of,
+        series::{Series, SeriesStatus},
+        team::{TeamGame, TeamGameStatus},
+        tournament::{Standing, Tournament, TournamentStatus},
+        AcceptedMove, AccountGame, BlockEvent, MoveRecord, PlayerStats, RatingChange, State,
+    },
+    vm::{Health, Vm, BUILD_COMMIT},
+};
+use avalanche_types::{
+    codec::serde::hex_0x_bytes::Hex0xBytes, ids, proto::http::Element,
+    subnet::rpc::http::handle::Handle,
+};
 use bytes::Bytes;
-use jsonrpc_core::{BoxFuture, Error, ErrorCode, IoHandler, Result};
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, IoHandler, MetaIoHandler, Params, Result};
 use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{
+    typed::{Sink, Subscriber},
+    PubSubHandler, Session, SubscriptionId,
+};
+use jsonrpc_ws_server::RequestContext;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{borrow::Borrow, io, marker::PhantomData, str::FromStr};
+use serde_with::serde_as;
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    io,
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+use tracing::Instrument;
 
-use super::de_request;
+use super::{
+    de_method_call, forwarded_source, request_id, ser_method_call, REQUEST_ID_HEADER,
+};
+
+tokio::task_local! {
+    /// IP of the client whose call is being handled, if a proxy forwarded it
+    static SOURCE: Option<IpAddr>;
+}
+
+/// Returns the IP of the client whose call is being handled, keying the rate limit
+/// of unsigned submissions. Unknown over WebSocket and unless a proxy forwarded it.
+fn request_source() -> Option<IpAddr> {
+    SOURCE.try_with(|source| *source).ok().flatten()
+}
 
 /// Defines RPCs specific to the chain.
 #[rpc]
 pub trait Rpc {
     /// Pings the VM.
-    #[rpc(name = "ping", alias("tic_tac_toe.ping"))]
+    #[rpc(name = "ping", alias("tic_tac_toe.ping", "tictactoe.v1.ping"))]
     fn ping(&self) -> BoxFuture<Result<crate::api::PingResponse>>;
 
-    /// Proposes a player move.
-    #[rpc(name = "proposeMove", alias("tic_tac_toe.proposeMove"))]
-    fn propose_move(&self, args: ProposedMoveArgs) -> BoxFuture<Result<ProposedMoveResponse>>;
+    /// Proposes a player move.
+    #[rpc(name = "proposeMove", alias("tic_tac_toe.proposeMove", "tictactoe.v1.proposeMove"))]
+    fn propose_move(&self, args: ProposedMoveArgs) -> BoxFuture<Result<ProposedMoveResponse>>;
+
+    /// Proposes an ordered list of player moves, reporting the outcome of each.
+    #[rpc(
+        name = "batchProposeMoves",
+        alias("tic_tac_toe.batchProposeMoves", "tictactoe.v1.batchProposeMoves")
+    )]
+    fn batch_propose_moves(
+        &self,
+        args: BatchProposeMovesArgs,
+    ) -> BoxFuture<Result<BatchProposeMovesResponse>>;
+
+    /// Validates a player move against the current state without proposing it.
+    #[rpc(name = "simulateMove", alias("tic_tac_toe.simulateMove", "tictactoe.v1.simulateMove"))]
+    fn simulate_move(&self, args: SimulateMoveArgs) -> BoxFuture<Result<SimulateMoveResponse>>;
+
+    /// Proposes a transfer of tokens signed by the sender.
+    #[rpc(
+        name = "proposeTransfer",
+        alias("tic_tac_toe.proposeTransfer", "tictactoe.v1.proposeTransfer")
+    )]
+    fn propose_transfer(
+        &self,
+        args: ProposeTransferArgs,
+    ) -> BoxFuture<Result<ProposeTransferResponse>>;
+
+    /// Proposes a seek signed by the seeker, queueing it for matchmaking.
+    #[rpc(name = "seekGame", alias("tic_tac_toe.seekGame", "tictactoe.v1.seekGame"))]
+    fn seek_game(&self, args: SeekGameArgs) -> BoxFuture<Result<SeekGameResponse>>;
+
+    /// Proposes a tournament action signed by the address taking it, creating a
+    /// tournament or registering in one.
+    #[rpc(
+        name = "proposeTournamentAction",
+        alias("tic_tac_toe.proposeTournamentAction", "tictactoe.v1.proposeTournamentAction")
+    )]
+    fn propose_tournament_action(
+        &self,
+        args: ProposeTournamentActionArgs,
+    ) -> BoxFuture<Result<ProposeTournamentActionResponse>>;
+
+    /// Proposes a series action signed by the address taking it, challenging an
+    /// address to a best-of-N series or accepting one.
+    #[rpc(
+        name = "proposeSeriesAction",
+        alias("tic_tac_toe.proposeSeriesAction", "tictactoe.v1.proposeSeriesAction")
+    )]
+    fn propose_series_action(
+        &self,
+        args: ProposeSeriesActionArgs,
+    ) -> BoxFuture<Result<ProposeSeriesActionResponse>>;
+
+    /// Proposes a team action signed by the address taking it, challenging two
+    /// addresses to a 2v2 team game with a partner or accepting one.
+    #[rpc(
+        name = "proposeTeamAction",
+        alias("tic_tac_toe.proposeTeamAction", "tictactoe.v1.proposeTeamAction")
+    )]
+    fn propose_team_action(
+        &self,
+        args: ProposeTeamActionArgs,
+    ) -> BoxFuture<Result<ProposeTeamActionResponse>>;
+
+    /// Fetches the current game state
+    #[rpc(name = "getBoard", alias("tic_tac_toe.getBoard", "tictactoe.v1.getBoard"))]
+    fn get_board(&self) -> BoxFuture<Result<GetBoardResponse>>;
+
+    /// Fetches the winner of the ith game
+    #[rpc(name = "getWinner", alias("tic_tac_toe.getWinner", "tictactoe.v1.getWinner"))]
+    fn get_winner(&self, args: GetWinnerArgs) -> BoxFuture<Result<GetWinnerResponse>>;
+
+    /// Fetches accepted blocks in height order, a chunk at a time
+    #[rpc(name = "streamBlocks", alias("tic_tac_toe.streamBlocks", "tictactoe.v1.streamBlocks"))]
+    fn stream_blocks(&self, args: StreamBlocksArgs) -> BoxFuture<Result<StreamBlocksResponse>>;
+
+    /// Fetches the accepted block at the given height
+    #[rpc(
+        name = "getBlockByHeight",
+        alias("tic_tac_toe.getBlockByHeight", "tictactoe.v1.getBlockByHeight")
+    )]
+    fn get_block_by_height(
+        &self,
+        args: GetBlockByHeightArgs,
+    ) -> BoxFuture<Result<GetBlockByHeightResponse>>;
+
+    /// Fetches the board as it stood after the block at the given height
+    #[rpc(
+        name = "getBoardAtHeight",
+        alias("tic_tac_toe.getBoardAtHeight", "tictactoe.v1.getBoardAtHeight")
+    )]
+    fn get_board_at_height(
+        &self,
+        args: GetBoardAtHeightArgs,
+    ) -> BoxFuture<Result<GetBoardResponse>>;
+
+    /// Fetches the board as it stood after the given block
+    #[rpc(
+        name = "getBoardAtBlock",
+        alias("tic_tac_toe.getBoardAtBlock", "tictactoe.v1.getBoardAtBlock")
+    )]
+    fn get_board_at_block(&self, args: GetBoardAtBlockArgs) -> BoxFuture<Result<GetBoardResponse>>;
+
+    /// Fetches the events emitted by an accepted block
+    #[rpc(
+        name = "getBlockEvents",
+        alias("tic_tac_toe.getBlockEvents", "tictactoe.v1.getBlockEvents")
+    )]
+    fn get_block_events(
+        &self,
+        args: GetBlockEventsArgs,
+    ) -> BoxFuture<Result<GetBlockEventsResponse>>;
+
+    /// Fetches the ordered moves of the given game
+    #[rpc(
+        name = "getMoveHistory",
+        alias("tic_tac_toe.getMoveHistory", "tictactoe.v1.getMoveHistory")
+    )]
+    fn get_move_history(
+        &self,
+        args: GetMoveHistoryArgs,
+    ) -> BoxFuture<Result<GetMoveHistoryResponse>>;
+
+    /// Fetches the player expected to make the next move of the given game
+    #[rpc(name = "whoseTurn", alias("tic_tac_toe.whoseTurn", "tictactoe.v1.whoseTurn"))]
+    fn whose_turn(&self, args: WhoseTurnArgs) -> BoxFuture<Result<WhoseTurnResponse>>;
+
+    /// Fetches the empty cells the next player may mark in the given game
+    #[rpc(name = "getLegalMoves", alias("tic_tac_toe.getLegalMoves", "tictactoe.v1.getLegalMoves"))]
+    fn get_legal_moves(
+        &self,
+        args: GetLegalMovesArgs,
+    ) -> BoxFuture<Result<GetLegalMovesResponse>>;
+
+    /// Fetches whether the given game is in progress, won or drawn
+    #[rpc(name = "getGameStatus", alias("tic_tac_toe.getGameStatus", "tictactoe.v1.getGameStatus"))]
+    fn get_game_status(&self, args: GetGameStatusArgs) -> BoxFuture<Result<GetGameStatusResponse>>;
+
+    /// Fetches a page of finished game results
+    #[rpc(name = "getWinners", alias("tic_tac_toe.getWinners", "tictactoe.v1.getWinners"))]
+    fn get_winners(&self, args: GetWinnersArgs) -> BoxFuture<Result<GetWinnersResponse>>;
+
+    /// Fetches the results of finished games the given address played in
+    #[rpc(
+        name = "getPlayerStats",
+        alias("tic_tac_toe.getPlayerStats", "tictactoe.v1.getPlayerStats")
+    )]
+    fn get_player_stats(
+        &self,
+        args: GetPlayerStatsArgs,
+    ) -> BoxFuture<Result<GetPlayerStatsResponse>>;
+
+    /// Fetches the accepted balance and transfer nonce of the given address
+    #[rpc(name = "getBalance", alias("tic_tac_toe.getBalance", "tictactoe.v1.getBalance"))]
+    fn get_balance(&self, args: GetBalanceArgs) -> BoxFuture<Result<GetBalanceResponse>>;
+
+    /// Fetches the nonce the next transfer of the given address must carry
+    #[rpc(name = "getNonce", alias("tic_tac_toe.getNonce", "tictactoe.v1.getNonce"))]
+    fn get_nonce(&self, args: GetNonceArgs) -> BoxFuture<Result<GetNonceResponse>>;
+
+    /// Lists the games the given address took a seat in, oldest first
+    #[rpc(
+        name = "getAccountGames",
+        alias("tic_tac_toe.getAccountGames", "tictactoe.v1.getAccountGames")
+    )]
+    fn get_account_games(
+        &self,
+        args: GetAccountGamesArgs,
+    ) -> BoxFuture<Result<GetAccountGamesResponse>>;
+
+    /// Fetches the rating of the given address
+    #[rpc(name = "getRating", alias("tic_tac_toe.getRating", "tictactoe.v1.getRating"))]
+    fn get_rating(&self, args: GetRatingArgs) -> BoxFuture<Result<GetRatingResponse>>;
+
+    /// Lists the changes of the given address's rating, oldest first
+    #[rpc(
+        name = "getRatingHistory",
+        alias("tic_tac_toe.getRatingHistory", "tictactoe.v1.getRatingHistory")
+    )]
+    fn get_rating_history(
+        &self,
+        args: GetRatingHistoryArgs,
+    ) -> BoxFuture<Result<GetRatingHistoryResponse>>;
+
+    /// Fetches a page of the top addresses, by rating or by wins
+    #[rpc(
+        name = "getLeaderboard",
+        alias("tic_tac_toe.getLeaderboard", "tictactoe.v1.getLeaderboard")
+    )]
+    fn get_leaderboard(
+        &self,
+        args: GetLeaderboardArgs,
+    ) -> BoxFuture<Result<GetLeaderboardResponse>>;
+
+    /// Fetches where the given address waits in the matchmaking queue, and the games
+    /// it was paired into that aren't over
+    #[rpc(
+        name = "getQueuePosition",
+        alias("tic_tac_toe.getQueuePosition", "tictactoe.v1.getQueuePosition")
+    )]
+    fn get_queue_position(
+        &self,
+        args: GetQueuePositionArgs,
+    ) -> BoxFuture<Result<GetQueuePositionResponse>>;
+
+    /// Fetches a tournament's players and bracket
+    #[rpc(name = "getTournament", alias("tic_tac_toe.getTournament", "tictactoe.v1.getTournament"))]
+    fn get_tournament(&self, args: GetTournamentArgs) -> BoxFuture<Result<GetTournamentResponse>>;
+
+    /// Fetches a tournament's standings, by points and then by seed
+    #[rpc(
+        name = "getTournamentStandings",
+        alias("tic_tac_toe.getTournamentStandings", "tictactoe.v1.getTournamentStandings")
+    )]
+    fn get_tournament_standings(
+        &self,
+        args: GetTournamentArgs,
+    ) -> BoxFuture<Result<GetTournamentStandingsResponse>>;
+
+    /// Fetches a series' players, games and score
+    #[rpc(name = "getSeries", alias("tic_tac_toe.getSeries", "tictactoe.v1.getSeries"))]
+    fn get_series(&self, args: GetSeriesArgs) -> BoxFuture<Result<GetSeriesResponse>>;
+
+    /// Fetches a team game's teams, acceptances and reserved game
+    #[rpc(
+        name = "getTeamGame",
+        alias("tic_tac_toe.getTeamGame", "tictactoe.v1.getTeamGame")
+    )]
+    fn get_team_game(&self, args: GetTeamGameArgs) -> BoxFuture<Result<GetTeamGameResponse>>;
+
+    /// Fetches the fee a move proposed now would pay, given the mempool's depth
+    #[rpc(
+        name = "getFeeEstimate",
+        alias("tic_tac_toe.getFeeEstimate", "tictactoe.v1.getFeeEstimate")
+    )]
+    fn get_fee_estimate(&self) -> BoxFuture<Result<GetFeeEstimateResponse>>;
+
+    /// Exports the given game in a portable notation, see [`game::notation`]
+    #[rpc(name = "exportGame", alias("tic_tac_toe.exportGame", "tictactoe.v1.exportGame"))]
+    fn export_game(&self, args: ExportGameArgs) -> BoxFuture<Result<ExportGameResponse>>;
+
+    /// Packs a player and cell index into an action byte
+    #[rpc(name = "encodeMove", alias("tic_tac_toe.encodeMove", "tictactoe.v1.encodeMove"))]
+    fn encode_move(&self, args: DecodedMove) -> BoxFuture<Result<EncodedMove>>;
+
+    /// Unpacks an action byte into its player and cell index
+    #[rpc(name = "decodeMove", alias("tic_tac_toe.decodeMove", "tictactoe.v1.decodeMove"))]
+    fn decode_move(&self, args: EncodedMove) -> BoxFuture<Result<DecodedMove>>;
+
+    /// Waits for the next accepted move of a game, for clients that can't subscribe
+    #[rpc(name = "waitForMove", alias("tic_tac_toe.waitForMove", "tictactoe.v1.waitForMove"))]
+    fn wait_for_move(&self, args: WaitForMoveArgs) -> BoxFuture<Result<WaitForMoveResponse>>;
+
+    /// Fetches the build and chain this node is serving
+    #[rpc(name = "getNodeInfo", alias("tic_tac_toe.getNodeInfo", "tictactoe.v1.getNodeInfo"))]
+    fn get_node_info(&self) -> BoxFuture<Result<GetNodeInfoResponse>>;
+
+    /// Lists the moves waiting for blocks to be built, along with eviction counts
+    #[rpc(
+        name = "getPendingMoves",
+        alias("tic_tac_toe.getPendingMoves", "tictactoe.v1.getPendingMoves")
+    )]
+    fn get_pending_moves(&self) -> BoxFuture<Result<GetPendingMovesResponse>>;
+
+    /// Checks the database and engine channel, and reports chain and mempool progress
+    #[rpc(name = "health", alias("tic_tac_toe.health", "tictactoe.v1.health"))]
+    fn health(&self) -> BoxFuture<Result<Health>>;
+
+    /// Reports percentiles of the time from moves being proposed to this node to their
+    /// blocks being accepted, over the last accepted moves
+    #[rpc(
+        name = "getLatencyStats",
+        alias("tic_tac_toe.getLatencyStats", "tictactoe.v1.getLatencyStats")
+    )]
+    fn get_latency_stats(&self) -> BoxFuture<Result<LatencyStats>>;
+
+    /// Lists the available methods along with their versions and aliases
+    #[rpc(name = "rpc_methods", alias("tictactoe.v1.rpc_methods"))]
+    fn rpc_methods(&self) -> BoxFuture<Result<RpcMethodsResponse>>;
+}
+
+/// Defines subscriptions to chain events, only served over WebSocket.
+#[rpc(server)]
+pub trait SubscriptionRpc {
+    type Metadata;
+
+    /// Notifies the subscriber of every accepted block
+    #[pubsub(
+        subscription = "newHeads",
+        subscribe,
+        name = "subscribeNewHeads",
+        alias("tic_tac_toe.subscribeNewHeads", "tictactoe.v1.subscribeNewHeads")
+    )]
+    fn subscribe_new_heads(&self, meta: Self::Metadata, subscriber: Subscriber<NewHead>);
+
+    /// Cancels a `newHeads` subscription
+    /// # Errors
+    /// Fails if the subscription does not exist
+    #[pubsub(
+        subscription = "newHeads",
+        unsubscribe,
+        name = "unsubscribeNewHeads",
+        alias("tic_tac_toe.unsubscribeNewHeads", "tictactoe.v1.unsubscribeNewHeads")
+    )]
+    fn unsubscribe_new_heads(
+        &self,
+        meta: Option<Self::Metadata>,
+        subscription: SubscriptionId,
+    ) -> Result<bool>;
+
+    /// Notifies the subscriber of the board and turn after every accepted move of
+    /// the given game. Games that already ended never notify.
+    #[pubsub(
+        subscription = "boardUpdated",
+        subscribe,
+        name = "subscribeBoardUpdated",
+        alias("tic_tac_toe.subscribeBoardUpdated", "tictactoe.v1.subscribeBoardUpdated")
+    )]
+    fn subscribe_board_updated(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<BoardUpdate>,
+        game: usize,
+    );
+
+    /// Cancels a `boardUpdated` subscription
+    /// # Errors
+    /// Fails if the subscription does not exist
+    #[pubsub(
+        subscription = "boardUpdated",
+        unsubscribe,
+        name = "unsubscribeBoardUpdated",
+        alias("tic_tac_toe.unsubscribeBoardUpdated", "tictactoe.v1.unsubscribeBoardUpdated")
+    )]
+    fn unsubscribe_board_updated(
+        &self,
+        meta: Option<Self::Metadata>,
+        subscription: SubscriptionId,
+    ) -> Result<bool>;
+}
+
+/// Namespace every method was originally served under.
+pub const LEGACY_NAMESPACE: &str = "tic_tac_toe";
+
+/// Current versioned namespace, every method is also served as `tictactoe.v1.<name>`.
+pub const V1_NAMESPACE: &str = "tictactoe.v1";
+
+/// Methods served under [`V1_NAMESPACE`] and [`LEGACY_NAMESPACE`].
+/// New methods must be added here to be listed by `rpc_methods`.
+pub const METHODS: &[&str] = &[
+    "ping",
+    "proposeMove",
+    "batchProposeMoves",
+    "simulateMove",
+    "proposeTransfer",
+    "seekGame",
+    "proposeTournamentAction",
+    "proposeSeriesAction",
+    "proposeTeamAction",
+    "getBoard",
+    "getWinner",
+    "getBlockByHeight",
+    "streamBlocks",
+    "getBoardAtHeight",
+    "getBoardAtBlock",
+    "getBlockEvents",
+    "getMoveHistory",
+    "whoseTurn",
+    "getLegalMoves",
+    "getGameStatus",
+    "getWinners",
+    "getPlayerStats",
+    "getBalance",
+    "getNonce",
+    "getAccountGames",
+    "getRating",
+    "getRatingHistory",
+    "getLeaderboard",
+    "getQueuePosition",
+    "getTournament",
+    "getTournamentStandings",
+    "getSeries",
+    "getTeamGame",
+    "getFeeEstimate",
+    "exportGame",
+    "encodeMove",
+    "decodeMove",
+    "getNodeInfo",
+    "getPendingMoves",
+    "health",
+    "getLatencyStats",
+    "waitForMove",
+    "subscribeNewHeads",
+    "unsubscribeNewHeads",
+    "subscribeBoardUpdated",
+    "unsubscribeBoardUpdated",
+];
+
+/// Maximum time a single `waitForMove` call waits for, in milliseconds.
+pub const MAX_WAIT_FOR_MOVE_MS: u64 = 60_000;
+
+/// Maximum number of blocks returned by a single `streamBlocks` call.
+pub const MAX_BLOCKS_CHUNK: usize = 100;
+
+/// Maximum number of results returned by a single `getWinners` call.
+pub const MAX_WINNERS_PAGE: usize = 1000;
+
+/// Maximum number of addresses returned by a single `getLeaderboard` call.
+pub const MAX_LEADERBOARD_PAGE: usize = 100;
+
+/// Longest parameters logged with a slow call, in bytes.
+const MAX_LOGGED_PARAMS_LEN: usize = 1024;
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct WaitForMoveArgs {
+    pub game: usize,
+    /// Height of the last move the client has seen, only later moves are returned
+    #[serde(default)]
+    pub last_seen_height: u64,
+    /// Capped at [`MAX_WAIT_FOR_MOVE_MS`]
+    pub timeout_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct WaitForMoveResponse {
+    pub game: usize,
+    /// Whether the timeout elapsed before a move was accepted
+    pub timed_out: bool,
+    #[serde(rename = "move", skip_serializing_if = "Option::is_none")]
+    pub next_move: Option<MoveRecord>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetNodeInfoResponse {
+    /// Semantic version of the VM
+    pub version: String,
+    /// Git commit the VM was built from
+    pub commit: String,
+    #[schemars(with = "String")]
+    pub chain_id: ids::Id,
+    /// Height of the last accepted block
+    pub height: u64,
+    /// Hash of the genesis bytes the chain was initialized with
+    #[schemars(with = "String")]
+    pub genesis_hash: ids::Id,
+    /// Tokens burned from the signer's balance for each move
+    #[serde(default)]
+    pub move_fee: u64,
+    /// Cap congestion raises the move fee to
+    #[serde(default)]
+    pub max_move_fee: u64,
+    /// Leading zero bits each move's proof of work must have
+    #[serde(default)]
+    pub move_work: u8,
+    /// Tokens escrowed from each address taking a seat in a game
+    #[serde(default)]
+    pub game_stake: u64,
+}
+
+/// A move waiting for its block to be built.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct PendingMoveEntry {
+    /// Id of the pending block carrying the move
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    /// Game the move will be played in, once the moves queued before it are
+    pub game: usize,
+    /// Number of moves played in the game before this one
+    pub turn: u8,
+    pub player: u8,
+    pub cell: u8,
+    /// Time since the move was received, in milliseconds
+    pub age_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub address: Option<ids::short::Id>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetPendingMovesResponse {
+    /// Pending moves, in the order their blocks will be built
+    pub moves: Vec<PendingMoveEntry>,
+    /// Moves dropped from the mempool since the node started
+    pub evicted: EvictionStats,
+}
+
+/// Notification sent to `newHeads` subscribers once a block is accepted.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct NewHead {
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+    pub game: usize,
+    pub player: u8,
+    pub cell: u8,
+    pub row: u8,
+    pub col: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub address: Option<ids::short::Id>,
+}
+
+impl From<&AcceptedMove> for NewHead {
+    fn from(accepted: &AcceptedMove) -> Self {
+        let (row, col) = game::position(accepted.record.cell);
+        Self {
+            block_id: accepted.record.block_id,
+            height: accepted.record.height,
+            game: accepted.game,
+            player: accepted.record.player,
+            cell: accepted.record.cell,
+            row,
+            col,
+            address: accepted.record.address.clone(),
+        }
+    }
+}
+
+/// Notification sent to `boardUpdated` subscribers of a game once a move is accepted.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct BoardUpdate {
+    pub game: usize,
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+    #[serde(flatten)]
+    pub board: GetBoardResponse,
+    #[serde(flatten)]
+    pub status: GameStatus,
+}
+
+impl From<&AcceptedMove> for BoardUpdate {
+    fn from(accepted: &AcceptedMove) -> Self {
+        Self {
+            game: accepted.game,
+            block_id: accepted.record.block_id,
+            height: accepted.record.height,
+            board: GetBoardResponse::new(accepted.board),
+            status: accepted.status,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct RpcMethod {
+    pub name: String,
+    /// Version of the namespace the method was introduced in
+    pub version: u32,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct RpcMethodsResponse {
+    /// Latest version of the API
+    pub version: u32,
+    pub methods: Vec<RpcMethod>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposedMoveArgs {
+    pub action: u8,
+    /// Optional signature over [`move_digest`](crate::block::move_digest)
+    /// for the current game, attributing the move to the signer
+    #[serde_as(as = "Option<Hex0xBytes>")]
+    // Write-only, which also keeps schemars from serializing the default through
+    // `Hex0xBytes` in a module where `Result` is jsonrpc's
+    #[schemars(with = "Option<String>", skip_serializing)]
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
+    /// Nonce ground for the move's proof of work, see
+    /// [`work_hash`](crate::block::work_hash), if the chain requires one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work: Option<u64>,
+    /// Most the signer agreed to pay for the move, covered by the signature. The
+    /// move pays only the base fee if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposedMoveResponse {
+    pub success: bool,
+    /// Id of the block carrying the move, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+    /// Board as it will look once the move is accepted
+    pub predicted: GetBoardResponse,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct BatchProposeMovesArgs {
+    pub moves: Vec<ProposedMoveArgs>,
+}
+
+/// Outcome of a single move of a batch.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct BatchMoveResult {
+    pub action: u8,
+    pub queued: bool,
+    /// Why the move was not queued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub block_id: Option<ids::Id>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predicted: Option<GetBoardResponse>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct BatchProposeMovesResponse {
+    pub queued: usize,
+    pub results: Vec<BatchMoveResult>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct SimulateMoveArgs {
+    pub action: u8,
+    #[serde_as(as = "Option<Hex0xBytes>")]
+    // Write-only, which also keeps schemars from serializing the default through
+    // `Hex0xBytes` in a module where `Result` is jsonrpc's
+    #[schemars(with = "Option<String>", skip_serializing)]
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
+    /// Game the move is meant for, defaults to the current game
+    #[serde(default)]
+    pub game: Option<usize>,
+    /// Nonce ground for the move's proof of work, if the chain requires one
+    #[serde(default)]
+    pub work: Option<u64>,
+    /// Most the signer agreed to pay for the move, covered by the signature
+    #[serde(default)]
+    pub max_fee: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct SimulateMoveResponse {
+    pub valid: bool,
+    /// Why the move would be rejected, if it is not valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Board as it would look once the move is accepted, if it is valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predicted: Option<GetBoardResponse>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBoardArgs {
+    pub id: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBoardResponse {
+    /// Packed board, two bits per cell
+    pub board: u32,
+    /// Board rows of "X", "O" or "" (empty)
+    pub grid: [[String; 3]; 3],
+}
+
+impl GetBoardResponse {
+    #[must_use]
+    pub fn new(board: u32) -> Self {
+        Self {
+            board,
+            grid: game::grid(board).map(|row| row.map(String::from)),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetWinnerArgs {
+    pub req: usize,
+}
+
+/// How a finished game ended.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultKind {
+    Win,
+    Draw,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetWinnerResponse {
+    /// Whether the game has finished
+    pub found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ResultKind>,
+    /// Winning player (1 or 2), absent for draws
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player: Option<u8>,
+    /// Height of the block whose move ended the game
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+}
+
+impl From<Option<GameResult>> for GetWinnerResponse {
+    fn from(result: Option<GameResult>) -> Self {
+        match result {
+            Some(result) => Self {
+                found: true,
+                kind: Some(if result.winner.is_some() {
+                    ResultKind::Win
+                } else {
+                    ResultKind::Draw
+                }),
+                player: result.winner,
+                height: Some(result.height),
+            },
+            None => Self {
+                found: false,
+                kind: None,
+                player: None,
+                height: None,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBlockByHeightArgs {
+    pub height: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBlockByHeightResponse {
+    #[schemars(with = "String")]
+    pub id: ids::Id,
+    pub block: Block,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct StreamBlocksArgs {
+    pub from_height: u64,
+    /// Defaults to (and is capped at) [`MAX_BLOCKS_CHUNK`]
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct StreamBlocksResponse {
+    pub blocks: Vec<GetBlockByHeightResponse>,
+    /// Height to pass as `from_height` to fetch the next chunk
+    pub next_height: u64,
+    /// Height of the last accepted block
+    pub height: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBoardAtHeightArgs {
+    pub height: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBoardAtBlockArgs {
+    #[schemars(with = "String")]
+    pub id: ids::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBlockEventsArgs {
+    #[schemars(with = "String")]
+    pub id: ids::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBlockEventsResponse {
+    #[schemars(with = "String")]
+    pub id: ids::Id,
+    pub events: Vec<BlockEvent>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetMoveHistoryArgs {
+    pub game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetMoveHistoryResponse {
+    pub moves: Vec<MoveRecord>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct WhoseTurnArgs {
+    pub game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct WhoseTurnResponse {
+    pub game: usize,
+    pub player: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetLegalMovesArgs {
+    pub game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct LegalMove {
+    pub row: u8,
+    pub col: u8,
+    /// Packed action byte to submit via `proposeMove`
+    pub action: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetLegalMovesResponse {
+    pub game: usize,
+    pub player: u8,
+    pub moves: Vec<LegalMove>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetGameStatusArgs {
+    pub game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetGameStatusResponse {
+    pub game: usize,
+    #[serde(flatten)]
+    pub status: GameStatus,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ExportGameArgs {
+    pub game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ExportGameResponse {
+    pub game: usize,
+    #[schemars(with = "String")]
+    pub chain_id: ids::Id,
+    /// Result token: "1-0" if X won, "0-1" if O won, "1/2-1/2" or "*" in progress
+    pub result: String,
+    pub status: GameStatus,
+    /// Moves in order, placed in time by the heights of their blocks
+    pub moves: Vec<MoveRecord>,
+    /// The game as text, see [`game::notation`]
+    pub notation: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetWinnersArgs {
+    #[serde(default)]
+    pub offset: usize,
+    /// Defaults to (and is capped at) [`MAX_WINNERS_PAGE`]
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct WinnerEntry {
+    pub game: usize,
+    #[serde(flatten)]
+    pub result: GameResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetWinnersResponse {
+    /// Total number of finished games
+    pub total: usize,
+    pub results: Vec<WinnerEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetPlayerStatsArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetPlayerStatsResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    #[serde(flatten)]
+    pub stats: PlayerStats,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTransferArgs {
+    pub transfer: Transfer,
+    /// Signature over [`transfer_digest`](crate::block::transfer_digest), by the sender
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTransferResponse {
+    /// Id of the block carrying the transfer, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct SeekGameArgs {
+    pub seek: Seek,
+    /// Signature over [`seek_digest`](crate::block::seek_digest), by the seeker
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct SeekGameResponse {
+    /// Id of the block carrying the seek, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTournamentActionArgs {
+    pub action: TournamentAction,
+    /// Signature over [`tournament_digest`](crate::block::tournament_digest), by the
+    /// address taking the action
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTournamentActionResponse {
+    /// Id of the block carrying the action, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeSeriesActionArgs {
+    pub action: SeriesAction,
+    /// Signature over [`series_digest`](crate::block::series_digest), by the address
+    /// taking the action
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeSeriesActionResponse {
+    /// Id of the block carrying the action, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTeamActionArgs {
+    pub action: TeamAction,
+    /// Signature over [`team_digest`](crate::block::team_digest), by the address
+    /// taking the action
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct ProposeTeamActionResponse {
+    /// Id of the block carrying the action, to poll for its acceptance
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    pub height: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBalanceArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetBalanceResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    pub balance: u64,
+    /// Nonce the address's next transfer must carry
+    pub nonce: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetNonceArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetNonceResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    /// Nonce the address's next transfer must carry
+    pub nonce: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetAccountGamesArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetAccountGamesResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    /// Seats the address took, oldest first. The last may be in the game being played
+    pub games: Vec<AccountGame>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetRatingArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetRatingResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    pub rating: u32,
+    /// Number of finished games the rating accounts for
+    pub games_rated: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetRatingHistoryArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetRatingHistoryResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    /// One change per rated game, oldest first
+    pub history: Vec<RatingChange>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetLeaderboardArgs {
+    #[serde(default)]
+    pub offset: usize,
+    /// Defaults to (and is capped at) [`MAX_LEADERBOARD_PAGE`]
+    pub limit: Option<usize>,
+    /// Defaults to ranking by rating
+    #[serde(default)]
+    pub order: LeaderboardOrder,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct LeaderboardEntry {
+    /// Position on the leaderboard, 1 for the top address
+    pub rank: usize,
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    pub rating: u32,
+    #[serde(flatten)]
+    pub stats: PlayerStats,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetLeaderboardResponse {
+    pub order: LeaderboardOrder,
+    /// Total number of addresses ranked
+    pub total: usize,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetQueuePositionArgs {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetQueuePositionResponse {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    /// Number of seekers waiting ahead of the address, unset if it isn't waiting
+    pub position: Option<usize>,
+    /// Number of seekers waiting
+    pub queue_length: usize,
+    /// Games the address was paired into, from the one being played on
+    pub games: Vec<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetTournamentArgs {
+    pub tournament: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetTournamentResponse {
+    pub tournament: usize,
+    pub status: TournamentStatus,
+    #[serde(flatten)]
+    pub details: Tournament,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetTournamentStandingsResponse {
+    pub tournament: usize,
+    /// Number of rounds scheduled so far
+    pub rounds: usize,
+    /// Seeded players, empty while the tournament is open for registration
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetSeriesArgs {
+    pub series: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetSeriesResponse {
+    pub series: usize,
+    pub status: SeriesStatus,
+    #[serde(flatten)]
+    pub details: Series,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetTeamGameArgs {
+    pub team_game: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetTeamGameResponse {
+    pub team_game: usize,
+    pub status: TeamGameStatus,
+    #[serde(flatten)]
+    pub details: TeamGame,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct GetFeeEstimateResponse {
+    /// Fee a move proposed now would pay, on top of any game stake
+    pub fee: u64,
+    /// Fee moves pay while the mempool is shallow
+    pub base_fee: u64,
+    /// Cap congestion raises the fee to
+    pub max_fee: u64,
+    /// Blocks waiting in the mempool, which the fee scales with
+    pub pending: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct DecodedMove {
+    pub player: u8,
+    pub index: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+pub struct EncodedMove {
+    pub action: u8,
+}
+
+impl<A> Rpc for ChainService<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn ping(&self) -> BoxFuture<Result<crate::api::PingResponse>> {
+        log::debug!("ping called");
+        Box::pin(async move { Ok(crate::api::PingResponse { success: true }) })
+    }
+    fn propose_move(&self,args:ProposedMoveArgs) -> BoxFuture<Result<ProposedMoveResponse> > {
+        log::debug!("propose move called!");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let (block, board) = vm
+                .propose_block(
+                    args.action,
+                    args.signature.unwrap_or_default(),
+                    args.max_fee,
+                    args.work,
+                    request_source(),
+                )
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(ProposedMoveResponse {
+                success: true,
+                block_id: block.id(),
+                height: block.height(),
+                predicted: GetBoardResponse::new(board),
+            })
+        })
+    }
+
+    fn batch_propose_moves(
+        &self,
+        args: BatchProposeMovesArgs,
+    ) -> BoxFuture<Result<BatchProposeMovesResponse>> {
+        log::debug!("batch_propose_moves called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let actions: Vec<u8> = args.moves.iter().map(|m| m.action).collect();
+            let moves = args
+                .moves
+                .into_iter()
+                .map(|m| (m.action, m.signature.unwrap_or_default(), m.max_fee, m.work))
+                .collect();
+            let proposed = vm
+                .propose_blocks(moves, request_source())
+                .await
+                .map_err(create_jsonrpc_error)?;
+
+            let results: Vec<BatchMoveResult> = actions
+                .into_iter()
+                .zip(proposed)
+                .map(|(action, result)| match result {
+                    Ok((block, board)) => BatchMoveResult {
+                        action,
+                        queued: true,
+                        error: None,
+                        block_id: Some(block.id()),
+                        height: Some(block.height()),
+                        predicted: Some(GetBoardResponse::new(board)),
+                    },
+                    Err(e) => BatchMoveResult {
+                        action,
+                        queued: false,
+                        error: Some(e.to_string()),
+                        block_id: None,
+                        height: None,
+                        predicted: None,
+                    },
+                })
+                .collect();
+
+            Ok(BatchProposeMovesResponse {
+                queued: results.iter().filter(|r| r.queued).count(),
+                results,
+            })
+        })
+    }
+
+    fn propose_transfer(
+        &self,
+        args: ProposeTransferArgs,
+    ) -> BoxFuture<Result<ProposeTransferResponse>> {
+        log::debug!("propose_transfer called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let block = vm
+                .propose_account_action(
+                    AccountAction::Transfer(args.transfer),
+                    args.signature,
+                    request_source(),
+                )
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(ProposeTransferResponse {
+                block_id: block.id(),
+                height: block.height(),
+            })
+        })
+    }
+
+    fn seek_game(&self, args: SeekGameArgs) -> BoxFuture<Result<SeekGameResponse>> {
+        log::debug!("seek_game called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let block = vm
+                .propose_account_action(AccountAction::Seek(args.seek), args.signature, request_source())
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(SeekGameResponse {
+                block_id: block.id(),
+                height: block.height(),
+            })
+        })
+    }
+
+    fn propose_tournament_action(
+        &self,
+        args: ProposeTournamentActionArgs,
+    ) -> BoxFuture<Result<ProposeTournamentActionResponse>> {
+        log::debug!("propose_tournament_action called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let block = vm
+                .propose_account_action(
+                    AccountAction::Tournament(args.action),
+                    args.signature,
+                    request_source(),
+                )
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(ProposeTournamentActionResponse {
+                block_id: block.id(),
+                height: block.height(),
+            })
+        })
+    }
+
+    fn propose_series_action(
+        &self,
+        args: ProposeSeriesActionArgs,
+    ) -> BoxFuture<Result<ProposeSeriesActionResponse>> {
+        log::debug!("propose_series_action called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let block = vm
+                .propose_account_action(
+                    AccountAction::Series(args.action),
+                    args.signature,
+                    request_source(),
+                )
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(ProposeSeriesActionResponse {
+                block_id: block.id(),
+                height: block.height(),
+            })
+        })
+    }
+
+    fn propose_team_action(
+        &self,
+        args: ProposeTeamActionArgs,
+    ) -> BoxFuture<Result<ProposeTeamActionResponse>> {
+        log::debug!("propose_team_action called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let block = vm
+                .propose_account_action(
+                    AccountAction::Team(args.action),
+                    args.signature,
+                    request_source(),
+                )
+                .await
+                .map_err(create_jsonrpc_error)?;
+            Ok(ProposeTeamActionResponse {
+                block_id: block.id(),
+                height: block.height(),
+            })
+        })
+    }
+
+    fn simulate_move(&self, args: SimulateMoveArgs) -> BoxFuture<Result<SimulateMoveResponse>> {
+        log::debug!("simulate_move called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let curr_game = state.get_curr_game_index().await;
+                let verdict = match args.game {
+                    Some(game) if game < curr_game => Err(GameError::GameOver {
+                        game,
+                        current: curr_game,
+                    }
+                    .into()),
+                    Some(game) if game > curr_game => Err(GameError::UnknownGame(game).into()),
+                    _ => {
+                        // Simulated at the fee a move proposed now would pay
+                        let fee = state.congestion_fee(vm.mempool.read().await.len());
+                        let signature = args.signature.unwrap_or_default();
+                        let (max_fee, work) = (args.max_fee, args.work);
+                        state
+                            .validate_move(args.action, &signature, Some(fee), max_fee, work)
+                            .await
+                    }
+                };
+
+                return Ok(match verdict {
+                    Ok(()) => {
+                        let (player, index) = game::decode_action(args.action);
+                        let board = game::place(state.get_curr_game().await, player, index);
+                        SimulateMoveResponse {
+                            valid: true,
+                            reason: None,
+                            predicted: Some(GetBoardResponse::new(board)),
+                        }
+                    }
+                    Err(e) => SimulateMoveResponse {
+                        valid: false,
+                        reason: Some(e.to_string()),
+                        predicted: None,
+                    },
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_board(&self) -> BoxFuture<Result<GetBoardResponse> > {
+        log::debug!("get_board called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let curr_board = vm.latest.load().board;
+
+            Ok(GetBoardResponse::new(curr_board))
+        })
+    }
+
+    fn get_winner(&self, args: GetWinnerArgs) -> BoxFuture<Result<GetWinnerResponse>> {
+        log::debug!("get_winner called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let result = vm.latest.load().winners.get(args.req).copied();
+
+            Ok(GetWinnerResponse::from(result))
+        })
+    }
+
+    fn stream_blocks(&self, args: StreamBlocksArgs) -> BoxFuture<Result<StreamBlocksResponse>> {
+        log::debug!("stream_blocks called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let limit = args.limit.unwrap_or(MAX_BLOCKS_CHUNK).min(MAX_BLOCKS_CHUNK);
+                let blocks: Vec<GetBlockByHeightResponse> = state
+                    .get_blocks_from(args.from_height, limit)
+                    .await
+                    .into_iter()
+                    .map(|block| GetBlockByHeightResponse {
+                        id: block.id(),
+                        block,
+                    })
+                    .collect();
+
+                return Ok(StreamBlocksResponse {
+                    next_height: blocks
+                        .last()
+                        .map_or(args.from_height, |last| last.block.height() + 1),
+                    height: state.get_height().await,
+                    blocks,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_block_by_height(
+        &self,
+        args: GetBlockByHeightArgs,
+    ) -> BoxFuture<Result<GetBlockByHeightResponse>> {
+        log::debug!("get_block_by_height called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let block = state
+                    .get_block_by_height(args.height)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetBlockByHeightResponse {
+                    id: block.id(),
+                    block,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_board_at_height(
+        &self,
+        args: GetBoardAtHeightArgs,
+    ) -> BoxFuture<Result<GetBoardResponse>> {
+        log::debug!("get_board_at_height called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let board = state
+                    .get_board_at_height(args.height)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetBoardResponse::new(board));
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_board_at_block(&self, args: GetBoardAtBlockArgs) -> BoxFuture<Result<GetBoardResponse>> {
+        log::debug!("get_board_at_block called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let board = state
+                    .get_board_at_block(&args.id)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetBoardResponse::new(board));
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_block_events(
+        &self,
+        args: GetBlockEventsArgs,
+    ) -> BoxFuture<Result<GetBlockEventsResponse>> {
+        log::debug!("get_block_events called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let events = state
+                    .get_block_events(&args.id)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetBlockEventsResponse {
+                    id: args.id,
+                    events,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_move_history(
+        &self,
+        args: GetMoveHistoryArgs,
+    ) -> BoxFuture<Result<GetMoveHistoryResponse>> {
+        log::debug!("get_move_history called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let moves = state
+                    .get_move_history(args.game)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetMoveHistoryResponse { moves });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn whose_turn(&self, args: WhoseTurnArgs) -> BoxFuture<Result<WhoseTurnResponse>> {
+        log::debug!("whose_turn called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let curr_game = state.get_curr_game_index().await;
+                check_game_in_progress(args.game, curr_game)?;
+
+                let player = state.get_next_player().await;
+                return Ok(WhoseTurnResponse {
+                    game: curr_game,
+                    player,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_legal_moves(
+        &self,
+        args: GetLegalMovesArgs,
+    ) -> BoxFuture<Result<GetLegalMovesResponse>> {
+        log::debug!("get_legal_moves called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let curr_game = state.get_curr_game_index().await;
+                check_game_in_progress(args.game, curr_game)?;
+
+                let player = state.get_next_player().await;
+                let board = state.get_curr_game().await;
+                let moves = game::empty_cells(board)
+                    .into_iter()
+                    .map(|index| {
+                        let (row, col) = game::position(index);
+                        LegalMove {
+                            row,
+                            col,
+                            action: game::encode_action(player, index),
+                        }
+                    })
+                    .collect();
+
+                return Ok(GetLegalMovesResponse {
+                    game: curr_game,
+                    player,
+                    moves,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_game_status(
+        &self,
+        args: GetGameStatusArgs,
+    ) -> BoxFuture<Result<GetGameStatusResponse>> {
+        log::debug!("get_game_status called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let status = state
+                    .get_game_status(args.game)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+
+                return Ok(GetGameStatusResponse {
+                    game: args.game,
+                    status,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_winners(&self, args: GetWinnersArgs) -> BoxFuture<Result<GetWinnersResponse>> {
+        log::debug!("get_winners called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let limit = args.limit.unwrap_or(MAX_WINNERS_PAGE).min(MAX_WINNERS_PAGE);
+                let (page, total) = state.get_winners(args.offset, limit).await;
+
+                let results = page
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, result)| WinnerEntry {
+                        game: args.offset + i,
+                        result,
+                    })
+                    .collect();
+                return Ok(GetWinnersResponse { total, results });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_player_stats(
+        &self,
+        args: GetPlayerStatsArgs,
+    ) -> BoxFuture<Result<GetPlayerStatsResponse>> {
+        log::debug!("get_player_stats called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let stats = state.get_player_stats(&args.address).await;
+                return Ok(GetPlayerStatsResponse {
+                    address: args.address,
+                    stats,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_balance(&self, args: GetBalanceArgs) -> BoxFuture<Result<GetBalanceResponse>> {
+        log::debug!("get_balance called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return Ok(GetBalanceResponse {
+                    balance: state.get_balance(&args.address).await,
+                    nonce: state.get_nonce(&args.address).await,
+                    address: args.address,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_nonce(&self, args: GetNonceArgs) -> BoxFuture<Result<GetNonceResponse>> {
+        log::debug!("get_nonce called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return Ok(GetNonceResponse {
+                    nonce: state.get_nonce(&args.address).await,
+                    address: args.address,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_account_games(
+        &self,
+        args: GetAccountGamesArgs,
+    ) -> BoxFuture<Result<GetAccountGamesResponse>> {
+        log::debug!("get_account_games called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return Ok(GetAccountGamesResponse {
+                    games: state.get_account_games(&args.address).await,
+                    address: args.address,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_rating(&self, args: GetRatingArgs) -> BoxFuture<Result<GetRatingResponse>> {
+        log::debug!("get_rating called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let games_rated = state.get_rating_history(&args.address).await.len();
+                return Ok(GetRatingResponse {
+                    rating: state.get_rating(&args.address).await,
+                    games_rated,
+                    address: args.address,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_rating_history(
+        &self,
+        args: GetRatingHistoryArgs,
+    ) -> BoxFuture<Result<GetRatingHistoryResponse>> {
+        log::debug!("get_rating_history called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return Ok(GetRatingHistoryResponse {
+                    history: state.get_rating_history(&args.address).await,
+                    address: args.address,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_leaderboard(
+        &self,
+        args: GetLeaderboardArgs,
+    ) -> BoxFuture<Result<GetLeaderboardResponse>> {
+        log::debug!("get_leaderboard called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let limit = args.limit.unwrap_or(MAX_LEADERBOARD_PAGE).min(MAX_LEADERBOARD_PAGE);
+                let (page, total) = state.get_leaderboard(args.order, args.offset, limit).await;
+
+                let mut entries = Vec::with_capacity(page.len());
+                for (i, address) in page.into_iter().enumerate() {
+                    entries.push(LeaderboardEntry {
+                        rank: args.offset + i + 1,
+                        rating: state.get_rating(&address).await,
+                        stats: state.get_player_stats(&address).await,
+                        address,
+                    });
+                }
+                return Ok(GetLeaderboardResponse {
+                    order: args.order,
+                    total,
+                    entries,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
 
-    /// Fetches the current game state
-    #[rpc(name="getBoard", alias("tic_tac_toe.getBoard"))]
-    fn get_board(&self) -> BoxFuture<Result<GetBoardResponse>>;
+    fn get_queue_position(
+        &self,
+        args: GetQueuePositionArgs,
+    ) -> BoxFuture<Result<GetQueuePositionResponse>> {
+        log::debug!("get_queue_position called");
+        let vm = self.vm.clone();
 
-    /// Fetches the winner of the ith game
-    #[rpc(name="getWinner", alias("tic_tac_toe.getWinner"))]
-    fn get_winner(&self, args: GetWinnerArgs) -> BoxFuture<Result<GetWinnerResponse>>;
-}
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let curr = state.get_curr_game_index().await;
+                return Ok(GetQueuePositionResponse {
+                    position: state.get_seek_position(&args.address).await,
+                    queue_length: state.get_seekers_len().await,
+                    games: state.get_matched_games(&args.address, curr).await,
+                    address: args.address,
+                });
+            }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct ProposedMoveArgs {
-    pub action: u8
-}
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct ProposedMoveResponse {
-    pub success: bool,
-}
+    fn get_tournament(&self, args: GetTournamentArgs) -> BoxFuture<Result<GetTournamentResponse>> {
+        log::debug!("get_tournament called");
+        let vm = self.vm.clone();
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct GetBoardArgs {
-    pub id: usize,
-}
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let details = state.get_tournament(args.tournament).await.ok_or_else(|| {
+                    create_jsonrpc_error(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("tournament {} not found", args.tournament),
+                    ))
+                })?;
+                return Ok(GetTournamentResponse {
+                    tournament: args.tournament,
+                    status: details.status(),
+                    details,
+                });
+            }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct GetBoardResponse {
-    pub board: u32,
-}
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct GetWinnerArgs {
-    pub req: usize
-}
+    fn get_tournament_standings(
+        &self,
+        args: GetTournamentArgs,
+    ) -> BoxFuture<Result<GetTournamentStandingsResponse>> {
+        log::debug!("get_tournament_standings called");
+        let vm = self.vm.clone();
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct GetWinnerResponse {
-    pub win: u32,
-}
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let details = state.get_tournament(args.tournament).await.ok_or_else(|| {
+                    create_jsonrpc_error(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("tournament {} not found", args.tournament),
+                    ))
+                })?;
+                return Ok(GetTournamentStandingsResponse {
+                    tournament: args.tournament,
+                    rounds: details.rounds.len(),
+                    standings: details.standings(),
+                });
+            }
 
-impl<A> Rpc for ChainService<A>
-where
-    A: Send + Sync + Clone + 'static,
-{
-    fn ping(&self) -> BoxFuture<Result<crate::api::PingResponse>> {
-        log::debug!("ping called");
-        Box::pin(async move { Ok(crate::api::PingResponse { success: true }) })
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
     }
-    fn propose_move(&self,args:ProposedMoveArgs) -> BoxFuture<Result<ProposedMoveResponse> > {
-        log::debug!("propose move called!");
+
+    fn get_series(&self, args: GetSeriesArgs) -> BoxFuture<Result<GetSeriesResponse>> {
+        log::debug!("get_series called");
         let vm = self.vm.clone();
 
         Box::pin(async move {
-            vm.propose_block(args.action)
-                .await
-                .map_err(create_jsonrpc_error)?;
-            Ok(ProposedMoveResponse { success: true })
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let details = state.get_series(args.series).await.ok_or_else(|| {
+                    create_jsonrpc_error(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("series {} not found", args.series),
+                    ))
+                })?;
+                return Ok(GetSeriesResponse {
+                    series: args.series,
+                    status: details.status(),
+                    details,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
         })
     }
 
-    fn get_board(&self) -> BoxFuture<Result<GetBoardResponse> > {
-        log::debug!("propose move called!");
+    fn get_team_game(&self, args: GetTeamGameArgs) -> BoxFuture<Result<GetTeamGameResponse>> {
+        log::debug!("get_team_game called");
         let vm = self.vm.clone();
 
         Box::pin(async move {
             let vm_state = vm.state.read().await;
             if let Some(state) = &vm_state.state {
-                let curr_board = state
-                    .get_curr_game()
-                    .await
-                    .map_err(create_jsonrpc_error)?;
+                let details = state.get_team_game(args.team_game).await.ok_or_else(|| {
+                    create_jsonrpc_error(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("team game {} not found", args.team_game),
+                    ))
+                })?;
+                return Ok(GetTeamGameResponse {
+                    team_game: args.team_game,
+                    status: details.status(),
+                    details,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
 
-                return Ok(GetBoardResponse {board:curr_board });
+    fn get_fee_estimate(&self) -> BoxFuture<Result<GetFeeEstimateResponse>> {
+        log::debug!("get_fee_estimate called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let pending = vm.mempool.read().await.len();
+                return Ok(GetFeeEstimateResponse {
+                    fee: state.congestion_fee(pending),
+                    base_fee: state.move_fee,
+                    max_fee: state.max_move_fee.max(state.move_fee),
+                    pending,
+                });
             }
 
             Err(Error {
@@ -104,19 +2135,155 @@ where
         })
     }
 
-    fn get_winner(&self,args:GetWinnerArgs) -> BoxFuture<Result<GetWinnerResponse> > {
-        log::debug!("propose move called!");
+    fn export_game(&self, args: ExportGameArgs) -> BoxFuture<Result<ExportGameResponse>> {
+        log::debug!("export_game called");
         let vm = self.vm.clone();
 
         Box::pin(async move {
             let vm_state = vm.state.read().await;
             if let Some(state) = &vm_state.state {
-                let curr_board = state
-                    .get_winner(args.req)
+                let (moves, status) = state
+                    .get_game_record(args.game)
+                    .await
+                    .map_err(create_jsonrpc_error)?;
+                let chain_id = vm_state
+                    .ctx
+                    .as_ref()
+                    .map_or_else(ids::Id::empty, |ctx| ctx.chain_id);
+
+                let mut tags = vec![
+                    ("Chain", chain_id.to_string()),
+                    ("Game", args.game.to_string()),
+                ];
+                for (tag, player) in [("X", 1), ("O", 2)] {
+                    if let Some(address) = seat_of(&moves, player) {
+                        tags.push((tag, address.to_string()));
+                    }
+                }
+                if let Some(first) = moves.first() {
+                    tags.push(("StartHeight", first.height.to_string()));
+                }
+                if let GameStatus::Won { at_height, .. } | GameStatus::Drawn { at_height } = status
+                {
+                    tags.push(("EndHeight", at_height.to_string()));
+                }
+                let cells: Vec<u8> = moves.iter().map(|m| m.cell).collect();
+
+                return Ok(ExportGameResponse {
+                    game: args.game,
+                    chain_id,
+                    result: String::from(game::notation::result(&status)),
+                    notation: game::notation::write(&tags, &cells, &status),
+                    status,
+                    moves,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn encode_move(&self, args: DecodedMove) -> BoxFuture<Result<EncodedMove>> {
+        log::debug!("encode_move called");
+
+        Box::pin(async move {
+            check_move(args.player, args.index)?;
+            Ok(EncodedMove {
+                action: game::encode_action(args.player, args.index),
+            })
+        })
+    }
+
+    fn decode_move(&self, args: EncodedMove) -> BoxFuture<Result<DecodedMove>> {
+        log::debug!("decode_move called");
+
+        Box::pin(async move {
+            if args.action & !0b0001_1111 != 0 {
+                return Err(Error::invalid_params(format!(
+                    "action {:#010b} sets bits outside the player and cell fields",
+                    args.action
+                )));
+            }
+
+            let (player, index) = game::decode_action(args.action);
+            check_move(player, index)?;
+            Ok(DecodedMove { player, index })
+        })
+    }
+
+    fn wait_for_move(&self, args: WaitForMoveArgs) -> BoxFuture<Result<WaitForMoveResponse>> {
+        log::debug!("wait_for_move called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            // Don't hold the Vm lock while waiting
+            let state = vm.state.read().await.state.clone();
+            if let Some(state) = state {
+                let timeout = Duration::from_millis(args.timeout_ms.min(MAX_WAIT_FOR_MOVE_MS));
+                let next_move = state
+                    .wait_for_move(args.game, args.last_seen_height, timeout)
                     .await
                     .map_err(create_jsonrpc_error)?;
 
-                return Ok(GetBoardResponse {board:curr_board });
+                return Ok(WaitForMoveResponse {
+                    game: args.game,
+                    timed_out: next_move.is_none(),
+                    next_move,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_node_info(&self) -> BoxFuture<Result<GetNodeInfoResponse>> {
+        log::debug!("get_node_info called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                return Ok(GetNodeInfoResponse {
+                    version: vm_state.version.to_string(),
+                    commit: String::from(BUILD_COMMIT),
+                    chain_id: vm_state
+                        .ctx
+                        .as_ref()
+                        .map_or_else(ids::Id::empty, |ctx| ctx.chain_id),
+                    height: state.get_height().await,
+                    genesis_hash: vm_state.genesis_hash,
+                    move_fee: vm_state.genesis.move_fee,
+                    max_move_fee: vm_state.genesis.max_move_fee,
+                    move_work: vm_state.genesis.move_work,
+                    game_stake: vm_state.genesis.game_stake,
+                });
+            }
+
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: String::from("no state manager found"),
+                data: None,
+            })
+        })
+    }
+
+    fn get_pending_moves(&self) -> BoxFuture<Result<GetPendingMovesResponse>> {
+        log::debug!("get_pending_moves called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move {
+            let vm_state = vm.state.read().await;
+            if let Some(state) = &vm_state.state {
+                let mempool = vm.mempool.read().await;
+                return Ok(pending_moves(state, &mempool).await);
             }
 
             Err(Error {
@@ -126,11 +2293,130 @@ where
             })
         })
     }
+
+    fn health(&self) -> BoxFuture<Result<Health>> {
+        log::debug!("health called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move { vm.health().await.map_err(create_jsonrpc_error) })
+    }
+
+    fn get_latency_stats(&self) -> BoxFuture<Result<LatencyStats>> {
+        log::debug!("get_latency_stats called");
+        let vm = self.vm.clone();
+
+        Box::pin(async move { Ok(vm.metrics.latency_stats()) })
+    }
+
+    fn rpc_methods(&self) -> BoxFuture<Result<RpcMethodsResponse>> {
+        log::debug!("rpc_methods called");
+
+        Box::pin(async move {
+            let methods = METHODS
+                .iter()
+                .map(|name| RpcMethod {
+                    name: (*name).to_string(),
+                    version: 1,
+                    aliases: vec![
+                        format!("{LEGACY_NAMESPACE}.{name}"),
+                        format!("{V1_NAMESPACE}.{name}"),
+                    ],
+                })
+                .collect();
+
+            Ok(RpcMethodsResponse {
+                version: 1,
+                methods,
+            })
+        })
+    }
+}
+
+impl<A> SubscriptionRpc for ChainService<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    type Metadata = Arc<Session>;
+
+    fn subscribe_new_heads(&self, _meta: Self::Metadata, subscriber: Subscriber<NewHead>) {
+        log::debug!("subscribe_new_heads called");
+        let id = self.subscriptions.next_id();
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            let mut new_heads = self
+                .subscriptions
+                .new_heads
+                .lock()
+                .expect("subscriptions lock poisoned");
+            new_heads.insert(id, sink);
+        }
+    }
+
+    fn unsubscribe_new_heads(
+        &self,
+        _meta: Option<Self::Metadata>,
+        subscription: SubscriptionId,
+    ) -> Result<bool> {
+        log::debug!("unsubscribe_new_heads called");
+        let mut new_heads = self
+            .subscriptions
+            .new_heads
+            .lock()
+            .expect("subscriptions lock poisoned");
+        if new_heads.remove(&subscription).is_some() {
+            return Ok(true);
+        }
+
+        Err(Error::invalid_params(format!(
+            "subscription {subscription:?} does not exist"
+        )))
+    }
+
+    fn subscribe_board_updated(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<BoardUpdate>,
+        game: usize,
+    ) {
+        log::debug!("subscribe_board_updated called");
+        let id = self.subscriptions.next_id();
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            let mut board_updated = self
+                .subscriptions
+                .board_updated
+                .lock()
+                .expect("subscriptions lock poisoned");
+            board_updated.insert(id, (game, sink));
+        }
+    }
+
+    fn unsubscribe_board_updated(
+        &self,
+        _meta: Option<Self::Metadata>,
+        subscription: SubscriptionId,
+    ) -> Result<bool> {
+        log::debug!("unsubscribe_board_updated called");
+        let mut board_updated = self
+            .subscriptions
+            .board_updated
+            .lock()
+            .expect("subscriptions lock poisoned");
+        if board_updated.remove(&subscription).is_some() {
+            return Ok(true);
+        }
+
+        Err(Error::invalid_params(format!(
+            "subscription {subscription:?} does not exist"
+        )))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ChainHandler<T> {
     pub handler: IoHandler,
+    /// Records how long each call takes, if set
+    pub metrics: Option<Arc<Metrics>>,
+    /// Calls taking longer are logged along with their parameters, if set
+    pub slow_call_threshold: Option<Duration>,
     _marker: PhantomData<T>,
 }
 
@@ -140,9 +2426,57 @@ impl<T: Rpc> ChainHandler<T> {
         handler.extend_with(Rpc::to_delegate(service));
         Self {
             handler,
+            metrics: None,
+            slow_call_threshold: None,
             _marker: PhantomData,
         }
     }
+
+    /// Records the duration of each call, by method.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Logs every call taking longer than `threshold`, along with its parameters.
+    #[must_use]
+    pub fn with_slow_call_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_call_threshold = Some(threshold);
+        self
+    }
+}
+
+/// Serves the chain RPC and its subscriptions over WebSocket on the given address,
+/// on the current runtime. Requests dispatch to the same [`Rpc`] methods as
+/// [`ChainHandler`]. The server stops once the returned handle is closed or dropped.
+/// # Errors
+/// Fails if the server can't listen on the address.
+pub fn serve_ws<A>(
+    service: ChainService<A>,
+    accepted: broadcast::Receiver<AcceptedMove>,
+    addr: &SocketAddr,
+) -> io::Result<jsonrpc_ws_server::Server>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    tokio::spawn(service.subscriptions.clone().forward(accepted));
+
+    let mut handler = PubSubHandler::new(MetaIoHandler::default());
+    handler.extend_with(Rpc::to_delegate(service.clone()));
+    handler.extend_with(SubscriptionRpc::to_delegate(service));
+
+    jsonrpc_ws_server::ServerBuilder::with_meta_extractor(handler, |context: &RequestContext| {
+        Arc::new(Session::new(context.sender()))
+    })
+    .event_loop_executor(tokio::runtime::Handle::current())
+    .start(addr)
+    .map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("failed to serve WebSocket RPC on {addr}: {e}"),
+        )
+    })
 }
 
 #[tonic::async_trait]
@@ -153,33 +2487,243 @@ where
     async fn request(
         &self,
         req: &Bytes,
-        _headers: &[Element],
+        headers: &[Element],
     ) -> std::io::Result<(Bytes, Vec<Element>)> {
-        match self.handler.handle_request(&de_request(req)?).await {
-            Some(resp) => Ok((Bytes::from(resp), Vec::new())),
+        let request_id = request_id(headers);
+        let method_call = de_method_call(req).map_err(|e| {
+            io::Error::new(e.kind(), format!("request {request_id}: {e}"))
+        })?;
+        log::debug!("request {request_id}: {} called", method_call.method);
+        let timer = self.metrics.as_ref().map(|metrics| {
+            metrics
+                .rpc_duration
+                .with_label_values(&[method_label(&method_call.method)])
+                .start_timer()
+        });
+        let span = tracing::info_span!(
+            "rpc",
+            method = %method_call.method,
+            request_id = %request_id
+        );
+        let started = Instant::now();
+        let handled = self
+            .handler
+            .handle_request(&ser_method_call(&method_call)?)
+            .instrument(span);
+        let resp = SOURCE.scope(forwarded_source(headers), handled).await;
+        if let Some(timer) = timer {
+            timer.observe_duration();
+        }
+        let elapsed = started.elapsed();
+        if self.slow_call_threshold.map_or(false, |threshold| elapsed > threshold) {
+            log::warn!(
+                "request {request_id}: slow call to {} took {}ms with params {}",
+                method_call.method,
+                elapsed.as_millis(),
+                params_summary(&method_call.params)
+            );
+        }
+
+        match resp {
+            Some(resp) => {
+                log::debug!("request {request_id}: {} handled", method_call.method);
+                Ok((
+                    Bytes::from(resp),
+                    vec![Element {
+                        key: String::from(REQUEST_ID_HEADER),
+                        values: vec![request_id],
+                    }],
+                ))
+            }
             None => Err(io::Error::new(
                 io::ErrorKind::Other,
-                "failed to handle request",
+                format!("request {request_id}: failed to handle request"),
             )),
         }
     }
 }
 
-fn create_jsonrpc_error<E: Borrow<std::io::Error>>(e: E) -> Error {
+/// Lists the pending moves in the order their blocks will be built, along with
+/// eviction counts.
+pub(crate) async fn pending_moves(state: &State, mempool: &Mempool) -> GetPendingMovesResponse {
+    let game = state.get_curr_game_index().await;
+    let board = state.get_curr_game().await;
+
+    let (keys, _) = mempool.keys(game, board);
+    let moves = mempool
+        .iter()
+        .filter(|pending| pending.block.is_move())
+        .zip(keys)
+        .map(|(pending, key)| PendingMoveEntry {
+            block_id: pending.block.id(),
+            game: key.game,
+            turn: key.turn,
+            player: key.player,
+            cell: key.cell,
+            age_ms: u64::try_from(pending.received.elapsed().as_millis()).unwrap_or(u64::MAX),
+            address: pending.block.signer(key.game).ok().flatten(),
+        })
+        .collect();
+
+    GetPendingMovesResponse {
+        moves,
+        evicted: mempool.evicted(),
+    }
+}
+
+/// Returns the parameters of a call as logged, cut short so huge ones can't flood the logs.
+fn params_summary(params: &Params) -> String {
+    let mut params = serde_json::to_string(params).unwrap_or_default();
+    if params.len() > MAX_LOGGED_PARAMS_LEN {
+        let mut end = MAX_LOGGED_PARAMS_LEN;
+        while !params.is_char_boundary(end) {
+            end -= 1;
+        }
+        params.truncate(end);
+        params.push_str("...");
+    }
+    params
+}
+
+/// Returns the method a call is recorded under, the same for every alias of a method.
+/// Methods that don't exist are all recorded as "unknown", so clients can't add labels.
+fn method_label(method: &str) -> &str {
+    let name = method.rsplit('.').next().unwrap_or(method);
+    METHODS
+        .iter()
+        .find(|known| **known == name)
+        .copied()
+        .unwrap_or("unknown")
+}
+
+/// JSON-RPC error code for moves that don't describe a playable move.
+pub const INVALID_MOVE: i64 = -32001;
+/// JSON-RPC error code for moves onto a cell that is already marked.
+pub const CELL_OCCUPIED: i64 = -32002;
+/// JSON-RPC error code for moves made out of turn.
+pub const WRONG_TURN: i64 = -32003;
+/// JSON-RPC error code for requests about a game that has ended.
+pub const GAME_OVER: i64 = -32004;
+/// JSON-RPC error code for requests about a game that hasn't started.
+pub const UNKNOWN_GAME: i64 = -32005;
+/// JSON-RPC error code for requests about a block that doesn't exist.
+pub const UNKNOWN_BLOCK: i64 = -32006;
+/// JSON-RPC error code for moves rejected by the submitter's rate limit.
+pub const RATE_LIMITED: i64 = -32007;
+
+/// Maps game and rate limit errors to their own error codes, everything else
+/// to `InternalError`.
+pub(crate) fn create_jsonrpc_error<E: Borrow<std::io::Error>>(e: E) -> Error {
     let e = e.borrow();
-    let mut error = Error::new(ErrorCode::InternalError);
+    let code = match GameError::from_io(e) {
+        Some(game_error) => ErrorCode::ServerError(game_error_code(game_error)),
+        None if RateLimited::from_io(e).is_some() => ErrorCode::ServerError(RATE_LIMITED),
+        None => ErrorCode::InternalError,
+    };
+    let mut error = Error::new(code);
     error.message = format!("{e}");
     error
 }
 
+fn game_error_code(e: &GameError) -> i64 {
+    match e {
+        GameError::InvalidMove(_) => INVALID_MOVE,
+        GameError::CellOccupied(_) => CELL_OCCUPIED,
+        GameError::WrongTurn { .. } => WRONG_TURN,
+        GameError::GameOver { .. } => GAME_OVER,
+        GameError::UnknownGame(_) => UNKNOWN_GAME,
+        GameError::UnknownBlock(_) | GameError::UnknownHeight(_) | GameError::PrunedBlock(_) => {
+            UNKNOWN_BLOCK
+        }
+    }
+}
+
+/// Rejects queries about a game other than the one currently being played.
+fn check_game_in_progress(game: usize, curr_game: usize) -> Result<()> {
+    if game < curr_game {
+        return Err(create_jsonrpc_error(io::Error::from(GameError::GameOver {
+            game,
+            current: curr_game,
+        })));
+    }
+    if game > curr_game {
+        return Err(create_jsonrpc_error(io::Error::from(GameError::UnknownGame(game))));
+    }
+    Ok(())
+}
+
+/// Rejects players other than 1 or 2 and cells outside the board.
+fn check_move(player: u8, index: u8) -> Result<()> {
+    if player != 1 && player != 2 {
+        return Err(Error::invalid_params(format!(
+            "player must be 1 or 2, got {player}"
+        )));
+    }
+    if index >= game::CELLS {
+        return Err(Error::invalid_params(format!(
+            "cell index must be below {}, got {index}",
+            game::CELLS
+        )));
+    }
+    Ok(())
+}
+
 /// Implements API services for the chain-specific handlers.
 #[derive(Clone)]
 pub struct ChainService<A> {
     pub vm: Vm<A>,
+    pub subscriptions: Subscriptions,
 }
 
 impl<A> ChainService<A> {
     pub fn new(vm: Vm<A>) -> Self {
-        Self { vm }
+        Self {
+            vm,
+            subscriptions: Subscriptions::default(),
+        }
+    }
+}
+
+/// Maps each subscription Id to its subscriber.
+type SubscriberMap<T> = Arc<Mutex<HashMap<SubscriptionId, T>>>;
+
+/// Active subscriptions of a [`ChainService`], keyed by subscription Id.
+#[derive(Clone, Default)]
+pub struct Subscriptions {
+    next_id: Arc<AtomicU64>,
+    new_heads: SubscriberMap<Sink<NewHead>>,
+    /// Subscribers of `boardUpdated`, along with the game each follows
+    board_updated: SubscriberMap<(usize, Sink<BoardUpdate>)>,
+}
+
+impl Subscriptions {
+    fn next_id(&self) -> SubscriptionId {
+        SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Forwards accepted moves to subscribers until the channel closes.
+    /// Subscribers whose connection is gone are dropped.
+    async fn forward(self, mut accepted: broadcast::Receiver<AcceptedMove>) {
+        loop {
+            let accepted = match accepted.recv().await {
+                Ok(accepted) => accepted,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("subscriptions fell behind, skipped {skipped} accepted moves");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+
+            let head = NewHead::from(&accepted);
+            let mut new_heads = self.new_heads.lock().expect("subscriptions lock poisoned");
+            new_heads.retain(|_, sink| sink.notify(Ok(head.clone())).is_ok());
+            drop(new_heads);
+
+            let update = BoardUpdate::from(&accepted);
+            let mut board_updated = self.board_updated.lock().expect("subscriptions lock poisoned");
+            board_updated.retain(|_, (game, sink)| {
+                *game != update.game || sink.notify(Ok(update.clone())).is_ok()
+            });
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+//! Streams human-readable game events as server-sent events.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/events`.
+//!
+//! Requests proxied through the node can't be held open, so each request returns
+//! the events accepted since the `Last-Event-ID` header (or waits briefly for the
+//! next one) and ends. `EventSource` clients then reconnect on their own, resuming
+//! from the last event they received.
+
+use std::{fmt::Write, io, time::Duration};
+
+use avalanche_types::{proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+
+use crate::{
+    game::{self, GameStatus},
+    state::AcceptedMove,
+    vm::Vm,
+};
+
+/// Maximum number of events returned by a single request.
+pub const MAX_EVENTS: usize = 100;
+
+/// How long a request waits for the next event when there is none to return.
+const WAIT_FOR_EVENT: Duration = Duration::from_secs(15);
+
+/// How long clients should wait before reconnecting, in milliseconds.
+const RETRY_MS: u64 = 500;
+
+/// Serves game events of the chain as a `text/event-stream`.
+#[derive(Clone)]
+pub struct EventStreamHandler<A> {
+    pub vm: Vm<A>,
+}
+
+impl<A> EventStreamHandler<A> {
+    pub fn new(vm: Vm<A>) -> Self {
+        Self { vm }
+    }
+}
+
+#[tonic::async_trait]
+impl<A> Handle for EventStreamHandler<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    async fn request(
+        &self,
+        _req: &Bytes,
+        headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        // Don't hold the Vm lock while waiting
+        let state = self.vm.state.read().await.state.clone();
+        if let Some(state) = state {
+            // Subscribe before looking at the history so no move accepted in between is missed
+            let mut accepted = state.subscribe_accepted();
+            let last_event_id = match last_event_id(headers) {
+                Some(height) => height,
+                None => state.get_height().await,
+            };
+
+            let mut moves = state.get_accepted_after(last_event_id, MAX_EVENTS).await;
+            if moves.is_empty() {
+                if let Ok(Ok(next)) = tokio::time::timeout(WAIT_FOR_EVENT, accepted.recv()).await {
+                    moves.push(next);
+                }
+            }
+
+            let mut body = format!("retry: {RETRY_MS}\n\n");
+            if moves.is_empty() {
+                // Carries the position over to the next request without dispatching an event
+                let _ = write!(body, "id: {last_event_id}\n\n");
+            }
+            for accepted in &moves {
+                write_events(&mut body, accepted);
+            }
+
+            return Ok((
+                Bytes::from(body),
+                vec![
+                    Element {
+                        key: String::from("Content-Type"),
+                        values: vec![String::from("text/event-stream")],
+                    },
+                    Element {
+                        key: String::from("Cache-Control"),
+                        values: vec![String::from("no-cache")],
+                    },
+                ],
+            ));
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "state manager not found",
+        ))
+    }
+}
+
+/// Returns the height carried by the `Last-Event-ID` header, if any.
+fn last_event_id(headers: &[Element]) -> Option<u64> {
+    headers
+        .iter()
+        .find(|header| header.key.eq_ignore_ascii_case("Last-Event-ID"))
+        .and_then(|header| header.values.first())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Appends the events describing an accepted move, identified by its block height.
+fn write_events(body: &mut String, accepted: &AcceptedMove) {
+    let id = accepted.record.height;
+    let game = accepted.game;
+    let player = accepted.record.player;
+    let symbol = game::symbol(player);
+    let (row, col) = game::position(accepted.record.cell);
+
+    let _ = write!(
+        body,
+        "id: {id}\nevent: move\ndata: Game {game}: player {player} ({symbol}) marked {}\n\n",
+        describe_cell(row, col),
+    );
+    match accepted.status {
+        GameStatus::Won { .. } => {
+            let _ = write!(
+                body,
+                "id: {id}\nevent: won\ndata: Game {game}: player {player} ({symbol}) won\n\n",
+            );
+        }
+        GameStatus::Drawn { .. } => {
+            let _ = write!(body, "id: {id}\nevent: draw\ndata: Game {game}: ended in a draw\n\n");
+        }
+        GameStatus::InProgress { .. } => {}
+    }
+}
+
+/// Describes a cell position in words, e.g. "the top-left corner".
+fn describe_cell(row: u8, col: u8) -> &'static str {
+    match (row, col) {
+        (0, 0) => "the top-left corner",
+        (0, 1) => "the top edge",
+        (0, 2) => "the top-right corner",
+        (1, 0) => "the left edge",
+        (1, 1) => "the center",
+        (1, 2) => "the right edge",
+        (2, 0) => "the bottom-left corner",
+        (2, 1) => "the bottom edge",
+        _ => "the bottom-right corner",
+    }
+}
@@ -0,0 +1,210 @@
+//! Implements the faucet RPC, crediting developers on local and test networks with
+//! play tokens, so they needn't be allocated in the genesis.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/faucet`, only if configured.
+//!
+//! Tokens are sent by a transfer signed with the faucet's key, so the address it
+//! controls must be funded at genesis. Each address may only be dripped to once
+//! per interval.
+
+use std::{
+    collections::HashMap,
+    io::{self, Error as IoError, ErrorKind},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use avalanche_types::{
+    ids, key::secp256k1::private_key, proto::http::Element, subnet::rpc::http::handle::Handle,
+};
+use bytes::Bytes;
+use jsonrpc_core::{BoxFuture, IoHandler, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{
+    block::{self, AccountAction, Transfer},
+    vm::Vm,
+};
+
+use super::{chain_handlers::create_jsonrpc_error, de_request};
+
+/// Time an address waits between drips, unless configured otherwise.
+pub const DEFAULT_DRIP_INTERVAL_SECS: u64 = 3600;
+
+/// Configures the faucet. Its key is kept in the clear, so it is only meant for
+/// networks whose tokens are worthless.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Faucet {
+    /// Hex-encoded private key of the address tokens are sent from
+    pub key: String,
+    /// Tokens sent per drip
+    pub amount: u64,
+    /// Time an address waits between drips, in seconds. Defaults to
+    /// [`DEFAULT_DRIP_INTERVAL_SECS`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_secs: Option<u64>,
+}
+
+impl Faucet {
+    /// Returns how long an address waits between drips.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.unwrap_or(DEFAULT_DRIP_INTERVAL_SECS))
+    }
+}
+
+/// Defines the faucet RPCs, all prefixed with `faucet.`.
+#[rpc]
+pub trait FaucetRpc {
+    /// Sends the configured amount of tokens to the given address
+    #[rpc(name = "faucet.drip")]
+    fn drip(&self, args: DripArgs) -> BoxFuture<Result<DripResponse>>;
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DripArgs {
+    pub address: ids::short::Id,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DripResponse {
+    /// Id of the block carrying the transfer, to poll for its acceptance
+    pub block_id: ids::Id,
+    pub height: u64,
+    pub amount: u64,
+}
+
+/// Implements the faucet RPCs on top of the Vm.
+#[derive(Clone)]
+pub struct FaucetService<A> {
+    pub vm: Vm<A>,
+    key: private_key::Key,
+    amount: u64,
+    interval: Duration,
+    /// When each address was last dripped to
+    drips: Arc<RwLock<HashMap<ids::short::Id, Instant>>>,
+}
+
+impl<A> FaucetService<A> {
+    /// Creates the faucet described by the config.
+    /// # Errors
+    /// Fails if the key is malformed.
+    pub fn new(vm: Vm<A>, faucet: &Faucet) -> io::Result<Self> {
+        Ok(Self {
+            vm,
+            key: private_key::Key::from_hex(&faucet.key)?,
+            amount: faucet.amount,
+            interval: faucet.interval(),
+            drips: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// Returns the address tokens are sent from.
+    /// # Errors
+    /// Fails if the address can't be derived from the key.
+    pub fn address(&self) -> io::Result<ids::short::Id> {
+        self.key.to_public_key().to_short_id()
+    }
+}
+
+impl<A> FaucetRpc for FaucetService<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn drip(&self, args: DripArgs) -> BoxFuture<Result<DripResponse>> {
+        log::debug!("faucet.drip called");
+        let faucet = self.clone();
+
+        Box::pin(async move {
+            faucet
+                .send(args.address)
+                .await
+                .map_err(create_jsonrpc_error)
+        })
+    }
+}
+
+impl<A> FaucetService<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    /// Queues a transfer of the configured amount to the address, unless it was
+    /// dripped to within the interval.
+    async fn send(&self, to: ids::short::Id) -> io::Result<DripResponse> {
+        // Held throughout, so concurrent drips to an address can't both pass
+        let mut drips = self.drips.write().await;
+        if let Some(last) = drips.get(&to) {
+            let wait = self.interval.saturating_sub(last.elapsed());
+            if !wait.is_zero() {
+                return Err(IoError::new(
+                    ErrorKind::WouldBlock,
+                    format!("{to} was dripped to recently, retry in {}s", wait.as_secs()),
+                ));
+            }
+        }
+
+        let from = self.address()?;
+        let nonce = {
+            let vm_state = self.vm.state.read().await;
+            let state = vm_state
+                .state
+                .as_ref()
+                .ok_or_else(|| IoError::new(ErrorKind::NotFound, "state manager not found"))?;
+            state.get_nonce(&from).await
+        };
+        let transfer = Transfer {
+            to: to.clone(),
+            amount: self.amount,
+            nonce,
+        };
+        let signature = block::sign_transfer(&self.key, &transfer)?;
+        let block = self.vm.admit_account_action(AccountAction::Transfer(transfer), signature).await?;
+
+        drips.insert(to.clone(), Instant::now());
+        // Forget addresses that may be dripped to again, so the map stays bounded
+        drips.retain(|_, last| last.elapsed() < self.interval);
+        log::info!("dripped {} to {to} in block {}", self.amount, block.id());
+
+        Ok(DripResponse {
+            block_id: block.id(),
+            height: block.height(),
+            amount: self.amount,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct FaucetHandler {
+    pub handler: IoHandler,
+}
+
+impl FaucetHandler {
+    #[must_use]
+    pub fn new<A>(service: FaucetService<A>) -> Self
+    where
+        A: Send + Sync + Clone + 'static,
+    {
+        let mut handler = jsonrpc_core::IoHandler::new();
+        handler.extend_with(FaucetRpc::to_delegate(service));
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl Handle for FaucetHandler {
+    async fn request(
+        &self,
+        req: &Bytes,
+        _headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        match self.handler.handle_request(&de_request(req)?).await {
+            Some(resp) => Ok((Bytes::from(resp), Vec::new())),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to handle request",
+            )),
+        }
+    }
+}
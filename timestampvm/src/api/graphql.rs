@@ -0,0 +1,282 @@
+//! Read-only GraphQL view of games, moves and blocks.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/graphql`.
+//!
+//! Lets explorers fetch nested data (a game, its moves and the blocks carrying
+//! them) in a single request. Takes the usual `{"query", "variables"}` POST body.
+
+use std::{io, str::FromStr};
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Json, Object, Request, Result, Schema,
+};
+use avalanche_types::{ids, proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+
+use crate::{
+    block::Block,
+    game::{self, GameStatus},
+    state::{BlockEvent, MoveRecord, State},
+    vm::Vm,
+};
+
+/// Maximum number of games returned by a single `games` query.
+pub const MAX_GAMES_PAGE: usize = 100;
+
+/// Deepest nesting a query may use.
+const MAX_QUERY_DEPTH: usize = 8;
+
+pub type ChainSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Serves GraphQL queries over the chain state.
+#[derive(Clone)]
+pub struct GraphQlHandler<A> {
+    pub vm: Vm<A>,
+    pub schema: ChainSchema,
+}
+
+impl<A> GraphQlHandler<A> {
+    pub fn new(vm: Vm<A>) -> Self {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .limit_depth(MAX_QUERY_DEPTH)
+            .finish();
+        Self { vm, schema }
+    }
+}
+
+#[tonic::async_trait]
+impl<A> Handle for GraphQlHandler<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    async fn request(
+        &self,
+        req: &Bytes,
+        _headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        let request: Request = serde_json::from_slice(req).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("failed to deserialize GraphQL request: {e}"),
+            )
+        })?;
+
+        // Resolvers only need the state, so don't hold the Vm lock while they run
+        let state = self.vm.state.read().await.state.clone();
+        if let Some(state) = state {
+            let response = self.schema.execute(request.data(state)).await;
+            let body = serde_json::to_vec(&response).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("failed to serialize GraphQL response: {e}"),
+                )
+            })?;
+
+            return Ok((
+                Bytes::from(body),
+                vec![Element {
+                    key: String::from("Content-Type"),
+                    values: vec![String::from("application/json")],
+                }],
+            ));
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "state manager not found",
+        ))
+    }
+}
+
+/// Root of all queries.
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Height of the last accepted block
+    async fn height(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(ctx.data::<State>()?.get_height().await)
+    }
+
+    /// Game currently being played
+    async fn current_game(&self, ctx: &Context<'_>) -> Result<GameNode> {
+        let state = ctx.data::<State>()?;
+        Ok(GameNode {
+            index: state.get_curr_game_index().await,
+        })
+    }
+
+    /// Game with the given index, if it has started
+    async fn game(&self, ctx: &Context<'_>, index: usize) -> Result<Option<GameNode>> {
+        let state = ctx.data::<State>()?;
+        if index > state.get_curr_game_index().await {
+            return Ok(None);
+        }
+        Ok(Some(GameNode { index }))
+    }
+
+    /// Games in order, `limit` defaults to (and is capped at) 100
+    async fn games(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<GameNode>> {
+        let state = ctx.data::<State>()?;
+        let limit = limit.unwrap_or(MAX_GAMES_PAGE).min(MAX_GAMES_PAGE);
+        let games = (offset..=state.get_curr_game_index().await)
+            .take(limit)
+            .map(|index| GameNode { index })
+            .collect();
+        Ok(games)
+    }
+
+    /// Verified or accepted block with the given Id
+    async fn block(&self, ctx: &Context<'_>, id: String) -> Result<Option<BlockNode>> {
+        let state = ctx.data::<State>()?;
+        let blk_id = ids::Id::from_str(&id)?;
+        Ok(state.get_block(&blk_id).await.ok().map(BlockNode))
+    }
+
+    /// Accepted block at the given height
+    async fn block_by_height(&self, ctx: &Context<'_>, height: u64) -> Result<Option<BlockNode>> {
+        let state = ctx.data::<State>()?;
+        Ok(state.get_block_by_height(height).await.ok().map(BlockNode))
+    }
+}
+
+/// A single game, identified by its index.
+pub struct GameNode {
+    index: usize,
+}
+
+#[Object(name = "Game")]
+impl GameNode {
+    async fn index(&self) -> usize {
+        self.index
+    }
+
+    /// One of `InProgress`, `Won` or `Drawn`
+    async fn status(&self, ctx: &Context<'_>) -> Result<String> {
+        let status = ctx.data::<State>()?.get_game_status(self.index).await?;
+        Ok(String::from(match status {
+            GameStatus::InProgress { .. } => "InProgress",
+            GameStatus::Won { .. } => "Won",
+            GameStatus::Drawn { .. } => "Drawn",
+        }))
+    }
+
+    /// Winning player, if the game was won
+    async fn winner(&self, ctx: &Context<'_>) -> Result<Option<u8>> {
+        let state = ctx.data::<State>()?;
+        Ok(state.get_winner(self.index).await.and_then(|result| result.winner))
+    }
+
+    /// Player due to move, if the game is in progress
+    async fn next_player(&self, ctx: &Context<'_>) -> Result<Option<u8>> {
+        let status = ctx.data::<State>()?.get_game_status(self.index).await?;
+        if let GameStatus::InProgress { next_player } = status {
+            return Ok(Some(next_player));
+        }
+        Ok(None)
+    }
+
+    /// Board as of the game's last move, packed two bits per cell
+    async fn board(&self, ctx: &Context<'_>) -> Result<u32> {
+        board(ctx.data::<State>()?, self.index).await
+    }
+
+    /// Board as of the game's last move, as rows of "X", "O" or ""
+    async fn grid(&self, ctx: &Context<'_>) -> Result<Vec<Vec<String>>> {
+        let board = board(ctx.data::<State>()?, self.index).await?;
+        Ok(game::grid(board)
+            .iter()
+            .map(|row| row.iter().map(|cell| String::from(*cell)).collect())
+            .collect())
+    }
+
+    /// Moves of the game, in order
+    async fn moves(&self, ctx: &Context<'_>) -> Result<Vec<MoveNode>> {
+        let state = ctx.data::<State>()?;
+        let moves = state.get_move_history(self.index).await?;
+        Ok(moves.into_iter().map(MoveNode).collect())
+    }
+}
+
+/// Returns the board as of the last move of the game.
+async fn board(state: &State, game: usize) -> Result<u32> {
+    let moves = state.get_move_history(game).await?;
+    if let Some(last) = moves.last() {
+        return Ok(state.get_board_at_block(&last.block_id).await?);
+    }
+    Ok(0)
+}
+
+/// A single accepted move.
+pub struct MoveNode(MoveRecord);
+
+#[Object(name = "Move")]
+impl MoveNode {
+    async fn player(&self) -> u8 {
+        self.0.player
+    }
+
+    async fn cell(&self) -> u8 {
+        self.0.cell
+    }
+
+    async fn row(&self) -> u8 {
+        game::position(self.0.cell).0
+    }
+
+    async fn col(&self) -> u8 {
+        game::position(self.0.cell).1
+    }
+
+    async fn height(&self) -> u64 {
+        self.0.height
+    }
+
+    /// Address that signed the move, if it was signed
+    async fn address(&self) -> Option<String> {
+        self.0.address.as_ref().map(ToString::to_string)
+    }
+
+    /// Block carrying the move
+    async fn block(&self, ctx: &Context<'_>) -> Result<BlockNode> {
+        let block = ctx.data::<State>()?.get_block(&self.0.block_id).await?;
+        Ok(BlockNode(block))
+    }
+}
+
+/// A single block.
+pub struct BlockNode(Block);
+
+#[Object(name = "Block")]
+impl BlockNode {
+    async fn id(&self) -> String {
+        self.0.id().to_string()
+    }
+
+    async fn parent_id(&self) -> String {
+        self.0.parent_id().to_string()
+    }
+
+    async fn height(&self) -> u64 {
+        self.0.height()
+    }
+
+    /// Action byte carried by the block
+    async fn action(&self) -> u8 {
+        self.0.get_player_move()
+    }
+
+    async fn status(&self) -> String {
+        self.0.status().to_string()
+    }
+
+    /// Events emitted once the block was accepted, empty if it wasn't
+    async fn events(&self, ctx: &Context<'_>) -> Result<Json<Vec<BlockEvent>>> {
+        let state = ctx.data::<State>()?;
+        Ok(Json(state.get_block_events(&self.0.id()).await.unwrap_or_default()))
+    }
+}
@@ -0,0 +1,196 @@
+//! Serves the chain queries and moves over gRPC, as defined by
+//! `proto/tictactoe/v1/chain.proto`, with the messages of [`crate::proto`].
+//!
+//! Like the WebSocket transport, it is served on its own address (`grpcAddr` in
+//! the chain config) since requests proxied through the node are plain HTTP.
+
+use std::{borrow::Borrow, io, net::SocketAddr};
+
+use tokio::sync::oneshot;
+use tonic::{
+    transport::{server::TcpIncoming, Server},
+    Request, Response, Status,
+};
+
+use crate::{
+    game::GameError,
+    mempool::rate_limit::RateLimited,
+    proto::{
+        self,
+        chain_server::{Chain, ChainServer},
+    },
+    state,
+    vm::Vm,
+};
+
+/// Implements the gRPC chain service on top of the [`Vm`].
+#[derive(Clone)]
+pub struct ChainGrpc<A> {
+    pub vm: Vm<A>,
+}
+
+impl<A> ChainGrpc<A> {
+    pub fn new(vm: Vm<A>) -> Self {
+        Self { vm }
+    }
+}
+
+impl<A> ChainGrpc<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    /// Returns the state manager, without holding the Vm lock.
+    async fn state(&self) -> Result<state::State, Status> {
+        let vm_state = self.vm.state.read().await;
+        vm_state
+            .state
+            .clone()
+            .ok_or_else(|| Status::unavailable("no state manager found"))
+    }
+}
+
+#[tonic::async_trait]
+impl<A> Chain for ChainGrpc<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    async fn propose_move(
+        &self,
+        request: Request<proto::ProposeMoveRequest>,
+    ) -> Result<Response<proto::ProposeMoveResponse>, Status> {
+        let source = request.remote_addr().map(|addr| addr.ip());
+        let request = request.into_inner();
+        let action = u8::try_from(request.action)
+            .map_err(|_| Status::invalid_argument(format!("invalid action {}", request.action)))?;
+
+        let (block, board) = self
+            .vm
+            .propose_block(
+                action,
+                request.signature,
+                request.max_fee,
+                request.work,
+                source,
+            )
+            .await
+            .map_err(create_status)?;
+
+        Ok(Response::new(proto::ProposeMoveResponse {
+            block_id: block.id().to_string(),
+            height: block.height(),
+            predicted: Some(proto::Board::new(board)),
+        }))
+    }
+
+    async fn get_board(
+        &self,
+        _request: Request<proto::GetBoardRequest>,
+    ) -> Result<Response<proto::Board>, Status> {
+        let board = self.vm.latest.load().board;
+        Ok(Response::new(proto::Board::new(board)))
+    }
+
+    async fn get_game_status(
+        &self,
+        request: Request<proto::GetGameStatusRequest>,
+    ) -> Result<Response<proto::GameStatus>, Status> {
+        let game = game_index(request.into_inner().game)?;
+        let status = self
+            .state()
+            .await?
+            .get_game_status(game)
+            .await
+            .map_err(create_status)?;
+
+        Ok(Response::new(proto::GameStatus::new(game, status)))
+    }
+
+    async fn get_move_history(
+        &self,
+        request: Request<proto::GetMoveHistoryRequest>,
+    ) -> Result<Response<proto::GetMoveHistoryResponse>, Status> {
+        let game = game_index(request.into_inner().game)?;
+        let moves = self
+            .state()
+            .await?
+            .get_move_history(game)
+            .await
+            .map_err(create_status)?;
+
+        Ok(Response::new(proto::GetMoveHistoryResponse {
+            game: game as u64,
+            moves: moves.into_iter().map(proto::Move::from).collect(),
+        }))
+    }
+
+    async fn get_block_by_height(
+        &self,
+        request: Request<proto::GetBlockByHeightRequest>,
+    ) -> Result<Response<proto::Block>, Status> {
+        let block = self
+            .state()
+            .await?
+            .get_block_by_height(request.into_inner().height)
+            .await
+            .map_err(create_status)?;
+
+        Ok(Response::new(proto::Block::from(&block)))
+    }
+}
+
+/// Starts serving the gRPC chain service on the given address.
+/// Returns a sender that stops the server once used or dropped.
+/// # Errors
+/// Fails if the address can't be bound.
+pub fn serve_grpc<A>(service: ChainGrpc<A>, addr: SocketAddr) -> io::Result<oneshot::Sender<()>>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    let incoming = TcpIncoming::new(addr, true, None).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("failed to bind gRPC server to {addr}: {e}"),
+        )
+    })?;
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let res = Server::builder()
+            .add_service(ChainServer::new(service))
+            .serve_with_incoming_shutdown(incoming, async {
+                let _ = stopped.await;
+            })
+            .await;
+        if let Err(e) = res {
+            log::warn!("gRPC server on {addr} stopped: {e}");
+        }
+    });
+
+    Ok(stop)
+}
+
+/// Maps an error to the gRPC status reported to clients.
+fn create_status<E: Borrow<io::Error>>(e: E) -> Status {
+    let e = e.borrow();
+    match GameError::from_io(e) {
+        Some(GameError::InvalidMove(_) | GameError::CellOccupied(_)) => {
+            Status::invalid_argument(e.to_string())
+        }
+        Some(GameError::WrongTurn { .. } | GameError::GameOver { .. }) => {
+            Status::failed_precondition(e.to_string())
+        }
+        Some(
+            GameError::UnknownGame(_)
+            | GameError::UnknownBlock(_)
+            | GameError::UnknownHeight(_)
+            | GameError::PrunedBlock(_),
+        ) => Status::not_found(e.to_string()),
+        None if RateLimited::from_io(e).is_some() => Status::resource_exhausted(e.to_string()),
+        None => Status::internal(e.to_string()),
+    }
+}
+
+#[allow(clippy::result_large_err)] // the status is returned as is
+fn game_index(game: u64) -> Result<usize, Status> {
+    usize::try_from(game).map_err(|_| Status::invalid_argument(format!("invalid game {game}")))
+}
@@ -0,0 +1,48 @@
+//! Exposes the VM's Prometheus metrics in the text format.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/metrics`.
+
+use std::io;
+
+use avalanche_types::{proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+
+use crate::{metrics, vm::Vm};
+
+/// Serves the metrics of the chain, whatever the request.
+#[derive(Clone)]
+pub struct MetricsHandler<A> {
+    pub vm: Vm<A>,
+}
+
+impl<A> MetricsHandler<A> {
+    pub fn new(vm: Vm<A>) -> Self {
+        Self { vm }
+    }
+}
+
+#[tonic::async_trait]
+impl<A> Handle for MetricsHandler<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    async fn request(
+        &self,
+        _req: &Bytes,
+        _headers: &[Element],
+    ) -> io::Result<(Bytes, Vec<Element>)> {
+        // Gauges are sampled on scrape rather than kept up to date on every change
+        let depth = self.vm.mempool.read().await.len();
+        self.vm
+            .metrics
+            .mempool_depth
+            .set(i64::try_from(depth).unwrap_or(i64::MAX));
+
+        Ok((
+            Bytes::from(self.vm.metrics.encode()?),
+            vec![Element {
+                key: String::from("Content-Type"),
+                values: vec![metrics::content_type()],
+            }],
+        ))
+    }
+}
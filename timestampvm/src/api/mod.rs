@@ -1,16 +1,50 @@
 //! Implementation of timestampvm APIs, to be registered via
-//! 'create_handlers` in the [`vm`](crate::vm) crate.
+//! `create_handlers` in the [`vm`](crate::vm) crate.
 
+pub mod admin;
 pub mod chain_handlers;
-// pub mod static_handlers;
+pub mod event_stream;
+pub mod faucet;
+pub mod graphql;
+pub mod grpc;
+pub mod metrics;
+#[cfg(feature = "profiling")]
+pub mod profile;
+pub mod rest;
+pub mod schema;
+pub mod static_handlers;
 
-use std::io;
+use std::{io, net::IpAddr};
 
+use avalanche_types::{proto::http::Element, subnet::rpc::http::handle::Handle};
 use bytes::Bytes;
 use jsonrpc_core::MethodCall;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use self::{
+    admin::AdminHandler,
+    chain_handlers::{ChainHandler, ChainService},
+    event_stream::EventStreamHandler,
+    faucet::FaucetHandler,
+    graphql::GraphQlHandler,
+    metrics::MetricsHandler,
+    schema::SchemaHandler,
+};
+
+/// Header carrying the Id that correlates a call with the logs it produced.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest request Id taken from a client, longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Header the proxies in front of the node append the IP of their client to.
+pub const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// Header a proxy in front of the node sets to the IP of its client.
+pub const REAL_IP_HEADER: &str = "X-Real-IP";
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
 pub struct PingResponse {
     pub success: bool,
 }
@@ -19,16 +53,110 @@ pub struct PingResponse {
 /// # Errors
 /// Fails if the request is not a valid JSON-RPC method call.
 pub fn de_request(req: &Bytes) -> io::Result<String> {
-    let method_call: MethodCall = serde_json::from_slice(req).map_err(|e| {
+    ser_method_call(&de_method_call(req)?)
+}
+
+/// Parses a JSON-RPC method call.
+/// # Errors
+/// Fails if the request is not a valid JSON-RPC method call.
+pub fn de_method_call(req: &Bytes) -> io::Result<MethodCall> {
+    serde_json::from_slice(req).map_err(|e| {
         io::Error::new(
             io::ErrorKind::Other,
             format!("failed to deserialize request: {e}"),
         )
-    })?;
-    serde_json::to_string(&method_call).map_err(|e| {
+    })
+}
+
+/// Serializes a JSON-RPC method call, as the handlers expect it.
+/// # Errors
+/// Fails if the method call can't be serialized.
+pub fn ser_method_call(method_call: &MethodCall) -> io::Result<String> {
+    serde_json::to_string(method_call).map_err(|e| {
         io::Error::new(
             io::ErrorKind::Other,
             format!("failed to serialize request: {e}"),
         )
     })
 }
+
+/// Returns the request Id sent in the [`REQUEST_ID_HEADER`] header, or a new one if
+/// there's none or it isn't short printable ASCII (so it can't garble the logs).
+#[must_use]
+pub fn request_id(headers: &[Element]) -> String {
+    headers
+        .iter()
+        .find(|header| header.key.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+        .and_then(|header| header.values.first())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .cloned()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Returns the IP of the client that sent a request proxied through the node: the
+/// last address in the [`FORWARDED_FOR_HEADER`] header, which the nearest proxy
+/// appended rather than the client, or else the [`REAL_IP_HEADER`] header.
+#[must_use]
+pub fn forwarded_source(headers: &[Element]) -> Option<IpAddr> {
+    let last_value = |key: &str| {
+        headers
+            .iter()
+            .filter(|header| header.key.eq_ignore_ascii_case(key))
+            .flat_map(|header| header.values.iter())
+            .last()
+    };
+    last_value(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.rsplit(',').next())
+        .or_else(|| last_value(REAL_IP_HEADER).map(String::as_str))
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+/// Serves one of the chain-specific HTTP endpoints.
+#[derive(Clone)]
+pub enum ChainEndpoint<A> {
+    /// JSON-RPC API, see [`chain_handlers`]
+    Rpc(ChainHandler<ChainService<A>>),
+    /// Server-sent game events, see [`event_stream`]
+    Events(EventStreamHandler<A>),
+    /// Read-only GraphQL queries, see [`graphql`]
+    GraphQl(GraphQlHandler<A>),
+    /// Prometheus metrics, see [`metrics`]
+    Metrics(MetricsHandler<A>),
+    /// `OpenRPC` document of the JSON-RPC API, see [`schema`]
+    Schema(SchemaHandler),
+    /// Admin RPC, see [`admin`]
+    Admin(AdminHandler),
+    /// Faucet RPC, see [`faucet`]
+    Faucet(FaucetHandler),
+    /// CPU and heap profiles, see [`profile`]
+    #[cfg(feature = "profiling")]
+    Profile(profile::ProfileHandler),
+}
+
+#[tonic::async_trait]
+impl<A> Handle for ChainEndpoint<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    async fn request(
+        &self,
+        req: &Bytes,
+        headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        match self {
+            Self::Rpc(handler) => handler.request(req, headers).await,
+            Self::Events(handler) => handler.request(req, headers).await,
+            Self::GraphQl(handler) => handler.request(req, headers).await,
+            Self::Metrics(handler) => handler.request(req, headers).await,
+            Self::Schema(handler) => handler.request(req, headers).await,
+            Self::Admin(handler) => handler.request(req, headers).await,
+            Self::Faucet(handler) => handler.request(req, headers).await,
+            #[cfg(feature = "profiling")]
+            Self::Profile(handler) => handler.request(req, headers).await,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Captures CPU and heap profiles of the running VM in the pprof format, so live
+//! validators can be diagnosed without rebuilding the plugin.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/pprof`, only if built with the
+//! `profiling` feature and enabled in the config.
+//!
+//! Heap profiles rely on jemalloc's sampling, which has to be enabled when the
+//! node starts, e.g. with `_RJEM_MALLOC_CONF=prof:true,prof_active:false`.
+
+use std::{
+    ffi::CString,
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use avalanche_types::{proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+use pprof::protos::Message;
+use serde::{Deserialize, Serialize};
+
+/// Profile taken when the request doesn't say, in seconds.
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+
+/// Longest profile that can be requested, in seconds.
+pub const MAX_PROFILE_SECONDS: u64 = 300;
+
+/// Rate the CPU profiler samples stacks at, in Hz.
+const CPU_SAMPLING_FREQUENCY: i32 = 100;
+
+/// Kind of profile to capture.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileKind {
+    #[default]
+    Cpu,
+    /// Allocations made while profiling that are still live at the end
+    Heap,
+}
+
+/// Body of a profile request, an empty body captures the default CPU profile.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ProfileArgs {
+    #[serde(default)]
+    pub kind: ProfileKind,
+    /// How long to profile for, at most [`MAX_PROFILE_SECONDS`]
+    #[serde(default)]
+    pub seconds: Option<u64>,
+}
+
+/// Captures a profile for as long as requested and returns it, pprof-encoded.
+#[derive(Clone, Default)]
+pub struct ProfileHandler {}
+
+impl ProfileHandler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[tonic::async_trait]
+impl Handle for ProfileHandler {
+    async fn request(
+        &self,
+        req: &Bytes,
+        _headers: &[Element],
+    ) -> io::Result<(Bytes, Vec<Element>)> {
+        let args: ProfileArgs = if req.is_empty() {
+            ProfileArgs::default()
+        } else {
+            serde_json::from_slice(req).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid profile request: {e}"),
+                )
+            })?
+        };
+        let seconds = args.seconds.unwrap_or(DEFAULT_PROFILE_SECONDS);
+        if seconds == 0 || seconds > MAX_PROFILE_SECONDS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("profiles last 1 to {MAX_PROFILE_SECONDS} seconds, not {seconds}"),
+            ));
+        }
+
+        log::info!("capturing {:?} profile for {seconds}s", args.kind);
+        let duration = Duration::from_secs(seconds);
+        let profile = match args.kind {
+            ProfileKind::Cpu => cpu_profile(duration).await?,
+            ProfileKind::Heap => heap_profile(duration).await?,
+        };
+
+        Ok((
+            Bytes::from(profile),
+            vec![Element {
+                key: String::from("Content-Type"),
+                values: vec![String::from("application/octet-stream")],
+            }],
+        ))
+    }
+}
+
+/// Samples the stacks of every thread for `duration`, returning the protobuf-encoded
+/// profile. Only one CPU profile can be captured at a time.
+async fn cpu_profile(duration: Duration) -> io::Result<Vec<u8>> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(CPU_SAMPLING_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| profile_error(&e))?;
+    tokio::time::sleep(duration).await;
+
+    let profile = guard
+        .report()
+        .build()
+        .and_then(|report| report.pprof())
+        .map_err(|e| profile_error(&e))?;
+    let mut buf = Vec::new();
+    profile
+        .encode(&mut buf)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode profile {e}")))?;
+    Ok(buf)
+}
+
+/// Samples allocations for `duration`, returning jemalloc's heap profile of those
+/// still live, which `pprof` and `jeprof` both read.
+async fn heap_profile(duration: Duration) -> io::Result<Vec<u8>> {
+    // SAFETY: "opt.prof" is a read-only boolean option
+    let enabled = unsafe { tikv_jemalloc_ctl::raw::read::<bool>(b"opt.prof\0") };
+    if !matches!(enabled, Ok(true)) {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "heap profiling is disabled, start the node with _RJEM_MALLOC_CONF=prof:true",
+        ));
+    }
+
+    set_heap_sampling(true)?;
+    tokio::time::sleep(duration).await;
+    set_heap_sampling(false)?;
+
+    let path = std::env::temp_dir().join(format!("tictactoevm-{}.heap", uuid::Uuid::new_v4()));
+    let c_path = CString::new(path.to_string_lossy().as_bytes())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    // SAFETY: "prof.dump" takes the path of the file to write, as a C string that
+    // outlives the call
+    unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to dump heap profile {e}")))?;
+
+    let profile = tokio::fs::read(&path).await;
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        log::warn!("failed to remove heap profile {}: {e}", path.display());
+    }
+    profile
+}
+
+/// Starts or stops sampling allocations.
+fn set_heap_sampling(active: bool) -> io::Result<()> {
+    // SAFETY: "prof.active" is a writable boolean
+    unsafe { tikv_jemalloc_ctl::raw::write(b"prof.active\0", active) }
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to set heap sampling {e}")))
+}
+
+fn profile_error(e: &pprof::Error) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("failed to capture CPU profile {e}"),
+    )
+}
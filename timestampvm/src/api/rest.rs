@@ -0,0 +1,225 @@
+//! Maps read queries onto plain `GET` requests, for consumers that can't easily
+//! speak JSON-RPC. Responses are those of the matching chain RPC methods:
+//!
+//! * `GET [...]/games/{game}/board`: board of the game, as it stood once it ended
+//!   if it did (`getBoard` or `getBoardAtHeight`)
+//! * `GET [...]/games/{game}/moves`: ordered moves of the game (`getMoveHistory`)
+//! * `GET [...]/blocks/{id or height}`: accepted block (`getBlockByHeight`)
+//!
+//! Errors are returned as a JSON-RPC error object, with status 404 for unknown
+//! paths, games and blocks and 400 for malformed block Ids.
+//!
+//! Requests proxied through the node only carry their body, so like the
+//! WebSocket transport the gateway is served on its own address (`restAddr` in
+//! the chain config), under any prefix such as `/ext/bc/[CHAIN ID]`. The
+//! standalone server also serves it at the chain handler path.
+
+use std::{convert::Infallible, io, net::SocketAddr, str::FromStr};
+
+use avalanche_types::ids;
+use bytes::Bytes;
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::game::GameStatus;
+
+use super::chain_handlers::{
+    create_jsonrpc_error, ChainService, GetBlockByHeightArgs, GetBlockByHeightResponse,
+    GetBoardAtHeightArgs, GetBoardResponse, GetGameStatusArgs, GetMoveHistoryArgs, Rpc,
+    UNKNOWN_BLOCK, UNKNOWN_GAME,
+};
+
+/// Length of the shortest string encoding an Id along with its checksum. Shorter
+/// ones make the decoder panic, so are rejected up front.
+const MIN_ID_LEN: usize = 36;
+
+/// Resource named by the path of a `GET` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    Board(usize),
+    Moves(usize),
+    Block(String),
+}
+
+impl Route {
+    /// Parses the trailing segments of a path, whatever its prefix.
+    #[must_use]
+    pub fn parse(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_end_matches('/').rsplit('/').take(3).collect();
+        match segments.as_slice() {
+            ["board", game, "games"] => game.parse().ok().map(Self::Board),
+            ["moves", game, "games"] => game.parse().ok().map(Self::Moves),
+            [id, "blocks", ..] => Some(Self::Block((*id).to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Serves the [`Route`]s through the chain RPC service.
+#[derive(Clone)]
+pub struct RestGateway<A> {
+    pub service: ChainService<A>,
+}
+
+impl<A> RestGateway<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    pub fn new(service: ChainService<A>) -> Self {
+        Self { service }
+    }
+
+    /// Handles a request, whose path must name a [`Route`].
+    pub async fn handle(&self, req: &Request<Body>) -> Response<Body> {
+        let path = req.uri().path();
+        if req.method() != Method::GET {
+            let mut error = Error::invalid_request();
+            error.message = String::from("only GET is supported");
+            return respond(StatusCode::METHOD_NOT_ALLOWED, &error);
+        }
+        if let Some(route) = Route::parse(path) {
+            return self.serve(route).await;
+        }
+        let mut error = Error::method_not_found();
+        error.message = format!("no route for {path}");
+        respond(StatusCode::NOT_FOUND, &error)
+    }
+
+    async fn serve(&self, route: Route) -> Response<Body> {
+        log::debug!("REST {route:?} requested");
+        let res = match route {
+            Route::Board(game) => self.board(game).await.and_then(to_json),
+            Route::Moves(game) => self
+                .service
+                .get_move_history(GetMoveHistoryArgs { game })
+                .await
+                .and_then(to_json),
+            Route::Block(id) => self.block(&id).await.and_then(to_json),
+        };
+        match res {
+            Ok(body) => respond_with(StatusCode::OK, body),
+            Err(e) => respond(status_of(&e), &e),
+        }
+    }
+
+    /// Returns the board of the game, as it stood once it ended if it did.
+    async fn board(&self, game: usize) -> Result<GetBoardResponse> {
+        let status = self
+            .service
+            .get_game_status(GetGameStatusArgs { game })
+            .await?
+            .status;
+        match status {
+            GameStatus::InProgress { .. } => self.service.get_board().await,
+            GameStatus::Won { at_height, .. } | GameStatus::Drawn { at_height } => {
+                self.service
+                    .get_board_at_height(GetBoardAtHeightArgs { height: at_height })
+                    .await
+            }
+        }
+    }
+
+    /// Returns the accepted block with the given Id or at the given height.
+    async fn block(&self, id: &str) -> Result<GetBlockByHeightResponse> {
+        if let Ok(height) = id.parse() {
+            return self
+                .service
+                .get_block_by_height(GetBlockByHeightArgs { height })
+                .await;
+        }
+
+        let invalid = || Error::invalid_params(format!("'{id}' isn't a block Id or height"));
+        if id.len() < MIN_ID_LEN {
+            return Err(invalid());
+        }
+        let id = ids::Id::from_str(id).map_err(|_| invalid())?;
+        let state = self.service.vm.state.read().await.state.clone();
+        if let Some(state) = state {
+            let block = state.get_block(&id).await.map_err(create_jsonrpc_error)?;
+            return Ok(GetBlockByHeightResponse { id, block });
+        }
+
+        Err(Error {
+            code: ErrorCode::InternalError,
+            message: String::from("no state manager found"),
+            data: None,
+        })
+    }
+}
+
+/// Maps an RPC error to the HTTP status reported to clients.
+fn status_of(e: &Error) -> StatusCode {
+    match e.code {
+        ErrorCode::ServerError(UNKNOWN_GAME | UNKNOWN_BLOCK) => StatusCode::NOT_FOUND,
+        ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
+fn to_json(value: impl Serialize) -> Result<Vec<u8>> {
+    serde_json::to_vec(&value).map_err(|e| Error {
+        code: ErrorCode::InternalError,
+        message: format!("failed to serialize response {e}"),
+        data: None,
+    })
+}
+
+fn respond(status: StatusCode, error: &Error) -> Response<Body> {
+    respond_with(status, serde_json::to_vec(error).unwrap_or_default())
+}
+
+fn respond_with(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+    let mut response = Response::new(Body::from(Bytes::from(body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// Serves the gateway on the given address until the returned sender is used or dropped.
+/// # Errors
+/// Fails if the address can't be bound.
+pub fn serve_rest<A>(gateway: RestGateway<A>, addr: SocketAddr) -> io::Result<oneshot::Sender<()>>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    let server = Server::try_bind(&addr).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("failed to bind REST gateway to {addr}: {e}"),
+        )
+    })?;
+
+    let make_service = make_service_fn(move |_| {
+        let gateway = gateway.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let gateway = gateway.clone();
+                async move { Ok::<_, Infallible>(gateway.handle(&req).await) }
+            }))
+        }
+    });
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let res = server
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            })
+            .await;
+        if let Err(e) = res {
+            log::warn!("REST gateway on {addr} stopped: {e}");
+        }
+    });
+
+    Ok(stop)
+}
@@ -0,0 +1,363 @@
+//! Describes the chain RPC as an [OpenRPC](https://spec.open-rpc.org) document, with
+//! JSON schemas derived from the args and response types of [`chain_handlers`], so
+//! clients in other languages can generate bindings.
+//! To be served via `[HOST]/ext/bc/[CHAIN ID]/schema`.
+
+use std::io;
+
+use avalanche_types::{proto::http::Element, subnet::rpc::http::handle::Handle};
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    JsonSchema,
+};
+use serde_json::{json, Value};
+
+use crate::{api::PingResponse, metrics::LatencyStats, vm::Health};
+
+use super::chain_handlers::{
+    BatchProposeMovesArgs, BatchProposeMovesResponse, BoardUpdate, DecodedMove, EncodedMove,
+    ExportGameArgs, ExportGameResponse, GetAccountGamesArgs, GetAccountGamesResponse,
+    GetBalanceArgs, GetBalanceResponse, GetBlockByHeightArgs, GetBlockByHeightResponse,
+    GetBlockEventsArgs, GetBlockEventsResponse, GetBoardAtBlockArgs, GetBoardAtHeightArgs,
+    GetBoardResponse, GetFeeEstimateResponse, GetGameStatusArgs, GetGameStatusResponse,
+    GetLeaderboardArgs, GetLeaderboardResponse, GetLegalMovesArgs, GetLegalMovesResponse,
+    GetMoveHistoryArgs, GetMoveHistoryResponse, GetNodeInfoResponse, GetNonceArgs,
+    GetNonceResponse, GetPendingMovesResponse, GetPlayerStatsArgs, GetPlayerStatsResponse,
+    GetQueuePositionArgs, GetQueuePositionResponse, GetRatingArgs, GetRatingHistoryArgs,
+    GetRatingHistoryResponse, GetRatingResponse, GetSeriesArgs, GetSeriesResponse, GetTeamGameArgs,
+    GetTeamGameResponse, GetTournamentArgs, GetTournamentResponse, GetTournamentStandingsResponse,
+    GetWinnerArgs, GetWinnerResponse, GetWinnersArgs, GetWinnersResponse, NewHead,
+    ProposeSeriesActionArgs, ProposeSeriesActionResponse, ProposeTeamActionArgs,
+    ProposeTeamActionResponse, ProposeTournamentActionArgs, ProposeTournamentActionResponse,
+    ProposeTransferArgs, ProposeTransferResponse, ProposedMoveArgs, ProposedMoveResponse,
+    RpcMethodsResponse, SeekGameArgs, SeekGameResponse, SimulateMoveArgs, SimulateMoveResponse,
+    StreamBlocksArgs, StreamBlocksResponse, WaitForMoveArgs, WaitForMoveResponse, WhoseTurnArgs,
+    WhoseTurnResponse, V1_NAMESPACE,
+};
+
+/// Version of the `OpenRPC` specification the document follows.
+const OPENRPC_VERSION: &str = "1.2.6";
+
+/// The document, serialized once as it only depends on the build.
+static DOCUMENT: Lazy<Bytes> = Lazy::new(|| Bytes::from(document().to_string()));
+
+/// Collects the methods of the document, along with the schemas they refer to.
+struct Methods {
+    gen: SchemaGenerator,
+    methods: Vec<Value>,
+}
+
+impl Methods {
+    fn new() -> Self {
+        let settings = SchemaSettings::draft07().with(|s| {
+            s.definitions_path = String::from("#/components/schemas/");
+        });
+        Self {
+            gen: settings.into_generator(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Adds a method taking no params.
+    fn add<R: JsonSchema>(&mut self, name: &str, summary: &str) -> &mut Self {
+        let result = self.gen.subschema_for::<R>();
+        self.push(name, summary, &json!([]), &json!(result))
+    }
+
+    /// Adds a method taking a single positional param.
+    fn add_with<P: JsonSchema, R: JsonSchema>(&mut self, name: &str, summary: &str) -> &mut Self {
+        let params = json!([{
+            "name": "args",
+            "required": true,
+            "schema": self.gen.subschema_for::<P>(),
+        }]);
+        let result = self.gen.subschema_for::<R>();
+        self.push(name, summary, &params, &json!(result))
+    }
+
+    /// Adds a subscription, whose notifications carry `N`.
+    fn add_subscription<N: JsonSchema>(
+        &mut self,
+        name: &str,
+        summary: &str,
+        subscription: &str,
+        params: &Value,
+    ) -> &mut Self {
+        let notification = self.gen.subschema_for::<N>();
+        self.push(name, summary, params, &json!({ "type": "string" }));
+        if let Some(method) = self.methods.last_mut() {
+            method["x-notification"] = json!({
+                "name": subscription,
+                "schema": notification,
+            });
+        }
+        self
+    }
+
+    /// Adds the method cancelling a subscription, given its Id.
+    fn add_unsubscription(&mut self, name: &str, summary: &str) -> &mut Self {
+        let params = json!([{
+            "name": "subscription",
+            "required": true,
+            "schema": { "type": "string" },
+        }]);
+        self.push(name, summary, &params, &json!({ "type": "boolean" }))
+    }
+
+    fn push(&mut self, name: &str, summary: &str, params: &Value, result: &Value) -> &mut Self {
+        self.methods.push(json!({
+            "name": format!("{V1_NAMESPACE}.{name}"),
+            "summary": summary,
+            "paramStructure": "by-position",
+            "params": params,
+            "result": { "name": "result", "schema": result },
+        }));
+        self
+    }
+}
+
+/// Returns the `OpenRPC` document of the chain RPC.
+#[must_use]
+pub fn document() -> Value {
+    let mut m = Methods::new();
+    add_game_methods(&mut m);
+    add_account_methods(&mut m);
+    add_node_methods(&mut m);
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "timestampvm chain RPC",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": m.methods,
+        "components": { "schemas": m.gen.take_definitions() },
+    })
+}
+
+/// Adds the methods playing and querying games.
+fn add_game_methods(m: &mut Methods) {
+    m.add::<PingResponse>("ping", "Pings the VM")
+        .add_with::<ProposedMoveArgs, ProposedMoveResponse>("proposeMove", "Proposes a player move")
+        .add_with::<BatchProposeMovesArgs, BatchProposeMovesResponse>(
+            "batchProposeMoves",
+            "Proposes an ordered list of player moves, reporting the outcome of each",
+        )
+        .add_with::<SimulateMoveArgs, SimulateMoveResponse>(
+            "simulateMove",
+            "Validates a player move against the current state without proposing it",
+        )
+        .add::<GetBoardResponse>("getBoard", "Fetches the current game state")
+        .add_with::<GetWinnerArgs, GetWinnerResponse>(
+            "getWinner",
+            "Fetches the winner of the ith game",
+        )
+        .add_with::<StreamBlocksArgs, StreamBlocksResponse>(
+            "streamBlocks",
+            "Fetches accepted blocks in height order, a chunk at a time",
+        )
+        .add_with::<GetBlockByHeightArgs, GetBlockByHeightResponse>(
+            "getBlockByHeight",
+            "Fetches the accepted block at the given height",
+        )
+        .add_with::<GetBoardAtHeightArgs, GetBoardResponse>(
+            "getBoardAtHeight",
+            "Fetches the board as it stood after the block at the given height",
+        )
+        .add_with::<GetBoardAtBlockArgs, GetBoardResponse>(
+            "getBoardAtBlock",
+            "Fetches the board as it stood after the given block",
+        )
+        .add_with::<GetBlockEventsArgs, GetBlockEventsResponse>(
+            "getBlockEvents",
+            "Fetches the events emitted by an accepted block",
+        )
+        .add_with::<GetMoveHistoryArgs, GetMoveHistoryResponse>(
+            "getMoveHistory",
+            "Fetches the ordered moves of the given game",
+        )
+        .add_with::<WhoseTurnArgs, WhoseTurnResponse>(
+            "whoseTurn",
+            "Fetches the player expected to make the next move of the given game",
+        )
+        .add_with::<GetLegalMovesArgs, GetLegalMovesResponse>(
+            "getLegalMoves",
+            "Fetches the empty cells the next player may mark in the given game",
+        )
+        .add_with::<GetGameStatusArgs, GetGameStatusResponse>(
+            "getGameStatus",
+            "Fetches whether the given game is in progress, won or drawn",
+        )
+        .add_with::<GetWinnersArgs, GetWinnersResponse>(
+            "getWinners",
+            "Fetches a page of finished game results",
+        )
+        .add_with::<GetPlayerStatsArgs, GetPlayerStatsResponse>(
+            "getPlayerStats",
+            "Fetches the results of finished games the given address played in",
+        )
+        .add::<GetFeeEstimateResponse>(
+            "getFeeEstimate",
+            "Fetches the fee a move proposed now would pay, given the mempool's depth",
+        )
+        .add_with::<ExportGameArgs, ExportGameResponse>(
+            "exportGame",
+            "Exports the given game in a portable notation",
+        )
+        .add_with::<DecodedMove, EncodedMove>(
+            "encodeMove",
+            "Packs a player and cell index into an action byte",
+        )
+        .add_with::<EncodedMove, DecodedMove>(
+            "decodeMove",
+            "Unpacks an action byte into its player and cell index",
+        )
+        .add_with::<WaitForMoveArgs, WaitForMoveResponse>(
+            "waitForMove",
+            "Waits for the next accepted move of a game, for clients that can't subscribe",
+        );
+}
+
+/// Adds the methods moving tokens, seeking games and entering tournaments, series
+/// and team games on behalf of an address.
+fn add_account_methods(m: &mut Methods) {
+    m.add_with::<ProposeTransferArgs, ProposeTransferResponse>(
+        "proposeTransfer",
+        "Proposes a transfer of tokens signed by the sender",
+    )
+    .add_with::<SeekGameArgs, SeekGameResponse>(
+        "seekGame",
+        "Proposes a seek signed by the seeker, queueing it for matchmaking",
+    )
+    .add_with::<ProposeTournamentActionArgs, ProposeTournamentActionResponse>(
+        "proposeTournamentAction",
+        "Proposes a tournament action signed by the address taking it",
+    )
+    .add_with::<ProposeSeriesActionArgs, ProposeSeriesActionResponse>(
+        "proposeSeriesAction",
+        "Proposes a series action signed by the address taking it",
+    )
+    .add_with::<ProposeTeamActionArgs, ProposeTeamActionResponse>(
+        "proposeTeamAction",
+        "Proposes a team action signed by the address taking it",
+    )
+    .add_with::<GetBalanceArgs, GetBalanceResponse>(
+        "getBalance",
+        "Fetches the accepted balance and transfer nonce of the given address",
+    )
+    .add_with::<GetNonceArgs, GetNonceResponse>(
+        "getNonce",
+        "Fetches the nonce the next transfer of the given address must carry",
+    )
+    .add_with::<GetAccountGamesArgs, GetAccountGamesResponse>(
+        "getAccountGames",
+        "Lists the games the given address took a seat in, oldest first",
+    )
+    .add_with::<GetRatingArgs, GetRatingResponse>(
+        "getRating",
+        "Fetches the rating of the given address",
+    )
+    .add_with::<GetRatingHistoryArgs, GetRatingHistoryResponse>(
+        "getRatingHistory",
+        "Lists the changes of the given address's rating, oldest first",
+    )
+    .add_with::<GetLeaderboardArgs, GetLeaderboardResponse>(
+        "getLeaderboard",
+        "Fetches a page of the top addresses, by rating or by wins",
+    )
+    .add_with::<GetQueuePositionArgs, GetQueuePositionResponse>(
+        "getQueuePosition",
+        "Fetches where the given address waits in the matchmaking queue",
+    )
+    .add_with::<GetTournamentArgs, GetTournamentResponse>(
+        "getTournament",
+        "Fetches a tournament's players and bracket",
+    )
+    .add_with::<GetTournamentArgs, GetTournamentStandingsResponse>(
+        "getTournamentStandings",
+        "Fetches a tournament's standings, by points and then by seed",
+    )
+    .add_with::<GetSeriesArgs, GetSeriesResponse>(
+        "getSeries",
+        "Fetches a series' players, games and score",
+    )
+    .add_with::<GetTeamGameArgs, GetTeamGameResponse>(
+        "getTeamGame",
+        "Fetches a team game's teams, acceptances and reserved game",
+    );
+}
+
+/// Adds the methods reporting on the node, and the subscriptions.
+fn add_node_methods(m: &mut Methods) {
+    let game = json!([{ "name": "game", "required": true, "schema": { "type": "integer" } }]);
+
+    m.add::<GetNodeInfoResponse>(
+        "getNodeInfo",
+        "Fetches the build and chain this node is serving",
+    )
+    .add::<GetPendingMovesResponse>(
+        "getPendingMoves",
+        "Lists the moves waiting for blocks to be built, along with eviction counts",
+    )
+    .add::<Health>(
+        "health",
+        "Checks the database and engine channel, and reports chain and mempool progress",
+    )
+    .add::<LatencyStats>(
+        "getLatencyStats",
+        "Reports percentiles of the time from moves being proposed to this node to their \
+             blocks being accepted",
+    )
+    .add::<RpcMethodsResponse>(
+        "rpc_methods",
+        "Lists the available methods along with their versions and aliases",
+    )
+    .add_subscription::<NewHead>(
+        "subscribeNewHeads",
+        "Notifies the subscriber of every accepted block, over WebSocket only",
+        "newHeads",
+        &json!([]),
+    )
+    .add_unsubscription("unsubscribeNewHeads", "Cancels a `newHeads` subscription")
+    .add_subscription::<BoardUpdate>(
+        "subscribeBoardUpdated",
+        "Notifies the subscriber of the board and turn after every accepted move of the \
+             given game, over WebSocket only",
+        "boardUpdated",
+        &game,
+    )
+    .add_unsubscription(
+        "unsubscribeBoardUpdated",
+        "Cancels a `boardUpdated` subscription",
+    );
+}
+
+/// Serves the `OpenRPC` document, whatever the request.
+#[derive(Clone, Default)]
+pub struct SchemaHandler;
+
+impl SchemaHandler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[tonic::async_trait]
+impl Handle for SchemaHandler {
+    async fn request(
+        &self,
+        _req: &Bytes,
+        _headers: &[Element],
+    ) -> io::Result<(Bytes, Vec<Element>)> {
+        Ok((
+            DOCUMENT.clone(),
+            vec![Element {
+                key: String::from("Content-Type"),
+                values: vec![String::from("application/json")],
+            }],
+        ))
+    }
+}
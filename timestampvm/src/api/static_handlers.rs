@@ -0,0 +1,132 @@
+//! Implements static handlers specific to this VM, served before any chain exists.
+//! To be served via `[HOST]/ext/vm/[VM ID]/static`.
+//!
+//! Lets tooling build and check the genesis bytes of a new tic-tac-toe chain.
+
+use std::io;
+
+use avalanche_types::{
+    codec::serde::hex_0x_bytes::Hex0xBytes, proto::http::Element,
+    subnet::rpc::http::handle::Handle,
+};
+use bytes::Bytes;
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, IoHandler, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::genesis::Genesis;
+
+use super::de_request;
+
+/// Defines static handler RPCs for this VM.
+#[rpc]
+pub trait Rpc {
+    /// Pings the VM.
+    #[rpc(name = "ping", alias("tic_tac_toe.ping", "tictactoe.v1.ping"))]
+    fn ping(&self) -> BoxFuture<Result<crate::api::PingResponse>>;
+
+    /// Encodes a genesis into the bytes a new chain is created with.
+    #[rpc(name = "encodeGenesis", alias("tic_tac_toe.encodeGenesis", "tictactoe.v1.encodeGenesis"))]
+    fn encode_genesis(&self, args: EncodeGenesisArgs) -> BoxFuture<Result<EncodeGenesisResponse>>;
+
+    /// Decodes the genesis bytes of a chain.
+    #[rpc(name = "decodeGenesis", alias("tic_tac_toe.decodeGenesis", "tictactoe.v1.decodeGenesis"))]
+    fn decode_genesis(&self, args: DecodeGenesisArgs) -> BoxFuture<Result<DecodeGenesisResponse>>;
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EncodeGenesisArgs {
+    pub genesis: Genesis,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EncodeGenesisResponse {
+    #[serde_as(as = "Hex0xBytes")]
+    pub bytes: Vec<u8>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DecodeGenesisArgs {
+    #[serde_as(as = "Hex0xBytes")]
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DecodeGenesisResponse {
+    pub genesis: Genesis,
+}
+
+/// Implements API services for the static handlers.
+#[derive(Clone, Default)]
+pub struct StaticService {}
+
+impl StaticService {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Rpc for StaticService {
+    fn ping(&self) -> BoxFuture<Result<crate::api::PingResponse>> {
+        log::debug!("ping called");
+        Box::pin(async move { Ok(crate::api::PingResponse { success: true }) })
+    }
+
+    fn encode_genesis(&self, args: EncodeGenesisArgs) -> BoxFuture<Result<EncodeGenesisResponse>> {
+        log::debug!("encode_genesis called");
+
+        Box::pin(async move {
+            let bytes = args.genesis.to_vec().map_err(|e| Error {
+                code: ErrorCode::InternalError,
+                message: e.to_string(),
+                data: None,
+            })?;
+            Ok(EncodeGenesisResponse { bytes })
+        })
+    }
+
+    fn decode_genesis(&self, args: DecodeGenesisArgs) -> BoxFuture<Result<DecodeGenesisResponse>> {
+        log::debug!("decode_genesis called");
+
+        Box::pin(async move {
+            let genesis = Genesis::from_slice(&args.bytes)
+                .map_err(|e| Error::invalid_params(e.to_string()))?;
+            Ok(DecodeGenesisResponse { genesis })
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct StaticHandler {
+    pub handler: IoHandler,
+}
+
+impl StaticHandler {
+    #[must_use]
+    pub fn new(service: StaticService) -> Self {
+        let mut handler = jsonrpc_core::IoHandler::new();
+        handler.extend_with(Rpc::to_delegate(service));
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl Handle for StaticHandler {
+    async fn request(
+        &self,
+        req: &Bytes,
+        _headers: &[Element],
+    ) -> std::io::Result<(Bytes, Vec<Element>)> {
+        match self.handler.handle_request(&de_request(req)?).await {
+            Some(resp) => Ok((Bytes::from(resp), Vec::new())),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to handle request",
+            )),
+        }
+    }
+}
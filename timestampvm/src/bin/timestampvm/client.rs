@@ -0,0 +1,189 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{arg, value_parser, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::ProposedMoveArgs,
+    block, client,
+    game::{self, GameStatus},
+    keyfile::Keyfile,
+};
+
+use crate::{keygen, sign_move::Player};
+
+pub const NAME: &str = "client";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Plays on a chain through a node's RPC endpoint")
+        .arg(
+            arg!(--"http-rpc" <URL> "Base URL of the node")
+                .default_value("http://127.0.0.1:9650")
+                .global(true),
+        )
+        .arg(
+            arg!(--"url-path" <PATH> "Path of the chain's RPC endpoint")
+                .default_value("/ext/bc/standalone/rpc")
+                .global(true),
+        )
+        .subcommands(vec![
+            Command::new("board").about("Prints the board of the current game"),
+            Command::new("play")
+                .about("Marks a cell of the current game, and prints the resulting board")
+                .arg(
+                    arg!(--row <ROW> "Row of the marked cell, from 0")
+                        .value_parser(value_parser!(u8).range(..i64::from(game::SIDE)))
+                        .required(true),
+                )
+                .arg(
+                    arg!(--col <COL> "Column of the marked cell, from 0")
+                        .value_parser(value_parser!(u8).range(..i64::from(game::SIDE)))
+                        .required(true),
+                )
+                .arg(
+                    arg!(--player <PLAYER> "Player moving, defaults to whose turn it is")
+                        .value_parser(value_parser!(Player)),
+                )
+                .arg(arg!(--keyfile <FILE> "Signs the move with the player's keyfile"))
+                .arg(
+                    arg!(--"max-fee" <FEE> "Most the signed move may pay, else the base fee")
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(arg!(--"password-file" <FILE> "Reads the keyfile password from a file")),
+            Command::new("history")
+                .about("Prints the moves of a game")
+                .arg(
+                    arg!(--game <GAME> "Index of the game, defaults to the current one")
+                        .value_parser(value_parser!(usize)),
+                ),
+        ])
+        .subcommand_required(true)
+}
+
+/// Runs the client subcommand against the node.
+/// # Errors
+/// Fails if the node can't be reached, or rejects the call.
+pub async fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let http_rpc = matches
+        .get_one::<String>("http-rpc")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing --http-rpc"))?;
+    let url_path = matches
+        .get_one::<String>("url-path")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing --url-path"))?;
+
+    match matches.subcommand() {
+        Some(("board", _)) => {
+            let game_index = client::current_game(http_rpc, url_path).await?;
+            let board = client::get_board(http_rpc, url_path).await?;
+            println!("game {game_index}\n\n{}", render(board.board));
+            print_status(http_rpc, url_path, game_index).await
+        }
+        Some(("play", sub_matches)) => play(http_rpc, url_path, sub_matches).await,
+        Some(("history", sub_matches)) => {
+            let game_index = if let Some(game_index) = sub_matches.get_one::<usize>("game") {
+                *game_index
+            } else {
+                client::current_game(http_rpc, url_path).await?
+            };
+            let history = client::get_move_history(http_rpc, url_path, game_index).await?;
+
+            println!("game {game_index}");
+            for (i, m) in history.moves.iter().enumerate() {
+                let (row, col) = game::position(m.cell);
+                let by = m
+                    .address
+                    .as_ref()
+                    .map(|address| format!(" by {address}"))
+                    .unwrap_or_default();
+                println!(
+                    "{:>2}. {} at ({row}, {col}){by}, height {}",
+                    i + 1,
+                    game::symbol(m.player),
+                    m.height
+                );
+            }
+            if history.moves.is_empty() {
+                println!("no moves yet");
+            }
+            Ok(())
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "unknown client subcommand",
+        )),
+    }
+}
+
+/// Proposes the move described by `matches` in the current game.
+async fn play(http_rpc: &str, url_path: &str, matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let row = *matches.get_one::<u8>("row").ok_or_else(|| missing("row"))?;
+    let col = *matches.get_one::<u8>("col").ok_or_else(|| missing("col"))?;
+
+    let game_index = client::current_game(http_rpc, url_path).await?;
+    let player = match matches.get_one::<Player>("player") {
+        Some(Player::X) => 1,
+        Some(Player::O) => 2,
+        None => {
+            client::whose_turn(http_rpc, url_path, game_index)
+                .await?
+                .player
+        }
+    };
+    let action = game::encode_action(player, row * game::SIDE + col);
+
+    let max_fee = matches.get_one::<u64>("max-fee").copied();
+    let signature = if let Some(path) = matches.get_one::<String>("keyfile") {
+        let key = Keyfile::load(path)?.decrypt(&keygen::read_password(matches, false)?)?;
+        Some(block::sign_move(&key, game_index, action, max_fee)?)
+    } else {
+        None
+    };
+
+    let resp =
+        client::propose_move(http_rpc, url_path, ProposedMoveArgs {
+            action,
+            signature,
+            work: None,
+            max_fee,
+        }).await?;
+    println!(
+        "proposed {} at ({row}, {col}) in block {} (height {})\n\n{}",
+        game::symbol(player),
+        resp.block_id,
+        resp.height,
+        render(resp.predicted.board)
+    );
+    Ok(())
+}
+
+/// Prints whose turn it is in the given game, or how it ended.
+async fn print_status(http_rpc: &str, url_path: &str, game_index: usize) -> io::Result<()> {
+    match client::get_game_status(http_rpc, url_path, game_index)
+        .await?
+        .status
+    {
+        GameStatus::InProgress { next_player } => {
+            println!("\n{} to move", game::symbol(next_player));
+        }
+        GameStatus::Won { player, at_height } => {
+            println!("\n{} won at height {at_height}", game::symbol(player));
+        }
+        GameStatus::Drawn { at_height } => println!("\ndrawn at height {at_height}"),
+    }
+    Ok(())
+}
+
+/// Renders the board as ASCII rows, separated by rules.
+fn render(board: u32) -> String {
+    game::grid(board)
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|mark| format!(" {mark:1} "))
+                .collect::<Vec<_>>()
+                .join("|")
+        })
+        .collect::<Vec<_>>()
+        .join("\n---+---+---\n")
+}
@@ -0,0 +1,170 @@
+use std::{
+    fmt::Write,
+    io::{self, Error, ErrorKind},
+    str::FromStr,
+};
+
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command, ValueEnum};
+use timestampvm::genesis::{Allocation, Genesis, Treasury, MAX_MOVE_WORK};
+
+pub const NAME: &str = "genesis";
+
+/// Form the genesis is printed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Genesis JSON
+    Json,
+    /// 0x-prefixed hex of the genesis bytes, as taken by `platform.createBlockchain`
+    Hex,
+}
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Prints the genesis of a new chain, ready for a Create Chain transaction")
+        .arg(arg!(<DATA> "Genesis message data"))
+        .arg(
+            arg!(--format <FORMAT> "Form the genesis is printed in")
+                .value_parser(value_parser!(Format))
+                .default_value("json"),
+        )
+        .arg(allocation_arg())
+        .arg(move_fee_arg())
+        .arg(max_move_fee_arg())
+        .arg(game_stake_arg())
+        .arg(treasury_arg())
+        .arg(move_work_arg())
+        .arg(arg!(--validate "Checks the genesis bytes decode back to the same genesis"))
+        .arg_required_else_help(true)
+}
+
+/// Prints the genesis described by the subcommand's arguments.
+/// # Errors
+/// Fails if the genesis can't be encoded, or doesn't round-trip when validated.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let data = matches
+        .get_one::<String>("DATA")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing genesis data"))?;
+    let genesis = Genesis {
+        data: data.clone(),
+        allocations: allocations(matches),
+        move_fee: move_fee(matches),
+        max_move_fee: max_move_fee(matches),
+        game_stake: game_stake(matches),
+        treasury: treasury(matches),
+        move_work: move_work(matches),
+    };
+    let bytes = genesis.to_vec()?;
+
+    if matches.get_flag("validate") {
+        let decoded = Genesis::from_slice(&bytes)?;
+        if decoded != genesis {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("genesis decodes to {decoded} rather than {genesis}"),
+            ));
+        }
+        log::info!("genesis round-trips through the Vm's parser");
+    }
+
+    match matches.get_one::<Format>("format").copied().unwrap_or(Format::Json) {
+        Format::Json => println!("{genesis}"),
+        Format::Hex => println!("{}", to_hex(&bytes)),
+    }
+    Ok(())
+}
+
+/// Returns the repeatable `--allocation` argument, crediting an address at genesis.
+#[must_use]
+pub fn allocation_arg() -> clap::Arg {
+    arg!(--allocation <ALLOCATION> "Tokens credited at genesis, as ADDRESS=BALANCE")
+        .value_parser(Allocation::from_str)
+        .action(ArgAction::Append)
+        .required(false)
+}
+
+/// Returns the allocations passed with [`allocation_arg`].
+#[must_use]
+pub fn allocations(matches: &ArgMatches) -> Vec<Allocation> {
+    matches
+        .get_many::<Allocation>("allocation")
+        .map(|allocations| allocations.cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Returns the `--move-fee` argument, setting the tokens each move costs.
+#[must_use]
+pub fn move_fee_arg() -> clap::Arg {
+    arg!(--"move-fee" <FEE> "Tokens burned from the signer's balance for each move")
+        .value_parser(value_parser!(u64))
+        .default_value("0")
+}
+
+/// Returns the fee passed with [`move_fee_arg`].
+#[must_use]
+pub fn move_fee(matches: &ArgMatches) -> u64 {
+    matches.get_one::<u64>("move-fee").copied().unwrap_or_default()
+}
+
+/// Returns the `--max-move-fee` argument, capping the fee congestion raises it to.
+#[must_use]
+pub fn max_move_fee_arg() -> clap::Arg {
+    arg!(--"max-move-fee" <FEE> "Cap the move fee rises to as the mempool fills up")
+        .value_parser(value_parser!(u64))
+        .default_value("0")
+}
+
+/// Returns the cap passed with [`max_move_fee_arg`].
+#[must_use]
+pub fn max_move_fee(matches: &ArgMatches) -> u64 {
+    matches.get_one::<u64>("max-move-fee").copied().unwrap_or_default()
+}
+
+/// Returns the `--game-stake` argument, setting the tokens escrowed per seat.
+#[must_use]
+pub fn game_stake_arg() -> clap::Arg {
+    arg!(--"game-stake" <STAKE> "Tokens escrowed from each address taking a seat in a game")
+        .value_parser(value_parser!(u64))
+        .default_value("0")
+}
+
+/// Returns the stake passed with [`game_stake_arg`].
+#[must_use]
+pub fn game_stake(matches: &ArgMatches) -> u64 {
+    matches.get_one::<u64>("game-stake").copied().unwrap_or_default()
+}
+
+/// Returns the `--treasury` argument, setting where move fees go.
+#[must_use]
+pub fn treasury_arg() -> clap::Arg {
+    arg!(--treasury <TREASURY> "Address credited with a share of move fees, as ADDRESS=PERCENT")
+        .value_parser(Treasury::from_str)
+        .required(false)
+}
+
+/// Returns the treasury passed with [`treasury_arg`], if any.
+#[must_use]
+pub fn treasury(matches: &ArgMatches) -> Option<Treasury> {
+    matches.get_one::<Treasury>("treasury").cloned()
+}
+
+/// Returns the `--move-work` argument, setting the proof of work each move needs.
+#[must_use]
+pub fn move_work_arg() -> clap::Arg {
+    arg!(--"move-work" <BITS> "Leading zero bits each move's proof of work must have")
+        .value_parser(value_parser!(u8).range(..=i64::from(MAX_MOVE_WORK)))
+        .default_value("0")
+}
+
+/// Returns the work passed with [`move_work_arg`].
+#[must_use]
+pub fn move_work(matches: &ArgMatches) -> u8 {
+    matches.get_one::<u8>("move-work").copied().unwrap_or_default()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::from("0x"), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
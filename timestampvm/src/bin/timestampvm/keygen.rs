@@ -0,0 +1,62 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+};
+
+use avalanche_types::key::secp256k1::private_key;
+use clap::{arg, ArgMatches, Command};
+use timestampvm::keyfile::Keyfile;
+
+pub const NAME: &str = "keygen";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Generates a player key, and writes it to an encrypted keyfile")
+        .arg(arg!(--out <FILE> "Path the keyfile is written to").required(true))
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Generates a key, writes its keyfile and prints the player address.
+/// # Errors
+/// Fails if the password can't be read, or the keyfile can't be written.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let out = matches
+        .get_one::<String>("out")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing keyfile path"))?;
+    let password = read_password(matches, true)?;
+
+    let key = private_key::Key::generate()?;
+    let keyfile = Keyfile::encrypt(&key, &password)?;
+    keyfile.sync(out)?;
+
+    log::info!("wrote keyfile to '{out}'");
+    println!("{}", keyfile.address);
+    Ok(())
+}
+
+/// Reads the keyfile password from `--password-file`, or prompts for it,
+/// twice if `confirm` is set.
+/// # Errors
+/// Fails if the password can't be read, is empty, or isn't confirmed.
+pub fn read_password(matches: &ArgMatches, confirm: bool) -> io::Result<String> {
+    let password = if let Some(path) = matches.get_one::<String>("password-file") {
+        fs::read_to_string(path)?
+            .trim_end_matches(['\r', '\n'])
+            .to_string()
+    } else {
+        let password = rpassword::prompt_password("Keyfile password: ")?;
+        if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+            return Err(Error::new(ErrorKind::InvalidInput, "passwords don't match"));
+        }
+        password
+    };
+
+    if password.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "empty keyfile password",
+        ));
+    }
+    Ok(password)
+}
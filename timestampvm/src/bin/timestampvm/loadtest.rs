@@ -0,0 +1,245 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::{Duration, Instant},
+};
+
+use clap::{arg, value_parser, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::{ProposedMoveArgs, WaitForMoveArgs},
+    client,
+};
+
+pub const NAME: &str = "loadtest";
+
+/// Backoff after a failed call, or while a finished game has no legal moves.
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Drives simulated players proposing legal moves, and reports acceptance")
+        .arg(arg!(--"http-rpc" <URL> "Base URL of the node").default_value("http://127.0.0.1:9650"))
+        .arg(
+            arg!(--"url-path" <PATH> "Path of the chain's RPC endpoint")
+                .default_value("/ext/bc/standalone/rpc"),
+        )
+        .arg(
+            arg!(--players <N> "Number of concurrent players")
+                .value_parser(value_parser!(u32).range(1..))
+                .default_value("8"),
+        )
+        .arg(
+            arg!(--duration <SECS> "How long players keep proposing moves")
+                .value_parser(value_parser!(u64).range(1..))
+                .default_value("30"),
+        )
+        .arg(
+            arg!(--"accept-timeout-ms" <MS> "How long a queued move is waited on to be accepted")
+                .value_parser(value_parser!(u64).range(1..=10_000))
+                .default_value("5000"),
+        )
+}
+
+/// Outcomes of the moves proposed by one or more players.
+#[derive(Default)]
+struct Tally {
+    proposed: u32,
+    /// Refused by the node, as the cell was taken, it wasn't the player's turn, or
+    /// the mempool was full
+    rejected: u32,
+    queued: u32,
+    accepted: u32,
+    /// Another block was accepted at the height of the move's
+    superseded: u32,
+    timed_out: u32,
+    /// Calls that failed other than by the node refusing a move
+    errors: u32,
+    /// From proposing a move to the node queuing it
+    queue_latency: Vec<Duration>,
+    /// From proposing a move to seeing its block accepted
+    accept_latency: Vec<Duration>,
+}
+
+impl Tally {
+    fn merge(&mut self, other: Self) {
+        self.proposed += other.proposed;
+        self.rejected += other.rejected;
+        self.queued += other.queued;
+        self.accepted += other.accepted;
+        self.superseded += other.superseded;
+        self.timed_out += other.timed_out;
+        self.errors += other.errors;
+        self.queue_latency.extend(other.queue_latency);
+        self.accept_latency.extend(other.accept_latency);
+    }
+}
+
+/// Runs the load test, then prints its report.
+/// # Errors
+/// Fails if a player task panics.
+pub async fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let http_rpc = matches
+        .get_one::<String>("http-rpc")
+        .ok_or_else(|| missing("http-rpc"))?;
+    let url_path = matches
+        .get_one::<String>("url-path")
+        .ok_or_else(|| missing("url-path"))?;
+    let players = *matches
+        .get_one::<u32>("players")
+        .ok_or_else(|| missing("players"))?;
+    let duration = Duration::from_secs(
+        *matches
+            .get_one::<u64>("duration")
+            .ok_or_else(|| missing("duration"))?,
+    );
+    let accept_timeout_ms = *matches
+        .get_one::<u64>("accept-timeout-ms")
+        .ok_or_else(|| missing("accept-timeout-ms"))?;
+
+    log::info!("running {players} players against {http_rpc}{url_path} for {duration:?}");
+    let started = Instant::now();
+    let deadline = started + duration;
+    let handles: Vec<_> = (0..players)
+        .map(|player| {
+            let (http_rpc, url_path) = (http_rpc.clone(), url_path.clone());
+            tokio::spawn(async move {
+                simulate(player, &http_rpc, &url_path, deadline, accept_timeout_ms).await
+            })
+        })
+        .collect();
+
+    let mut tally = Tally::default();
+    for handle in handles {
+        let player = handle
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("player task failed {e}")))?;
+        tally.merge(player);
+    }
+
+    report(players, started.elapsed(), tally);
+    Ok(())
+}
+
+/// Proposes random legal moves of the current game until the deadline, waiting on
+/// each queued move to be accepted before proposing the next.
+async fn simulate(
+    player: u32,
+    http_rpc: &str,
+    url_path: &str,
+    deadline: Instant,
+    accept_timeout_ms: u64,
+) -> Tally {
+    let mut tally = Tally::default();
+    // xorshift, seeded per player so players spread over the legal moves
+    let mut seed = (0x9E37_79B9 ^ player.wrapping_mul(0x85EB_CA6B)) | 1;
+
+    while Instant::now() < deadline {
+        let legal = match client::current_game(http_rpc, url_path).await {
+            Ok(game) => client::get_legal_moves(http_rpc, url_path, game).await,
+            Err(e) => Err(e),
+        };
+        let legal = match legal {
+            Ok(legal) if !legal.moves.is_empty() => legal,
+            Ok(_) => {
+                tokio::time::sleep(RETRY_INTERVAL).await;
+                continue;
+            }
+            Err(e) => {
+                log::debug!("player {player} failed to fetch legal moves: {e}");
+                tally.errors += 1;
+                tokio::time::sleep(RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let action = legal.moves[seed as usize % legal.moves.len()].action;
+
+        tally.proposed += 1;
+        let proposed = Instant::now();
+        let args = ProposedMoveArgs {
+            action,
+            signature: None,
+            work: None,
+            max_fee: None,
+        };
+        let resp = match client::propose_move(http_rpc, url_path, args).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                log::debug!("player {player} move {action} rejected: {e}");
+                tally.rejected += 1;
+                continue;
+            }
+        };
+        tally.queued += 1;
+        tally.queue_latency.push(proposed.elapsed());
+
+        let args = WaitForMoveArgs {
+            game: legal.game,
+            last_seen_height: resp.height.saturating_sub(1),
+            timeout_ms: accept_timeout_ms,
+        };
+        match client::wait_for_move(http_rpc, url_path, args).await {
+            Ok(waited) => match waited.next_move {
+                Some(m) if m.block_id == resp.block_id => {
+                    tally.accepted += 1;
+                    tally.accept_latency.push(proposed.elapsed());
+                }
+                Some(_) => tally.superseded += 1,
+                None => tally.timed_out += 1,
+            },
+            Err(e) => {
+                log::debug!("player {player} failed to wait for {}: {e}", resp.block_id);
+                tally.errors += 1;
+            }
+        }
+    }
+    tally
+}
+
+fn report(players: u32, elapsed: Duration, mut tally: Tally) {
+    let secs = elapsed.as_secs_f64();
+    println!("{players} players for {secs:.1}s");
+    println!(
+        "proposed {}, rejected {}, queued {}",
+        tally.proposed, tally.rejected, tally.queued
+    );
+    println!(
+        "accepted {} ({:.2}/s), superseded {}, timed out {}, errors {}",
+        tally.accepted,
+        f64::from(tally.accepted) / secs,
+        tally.superseded,
+        tally.timed_out,
+        tally.errors
+    );
+    for (name, latencies) in [
+        ("queue latency ", &mut tally.queue_latency),
+        ("accept latency", &mut tally.accept_latency),
+    ] {
+        if latencies.is_empty() {
+            println!("{name}  no samples");
+            continue;
+        }
+        latencies.sort_unstable();
+        println!(
+            "{name}  p50 {:.1}ms  p90 {:.1}ms  p99 {:.1}ms  max {:.1}ms",
+            millis(percentile(latencies, 50)),
+            millis(percentile(latencies, 90)),
+            millis(percentile(latencies, 99)),
+            millis(percentile(latencies, 100)),
+        );
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty latencies.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    let rank = (sorted.len() * p + 99) / 100;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
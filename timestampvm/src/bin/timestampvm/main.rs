@@ -0,0 +1,96 @@
+pub mod client;
+pub mod genesis;
+pub mod keygen;
+pub mod loadtest;
+pub mod replay;
+pub mod sign_move;
+pub mod sign_seek;
+pub mod sign_series;
+pub mod sign_team;
+pub mod sign_tournament;
+pub mod sign_transfer;
+pub mod standalone;
+pub mod tui;
+pub mod vm;
+pub mod vm_id;
+
+use avalanche_types::subnet;
+use clap::{crate_version, Command};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+pub const APP_NAME: &str = "timestampvm";
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let matches = Command::new(APP_NAME)
+        .version(crate_version!())
+        .about("Tic-tac-toe Vm, and tooling for its chains")
+        .subcommands(vec![
+            vm::command(),
+            standalone::command(),
+            client::command(),
+            tui::command(),
+            loadtest::command(),
+            replay::command(),
+            genesis::command(),
+            keygen::command(),
+            sign_move::command(),
+            sign_seek::command(),
+            sign_series::command(),
+            sign_team::command(),
+            sign_tournament::command(),
+            sign_transfer::command(),
+            vm_id::command(),
+        ])
+        .get_matches();
+
+    timestampvm::logging::init()?;
+
+    match matches.subcommand() {
+        Some((client::NAME, sub_matches)) => client::execute(sub_matches).await,
+        Some((genesis::NAME, sub_matches)) => genesis::execute(sub_matches),
+        Some((keygen::NAME, sub_matches)) => keygen::execute(sub_matches),
+        Some((loadtest::NAME, sub_matches)) => loadtest::execute(sub_matches).await,
+        Some((replay::NAME, sub_matches)) => replay::execute(sub_matches).await,
+        Some((sign_move::NAME, sub_matches)) => sign_move::execute(sub_matches),
+        Some((sign_seek::NAME, sub_matches)) => sign_seek::execute(sub_matches),
+        Some((sign_series::NAME, sub_matches)) => sign_series::execute(sub_matches),
+        Some((sign_team::NAME, sub_matches)) => sign_team::execute(sub_matches),
+        Some((sign_tournament::NAME, sub_matches)) => sign_tournament::execute(sub_matches),
+        Some((sign_transfer::NAME, sub_matches)) => sign_transfer::execute(sub_matches),
+        Some((standalone::NAME, sub_matches)) => standalone::execute(sub_matches).await,
+        Some((tui::NAME, sub_matches)) => tui::execute(sub_matches).await,
+        Some((vm_id::NAME, sub_matches)) => {
+            let vm_name = sub_matches.get_one::<String>("VM_NAME").expect("required");
+            let id = subnet::vm_name_to_id(vm_name)?;
+            println!("{id}");
+            Ok(())
+        }
+        Some((vm::NAME, sub_matches)) => run_vm(vm::otlp_endpoint(sub_matches)).await,
+        // avalanchego starts plugins without arguments
+        _ => run_vm(None).await,
+    }
+}
+
+/// Serves the Vm to avalanchego until it is stopped, exporting tracing spans to
+/// the OTLP collector at `otlp_endpoint` if set.
+async fn run_vm(otlp_endpoint: Option<String>) -> std::io::Result<()> {
+    #[cfg(feature = "otlp")]
+    if let Some(endpoint) = otlp_endpoint {
+        timestampvm::telemetry::init_otlp(&endpoint)?;
+    }
+    #[cfg(not(feature = "otlp"))]
+    if otlp_endpoint.is_some() {
+        log::warn!("built without the otlp feature, tracing spans won't be exported");
+    }
+
+    log::info!("starting timestampvm");
+    let (stop_ch_tx, stop_ch_rx): (Sender<()>, Receiver<()>) = broadcast::channel(1);
+    let vm_server =
+        subnet::rpc::vm::server::Server::new(timestampvm::vm::Vm::new(), stop_ch_tx);
+    let served = subnet::rpc::vm::serve(vm_server, stop_ch_rx).await;
+
+    #[cfg(feature = "otlp")]
+    timestampvm::telemetry::shutdown();
+    served
+}
@@ -0,0 +1,262 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Error, ErrorKind, Write},
+};
+
+use avalanche_types::{choices::status::Status, ids};
+use clap::{arg, ArgMatches, Command};
+use serde::{Deserialize, Serialize};
+use timestampvm::{
+    api::chain_handlers::{
+        GetBlockByHeightResponse, GetWinnersArgs, StreamBlocksArgs, MAX_WINNERS_PAGE,
+    },
+    client,
+    game::GameResult,
+    genesis::Genesis,
+    state::State,
+};
+
+pub const NAME: &str = "replay";
+
+/// Line of a block log, which holds accepted blocks in height order, each
+/// optionally followed by a snapshot of the state the chain reached with it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Entry {
+    Block(Box<GetBlockByHeightResponse>),
+    Snapshot(Snapshot),
+}
+
+/// Chain state recorded as of the block at `height`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct Snapshot {
+    height: u64,
+    /// Index of the game in progress
+    game: usize,
+    /// Board once the block's move was played, unknown if the node pruned it
+    board: Option<u32>,
+    winners: Vec<GameResult>,
+}
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Exports accepted blocks, and replays them offline through the game rules")
+        .subcommands(vec![
+            Command::new("export")
+                .about(
+                    "Writes the accepted blocks of a node to a block log, then snapshots its state",
+                )
+                .arg(
+                    arg!(--"http-rpc" <URL> "Base URL of the node")
+                        .default_value("http://127.0.0.1:9650"),
+                )
+                .arg(
+                    arg!(--"url-path" <PATH> "Path of the chain's RPC endpoint")
+                        .default_value("/ext/bc/standalone/rpc"),
+                )
+                .arg(arg!(--out <FILE> "Path the block log is written to").required(true)),
+            Command::new("verify")
+                .about("Replays a block log, checking the recomputed state against its snapshots")
+                .arg(arg!(<FILE> "Block log to replay"))
+                .arg(arg!(--genesis <FILE> "Genesis of the chain, for its balances and fees")),
+        ])
+        .subcommand_required(true)
+}
+
+/// Runs the replay subcommand.
+/// # Errors
+/// Fails if the log can't be exported or read, or doesn't replay to its snapshots.
+pub async fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing {arg}"));
+    match matches.subcommand() {
+        Some(("export", sub_matches)) => {
+            let http_rpc = sub_matches
+                .get_one::<String>("http-rpc")
+                .ok_or_else(|| missing("--http-rpc"))?;
+            let url_path = sub_matches
+                .get_one::<String>("url-path")
+                .ok_or_else(|| missing("--url-path"))?;
+            let out = sub_matches
+                .get_one::<String>("out")
+                .ok_or_else(|| missing("--out"))?;
+            export(http_rpc, url_path, out).await
+        }
+        Some(("verify", sub_matches)) => {
+            let path = sub_matches
+                .get_one::<String>("FILE")
+                .ok_or_else(|| missing("FILE"))?;
+            let genesis = match sub_matches.get_one::<String>("genesis") {
+                Some(genesis) => Genesis::from_slice(fs::read(genesis)?)?,
+                None => Genesis::default(),
+            };
+            verify(path, &genesis).await
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "unknown replay subcommand",
+        )),
+    }
+}
+
+/// Writes the blocks the node accepted so far, then a snapshot as of the last one.
+async fn export(http_rpc: &str, url_path: &str, out: &str) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(out)?);
+
+    let mut from_height = 0;
+    let last_height = loop {
+        let args = StreamBlocksArgs {
+            from_height,
+            limit: None,
+        };
+        let chunk = client::stream_blocks(http_rpc, url_path, args).await?;
+        let last_height = chunk.height;
+        for block in chunk.blocks {
+            if block.block.height() <= last_height {
+                write_entry(&mut w, &Entry::Block(Box::new(block)))?;
+            }
+        }
+        if chunk.next_height > last_height {
+            break last_height;
+        }
+        from_height = chunk.next_height;
+    };
+
+    // Blocks accepted while exporting are left out of the snapshot
+    let mut winners = Vec::new();
+    loop {
+        let args = GetWinnersArgs {
+            offset: winners.len(),
+            limit: None,
+        };
+        let page = client::get_winners(http_rpc, url_path, args).await?;
+        let done = page.results.len() < MAX_WINNERS_PAGE;
+        winners.extend(page.results.into_iter().map(|entry| entry.result));
+        if done {
+            break;
+        }
+    }
+    winners.retain(|result| result.height <= last_height);
+    let board = match client::get_board_at_height(http_rpc, url_path, last_height).await {
+        Ok(board) => Some(board.board),
+        Err(e) => {
+            log::warn!("board at height {last_height} left out of the snapshot: {e}");
+            None
+        }
+    };
+    let snapshot = Snapshot {
+        height: last_height,
+        game: winners.len(),
+        board,
+        winners,
+    };
+    write_entry(&mut w, &Entry::Snapshot(snapshot))?;
+    w.flush()?;
+
+    println!("exported {} blocks to '{out}'", last_height + 1);
+    Ok(())
+}
+
+fn write_entry(w: &mut impl Write, entry: &Entry) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, entry)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize entry {e}")))?;
+    w.write_all(b"\n")
+}
+
+/// Replays the log into a fresh in-memory state, comparing it to each snapshot.
+async fn verify(path: &str, genesis: &Genesis) -> io::Result<()> {
+    let state = State {
+        move_fee: genesis.move_fee,
+        max_move_fee: genesis.max_move_fee,
+        game_stake: genesis.game_stake,
+        treasury: genesis.treasury.clone(),
+        move_work: genesis.move_work,
+        ..State::default()
+    };
+    state.seed_balances(&genesis.allocations).await?;
+    let mut parent_id = None;
+    let (mut blocks, mut snapshots, mut mismatches) = (0_u64, 0_usize, Vec::new());
+
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {} isn't a block log entry {e}", i + 1),
+            )
+        })?;
+
+        match entry {
+            Entry::Block(entry) => {
+                let GetBlockByHeightResponse { id, block } = *entry;
+                // Re-encodes the block, so its Id is recomputed from its contents
+                let mut block = block
+                    .with_parent(block.parent_id(), block.height())?
+                    .with_format_of(&id)?;
+                block.set_status(Status::Accepted);
+                if block.id() != id {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "block {id} at height {} encodes to {}",
+                            block.height(),
+                            block.id()
+                        ),
+                    ));
+                }
+                state
+                    .replay_block(&block, &parent_id.unwrap_or_else(ids::Id::empty))
+                    .await?;
+                parent_id = Some(id);
+                blocks += 1;
+            }
+            Entry::Snapshot(recorded) => {
+                snapshots += 1;
+                let height = state.get_height().await;
+                if parent_id.is_none() || recorded.height != height {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "snapshot at height {} follows block at height {height}",
+                            recorded.height
+                        ),
+                    ));
+                }
+                let (winners, _) = state.get_winners(0, usize::MAX).await;
+                let replayed = Snapshot {
+                    height,
+                    game: state.get_curr_game_index().await,
+                    board: match recorded.board {
+                        Some(_) => Some(state.get_board_at_height(height).await?),
+                        None => None,
+                    },
+                    winners,
+                };
+                if replayed == recorded {
+                    log::info!("snapshot at height {height} matches");
+                } else {
+                    log::error!("snapshot at height {height} differs from the replayed state");
+                    mismatches.push((recorded, replayed));
+                }
+            }
+        }
+    }
+
+    println!("replayed {blocks} blocks, checked {snapshots} snapshots");
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    for (recorded, replayed) in &mismatches {
+        println!("recorded {recorded:?}\nreplayed {replayed:?}");
+    }
+    Err(Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "{} snapshots differ from the replayed state",
+            mismatches.len()
+        ),
+    ))
+}
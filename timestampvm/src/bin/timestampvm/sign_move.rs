@@ -0,0 +1,103 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{arg, value_parser, ArgMatches, Command, ValueEnum};
+use timestampvm::{
+    api::chain_handlers::ProposedMoveArgs, block, game, genesis::MAX_MOVE_WORK, keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-move";
+
+/// Player a move is made for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Player {
+    /// Player 1, who opens each game
+    X,
+    /// Player 2
+    O,
+}
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a move, printing the params of a proposeMove call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the player signing the move").required(true))
+        .arg(
+            arg!(--game <GAME> "Index of the game the move is made in")
+                .value_parser(value_parser!(usize))
+                .required(true),
+        )
+        .arg(
+            arg!(--player <PLAYER> "Player the move is made for")
+                .value_parser(value_parser!(Player))
+                .required(true),
+        )
+        .arg(
+            arg!(--row <ROW> "Row of the marked cell, from 0")
+                .value_parser(value_parser!(u8).range(..i64::from(game::SIDE)))
+                .required(true),
+        )
+        .arg(
+            arg!(--col <COL> "Column of the marked cell, from 0")
+                .value_parser(value_parser!(u8).range(..i64::from(game::SIDE)))
+                .required(true),
+        )
+        .arg(
+            arg!(--work <BITS> "Grinds a proof of work with as many leading zero bits")
+                .value_parser(value_parser!(u8).range(..=i64::from(MAX_MOVE_WORK)))
+                .required(false),
+        )
+        .arg(
+            arg!(--"max-fee" <FEE> "Most the player agrees to pay for the move, else the base fee")
+                .value_parser(value_parser!(u64))
+                .required(false),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Signs the move described by the subcommand's arguments, and prints it as JSON.
+/// # Errors
+/// Fails if the keyfile can't be decrypted, or the move can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let game_index = *matches
+        .get_one::<usize>("game")
+        .ok_or_else(|| missing("game"))?;
+    let player = *matches
+        .get_one::<Player>("player")
+        .ok_or_else(|| missing("player"))?;
+    let row = *matches.get_one::<u8>("row").ok_or_else(|| missing("row"))?;
+    let col = *matches.get_one::<u8>("col").ok_or_else(|| missing("col"))?;
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let player = match player {
+        Player::X => 1,
+        Player::O => 2,
+    };
+    let action = game::encode_action(player, row * game::SIDE + col);
+    let max_fee = matches.get_one::<u64>("max-fee").copied();
+    let signature = block::sign_move(&key, game_index, action, max_fee)?;
+    let args = ProposedMoveArgs {
+        action,
+        work: matches
+            .get_one::<u8>("work")
+            .map(|bits| block::grind_work(game_index, action, &signature, *bits)),
+        signature: Some(signature),
+        max_fee,
+    };
+    log::info!(
+        "signed move {action} of game {game_index} as {}",
+        keyfile.address
+    );
+
+    let json = serde_json::to_string(&args)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize move {e}")))?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{arg, value_parser, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::SeekGameArgs,
+    block::{self, Seek},
+    keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-seek";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a seek, printing the params of a seekGame call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the address seeking a game").required(true))
+        .arg(
+            arg!(--nonce <NONCE> "Nonce of the seeker, as reported by getNonce")
+                .value_parser(value_parser!(u64))
+                .required(true),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Signs the seek described by the subcommand's arguments, and prints it as JSON.
+/// # Errors
+/// Fails if the keyfile can't be decrypted, or the seek can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let nonce = *matches
+        .get_one::<u64>("nonce")
+        .ok_or_else(|| missing("nonce"))?;
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let seek = Seek { nonce };
+    let args = SeekGameArgs {
+        signature: block::sign_seek(&key, &seek)?,
+        seek,
+    };
+    log::info!("signed seek as {}", keyfile.address);
+
+    let json = serde_json::to_string(&args)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize seek {e}")))?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::io::{self, Error, ErrorKind};
+
+use avalanche_types::ids::short;
+use clap::{arg, value_parser, ArgGroup, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::ProposeSeriesActionArgs,
+    block::{self, SeriesAction},
+    keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-series";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a series action, printing the params of a proposeSeriesAction call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the address taking the action").required(true))
+        .arg(
+            arg!(--nonce <NONCE> "Nonce of the address, as reported by getNonce")
+                .value_parser(value_parser!(u64))
+                .required(true),
+        )
+        .arg(arg!(--challenge <ADDRESS> "Challenges this address to a series"))
+        .arg(
+            arg!(--games <GAMES> "Number of games the challenged series is played over")
+                .value_parser(value_parser!(u32))
+                .default_value("3"),
+        )
+        .arg(arg!(--accept <SERIES> "Accepts the given series").value_parser(value_parser!(usize)))
+        .group(
+            ArgGroup::new("action")
+                .args(["challenge", "accept"])
+                .required(true),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Signs the series action described by the subcommand's arguments, and prints it
+/// as JSON.
+/// # Errors
+/// Fails if the challenged address is malformed, the keyfile can't be decrypted, or
+/// the action can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let nonce = *matches
+        .get_one::<u64>("nonce")
+        .ok_or_else(|| missing("nonce"))?;
+    let action = match (
+        matches.get_one::<String>("challenge"),
+        matches.get_one::<usize>("accept"),
+    ) {
+        (Some(opponent), _) => {
+            // The decoder panics on strings too short to hold the checksum
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid address '{opponent}'"),
+                )
+            };
+            if opponent.len() < short::LEN {
+                return Err(invalid());
+            }
+            SeriesAction::Challenge {
+                opponent: opponent.parse::<short::Id>().map_err(|_| invalid())?,
+                games: *matches
+                    .get_one::<u32>("games")
+                    .ok_or_else(|| missing("games"))?,
+                nonce,
+            }
+        }
+        (None, Some(series)) => SeriesAction::Accept {
+            series: *series,
+            nonce,
+        },
+        (None, None) => return Err(missing("challenge or --accept")),
+    };
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let args = ProposeSeriesActionArgs {
+        signature: block::sign_series_action(&key, &action)?,
+        action,
+    };
+    log::info!("signed series action as {}", keyfile.address);
+
+    let json = serde_json::to_string(&args).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize series action {e}"),
+        )
+    })?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Error, ErrorKind};
+
+use avalanche_types::ids::short;
+use clap::{arg, value_parser, ArgGroup, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::ProposeTeamActionArgs,
+    block::{self, TeamAction},
+    keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-team";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a team action, printing the params of a proposeTeamAction call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the address taking the action").required(true))
+        .arg(
+            arg!(--nonce <NONCE> "Nonce of the address, as reported by getNonce")
+                .value_parser(value_parser!(u64))
+                .required(true),
+        )
+        .arg(arg!(--partner <ADDRESS> "Challenges two opponents to a team game with this partner"))
+        .arg(
+            arg!(--opponents <ADDRESS> "Opponents of the challenged team game, in turn order")
+                .num_args(2)
+                .requires("partner"),
+        )
+        .arg(
+            arg!(--accept <TEAM_GAME> "Accepts the given team game")
+                .value_parser(value_parser!(usize)),
+        )
+        .group(
+            ArgGroup::new("action")
+                .args(["partner", "accept"])
+                .required(true),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Parses an address given on the command line.
+fn parse_address(address: &str) -> io::Result<short::Id> {
+    // The decoder panics on strings too short to hold the checksum
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid address '{address}'"),
+        )
+    };
+    if address.len() < short::LEN {
+        return Err(invalid());
+    }
+    address.parse::<short::Id>().map_err(|_| invalid())
+}
+
+/// Signs the team action described by the subcommand's arguments, and prints it as
+/// JSON.
+/// # Errors
+/// Fails if an address is malformed or missing, the keyfile can't be decrypted, or
+/// the action can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let nonce = *matches
+        .get_one::<u64>("nonce")
+        .ok_or_else(|| missing("nonce"))?;
+    let action = match (
+        matches.get_one::<String>("partner"),
+        matches.get_one::<usize>("accept"),
+    ) {
+        (Some(partner), _) => {
+            let opponents = matches
+                .get_many::<String>("opponents")
+                .ok_or_else(|| missing("opponents"))?
+                .map(|opponent| parse_address(opponent))
+                .collect::<io::Result<Vec<_>>>()?;
+            match <[short::Id; 2]>::try_from(opponents) {
+                Ok(opponents) => TeamAction::Challenge {
+                    partner: parse_address(partner)?,
+                    opponents,
+                    nonce,
+                },
+                Err(_) => return Err(missing("opponents")),
+            }
+        }
+        (None, Some(team_game)) => TeamAction::Accept {
+            team_game: *team_game,
+            nonce,
+        },
+        (None, None) => return Err(missing("partner or --accept")),
+    };
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let args = ProposeTeamActionArgs {
+        signature: block::sign_team_action(&key, &action)?,
+        action,
+    };
+    log::info!("signed team action as {}", keyfile.address);
+
+    let json = serde_json::to_string(&args).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize team action {e}"),
+        )
+    })?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{arg, value_parser, ArgGroup, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::ProposeTournamentActionArgs,
+    block::{self, TournamentAction},
+    keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-tournament";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a tournament action, printing the params of a proposeTournamentAction call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the address taking the action").required(true))
+        .arg(
+            arg!(--nonce <NONCE> "Nonce of the address, as reported by getNonce")
+                .value_parser(value_parser!(u64))
+                .required(true),
+        )
+        .arg(
+            arg!(--create <PLAYERS> "Creates a tournament for this many players")
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--rounds <ROUNDS> "Plays the created tournament over this many Swiss rounds")
+                .value_parser(value_parser!(u32))
+                .requires("create"),
+        )
+        .arg(
+            arg!(--register <TOURNAMENT> "Registers in the given tournament")
+                .value_parser(value_parser!(usize)),
+        )
+        .group(
+            ArgGroup::new("action")
+                .args(["create", "register"])
+                .required(true),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Signs the tournament action described by the subcommand's arguments, and prints
+/// it as JSON.
+/// # Errors
+/// Fails if the keyfile can't be decrypted, or the action can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let nonce = *matches
+        .get_one::<u64>("nonce")
+        .ok_or_else(|| missing("nonce"))?;
+    let action = match (
+        matches.get_one::<u32>("create"),
+        matches.get_one::<usize>("register"),
+    ) {
+        (Some(players), _) => TournamentAction::Create {
+            players: *players,
+            rounds: matches.get_one::<u32>("rounds").copied(),
+            nonce,
+        },
+        (None, Some(tournament)) => TournamentAction::Register {
+            tournament: *tournament,
+            nonce,
+        },
+        (None, None) => return Err(missing("create or --register")),
+    };
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let args = ProposeTournamentActionArgs {
+        signature: block::sign_tournament_action(&key, &action)?,
+        action,
+    };
+    log::info!("signed tournament action as {}", keyfile.address);
+
+    let json = serde_json::to_string(&args).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize tournament action {e}"),
+        )
+    })?;
+    println!("{json}");
+    Ok(())
+}
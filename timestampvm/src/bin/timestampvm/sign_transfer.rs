@@ -0,0 +1,82 @@
+use std::io::{self, Error, ErrorKind};
+
+use avalanche_types::ids::short;
+use clap::{arg, value_parser, ArgMatches, Command};
+use timestampvm::{
+    api::chain_handlers::ProposeTransferArgs,
+    block::{self, Transfer},
+    keyfile::Keyfile,
+};
+
+use crate::keygen;
+
+pub const NAME: &str = "sign-transfer";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Signs a transfer, printing the params of a proposeTransfer call")
+        .arg(arg!(--keyfile <FILE> "Keyfile of the address sending the tokens").required(true))
+        .arg(arg!(--to <ADDRESS> "Address receiving the tokens").required(true))
+        .arg(
+            arg!(--amount <AMOUNT> "Number of tokens to send")
+                .value_parser(value_parser!(u64).range(1..))
+                .required(true),
+        )
+        .arg(
+            arg!(--nonce <NONCE> "Nonce of the sender, as reported by getBalance")
+                .value_parser(value_parser!(u64))
+                .required(true),
+        )
+        .arg(arg!(--"password-file" <FILE> "Reads the password from a file rather than prompting"))
+}
+
+/// Signs the transfer described by the subcommand's arguments, and prints it as JSON.
+/// # Errors
+/// Fails if the recipient is malformed, the keyfile can't be decrypted, or the
+/// transfer can't be signed.
+pub fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let path = matches
+        .get_one::<String>("keyfile")
+        .ok_or_else(|| missing("keyfile"))?;
+    let to = matches
+        .get_one::<String>("to")
+        .ok_or_else(|| missing("to"))?;
+    let amount = *matches
+        .get_one::<u64>("amount")
+        .ok_or_else(|| missing("amount"))?;
+    let nonce = *matches
+        .get_one::<u64>("nonce")
+        .ok_or_else(|| missing("nonce"))?;
+
+    // The decoder panics on strings too short to hold the checksum
+    let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid address '{to}'"));
+    if to.len() < short::LEN {
+        return Err(invalid());
+    }
+    let to = to.parse::<short::Id>().map_err(|_| invalid())?;
+
+    let keyfile = Keyfile::load(path)?;
+    let key = keyfile.decrypt(&keygen::read_password(matches, false)?)?;
+
+    let transfer = Transfer { to, amount, nonce };
+    let args = ProposeTransferArgs {
+        signature: block::sign_transfer(&key, &transfer)?,
+        transfer,
+    };
+    log::info!(
+        "signed transfer of {amount} to {} as {}",
+        args.transfer.to,
+        keyfile.address
+    );
+
+    let json = serde_json::to_string(&args).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize transfer {e}"),
+        )
+    })?;
+    println!("{json}");
+    Ok(())
+}
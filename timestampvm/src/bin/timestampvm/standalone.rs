@@ -0,0 +1,59 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    net::SocketAddr,
+};
+
+use clap::{arg, value_parser, ArgMatches, Command};
+use timestampvm::genesis::Genesis;
+
+use crate::genesis;
+
+pub const NAME: &str = "standalone";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Runs the Vm without avalanchego, accepting every proposed move right away")
+        .arg(
+            arg!(--"http-addr" <ADDR> "Address to serve the chain handlers on")
+                .value_parser(value_parser!(SocketAddr))
+                .default_value("127.0.0.1:9650"),
+        )
+        .arg(arg!(--"genesis-data" <DATA> "Genesis message data").required(false))
+        .arg(genesis::allocation_arg())
+        .arg(genesis::move_fee_arg())
+        .arg(genesis::max_move_fee_arg())
+        .arg(genesis::game_stake_arg())
+        .arg(genesis::treasury_arg())
+        .arg(genesis::move_work_arg())
+        .arg(arg!(--config <FILE> "Chain config JSON file").required(false))
+}
+
+/// Serves a local chain until the server fails.
+/// # Errors
+/// Fails if the config can't be read, or the chain can't be served.
+pub async fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let addr = matches
+        .get_one::<SocketAddr>("http-addr")
+        .copied()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "missing HTTP address"))?;
+    let mut genesis = matches
+        .get_one::<String>("genesis-data")
+        .map_or_else(Genesis::default, |data| Genesis {
+            data: data.clone(),
+            ..Default::default()
+        });
+    genesis.allocations = genesis::allocations(matches);
+    genesis.move_fee = genesis::move_fee(matches);
+    genesis.max_move_fee = genesis::max_move_fee(matches);
+    genesis.game_stake = genesis::game_stake(matches);
+    genesis.treasury = genesis::treasury(matches);
+    genesis.move_work = genesis::move_work(matches);
+    let config = match matches.get_one::<String>("config") {
+        Some(path) => fs::read(path)?,
+        None => Vec::new(),
+    };
+
+    timestampvm::standalone::serve(addr, &genesis.to_vec()?, &config).await
+}
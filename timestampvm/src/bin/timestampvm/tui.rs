@@ -0,0 +1,287 @@
+use std::{
+    io::{self, Error, ErrorKind, Write},
+    thread,
+    time::Duration,
+};
+
+use avalanche_types::key::secp256k1::private_key;
+use clap::{arg, value_parser, ArgMatches, Command};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use timestampvm::{
+    api::chain_handlers::ProposedMoveArgs,
+    block, client,
+    game::{self, GameStatus},
+    keyfile::Keyfile,
+};
+use tokio::sync::mpsc;
+
+use crate::keygen;
+
+pub const NAME: &str = "tui";
+
+#[must_use]
+pub fn command() -> Command {
+    Command::new(NAME)
+        .about("Plays on a chain from an interactive terminal board")
+        .arg(arg!(--"http-rpc" <URL> "Base URL of the node").default_value("http://127.0.0.1:9650"))
+        .arg(
+            arg!(--"url-path" <PATH> "Path of the chain's RPC endpoint")
+                .default_value("/ext/bc/standalone/rpc"),
+        )
+        .arg(
+            arg!(--"refresh-ms" <MS> "Interval the board is polled at")
+                .value_parser(value_parser!(u64).range(100..))
+                .default_value("1000"),
+        )
+        .arg(arg!(--keyfile <FILE> "Signs moves with the player's keyfile"))
+        .arg(arg!(--"password-file" <FILE> "Reads the keyfile password from a file"))
+}
+
+/// What the terminal shows of the game in progress.
+struct Screen {
+    game: usize,
+    board: u32,
+    status: Option<GameStatus>,
+    /// Selected (row, column)
+    cursor: (u8, u8),
+    message: String,
+}
+
+/// Puts the terminal in raw mode on an alternate screen, until dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        stdout.flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Runs the terminal client until the player quits.
+/// # Errors
+/// Fails if the keyfile can't be decrypted, or the terminal can't be driven.
+pub async fn execute(matches: &ArgMatches) -> io::Result<()> {
+    let missing = |arg: &str| Error::new(ErrorKind::InvalidInput, format!("missing --{arg}"));
+    let http_rpc = matches
+        .get_one::<String>("http-rpc")
+        .ok_or_else(|| missing("http-rpc"))?;
+    let url_path = matches
+        .get_one::<String>("url-path")
+        .ok_or_else(|| missing("url-path"))?;
+    let refresh = Duration::from_millis(
+        *matches
+            .get_one::<u64>("refresh-ms")
+            .ok_or_else(|| missing("refresh-ms"))?,
+    );
+    // prompts for the password before the terminal goes raw
+    let key = if let Some(path) = matches.get_one::<String>("keyfile") {
+        Some(Keyfile::load(path)?.decrypt(&keygen::read_password(matches, false)?)?)
+    } else {
+        None
+    };
+
+    // crossterm reads block, so keys are forwarded from a dedicated thread
+    let (keys_tx, mut keys_rx) = mpsc::unbounded_channel();
+    thread::spawn(move || loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                if keys_tx.send(key).is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    });
+
+    let _terminal = RawTerminal::enter()?;
+    let mut screen = Screen {
+        game: 0,
+        board: 0,
+        status: None,
+        cursor: (1, 1),
+        message: String::from("connecting..."),
+    };
+    let mut ticker = tokio::time::interval(refresh);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if let Err(e) = refresh_screen(http_rpc, url_path, &mut screen).await {
+                    screen.message = format!("failed to refresh: {e}");
+                }
+            }
+            pressed = keys_rx.recv() => {
+                let quit = if let Some(pressed) = pressed {
+                    handle_key(http_rpc, url_path, key.as_ref(), pressed, &mut screen).await
+                } else {
+                    true
+                };
+                if quit {
+                    return Ok(());
+                }
+            }
+        }
+        draw(&screen)?;
+    }
+}
+
+/// Polls the node for the current game and its board.
+async fn refresh_screen(http_rpc: &str, url_path: &str, screen: &mut Screen) -> io::Result<()> {
+    let game_index = client::current_game(http_rpc, url_path).await?;
+    let board = client::get_board(http_rpc, url_path).await?;
+    let status = client::get_game_status(http_rpc, url_path, game_index).await?;
+
+    if screen.status.is_none() {
+        screen.message.clear();
+    }
+    screen.game = game_index;
+    screen.board = board.board;
+    screen.status = Some(status.status);
+    Ok(())
+}
+
+/// Applies a key press, returning whether the player quit.
+async fn handle_key(
+    http_rpc: &str,
+    url_path: &str,
+    key: Option<&private_key::Key>,
+    pressed: KeyEvent,
+    screen: &mut Screen,
+) -> bool {
+    let last = game::SIDE - 1;
+    let (row, col) = screen.cursor;
+    match pressed.code {
+        KeyCode::Char('q') | KeyCode::Esc => return true,
+        KeyCode::Char('c') if pressed.modifiers.contains(KeyModifiers::CONTROL) => return true,
+        KeyCode::Up => screen.cursor = (row.saturating_sub(1), col),
+        KeyCode::Down => screen.cursor = (row.saturating_add(1).min(last), col),
+        KeyCode::Left => screen.cursor = (row, col.saturating_sub(1)),
+        KeyCode::Right => screen.cursor = (row, col.saturating_add(1).min(last)),
+        KeyCode::Char('r') => {
+            if let Err(e) = refresh_screen(http_rpc, url_path, screen).await {
+                screen.message = format!("failed to refresh: {e}");
+            }
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            screen.message = match play(http_rpc, url_path, key, screen).await {
+                Ok(message) => message,
+                Err(e) => e.to_string(),
+            };
+            if let Err(e) = refresh_screen(http_rpc, url_path, screen).await {
+                screen.message = format!("failed to refresh: {e}");
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Proposes a move at the cursor for the player whose turn it is.
+async fn play(
+    http_rpc: &str,
+    url_path: &str,
+    key: Option<&private_key::Key>,
+    screen: &Screen,
+) -> io::Result<String> {
+    let player = match screen.status {
+        Some(GameStatus::InProgress { next_player }) => next_player,
+        _ => {
+            client::whose_turn(http_rpc, url_path, screen.game)
+                .await?
+                .player
+        }
+    };
+    let (row, col) = screen.cursor;
+    let action = game::encode_action(player, row * game::SIDE + col);
+    let signature = if let Some(key) = key {
+        Some(block::sign_move(key, screen.game, action, None)?)
+    } else {
+        None
+    };
+
+    let resp =
+        client::propose_move(http_rpc, url_path, ProposedMoveArgs {
+            action,
+            signature,
+            work: None,
+            max_fee: None,
+        }).await?;
+    Ok(format!(
+        "proposed {} at ({row}, {col}) in block {}",
+        game::symbol(player),
+        resp.block_id
+    ))
+}
+
+/// Redraws the whole screen.
+fn draw(screen: &Screen) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    queue!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        Print(format!("tic-tac-toe, game {}", screen.game)),
+    )?;
+
+    let grid = game::grid(screen.board);
+    for (r, cells) in (0u8..).zip(grid.iter()) {
+        let y = 2 + 2 * u16::from(r);
+        if r > 0 {
+            queue!(stdout, cursor::MoveTo(2, y - 1), Print("---+---+---"))?;
+        }
+        for (c, mark) in (0u8..).zip(cells.iter()) {
+            let selected = screen.cursor == (r, c);
+            queue!(stdout, cursor::MoveTo(2 + 4 * u16::from(c), y))?;
+            if selected {
+                queue!(stdout, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(stdout, Print(format!(" {mark:1} ")))?;
+            if selected {
+                queue!(stdout, SetAttribute(Attribute::Reset))?;
+            }
+            if c + 1 < game::SIDE {
+                queue!(stdout, Print("|"))?;
+            }
+        }
+    }
+
+    let status = match screen.status {
+        Some(GameStatus::InProgress { next_player }) => {
+            format!("{} to move", game::symbol(next_player))
+        }
+        Some(GameStatus::Won { player, at_height }) => {
+            format!("{} won at height {at_height}", game::symbol(player))
+        }
+        Some(GameStatus::Drawn { at_height }) => format!("drawn at height {at_height}"),
+        None => String::new(),
+    };
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 8),
+        Print(status),
+        cursor::MoveTo(0, 9),
+        Print(&screen.message),
+        cursor::MoveTo(0, 11),
+        Print("arrows: select  enter: play  r: refresh  q: quit"),
+    )?;
+    stdout.flush()
+}
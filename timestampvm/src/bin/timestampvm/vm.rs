@@ -0,0 +1,25 @@
+use clap::{arg, ArgMatches, Command};
+
+pub const NAME: &str = "vm";
+
+/// Runs the VM as an avalanchego plugin, as it does when started without a subcommand.
+#[must_use]
+pub fn command() -> Command {
+    let command = Command::new(NAME).about("Runs the Vm as an avalanchego plugin (default)");
+    if cfg!(feature = "otlp") {
+        return command.arg(
+            arg!(--"otlp-endpoint" <URL> "Exports tracing spans to this OTLP collector")
+                .required(false),
+        );
+    }
+    command
+}
+
+/// Returns the OTLP collector to export tracing spans to, if given.
+#[must_use]
+pub fn otlp_endpoint(matches: &ArgMatches) -> Option<String> {
+    if cfg!(feature = "otlp") {
+        return matches.get_one::<String>("otlp-endpoint").cloned();
+    }
+    None
+}
@@ -7,24 +7,549 @@ use std::{
     io::{self, Error, ErrorKind},
 };
 
-use crate::state;
+use crate::{game, state};
 use avalanche_types::{
     choices,
-    // codec::serde::hex_0x_bytes::Hex0xBytes,
-    ids,
+    codec::serde::hex_0x_bytes::Hex0xBytes,
+    hash, ids,
+    key::secp256k1::{private_key, signature::Sig},
     subnet::rpc::consensus::snowman::{self, Decidable},
 };
 // use chrono::{Duration, Utc};
 use derivative::{self, Derivative};
+use once_cell::sync::OnceCell;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
+/// Target of the structured logs emitted as blocks are verified, accepted or rejected.
+pub const LOG_TARGET: &str = "tictactoevm::blocks";
+
+/// Domain separator for signed moves.
+const MOVE_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm move";
+
+/// Domain separator for signed transfers.
+const TRANSFER_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm transfer";
+
+/// Domain separator for signed seeks.
+const SEEK_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm seek";
+
+/// Domain separator for signed tournament actions.
+const TOURNAMENT_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm tournament";
+
+/// Domain separator for signed series actions.
+const SERIES_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm series";
+
+/// Domain separator for signed team actions.
+const TEAM_DIGEST_PREFIX: &[u8] = b"tic-tac-toe-vm team";
+
+/// Leading byte of the binary encoding, versioning it. Legacy JSON blocks always
+/// start with `{`, so the two are told apart by it.
+pub const BINARY_VERSION: u8 = 1;
+
+/// Length of a binary block without its signature: version, parent Id, height
+/// and move.
+const BINARY_HEADER_LEN: usize = 1 + ids::LEN + 8 + 1;
+
+/// Encodings of a block, see [`Block::from_slice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The JSON object blocks were first encoded as
+    Json,
+    /// [`BINARY_VERSION`], then the parent Id, the big-endian height, the move and
+    /// finally the signature, if any
+    Binary,
+}
+
+impl Format {
+    /// Detects the format of encoded block bytes from their first byte.
+    /// # Errors
+    /// Fails if the bytes are empty or start with an unknown version.
+    pub fn detect(d: &[u8]) -> io::Result<Self> {
+        match d.first() {
+            Some(b'{') => Ok(Self::Json),
+            Some(&BINARY_VERSION) => Ok(Self::Binary),
+            Some(version) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown block encoding version {version}"),
+            )),
+            None => Err(Error::new(ErrorKind::InvalidData, "empty block bytes")),
+        }
+    }
+}
+
+/// Returns the SHA256 digest a player signs to attribute a move to their address.
+/// Binding the game index keeps a signed move from being replayed in later games.
+/// The most the player agrees to pay for the move, if set, is appended, so no
+/// proposer can raise its fee past it.
+#[must_use]
+pub fn move_digest(game: usize, player_move: u8, max_fee: Option<u64>) -> Vec<u8> {
+    let mut d = Vec::with_capacity(MOVE_DIGEST_PREFIX.len() + 17);
+    d.extend_from_slice(MOVE_DIGEST_PREFIX);
+    d.extend_from_slice(&(game as u64).to_be_bytes());
+    d.push(player_move);
+    if let Some(max_fee) = max_fee {
+        d.extend_from_slice(&max_fee.to_be_bytes());
+    }
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed `player_move` as part of the given game, for
+/// at most `max_fee` if set. Returns `None` if `signature` is empty.
+/// # Errors
+/// Fails if the signature is malformed.
+pub fn recover_signer(
+    game: usize,
+    player_move: u8,
+    max_fee: Option<u64>,
+    signature: &[u8],
+) -> io::Result<Option<ids::short::Id>> {
+    if signature.is_empty() {
+        return Ok(None);
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&move_digest(game, player_move, max_fee))?;
+    public_key.to_short_id().map(Some)
+}
+
+/// Signs `player_move` as part of the given game, attributing it to the key's address.
+/// The move may then pay at most `max_fee`, if set, or else only the base fee.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_move(
+    key: &private_key::Key,
+    game: usize,
+    player_move: u8,
+    max_fee: Option<u64>,
+) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&move_digest(game, player_move, max_fee))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Returns the hash a move's proof of work is measured on: its digest for the
+/// given game, without a maximum fee, followed by the move's signature and the
+/// ground nonce. Covering the signature ties the work to the address that signed
+/// the move, so no one else can reuse it.
+#[must_use]
+pub fn work_hash(game: usize, player_move: u8, signature: &[u8], nonce: u64) -> Vec<u8> {
+    let mut d = move_digest(game, player_move, None);
+    d.extend_from_slice(signature);
+    d.extend_from_slice(&nonce.to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Returns the number of leading zero bits of the move's [`work_hash`] for the nonce.
+#[must_use]
+pub fn work_bits(game: usize, player_move: u8, signature: &[u8], nonce: u64) -> u32 {
+    let hash = work_hash(game, player_move, signature, nonce);
+    let zero_bytes = hash.iter().take_while(|b| **b == 0).count();
+    let bits = hash.get(zero_bytes).map_or(0, |b| b.leading_zeros());
+    // A sha256 hash has 32 bytes, so this can't overflow
+    u32::try_from(zero_bytes * 8).unwrap_or(u32::MAX) + bits
+}
+
+/// Grinds the first nonce whose [`work_hash`] for the signed move has at least
+/// `difficulty` leading zero bits.
+#[must_use]
+pub fn grind_work(game: usize, player_move: u8, signature: &[u8], difficulty: u8) -> u64 {
+    (0..u64::MAX)
+        .find(|nonce| work_bits(game, player_move, signature, *nonce) >= u32::from(difficulty))
+        .unwrap_or(u64::MAX)
+}
+
+/// Tokens moved from the address that signed the block carrying it to another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Transfer {
+    #[schemars(with = "String")]
+    pub to: ids::short::Id,
+    pub amount: u64,
+    /// Number of transfers the sender made before this one, so a signed transfer
+    /// can't be replayed
+    pub nonce: u64,
+}
+
+/// Returns the SHA256 digest a sender signs to authorize a transfer.
+#[must_use]
+pub fn transfer_digest(transfer: &Transfer) -> Vec<u8> {
+    let mut d = Vec::with_capacity(TRANSFER_DIGEST_PREFIX.len() + ids::short::LEN + 16);
+    d.extend_from_slice(TRANSFER_DIGEST_PREFIX);
+    d.extend_from_slice(transfer.to.as_ref());
+    d.extend_from_slice(&transfer.amount.to_be_bytes());
+    d.extend_from_slice(&transfer.nonce.to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed a transfer, which it is sent from.
+/// # Errors
+/// Fails if the signature is missing or malformed.
+pub fn recover_sender(transfer: &Transfer, signature: &[u8]) -> io::Result<ids::short::Id> {
+    if signature.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "transfers must be signed"));
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&transfer_digest(transfer))?;
+    public_key.to_short_id()
+}
+
+/// Signs a transfer from the key's address.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_transfer(key: &private_key::Key, transfer: &Transfer) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&transfer_digest(transfer))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Request of the address that signed the block carrying it to be paired with
+/// another seeker in a new game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Seek {
+    /// Nonce of the seeker, shared with its transfers, so a signed seek can't be
+    /// replayed
+    pub nonce: u64,
+}
+
+/// Returns the SHA256 digest an address signs to seek a game.
+#[must_use]
+pub fn seek_digest(seek: &Seek) -> Vec<u8> {
+    let mut d = Vec::with_capacity(SEEK_DIGEST_PREFIX.len() + 8);
+    d.extend_from_slice(SEEK_DIGEST_PREFIX);
+    d.extend_from_slice(&seek.nonce.to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed a seek, which seeks the game.
+/// # Errors
+/// Fails if the signature is missing or malformed.
+pub fn recover_seeker(seek: &Seek, signature: &[u8]) -> io::Result<ids::short::Id> {
+    if signature.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "seeks must be signed"));
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&seek_digest(seek))?;
+    public_key.to_short_id()
+}
+
+/// Signs a seek for the key's address.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_seek(key: &private_key::Key, seek: &Seek) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&seek_digest(seek))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Step of a tournament taken by the address that signed the block carrying it.
+/// Its nonce is shared with the address's transfers and seeks, so a signed action
+/// can't be replayed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TournamentAction {
+    /// Opens a tournament for the given number of players: a bracket for a power of
+    /// two of them, or Swiss rounds for an even number of them if `rounds` is set
+    Create {
+        players: u32,
+        /// Number of Swiss rounds the tournament is played over, if not a bracket
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rounds: Option<u32>,
+        nonce: u64,
+    },
+    /// Registers the signer in a tournament still open for registration
+    Register { tournament: usize, nonce: u64 },
+}
+
+impl TournamentAction {
+    #[must_use]
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Create { nonce, .. } | Self::Register { nonce, .. } => *nonce,
+        }
+    }
+}
+
+/// Returns the SHA256 digest an address signs to take a tournament action.
+#[must_use]
+pub fn tournament_digest(action: &TournamentAction) -> Vec<u8> {
+    let mut d = Vec::with_capacity(TOURNAMENT_DIGEST_PREFIX.len() + 25);
+    d.extend_from_slice(TOURNAMENT_DIGEST_PREFIX);
+    match action {
+        TournamentAction::Create {
+            players,
+            rounds: None,
+            ..
+        } => {
+            d.push(0);
+            d.extend_from_slice(&u64::from(*players).to_be_bytes());
+        }
+        TournamentAction::Create {
+            players,
+            rounds: Some(rounds),
+            ..
+        } => {
+            d.push(2);
+            d.extend_from_slice(&u64::from(*players).to_be_bytes());
+            d.extend_from_slice(&u64::from(*rounds).to_be_bytes());
+        }
+        TournamentAction::Register { tournament, .. } => {
+            d.push(1);
+            d.extend_from_slice(&(*tournament as u64).to_be_bytes());
+        }
+    }
+    d.extend_from_slice(&action.nonce().to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed a tournament action, which takes it.
+/// # Errors
+/// Fails if the signature is missing or malformed.
+pub fn recover_entrant(
+    action: &TournamentAction,
+    signature: &[u8],
+) -> io::Result<ids::short::Id> {
+    if signature.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "tournament actions must be signed",
+        ));
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&tournament_digest(action))?;
+    public_key.to_short_id()
+}
+
+/// Signs a tournament action for the key's address.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_tournament_action(
+    key: &private_key::Key,
+    action: &TournamentAction,
+) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&tournament_digest(action))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Step of a best-of-N series taken by the address that signed the block carrying
+/// it. Its nonce is shared with the address's transfers, seeks and tournament
+/// actions, so a signed action can't be replayed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SeriesAction {
+    /// Challenges an address to a series over the given number of games, an odd
+    /// number
+    Challenge {
+        #[schemars(with = "String")]
+        opponent: ids::short::Id,
+        games: u32,
+        nonce: u64,
+    },
+    /// Accepts a series the signer was challenged to, reserving its first game
+    Accept { series: usize, nonce: u64 },
+}
+
+impl SeriesAction {
+    #[must_use]
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Challenge { nonce, .. } | Self::Accept { nonce, .. } => *nonce,
+        }
+    }
+}
+
+/// Returns the SHA256 digest an address signs to take a series action.
+#[must_use]
+pub fn series_digest(action: &SeriesAction) -> Vec<u8> {
+    let mut d = Vec::with_capacity(SERIES_DIGEST_PREFIX.len() + ids::short::LEN + 17);
+    d.extend_from_slice(SERIES_DIGEST_PREFIX);
+    match action {
+        SeriesAction::Challenge {
+            opponent, games, ..
+        } => {
+            d.push(0);
+            d.extend_from_slice(opponent.as_ref());
+            d.extend_from_slice(&u64::from(*games).to_be_bytes());
+        }
+        SeriesAction::Accept { series, .. } => {
+            d.push(1);
+            d.extend_from_slice(&(*series as u64).to_be_bytes());
+        }
+    }
+    d.extend_from_slice(&action.nonce().to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed a series action, which takes it.
+/// # Errors
+/// Fails if the signature is missing or malformed.
+pub fn recover_challenger(action: &SeriesAction, signature: &[u8]) -> io::Result<ids::short::Id> {
+    if signature.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "series actions must be signed"));
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&series_digest(action))?;
+    public_key.to_short_id()
+}
+
+/// Signs a series action for the key's address.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_series_action(key: &private_key::Key, action: &SeriesAction) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&series_digest(action))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Step of a 2v2 team game taken by the address that signed the block carrying
+/// it. Its nonce is shared with the address's transfers, seeks, tournament and
+/// series actions, so a signed action can't be replayed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TeamAction {
+    /// Challenges two addresses to a team game with a partner, the first opponent
+    /// opening for their side
+    Challenge {
+        #[schemars(with = "String")]
+        partner: ids::short::Id,
+        #[schemars(with = "[String; 2]")]
+        opponents: [ids::short::Id; 2],
+        nonce: u64,
+    },
+    /// Accepts a team game the signer was invited to, reserving its game once the
+    /// partner and both opponents did
+    Accept { team_game: usize, nonce: u64 },
+}
+
+impl TeamAction {
+    #[must_use]
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Challenge { nonce, .. } | Self::Accept { nonce, .. } => *nonce,
+        }
+    }
+}
+
+/// Returns the SHA256 digest an address signs to take a team action.
+#[must_use]
+pub fn team_digest(action: &TeamAction) -> Vec<u8> {
+    let mut d = Vec::with_capacity(TEAM_DIGEST_PREFIX.len() + 3 * ids::short::LEN + 9);
+    d.extend_from_slice(TEAM_DIGEST_PREFIX);
+    match action {
+        TeamAction::Challenge {
+            partner, opponents, ..
+        } => {
+            d.push(0);
+            d.extend_from_slice(partner.as_ref());
+            for opponent in opponents {
+                d.extend_from_slice(opponent.as_ref());
+            }
+        }
+        TeamAction::Accept { team_game, .. } => {
+            d.push(1);
+            d.extend_from_slice(&(*team_game as u64).to_be_bytes());
+        }
+    }
+    d.extend_from_slice(&action.nonce().to_be_bytes());
+    hash::sha256(d)
+}
+
+/// Recovers the address that signed a team action, which takes it.
+/// # Errors
+/// Fails if the signature is missing or malformed.
+pub fn recover_teammate(action: &TeamAction, signature: &[u8]) -> io::Result<ids::short::Id> {
+    if signature.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "team actions must be signed",
+        ));
+    }
+
+    let sig = Sig::from_bytes(signature)?;
+    let (public_key, _) = sig.recover_public_key(&team_digest(action))?;
+    public_key.to_short_id()
+}
+
+/// Signs a team action for the key's address.
+/// # Errors
+/// Fails if the digest can't be signed.
+pub fn sign_team_action(key: &private_key::Key, action: &TeamAction) -> io::Result<Vec<u8>> {
+    let sig = key.sign_digest(&team_digest(action))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
+/// Transfer, seek, tournament, series or team action a block carries instead of a
+/// move. Each is signed by the address taking it, and all of an address's actions
+/// share its nonce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountAction {
+    Transfer(Transfer),
+    Seek(Seek),
+    Tournament(TournamentAction),
+    Series(SeriesAction),
+    Team(TeamAction),
+}
+
+impl AccountAction {
+    #[must_use]
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Transfer(transfer) => transfer.nonce,
+            Self::Seek(seek) => seek.nonce,
+            Self::Tournament(action) => action.nonce(),
+            Self::Series(action) => action.nonce(),
+            Self::Team(action) => action.nonce(),
+        }
+    }
+
+    /// Names the kind of action, as logs and errors refer to it.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Transfer(_) => "transfer",
+            Self::Seek(_) => "seek",
+            Self::Tournament(_) => "tournament action",
+            Self::Series(_) => "series action",
+            Self::Team(_) => "team action",
+        }
+    }
+
+    /// Recovers the address that signed the action, which takes it.
+    /// # Errors
+    /// Fails if the signature is missing or malformed.
+    pub fn recover_signer(&self, signature: &[u8]) -> io::Result<ids::short::Id> {
+        match self {
+            Self::Transfer(transfer) => recover_sender(transfer, signature),
+            Self::Seek(seek) => recover_seeker(seek, signature),
+            Self::Tournament(action) => recover_entrant(action, signature),
+            Self::Series(action) => recover_challenger(action, signature),
+            Self::Team(action) => recover_teammate(action, signature),
+        }
+    }
+}
+
+/// Block reaching a consensus boundary, logged as a JSON object under [`LOG_TARGET`].
+#[derive(Serialize)]
+struct BlockLog<'a> {
+    event: &'a str,
+    block_id: ids::Id,
+    height: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cell: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 /// Represents a block, specific to [`Vm`](crate::vm::Vm).
 #[serde_as]
-#[derive(Serialize, Deserialize, Clone, Derivative, Default)]
+#[derive(Serialize, Deserialize, Clone, Derivative, Default, JsonSchema)]
 #[derivative(Debug, PartialEq, Eq)]
 pub struct Block {
     /// ID of parent block
+    #[schemars(with = "String")]
     parent_id: ids::Id,
 
     /// Height of block
@@ -37,15 +562,63 @@ pub struct Block {
     // #[serde_as(as = "Hex0xBytes")]
     player_move: u8,
 
+    /// Optional recoverable signature over [`move_digest`], attributing the
+    /// move to the signer's address. Omitted from the encoding when unsigned.
+    #[serde_as(as = "Hex0xBytes")]
+    #[schemars(with = "String")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    signature: Vec<u8>,
+
+    /// Transfer carried instead of a move, signed by its sender. Omitted from the
+    /// encoding for moves, so their encoding predates transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transfer: Option<Transfer>,
+
+    /// Fee the proposer set for the move as the mempool filled up. Omitted from
+    /// the encoding when unset, in which case the move pays the base fee.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fee: Option<u64>,
+
+    /// Most the signer agreed to pay for the move, signed along with it. Omitted
+    /// from the encoding when unset, in which case the move pays the base fee.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_fee: Option<u64>,
+
+    /// Seek carried instead of a move, signed by the seeker. Omitted from the
+    /// encoding for moves and transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seek: Option<Seek>,
+
+    /// Nonce ground for the move's proof of work, see [`work_hash`]. Omitted from
+    /// the encoding when unset, as chains with fees needn't require work.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    work: Option<u64>,
+
+    /// Tournament action carried instead of a move, signed by the address taking
+    /// it. Omitted from the encoding for other blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tournament: Option<TournamentAction>,
+
+    /// Series action carried instead of a move, signed by the address taking it.
+    /// Omitted from the encoding for other blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    series: Option<SeriesAction>,
+
+    /// Team action carried instead of a move, signed by the address taking it.
+    /// Omitted from the encoding for other blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    team: Option<TeamAction>,
+
     /// Current block status.
     #[serde(skip)]
     status: choices::status::Status,
     /// This block's encoded bytes.
     #[serde(skip)]
     bytes: Vec<u8>,
-    /// Generated block Id.
+    /// Generated block Id, derived from the bytes when first needed.
+    #[derivative(PartialEq = "ignore")]
     #[serde(skip)]
-    id: ids::Id,
+    id: OnceCell<ids::Id>,
 
     /// Reference to the Vm state manager for blocks.
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
@@ -61,23 +634,129 @@ impl Block {
         parent_id: ids::Id,
         height: u64,
         player_move: u8,
+        signature: Vec<u8>,
         status: choices::status::Status,
     ) -> io::Result<Self> {
         let mut b = Self {
-            parent_id,height, player_move, ..Default::default()
+            parent_id,height, player_move, signature, ..Default::default()
         };
 
         b.status = status;
         b.bytes = b.to_vec()?;
-        b.id = ids::Id::sha256(&b.bytes);
 
         Ok(b)
     }
 
+    /// Creates a block carrying a transfer, seek, tournament, series or team action
+    /// rather than a move.
+    /// # Errors
+    /// Will fail if the block can't be serialized to JSON.
+    pub fn try_new_account(
+        parent_id: ids::Id,
+        height: u64,
+        action: AccountAction,
+        signature: Vec<u8>,
+        status: choices::status::Status,
+    ) -> io::Result<Self> {
+        let mut b = Self {
+            parent_id,
+            height,
+            signature,
+            status,
+            ..Default::default()
+        };
+        match action {
+            AccountAction::Transfer(transfer) => b.transfer = Some(transfer),
+            AccountAction::Seek(seek) => b.seek = Some(seek),
+            AccountAction::Tournament(action) => b.tournament = Some(action),
+            AccountAction::Series(action) => b.series = Some(action),
+            AccountAction::Team(action) => b.team = Some(action),
+        }
+        b.bytes = b.to_vec()?;
+
+        Ok(b)
+    }
+
+    /// Re-creates the block on top of another parent, carrying the same move,
+    /// transfer, seek, tournament, series or team action in the same [`Format`].
+    /// # Errors
+    /// Will fail if the block can't be encoded.
+    pub fn with_parent(&self, parent_id: ids::Id, height: u64) -> io::Result<Self> {
+        let status = choices::status::Status::Processing;
+        let signature = self.signature.clone();
+        let block = if let Some(action) = self.account_action() {
+            Self::try_new_account(parent_id, height, action, signature, status)?
+        } else {
+            Self::try_new(parent_id, height, self.player_move, signature, status)?
+        };
+        let block = match self.max_fee {
+            Some(max_fee) => block.with_max_fee(max_fee)?,
+            None => block,
+        };
+        let block = match self.fee {
+            Some(fee) => block.with_fee(fee)?,
+            None => block,
+        };
+        let block = match self.work {
+            Some(nonce) => block.with_work(nonce)?,
+            None => block,
+        };
+        block.with_format(self.format())
+    }
+
+    /// Sets the fee the move pays, re-encoding the block to JSON as the binary
+    /// [`Format`] has no room for it.
+    /// # Errors
+    /// Will fail if the block carries an account action or can't be serialized to
+    /// JSON.
+    pub fn with_fee(mut self, fee: u64) -> io::Result<Self> {
+        self.fee = Some(fee);
+        self.check_kind()?;
+        self.bytes = self.to_vec()?;
+        self.id = OnceCell::new();
+        Ok(self)
+    }
+
+    /// Sets the most the signer agreed to pay for the move, which its signature
+    /// covers, re-encoding the block to JSON as the binary [`Format`] has no room
+    /// for it.
+    /// # Errors
+    /// Will fail if the block carries an account action or can't be serialized to
+    /// JSON.
+    pub fn with_max_fee(mut self, max_fee: u64) -> io::Result<Self> {
+        self.max_fee = Some(max_fee);
+        self.check_kind()?;
+        self.bytes = self.to_vec()?;
+        self.id = OnceCell::new();
+        Ok(self)
+    }
+
+    /// Sets the nonce ground for the move's proof of work, re-encoding the block
+    /// to JSON as the binary [`Format`] has no room for it.
+    /// # Errors
+    /// Will fail if the block carries an account action or can't be serialized to
+    /// JSON.
+    pub fn with_work(mut self, nonce: u64) -> io::Result<Self> {
+        self.work = Some(nonce);
+        self.check_kind()?;
+        self.bytes = self.to_vec()?;
+        self.id = OnceCell::new();
+        Ok(self)
+    }
+
     /// # Errors
     /// Can fail if the block can't be serialized to JSON.
     /// Returns string version of JSON'd Block
     pub fn to_json_string(&self) -> io::Result<String> {
+        // The encoded bytes are already JSON, so only serialize blocks without them
+        if self.format() == Format::Json {
+            if let Ok(s) = std::str::from_utf8(&self.bytes) {
+                if !s.is_empty() {
+                    return Ok(s.to_owned());
+                }
+            }
+        }
+
         serde_json::to_string(&self).map_err(|e| {
             Error::new(
                 ErrorKind::Other,
@@ -98,24 +777,116 @@ impl Block {
         })
     }
 
-    /// Loads [`Block`](Block) from JSON bytes.
+    /// Encodes the [`Block`](Block) in the binary [`Format`].
+    /// # Errors
+    /// Fails for blocks carrying anything but a move, and blocks carrying a fee, a
+    /// maximum fee or proof of work, which have no binary encoding yet.
+    pub fn to_binary_vec(&self) -> io::Result<Vec<u8>> {
+        if !self.is_move() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "only move blocks have a binary encoding",
+            ));
+        }
+        if self.fee.is_some() || self.max_fee.is_some() || self.work.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "blocks carrying a fee, a maximum fee or proof of work have no binary encoding",
+            ));
+        }
+
+        let mut d = Vec::with_capacity(BINARY_HEADER_LEN + self.signature.len());
+        d.push(BINARY_VERSION);
+        d.extend_from_slice(&self.parent_id.to_vec());
+        d.extend_from_slice(&self.height.to_be_bytes());
+        d.push(self.player_move);
+        d.extend_from_slice(&self.signature);
+        Ok(d)
+    }
+
+    /// Loads [`Block`](Block) from bytes in either [`Format`], so blocks
+    /// produced before the switch to the binary encoding still parse.
     /// # Errors
-    /// Will fail if the block can't be deserialized from JSON.
+    /// Will fail if the bytes are in neither format, can't be decoded, or carry more
+    /// than one kind of action (see [`Block::check_kind`]).
     pub fn from_slice(d: impl AsRef<[u8]>) -> io::Result<Self> {
         let dd = d.as_ref();
-        let mut b: Self = serde_json::from_slice(dd).map_err(|e| {
-            Error::new(
-                ErrorKind::Other,
-                format!("failed to deserialize Block from JSON {e}"),
-            )
-        })?;
+        let mut b = match Format::detect(dd)? {
+            Format::Json => serde_json::from_slice(dd).map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to deserialize Block from JSON {e}"),
+                )
+            })?,
+            Format::Binary => Self::from_binary(dd)?,
+        };
+        b.check_kind()?;
 
         b.bytes = dd.to_vec();
-        b.id = ids::Id::sha256(&b.bytes);
 
         Ok(b)
     }
 
+    fn from_binary(d: &[u8]) -> io::Result<Self> {
+        if d.len() < BINARY_HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("binary block of {} bytes is truncated", d.len()),
+            ));
+        }
+        let (parent_id, rest) = d[1..].split_at(ids::LEN);
+        let (height, rest) = rest.split_at(8);
+        let mut height_bytes = [0; 8];
+        height_bytes.copy_from_slice(height);
+
+        Ok(Self {
+            parent_id: ids::Id::from_slice(parent_id),
+            height: u64::from_be_bytes(height_bytes),
+            player_move: rest[0],
+            signature: rest[1..].to_vec(),
+            ..Default::default()
+        })
+    }
+
+    /// Returns the [`Format`] this block is encoded in.
+    #[must_use]
+    pub fn format(&self) -> Format {
+        Format::detect(&self.bytes).unwrap_or(Format::Json)
+    }
+
+    /// Re-encodes the block in the given [`Format`], which changes its Id unless
+    /// already encoded in it.
+    /// # Errors
+    /// Will fail if the block can't be encoded in the format.
+    pub fn with_format(mut self, format: Format) -> io::Result<Self> {
+        if self.format() == format && !self.bytes.is_empty() {
+            return Ok(self);
+        }
+        self.bytes = match format {
+            Format::Json => self.to_vec()?,
+            Format::Binary => self.to_binary_vec()?,
+        };
+        self.id = OnceCell::new();
+        Ok(self)
+    }
+
+    /// Re-encodes the block in the [`Format`] whose encoding has the given Id,
+    /// as the JSON representation served by the API doesn't record it. The block
+    /// is left as is if no encoding matches.
+    /// # Errors
+    /// Will fail if the block can't be serialized to JSON.
+    pub fn with_format_of(self, id: &ids::Id) -> io::Result<Self> {
+        if self.id() == *id {
+            return Ok(self);
+        }
+        if let Ok(binary) = self.clone().with_format(Format::Binary) {
+            if binary.id() == *id {
+                return Ok(binary);
+            }
+        }
+        Ok(self)
+    }
+
     /// Returns the parent block Id.
     #[must_use]
     pub fn parent_id(&self) -> ids::Id {
@@ -133,6 +904,140 @@ impl Block {
         self.player_move
     }
 
+    /// Returns the signature attributing this move, empty if unsigned.
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Returns the transfer this block carries instead of a move, if any.
+    #[must_use]
+    pub fn transfer(&self) -> Option<&Transfer> {
+        self.transfer.as_ref()
+    }
+
+    /// Returns the seek this block carries instead of a move, if any.
+    #[must_use]
+    pub fn seek(&self) -> Option<&Seek> {
+        self.seek.as_ref()
+    }
+
+    /// Returns the tournament action this block carries instead of a move, if any.
+    #[must_use]
+    pub fn tournament(&self) -> Option<&TournamentAction> {
+        self.tournament.as_ref()
+    }
+
+    /// Returns the series action this block carries instead of a move, if any.
+    #[must_use]
+    pub fn series(&self) -> Option<&SeriesAction> {
+        self.series.as_ref()
+    }
+
+    /// Returns the team action this block carries instead of a move, if any.
+    #[must_use]
+    pub fn team(&self) -> Option<&TeamAction> {
+        self.team.as_ref()
+    }
+
+    /// Returns the transfer, seek, tournament, series or team action this block
+    /// carries instead of a move, if any.
+    #[must_use]
+    pub fn account_action(&self) -> Option<AccountAction> {
+        if let Some(transfer) = &self.transfer {
+            Some(AccountAction::Transfer(transfer.clone()))
+        } else if let Some(seek) = &self.seek {
+            Some(AccountAction::Seek(seek.clone()))
+        } else if let Some(action) = &self.tournament {
+            Some(AccountAction::Tournament(action.clone()))
+        } else if let Some(action) = &self.series {
+            Some(AccountAction::Series(action.clone()))
+        } else {
+            self.team.clone().map(AccountAction::Team)
+        }
+    }
+
+    /// Checks the block carries either a move or a single transfer, seek,
+    /// tournament, series or team action, without the move's fields alongside one.
+    /// Anything else it carried would be ignored while still changing its Id, so
+    /// one signed action could be spread over many blocks.
+    /// # Errors
+    /// Fails if the block carries more than one kind of action.
+    pub fn check_kind(&self) -> io::Result<()> {
+        let kinds = [
+            self.transfer.is_some(),
+            self.seek.is_some(),
+            self.tournament.is_some(),
+            self.series.is_some(),
+            self.team.is_some(),
+        ];
+        match kinds.iter().filter(|set| **set).count() {
+            0 => Ok(()),
+            1 if self.player_move == 0
+                && self.fee.is_none()
+                && self.max_fee.is_none()
+                && self.work.is_none() =>
+            {
+                Ok(())
+            }
+            1 => Err(Error::new(
+                ErrorKind::InvalidData,
+                "blocks carrying an account action can't carry a move, fee or proof of work",
+            )),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "blocks can carry at most one account action",
+            )),
+        }
+    }
+
+    /// Returns whether this block carries a move, rather than a transfer, seek,
+    /// tournament, series or team action.
+    #[must_use]
+    pub fn is_move(&self) -> bool {
+        self.transfer.is_none()
+            && self.seek.is_none()
+            && self.tournament.is_none()
+            && self.series.is_none()
+            && self.team.is_none()
+    }
+
+    /// Recovers the address that signed this block's transfer, seek, tournament,
+    /// series or team action, which share its nonce. Returns `None` for moves.
+    /// # Errors
+    /// Fails if the signature is missing or malformed.
+    pub fn account_signer(&self) -> io::Result<Option<ids::short::Id>> {
+        self.account_action()
+            .map(|action| action.recover_signer(&self.signature))
+            .transpose()
+    }
+
+    /// Returns the fee the proposer set for this move, if any.
+    #[must_use]
+    pub fn fee(&self) -> Option<u64> {
+        self.fee
+    }
+
+    /// Returns the most the signer agreed to pay for this move, if set.
+    #[must_use]
+    pub fn max_fee(&self) -> Option<u64> {
+        self.max_fee
+    }
+
+    /// Returns the nonce ground for this move's proof of work, if any.
+    #[must_use]
+    pub fn work(&self) -> Option<u64> {
+        self.work
+    }
+
+    /// Recovers the address that signed this move as part of the given game.
+    /// Returns `None` for unsigned moves.
+    /// # Errors
+    /// Fails if the signature is malformed.
+    pub fn signer(&self, game: usize) -> io::Result<Option<ids::short::Id>> {
+        recover_signer(game, self.player_move, self.max_fee, &self.signature)
+    }
+
     /// Returns the status of this block.
     #[must_use]
     pub fn status(&self) -> choices::status::Status {
@@ -153,12 +1058,12 @@ impl Block {
     /// Returns the ID of this block
     #[must_use]
     pub fn id(&self) -> ids::Id {
-        self.id
+        *self.id.get_or_init(|| ids::Id::sha256(&self.bytes))
     }
     /// Gets the move of the player
     #[must_use]
     pub fn get_move_index(&self) -> u8 {
-        self.player_move & 0b00001111
+        game::decode_action(self.player_move).1
     }
 
     /// Updates the state of the block.
@@ -166,22 +1071,68 @@ impl Block {
         self.state = state;
     }
 
-    // Gets the ID of the player
+    /// Gets the ID of the player (1 or 2)
     #[must_use]
     pub fn get_player_id(&self) -> u8 {
-        self.player_move & 0b00010000
+        game::decode_action(self.player_move).0
     }
 
-    // NEED TO IMPLEMENT VERIFY
+    /// Verifies the [`Block`](Block) extends an already verified block and carries
+    /// a legal move or action in the state its parent reaches.
+    /// # Errors
+    /// Returns an error if the block carries more than one kind of action, the
+    /// parent is unknown, the height doesn't follow it, or the move or action is
+    /// illegal.
+    #[tracing::instrument(
+        name = "block.verify",
+        skip_all,
+        fields(block_id = %self.id(), height = self.height)
+    )]
     pub async fn verify(&mut self) -> io::Result<()> {
         // Don't worry about the Genesis Case
         // if already exists in database, it means it's already accepted
         // thus no need to verify once more
-        if self.state.get_block(&self.id).await.is_ok() {
-            log::debug!("block {} already verified", self.id);
+        let blk_id = self.id();
+        if self.state.get_block(&blk_id).await.is_ok() {
+            log::debug!("block {blk_id} already verified");
             return Ok(());
         }
 
+        if let Err(e) = self.check_kind() {
+            self.log_event("verify_failed", None, Some(&e));
+            return Err(e);
+        }
+
+        // Moves and actions are checked against the state the parent reaches, which
+        // may itself still be processing
+        let state = match self.check_extends_parent().await {
+            Ok(state) => state,
+            Err(e) => {
+                self.log_event("verify_failed", None, Some(&e));
+                return Err(e);
+            }
+        };
+        // A reserved game whose deadline passed ends before the block is checked, as
+        // it does before the block is applied
+        state.enforce_reservation_deadline(self).await;
+        let game = state.get_curr_game_index().await;
+        if let Err(e) = self.check_action(&state).await {
+            self.log_event("verify_failed", Some(game), Some(&e));
+            return Err(e);
+        }
+        state.update_board(self).await?;
+
+        // Add newly verified block to memory
+        self.state.add_verified(self.clone(), state).await;
+        self.state.metrics.blocks_verified.inc();
+        self.log_event("verified", Some(game), None);
+
+        Ok(())
+    }
+
+    /// Checks the block follows its parent, returning a copy of the state the
+    /// parent reaches to check and apply the block against.
+    async fn check_extends_parent(&self) -> io::Result<state::State> {
         let parent_block = self.state.get_block(&self.parent_id).await?;
 
         // ensure the height of the block is immediately following its parent
@@ -195,48 +1146,100 @@ impl Block {
             ));
         }
 
-        // Get the current game
-        let curr_game = self.state.get_curr_game().await;
-
-        // Bitmasking to get board index player wants to modify
-        let intended_position = self.get_move_index();
-        // Bitmasking to get id of player (1 or 2)
-        let player_id = self.get_player_id();
-
-        // Now time to check if the move is legal
-        let mut curr_box = curr_game >> (2 * intended_position);
-        curr_box = curr_box & 0b111;
-
-        if curr_box != 0 {
-            log::error!("consensus engine channel failed to initialized");
-            return Err(Error::new(ErrorKind::Other, "INVALID PLAYER MOVE!"));
-        } 
-
-        // Add newly verified block to memory
-        self.state.add_verified(&self.clone());
+        self.state.state_after(&self.parent_id).await
+    }
 
-        Ok(())
+    /// Checks the block carries a legal move, transfer, seek, tournament, series or
+    /// team action in the given state.
+    async fn check_action(&self, state: &state::State) -> io::Result<()> {
+        if let Some(action) = self.account_action() {
+            return state
+                .validate_account_action(&action, &self.signature)
+                .await
+                .map(|_| ());
+        }
+        state
+            .validate_move(
+                self.player_move,
+                &self.signature,
+                self.fee,
+                self.max_fee,
+                self.work,
+            )
+            .await
     }
 
     /// Mark this [`Block`](Block) accepted and updates [`State`](crate::state::State) accordingly.
     /// # Errors
     /// Returns an error if the state can't be updated.
+    #[tracing::instrument(
+        name = "block.accept",
+        skip_all,
+        fields(block_id = %self.id(), height = self.height)
+    )]
     pub async fn accept(&mut self) -> io::Result<()> {
         self.set_status(choices::status::Status::Accepted);
 
-        self.state.update_board(&self).await?;
+        let accepted = self.state.update_board(self).await?;
+        self.state.write_block(self).await?;
+        self.state.remove_verified(&self.id()).await;
+        self.state.metrics.blocks_accepted.inc();
+        self.state.metrics.move_accepted(&self.id());
+        self.log_event("accepted", accepted.as_ref().map(|a| a.game), None);
+
+        if let Some(accepted) = accepted {
+            self.state
+                .metrics
+                .game_move_accepted(accepted.game, accepted.board, accepted.status);
+            self.state.publish_accepted(accepted);
+        }
 
         Ok(())
     }
 
     /// Mark this [`Block`](Block) rejected
-    pub async fn reject(&mut self) -> io::Result<()>  {
-        self.set_status(choices::status::Status::Accepted);
+    /// # Errors
+    /// Never fails, dropping the block can't go wrong.
+    #[tracing::instrument(
+        name = "block.reject",
+        skip_all,
+        fields(block_id = %self.id(), height = self.height)
+    )]
+    pub async fn reject(&mut self) -> io::Result<()> {
+        self.set_status(choices::status::Status::Rejected);
 
+        let game = self.state.get_verified_game(&self.id()).await;
         self.state.remove_verified(&self.id()).await;
+        self.state.metrics.blocks_rejected.inc();
+        self.state.metrics.move_dropped(&self.id());
+        self.log_event("rejected", game, None);
 
         Ok(())
     }
+
+    /// Logs the block reaching a consensus boundary, see [`LOG_TARGET`].
+    pub(crate) fn log_event(&self, event: &str, game: Option<usize>, error: Option<&io::Error>) {
+        // The genesis block carries no move
+        let is_move = self.height > 0 && self.is_move();
+        let record = BlockLog {
+            event,
+            block_id: self.id(),
+            height: self.height,
+            game,
+            player: is_move.then(|| self.get_player_id()),
+            cell: is_move.then(|| self.get_move_index()),
+            error: error.map(ToString::to_string),
+        };
+        let level = if error.is_some() {
+            log::Level::Warn
+        } else {
+            log::Level::Info
+        };
+        match serde_json::to_string(&record) {
+            Ok(json) => log::log!(target: LOG_TARGET, level, "{json}"),
+            Err(e) => log::error!("failed to serialize block log {e}"),
+        }
+    }
 }
 
 impl fmt::Display for Block {
@@ -256,9 +1259,11 @@ impl snowman::Block for Block {
         self.height
     }
 
-    // async fn timestamp(&self) -> u64 {
-    //     self.timestamp
-    // }
+    /// Blocks don't record when they were built, as validators couldn't agree on
+    /// it, so all of them report the Unix epoch.
+    async fn timestamp(&self) -> u64 {
+        0
+    }
 
     async fn parent(&self) -> ids::Id {
         self.parent_id
@@ -277,7 +1282,7 @@ impl Decidable for Block {
     }
 
     async fn id(&self) -> ids::Id {
-        self.id
+        self.id()
     }
 
     async fn accept(&mut self) -> io::Result<()> {
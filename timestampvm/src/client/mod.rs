@@ -1,12 +1,33 @@
 //! Implements client for timestampvm APIs.
 
-use std::{
-    collections::HashMap,
-    io::{self, Error, ErrorKind},
-};
+use std::io::{self, Error, ErrorKind};
 
+use crate::{
+    api::chain_handlers::{
+        BatchProposeMovesArgs, BatchProposeMovesResponse, DecodedMove, EncodedMove, ExportGameArgs,
+        ExportGameResponse, GetAccountGamesArgs, GetAccountGamesResponse, GetBalanceArgs,
+        GetBalanceResponse, GetBlockByHeightArgs, GetBlockByHeightResponse, GetBlockEventsArgs,
+        GetBlockEventsResponse, GetBoardAtBlockArgs, GetBoardAtHeightArgs, GetBoardResponse,
+        GetFeeEstimateResponse, GetGameStatusArgs, GetGameStatusResponse, GetLeaderboardArgs,
+        GetLeaderboardResponse, GetLegalMovesArgs, GetLegalMovesResponse, GetMoveHistoryArgs,
+        GetMoveHistoryResponse, GetNodeInfoResponse, GetNonceArgs, GetNonceResponse,
+        GetPendingMovesResponse, GetPlayerStatsArgs, GetPlayerStatsResponse, GetQueuePositionArgs,
+        GetQueuePositionResponse, GetRatingArgs, GetRatingHistoryArgs, GetRatingHistoryResponse,
+        GetRatingResponse, GetSeriesArgs, GetSeriesResponse, GetTeamGameArgs, GetTeamGameResponse,
+        GetTournamentArgs, GetTournamentResponse, GetTournamentStandingsResponse, GetWinnerArgs,
+        GetWinnerResponse, GetWinnersArgs, GetWinnersResponse, ProposeSeriesActionArgs,
+        ProposeSeriesActionResponse, ProposeTeamActionArgs, ProposeTeamActionResponse,
+        ProposeTournamentActionArgs, ProposeTournamentActionResponse, ProposeTransferArgs,
+        ProposeTransferResponse, ProposedMoveArgs, ProposedMoveResponse, RpcMethodsResponse,
+        SeekGameArgs, SeekGameResponse, SimulateMoveArgs, SimulateMoveResponse, StreamBlocksArgs,
+        StreamBlocksResponse, WaitForMoveArgs, WaitForMoveResponse, WhoseTurnArgs,
+        WhoseTurnResponse,
+    },
+    metrics::LatencyStats,
+    vm::Health,
+};
 use avalanche_types::{ids, jsonrpc};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// Represents the RPC response for API `ping`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -38,6 +59,593 @@ pub async fn ping(http_rpc: &str, url_path: &str) -> io::Result<PingResponse> {
         .map_err(|e| Error::new(ErrorKind::Other, format!("failed ping '{e}'")))
 }
 
+/// Represents the RPC response of a chain API.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Response<T> {
+    pub jsonrpc: String,
+    pub id: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+
+    /// Returns non-empty if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<APIError>,
+}
+
+/// Calls a chain API, passing `params` (if any) as its only positional argument.
+/// # Errors
+/// Errors on an http failure, a failed deserialization or if the API returns an error.
+pub async fn call<P, T>(
+    http_rpc: &str,
+    url_path: &str,
+    method: &str,
+    params: Option<P>,
+) -> io::Result<T>
+where
+    P: Serialize,
+    T: DeserializeOwned,
+{
+    log::debug!("calling {method} on {http_rpc} with {url_path}");
+
+    let mut data = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": format!("tictactoe.v1.{method}"),
+    });
+    if let Some(params) = params {
+        data["params"] = serde_json::json!([params]);
+    }
+
+    let rb = http_manager::post_non_tls(http_rpc, url_path, &data.to_string()).await?;
+    let resp: Response<T> = serde_json::from_slice(&rb)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed {method} '{e}'")))?;
+    if let Some(e) = resp.error {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{method} failed: {} ({})", e.message, e.code),
+        ));
+    }
+    resp.result.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{method} returned no result"),
+        )
+    })
+}
+
+/// Typed async client for the chain APIs of a node, one method per RPC.
+///
+/// Requests and responses are the types [`chain_handlers`](crate::api::chain_handlers)
+/// serves, so callers don't need to redefine them.
+#[derive(Debug, Clone)]
+pub struct TicTacToeClient {
+    http_rpc: String,
+    url_path: String,
+}
+
+impl TicTacToeClient {
+    /// Creates a client for the chain served at `url_path` by the node at `http_rpc`,
+    /// e.g. `http://127.0.0.1:9650` and `/ext/bc/{chain_id}/rpc`.
+    #[must_use]
+    pub fn new(http_rpc: impl Into<String>, url_path: impl Into<String>) -> Self {
+        Self {
+            http_rpc: http_rpc.into(),
+            url_path: url_path.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn http_rpc(&self) -> &str {
+        &self.http_rpc
+    }
+
+    #[must_use]
+    pub fn url_path(&self) -> &str {
+        &self.url_path
+    }
+
+    async fn call<P: Serialize, T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Option<P>,
+    ) -> io::Result<T> {
+        call(&self.http_rpc, &self.url_path, method, params).await
+    }
+
+    /// Pings the VM.
+    /// # Errors
+    /// See [`call`].
+    pub async fn ping(&self) -> io::Result<crate::api::PingResponse> {
+        self.call("ping", None::<()>).await
+    }
+
+    /// Proposes a player move.
+    /// # Errors
+    /// See [`call`].
+    pub async fn propose_move(&self, args: ProposedMoveArgs) -> io::Result<ProposedMoveResponse> {
+        self.call("proposeMove", Some(args)).await
+    }
+
+    /// Proposes an ordered list of player moves, reporting the outcome of each.
+    /// # Errors
+    /// See [`call`].
+    pub async fn batch_propose_moves(
+        &self,
+        args: BatchProposeMovesArgs,
+    ) -> io::Result<BatchProposeMovesResponse> {
+        self.call("batchProposeMoves", Some(args)).await
+    }
+
+    /// Validates a player move against the current state without proposing it.
+    /// # Errors
+    /// See [`call`].
+    pub async fn simulate_move(&self, args: SimulateMoveArgs) -> io::Result<SimulateMoveResponse> {
+        self.call("simulateMove", Some(args)).await
+    }
+
+    /// Proposes a transfer of tokens signed by the sender.
+    /// # Errors
+    /// See [`call`].
+    pub async fn propose_transfer(
+        &self,
+        args: ProposeTransferArgs,
+    ) -> io::Result<ProposeTransferResponse> {
+        self.call("proposeTransfer", Some(args)).await
+    }
+
+    /// Proposes a seek signed by the seeker, queueing it for matchmaking.
+    /// # Errors
+    /// See [`call`].
+    pub async fn seek_game(&self, args: SeekGameArgs) -> io::Result<SeekGameResponse> {
+        self.call("seekGame", Some(args)).await
+    }
+
+    /// Proposes a tournament action signed by the address taking it, creating a
+    /// tournament or registering in one.
+    /// # Errors
+    /// See [`call`].
+    pub async fn propose_tournament_action(
+        &self,
+        args: ProposeTournamentActionArgs,
+    ) -> io::Result<ProposeTournamentActionResponse> {
+        self.call("proposeTournamentAction", Some(args)).await
+    }
+
+    /// Proposes a series action signed by the address taking it, challenging an
+    /// address to a best-of-N series or accepting one.
+    /// # Errors
+    /// See [`call`].
+    pub async fn propose_series_action(
+        &self,
+        args: ProposeSeriesActionArgs,
+    ) -> io::Result<ProposeSeriesActionResponse> {
+        self.call("proposeSeriesAction", Some(args)).await
+    }
+
+    /// Proposes a team action signed by the address taking it, challenging two
+    /// addresses to a 2v2 team game with a partner or accepting one.
+    /// # Errors
+    /// See [`call`].
+    pub async fn propose_team_action(
+        &self,
+        args: ProposeTeamActionArgs,
+    ) -> io::Result<ProposeTeamActionResponse> {
+        self.call("proposeTeamAction", Some(args)).await
+    }
+
+    /// Fetches the board of the current game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_board(&self) -> io::Result<GetBoardResponse> {
+        self.call("getBoard", None::<()>).await
+    }
+
+    /// Fetches the result of the given game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_winner(&self, game: usize) -> io::Result<GetWinnerResponse> {
+        self.call("getWinner", Some(GetWinnerArgs { req: game }))
+            .await
+    }
+
+    /// Fetches accepted blocks in height order, a chunk at a time.
+    /// # Errors
+    /// See [`call`].
+    pub async fn stream_blocks(&self, args: StreamBlocksArgs) -> io::Result<StreamBlocksResponse> {
+        self.call("streamBlocks", Some(args)).await
+    }
+
+    /// Fetches the accepted block at the given height.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_block_by_height(&self, height: u64) -> io::Result<GetBlockByHeightResponse> {
+        self.call("getBlockByHeight", Some(GetBlockByHeightArgs { height }))
+            .await
+    }
+
+    /// Fetches the board as it stood after the block at the given height.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_board_at_height(&self, height: u64) -> io::Result<GetBoardResponse> {
+        self.call("getBoardAtHeight", Some(GetBoardAtHeightArgs { height }))
+            .await
+    }
+
+    /// Fetches the board as it stood after the given block.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_board_at_block(&self, id: ids::Id) -> io::Result<GetBoardResponse> {
+        self.call("getBoardAtBlock", Some(GetBoardAtBlockArgs { id }))
+            .await
+    }
+
+    /// Fetches the events emitted by an accepted block.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_block_events(&self, id: ids::Id) -> io::Result<GetBlockEventsResponse> {
+        self.call("getBlockEvents", Some(GetBlockEventsArgs { id }))
+            .await
+    }
+
+    /// Fetches the ordered moves of the given game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_move_history(&self, game: usize) -> io::Result<GetMoveHistoryResponse> {
+        self.call("getMoveHistory", Some(GetMoveHistoryArgs { game }))
+            .await
+    }
+
+    /// Fetches the player expected to make the next move of the given game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn whose_turn(&self, game: usize) -> io::Result<WhoseTurnResponse> {
+        self.call("whoseTurn", Some(WhoseTurnArgs { game })).await
+    }
+
+    /// Fetches the empty cells the next player may mark in the given game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_legal_moves(&self, game: usize) -> io::Result<GetLegalMovesResponse> {
+        self.call("getLegalMoves", Some(GetLegalMovesArgs { game }))
+            .await
+    }
+
+    /// Fetches whether the given game is in progress, won or drawn.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_game_status(&self, game: usize) -> io::Result<GetGameStatusResponse> {
+        self.call("getGameStatus", Some(GetGameStatusArgs { game }))
+            .await
+    }
+
+    /// Fetches a page of finished game results.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_winners(&self, args: GetWinnersArgs) -> io::Result<GetWinnersResponse> {
+        self.call("getWinners", Some(args)).await
+    }
+
+    /// Fetches the index of the game in progress, that is the number of finished games.
+    /// # Errors
+    /// See [`call`].
+    pub async fn current_game(&self) -> io::Result<usize> {
+        let args = GetWinnersArgs {
+            offset: 0,
+            limit: Some(0),
+        };
+        Ok(self.get_winners(args).await?.total)
+    }
+
+    /// Fetches the results of finished games the given address played in.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_player_stats(
+        &self,
+        address: ids::short::Id,
+    ) -> io::Result<GetPlayerStatsResponse> {
+        self.call("getPlayerStats", Some(GetPlayerStatsArgs { address }))
+            .await
+    }
+
+    /// Fetches the accepted balance and transfer nonce of the given address.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_balance(&self, address: ids::short::Id) -> io::Result<GetBalanceResponse> {
+        self.call("getBalance", Some(GetBalanceArgs { address })).await
+    }
+
+    /// Fetches the nonce the next transfer of the given address must carry.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_nonce(&self, address: ids::short::Id) -> io::Result<GetNonceResponse> {
+        self.call("getNonce", Some(GetNonceArgs { address })).await
+    }
+
+    /// Lists the games the given address took a seat in, oldest first.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_account_games(
+        &self,
+        address: ids::short::Id,
+    ) -> io::Result<GetAccountGamesResponse> {
+        self.call("getAccountGames", Some(GetAccountGamesArgs { address }))
+            .await
+    }
+
+    /// Fetches the rating of the given address.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_rating(&self, address: ids::short::Id) -> io::Result<GetRatingResponse> {
+        self.call("getRating", Some(GetRatingArgs { address })).await
+    }
+
+    /// Lists the changes of the given address's rating, oldest first.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_rating_history(
+        &self,
+        address: ids::short::Id,
+    ) -> io::Result<GetRatingHistoryResponse> {
+        self.call("getRatingHistory", Some(GetRatingHistoryArgs { address }))
+            .await
+    }
+
+    /// Fetches a page of the top addresses, by rating or by wins.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_leaderboard(
+        &self,
+        args: GetLeaderboardArgs,
+    ) -> io::Result<GetLeaderboardResponse> {
+        self.call("getLeaderboard", Some(args)).await
+    }
+
+    /// Fetches where the given address waits in the matchmaking queue, and the games
+    /// it was paired into that aren't over.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_queue_position(
+        &self,
+        address: ids::short::Id,
+    ) -> io::Result<GetQueuePositionResponse> {
+        self.call("getQueuePosition", Some(GetQueuePositionArgs { address }))
+            .await
+    }
+
+    /// Fetches a tournament's players and bracket.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_tournament(&self, tournament: usize) -> io::Result<GetTournamentResponse> {
+        self.call("getTournament", Some(GetTournamentArgs { tournament }))
+            .await
+    }
+
+    /// Fetches a tournament's standings, by points and then by seed.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_tournament_standings(
+        &self,
+        tournament: usize,
+    ) -> io::Result<GetTournamentStandingsResponse> {
+        self.call("getTournamentStandings", Some(GetTournamentArgs { tournament }))
+            .await
+    }
+
+    /// Fetches a series' players, games and score.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_series(&self, series: usize) -> io::Result<GetSeriesResponse> {
+        self.call("getSeries", Some(GetSeriesArgs { series })).await
+    }
+
+    /// Fetches a team game's teams, acceptances and reserved game.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_team_game(&self, team_game: usize) -> io::Result<GetTeamGameResponse> {
+        self.call("getTeamGame", Some(GetTeamGameArgs { team_game }))
+            .await
+    }
+
+    /// Fetches the fee a move proposed now would pay, given the mempool's depth.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_fee_estimate(&self) -> io::Result<GetFeeEstimateResponse> {
+        self.call("getFeeEstimate", None::<()>).await
+    }
+
+    /// Exports the given game in a portable notation.
+    /// # Errors
+    /// See [`call`].
+    pub async fn export_game(&self, game: usize) -> io::Result<ExportGameResponse> {
+        self.call("exportGame", Some(ExportGameArgs { game })).await
+    }
+
+    /// Packs a player and cell index into an action byte.
+    /// # Errors
+    /// See [`call`].
+    pub async fn encode_move(&self, args: DecodedMove) -> io::Result<EncodedMove> {
+        self.call("encodeMove", Some(args)).await
+    }
+
+    /// Unpacks an action byte into its player and cell index.
+    /// # Errors
+    /// See [`call`].
+    pub async fn decode_move(&self, args: EncodedMove) -> io::Result<DecodedMove> {
+        self.call("decodeMove", Some(args)).await
+    }
+
+    /// Waits for the first move of the given game accepted above `last_seen_height`.
+    /// # Errors
+    /// See [`call`].
+    pub async fn wait_for_move(&self, args: WaitForMoveArgs) -> io::Result<WaitForMoveResponse> {
+        self.call("waitForMove", Some(args)).await
+    }
+
+    /// Fetches the build and chain the node is serving.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_node_info(&self) -> io::Result<GetNodeInfoResponse> {
+        self.call("getNodeInfo", None::<()>).await
+    }
+
+    /// Lists the moves waiting for blocks to be built, along with eviction counts.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_pending_moves(&self) -> io::Result<GetPendingMovesResponse> {
+        self.call("getPendingMoves", None::<()>).await
+    }
+
+    /// Fetches the health of the VM.
+    /// # Errors
+    /// See [`call`].
+    pub async fn health(&self) -> io::Result<Health> {
+        self.call("health", None::<()>).await
+    }
+
+    /// Fetches percentiles of the time from moves being proposed to the node to their
+    /// blocks being accepted.
+    /// # Errors
+    /// See [`call`].
+    pub async fn get_latency_stats(&self) -> io::Result<LatencyStats> {
+        self.call("getLatencyStats", None::<()>).await
+    }
+
+    /// Lists the methods the node serves, along with their versions and aliases.
+    /// # Errors
+    /// See [`call`].
+    pub async fn rpc_methods(&self) -> io::Result<RpcMethodsResponse> {
+        self.call("rpc_methods", None::<()>).await
+    }
+}
+
+/// Fetches the board of the current game.
+/// # Errors
+/// See [`call`].
+pub async fn get_board(http_rpc: &str, url_path: &str) -> io::Result<GetBoardResponse> {
+    call(http_rpc, url_path, "getBoard", None::<()>).await
+}
+
+/// Fetches the board as it stood after the block at the given height.
+/// # Errors
+/// See [`call`].
+pub async fn get_board_at_height(
+    http_rpc: &str,
+    url_path: &str,
+    height: u64,
+) -> io::Result<GetBoardResponse> {
+    let args = GetBoardAtHeightArgs { height };
+    call(http_rpc, url_path, "getBoardAtHeight", Some(args)).await
+}
+
+/// Fetches a page of finished game results.
+/// # Errors
+/// See [`call`].
+pub async fn get_winners(
+    http_rpc: &str,
+    url_path: &str,
+    args: GetWinnersArgs,
+) -> io::Result<GetWinnersResponse> {
+    call(http_rpc, url_path, "getWinners", Some(args)).await
+}
+
+/// Fetches accepted blocks in height order, a chunk at a time.
+/// # Errors
+/// See [`call`].
+pub async fn stream_blocks(
+    http_rpc: &str,
+    url_path: &str,
+    args: StreamBlocksArgs,
+) -> io::Result<StreamBlocksResponse> {
+    call(http_rpc, url_path, "streamBlocks", Some(args)).await
+}
+
+/// Fetches the index of the game in progress, that is the number of finished games.
+/// # Errors
+/// See [`call`].
+pub async fn current_game(http_rpc: &str, url_path: &str) -> io::Result<usize> {
+    let args = GetWinnersArgs {
+        offset: 0,
+        limit: Some(0),
+    };
+    Ok(get_winners(http_rpc, url_path, args).await?.total)
+}
+
+/// Fetches whether the given game is in progress, won or drawn.
+/// # Errors
+/// See [`call`].
+pub async fn get_game_status(
+    http_rpc: &str,
+    url_path: &str,
+    game: usize,
+) -> io::Result<GetGameStatusResponse> {
+    let args = GetGameStatusArgs { game };
+    call(http_rpc, url_path, "getGameStatus", Some(args)).await
+}
+
+/// Fetches the player expected to make the next move of the given game.
+/// # Errors
+/// See [`call`].
+pub async fn whose_turn(
+    http_rpc: &str,
+    url_path: &str,
+    game: usize,
+) -> io::Result<WhoseTurnResponse> {
+    call(
+        http_rpc,
+        url_path,
+        "whoseTurn",
+        Some(WhoseTurnArgs { game }),
+    )
+    .await
+}
+
+/// Fetches the empty cells the next player may mark in the given game.
+/// # Errors
+/// See [`call`].
+pub async fn get_legal_moves(
+    http_rpc: &str,
+    url_path: &str,
+    game: usize,
+) -> io::Result<GetLegalMovesResponse> {
+    let args = GetLegalMovesArgs { game };
+    call(http_rpc, url_path, "getLegalMoves", Some(args)).await
+}
+
+/// Waits for the first move of the given game accepted above `last_seen_height`.
+/// # Errors
+/// See [`call`].
+pub async fn wait_for_move(
+    http_rpc: &str,
+    url_path: &str,
+    args: WaitForMoveArgs,
+) -> io::Result<WaitForMoveResponse> {
+    call(http_rpc, url_path, "waitForMove", Some(args)).await
+}
+
+/// Fetches the ordered moves of the given game.
+/// # Errors
+/// See [`call`].
+pub async fn get_move_history(
+    http_rpc: &str,
+    url_path: &str,
+    game: usize,
+) -> io::Result<GetMoveHistoryResponse> {
+    let args = GetMoveHistoryArgs { game };
+    call(http_rpc, url_path, "getMoveHistory", Some(args)).await
+}
+
+/// Proposes a player move.
+/// # Errors
+/// See [`call`].
+pub async fn propose_move(
+    http_rpc: &str,
+    url_path: &str,
+    args: ProposedMoveArgs,
+) -> io::Result<ProposedMoveResponse> {
+    call(http_rpc, url_path, "proposeMove", Some(args)).await
+}
+
 /// Represents the error (if any) for APIs.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct APIError {
@@ -0,0 +1,153 @@
+//! Defines the chain configuration passed to the VM on initialization.
+
+use std::{
+    io::{self, Error, ErrorKind},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use avalanche_types::ids;
+use serde::{Deserialize, Serialize};
+
+use crate::{api::faucet::Faucet, mempool::rate_limit::RateLimit, webhook::Webhook};
+
+/// Time a proposed move may stay pending for, unless configured otherwise.
+pub const DEFAULT_PENDING_MOVE_TTL_SECS: u64 = 600;
+
+/// Represents the chain configuration specific to the VM.
+/// Every field is optional, an empty config leaves everything at its default.
+// Each flag toggles an independent feature
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Address to serve the chain RPC over WebSocket on, not served if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_addr: Option<SocketAddr>,
+
+    /// Address to serve the gRPC chain service on, not served if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc_addr: Option<SocketAddr>,
+
+    /// Address to serve the REST gateway on, not served if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_addr: Option<SocketAddr>,
+
+    /// How long proposed moves are collected for before blocks are built for
+    /// them, in milliseconds. Blocks are built as soon as moves arrive if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_window_ms: Option<u64>,
+
+    /// How long a proposed move may stay pending before it is dropped, in seconds.
+    /// Defaults to [`DEFAULT_PENDING_MOVE_TTL_SECS`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_move_ttl_secs: Option<u64>,
+
+    /// Limits how fast each submitter may propose moves, unlimited if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+
+    /// Limits how fast each peer may gossip moves and actions, unlimited if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip_rate_limit: Option<RateLimit>,
+
+    /// Whether pending moves are written to the database and re-admitted on restart
+    #[serde(default)]
+    pub persist_mempool: bool,
+
+    /// Addresses allowed to propose moves through this node, anyone may if empty.
+    /// Only applies to moves submitted to this node's APIs, as blocks and gossip from
+    /// other validators are held to the chain rules alone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowlist: Vec<ids::short::Id>,
+
+    /// Number of most recent accepted blocks whose board snapshots and events are
+    /// kept for historical queries, all of them if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_blocks: Option<u64>,
+
+    /// Whether accepted blocks are compressed with zstd before being written to the
+    /// database. Blocks already stored are read back either way
+    #[serde(default)]
+    pub compress_blocks: bool,
+
+    /// Chain RPC calls taking longer than this are logged along with their
+    /// parameters, in milliseconds. Not logged if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_rpc_threshold_ms: Option<u64>,
+
+    /// Whether the admin RPC is served on `/admin`. It exposes the node's internals
+    /// and can drop pending moves, so it is off unless enabled
+    #[serde(default)]
+    pub admin_api: bool,
+
+    /// Whether CPU and heap profiles can be captured on `/pprof`. Only served if the
+    /// VM was built with the `profiling` feature
+    #[serde(default)]
+    pub profiling: bool,
+
+    /// Webhooks notified whenever a game finishes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+
+    /// Faucet served on `/faucet`, crediting play tokens on test networks. Not
+    /// served unless configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faucet: Option<Faucet>,
+}
+
+impl Config {
+    /// Returns the batching window of the mempool.
+    #[must_use]
+    pub fn batch_window(&self) -> Duration {
+        Duration::from_millis(self.batch_window_ms.unwrap_or_default())
+    }
+
+    /// Returns the duration past which chain RPC calls are logged, if configured.
+    #[must_use]
+    pub fn slow_rpc_threshold(&self) -> Option<Duration> {
+        self.slow_rpc_threshold_ms.map(Duration::from_millis)
+    }
+
+    /// Returns how long a proposed move may stay pending for.
+    #[must_use]
+    pub fn pending_move_ttl(&self) -> Duration {
+        Duration::from_secs(self.pending_move_ttl_secs.unwrap_or(DEFAULT_PENDING_MOVE_TTL_SECS))
+    }
+
+    /// Checks that the given signer may propose moves through this node.
+    /// # Errors
+    /// Fails if there's an allowlist and the move is unsigned or its signer isn't listed.
+    pub fn check_allowed(&self, address: Option<&ids::short::Id>) -> io::Result<()> {
+        if self.allowlist.is_empty() {
+            return Ok(());
+        }
+        match address {
+            Some(address) if self.allowlist.contains(address) => Ok(()),
+            Some(address) => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{address} is not allowed to propose moves"),
+            )),
+            None => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "unsigned moves are not allowed",
+            )),
+        }
+    }
+
+    /// Decodes the config from JSON bytes, or returns the default config if empty.
+    /// # Errors
+    /// Fails if the bytes can't be deserialized
+    pub fn from_slice<S>(d: S) -> io::Result<Self>
+    where
+        S: AsRef<[u8]>,
+    {
+        let d = d.as_ref();
+        if d.is_empty() {
+            return Ok(Self::default());
+        }
+
+        serde_json::from_slice(d)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("failed to decode config {e}")))
+    }
+}
@@ -0,0 +1,76 @@
+//! Messages other chains in the subnet exchange with this one over the platform's
+//! cross-chain app messaging, to query game results without an external relayer.
+//!
+//! Requests and responses are JSON, tagged by `type`, e.g.
+//! `{"type":"GameStatus","game":17}` is answered with
+//! `{"type":"GameStatus","game":17,"status":{"status":"Won","player":1,"at_height":42}}`.
+
+use std::io::{self, Error, ErrorKind};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{game::GameStatus, state::State};
+
+/// Query sent by another chain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum CrossChainRequest {
+    /// Status of the given game, including its winner once it is won
+    GameStatus { game: usize },
+    /// Height of the last accepted block
+    Height,
+}
+
+/// Answer to a [`CrossChainRequest`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum CrossChainResponse {
+    GameStatus { game: usize, status: GameStatus },
+    Height { height: u64 },
+    /// The request was malformed or couldn't be answered
+    Error { message: String },
+}
+
+impl CrossChainRequest {
+    /// Answers the request from the accepted state.
+    pub async fn respond(&self, state: &State) -> CrossChainResponse {
+        match self {
+            Self::GameStatus { game } => match state.get_game_status(*game).await {
+                Ok(status) => CrossChainResponse::GameStatus {
+                    game: *game,
+                    status,
+                },
+                Err(e) => CrossChainResponse::Error {
+                    message: e.to_string(),
+                },
+            },
+            Self::Height => CrossChainResponse::Height {
+                height: state.get_height().await,
+            },
+        }
+    }
+}
+
+/// Encodes a cross-chain message to JSON in bytes.
+/// # Errors
+/// Fails if the message can't be serialized.
+pub fn to_vec<T: Serialize>(msg: &T) -> io::Result<Vec<u8>> {
+    serde_json::to_vec(msg).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize cross-chain message to JSON bytes {e}"),
+        )
+    })
+}
+
+/// Decodes a cross-chain message from JSON bytes.
+/// # Errors
+/// Fails if the bytes aren't a JSON-encoded message of the expected type.
+pub fn from_slice<T: DeserializeOwned>(d: impl AsRef<[u8]>) -> io::Result<T> {
+    serde_json::from_slice(d.as_ref()).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to deserialize cross-chain message from JSON {e}"),
+        )
+    })
+}
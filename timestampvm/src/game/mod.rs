@@ -0,0 +1,103 @@
+//! Tic-Tac-Toe rules shared by block verification, state and the APIs.
+//!
+//! The rules live in the `tictactoe-game` crate, which also builds for
+//! `wasm32` so frontends validate moves with the same logic. This module
+//! re-exports them, and carries rule violations in [`io::Error`]s.
+
+pub mod notation;
+pub mod rating;
+
+use std::{
+    fmt,
+    io::{self, ErrorKind},
+};
+
+use avalanche_types::ids;
+pub use tictactoe_game::{
+    advance, cell, decode_action, empty_cells, encode_action, grid, is_full, legal_moves,
+    next_player, place, position, symbol, to_move, turn, winner, GameResult, GameStatus, MoveError,
+    CELLS, LINES, SIDE,
+};
+
+/// Checks that `action` can be played on `board` when `next_player` is due to move.
+/// # Errors
+/// Fails if the cell is off the board or taken, or the player moved out of turn.
+pub fn check_move(board: u32, next_player: u8, action: u8) -> io::Result<()> {
+    tictactoe_game::check_move(board, next_player, action).map_err(|e| GameError::from(e).into())
+}
+
+/// Errors raised when a move breaks the rules or refers to something that
+/// doesn't exist. These are carried inside an [`io::Error`] so the APIs can
+/// tell them apart (see [`GameError::from_io`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// The action doesn't describe a playable move
+    InvalidMove(String),
+    /// The cell is already marked
+    CellOccupied(u8),
+    /// The player moved when the other player was due to
+    WrongTurn { player: u8, expected: u8 },
+    /// The game has already ended
+    GameOver { game: usize, current: usize },
+    /// The game hasn't started
+    UnknownGame(usize),
+    /// No such block was verified or accepted
+    UnknownBlock(ids::Id),
+    /// No block was accepted at the height
+    UnknownHeight(u64),
+    /// The block's history was pruned (see `historyBlocks` in the chain config)
+    PrunedBlock(ids::Id),
+}
+
+impl GameError {
+    /// Returns the game error carried by an [`io::Error`], if any.
+    #[must_use]
+    pub fn from_io(e: &io::Error) -> Option<&Self> {
+        e.get_ref().and_then(|inner| inner.downcast_ref::<Self>())
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMove(reason) => write!(f, "invalid move: {reason}"),
+            Self::CellOccupied(index) => write!(f, "cell {index} is already taken"),
+            Self::WrongTurn { player, expected } => {
+                write!(f, "player {player} moved out of turn, expected player {expected}")
+            }
+            Self::GameOver { game, current } => {
+                write!(f, "game {game} is over, current game is {current}")
+            }
+            Self::UnknownGame(game) => write!(f, "game {game} does not exist"),
+            Self::UnknownBlock(blk_id) => write!(f, "block {blk_id} does not exist"),
+            Self::UnknownHeight(height) => write!(f, "no accepted block at height {height}"),
+            Self::PrunedBlock(blk_id) => write!(f, "history of block {blk_id} was pruned"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<MoveError> for GameError {
+    fn from(e: MoveError) -> Self {
+        match e {
+            MoveError::OffBoard(_) => Self::InvalidMove(e.to_string()),
+            MoveError::CellOccupied(index) => Self::CellOccupied(index),
+            MoveError::WrongTurn { player, expected } => Self::WrongTurn { player, expected },
+        }
+    }
+}
+
+impl From<GameError> for io::Error {
+    fn from(e: GameError) -> Self {
+        let kind = match e {
+            GameError::InvalidMove(_) | GameError::CellOccupied(_) => ErrorKind::InvalidInput,
+            GameError::WrongTurn { .. } | GameError::GameOver { .. } => ErrorKind::InvalidData,
+            GameError::UnknownGame(_)
+            | GameError::UnknownBlock(_)
+            | GameError::UnknownHeight(_)
+            | GameError::PrunedBlock(_) => ErrorKind::NotFound,
+        };
+        Self::new(kind, e)
+    }
+}
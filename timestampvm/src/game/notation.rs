@@ -0,0 +1,76 @@
+//! Portable text notation of a game, modelled on chess PGN so games can be
+//! archived or fed to analysis tools:
+//!
+//! ```text
+//! [Chain "2JVSBoinj9C2J33VntvzYtVJNZdN2NKiwwKjcumHUWEb5DbBrm"]
+//! [Game "3"]
+//! [StartHeight "12"]
+//! [EndHeight "16"]
+//! [Result "1-0"]
+//!
+//! 1. b2 a1 2. c3 a3 3. a2 1-0
+//! ```
+//!
+//! Tag pairs come first, then the moves numbered in pairs, X's then O's, ending
+//! with the result. A square is named by its column, `a` to `c` from the left,
+//! then its row, `1` to `3` from the top. The result is `1-0` if X won, `0-1` if
+//! O won, `1/2-1/2` for a draw and `*` while the game is in progress.
+//!
+//! Blocks carry no wall-clock time, so moves are placed in time by the heights
+//! of their blocks.
+
+use std::fmt::Write;
+
+use super::{position, GameStatus, CELLS, SIDE};
+
+/// Returns the name of a cell's square, e.g. "b2" for the center.
+#[must_use]
+pub fn square(index: u8) -> String {
+    let (row, col) = position(index);
+    format!("{}{}", char::from(b'a' + col), row + 1)
+}
+
+/// Returns the cell index of a square name, if it is on the board.
+#[must_use]
+pub fn parse_square(name: &str) -> Option<u8> {
+    if let [col @ b'a'..=b'z', row @ b'1'..=b'9'] = name.as_bytes() {
+        let (row, col) = (row - b'1', col - b'a');
+        if row < SIDE && col < SIDE {
+            let index = row * SIDE + col;
+            return (index < CELLS).then_some(index);
+        }
+    }
+    None
+}
+
+/// Returns the result token of a game.
+#[must_use]
+pub fn result(status: &GameStatus) -> &'static str {
+    match status {
+        GameStatus::InProgress { .. } => "*",
+        GameStatus::Won { player: 1, .. } => "1-0",
+        GameStatus::Won { .. } => "0-1",
+        GameStatus::Drawn { .. } => "1/2-1/2",
+    }
+}
+
+/// Writes a game given its tag pairs, the cells marked in turn and its status.
+#[must_use]
+pub fn write(tags: &[(&str, String)], cells: &[u8], status: &GameStatus) -> String {
+    let mut out = String::new();
+    for (name, value) in tags {
+        let _ = writeln!(out, "[{name} \"{}\"]", value.replace('"', "'"));
+    }
+    let _ = writeln!(out, "[Result \"{}\"]", result(status));
+    out.push('\n');
+
+    for (i, cell) in cells.iter().enumerate() {
+        if i % 2 == 0 {
+            let _ = write!(out, "{}. ", i / 2 + 1);
+        }
+        let _ = write!(out, "{} ", square(*cell));
+    }
+    out.push_str(result(status));
+    out.push('\n');
+    out
+}
@@ -0,0 +1,173 @@
+//! Elo ratings of the addresses seated in games, updated as games finish.
+//!
+//! Every validator must rate a game identically, so ratings are integers and the
+//! expected score is read off a table, interpolated linearly, rather than computed
+//! in floating point. Rating differences past the end of the table count as its
+//! last entry.
+
+/// Rating an address starts from, before its first rated game.
+pub const INITIAL_RATING: u32 = 1200;
+
+/// Most points a single game moves a rating by.
+pub const K_FACTOR: i64 = 32;
+
+/// Rating difference two seekers are paired across, however briefly they waited.
+pub const MATCH_WINDOW: u32 = 200;
+
+/// Points the match window of a waiting seeker widens by per block it waited.
+pub const MATCH_WINDOW_GROWTH: u32 = 25;
+
+/// Rating difference between consecutive entries of [`EXPECTED_SCORES`].
+const SCORE_STEP: u32 = 25;
+
+/// Score the higher rated player is expected to make, in ten-thousandths, for
+/// each multiple of [`SCORE_STEP`] it is rated above its opponent, i.e.
+/// `10000 / (1 + 10^(-difference / 400))` rounded. The extra digit keeps the
+/// interpolated score within a thousandth of the formula.
+const EXPECTED_SCORES: [i64; 33] = [
+    5000, 5359, 5715, 6063, 6401, 6725, 7034, 7325, 7597, 7850, 8083, 8296, 8490, 8666, 8823, 8965,
+    9091, 9203, 9302, 9390, 9468, 9536, 9595, 9648, 9693, 9733, 9768, 9799, 9825, 9848, 9868, 9886,
+    9901,
+];
+
+/// Returns the score a player rated `rating` is expected to make against one
+/// rated `opponent`, in thousandths.
+#[must_use]
+pub fn expected_score(rating: u32, opponent: u32) -> i64 {
+    let diff = rating.abs_diff(opponent);
+    let last = EXPECTED_SCORES.len() - 1;
+    let index = usize::try_from(diff / SCORE_STEP).map_or(last, |index| index.min(last));
+    let step = i64::from(SCORE_STEP);
+    let mut score = EXPECTED_SCORES[index] * step;
+    if index < last {
+        let next = EXPECTED_SCORES[index + 1];
+        score += (next - EXPECTED_SCORES[index]) * i64::from(diff % SCORE_STEP);
+    }
+    // Rounds the ten-thousandths, scaled by the step, to the nearest thousandth
+    let score = (score + 5 * step) / (10 * step);
+    if rating >= opponent {
+        score
+    } else {
+        1000 - score
+    }
+}
+
+/// Returns the points a player rated `rating` gains, or loses if negative, by
+/// scoring `score` thousandths against one rated `opponent`: 1000 for a win, 500
+/// for a draw and 0 for a loss.
+#[must_use]
+pub fn rating_change(rating: u32, opponent: u32, score: i64) -> i64 {
+    let change = K_FACTOR * (score - expected_score(rating, opponent));
+    // Rounds to the nearest point, halves away from zero
+    (change + change.signum() * 500) / 1000
+}
+
+/// Returns the ratings of players 1 and 2 after a game between them, given the
+/// player that won it, if any. The points one player gains, the other loses.
+#[must_use]
+pub fn rate_game(ratings: [u32; 2], winner: Option<u8>) -> [u32; 2] {
+    let score = match winner {
+        Some(1) => 1000,
+        Some(_) => 0,
+        None => 500,
+    };
+    let change = rating_change(ratings[0], ratings[1], score);
+    [apply(ratings[0], change), apply(ratings[1], -change)]
+}
+
+/// Returns the ratings of the teammates of players 1 and 2 after a team game,
+/// given the player that won it, if any. Teams are rated as a player with the
+/// average rating of their teammates, and each teammate gains or loses the points
+/// its team did.
+#[must_use]
+pub fn rate_team_game(teams: [[u32; 2]; 2], winner: Option<u8>) -> [[u32; 2]; 2] {
+    let average = |team: [u32; 2]| {
+        // The average of two u32 always fits one
+        u32::try_from((u64::from(team[0]) + u64::from(team[1])) / 2).unwrap_or(u32::MAX)
+    };
+    let before = [average(teams[0]), average(teams[1])];
+    let after = rate_game(before, winner);
+    let change = |side: usize| i64::from(after[side]) - i64::from(before[side]);
+    [
+        teams[0].map(|rating| apply(rating, change(0))),
+        teams[1].map(|rating| apply(rating, change(1))),
+    ]
+}
+
+/// Returns the rating difference a seeker that waited `waited` blocks is paired
+/// across.
+#[must_use]
+pub fn match_window(waited: u64) -> u32 {
+    let waited = u32::try_from(waited).unwrap_or(u32::MAX);
+    MATCH_WINDOW.saturating_add(waited.saturating_mul(MATCH_WINDOW_GROWTH))
+}
+
+/// Moves a rating by `change` points, without going below zero.
+fn apply(rating: u32, change: i64) -> u32 {
+    u32::try_from((i64::from(rating) + change).max(0)).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expected_score, rate_game, rate_team_game, EXPECTED_SCORES, SCORE_STEP};
+
+    /// Ratings the zero-sum check pairs up, far enough above zero for no rating
+    /// to be clamped.
+    const RATINGS: [u32; 6] = [800, 1000, 1200, 1350, 1600, 2100];
+
+    #[test]
+    fn expected_score_stays_within_a_point_of_the_formula() {
+        let table_end = SCORE_STEP * (u32::try_from(EXPECTED_SCORES.len()).unwrap() - 1);
+        for diff in 0..=table_end {
+            let formula = 1000.0 / (1.0 + 10_f64.powf(-f64::from(diff) / 400.0));
+            for (rating, opponent, expected) in [
+                (1200 + diff, 1200, formula),
+                (1200, 1200 + diff, 1000.0 - formula),
+            ] {
+                let score = expected_score(rating, opponent);
+                assert!(
+                    (f64::from(i32::try_from(score).unwrap()) - expected).abs() <= 1.0,
+                    "expected score {score} at {rating} against {opponent} isn't {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rating_updates_are_zero_sum() {
+        for x in RATINGS {
+            for o in RATINGS {
+                for winner in [Some(1), Some(2), None] {
+                    let [x_after, o_after] = rate_game([x, o], winner);
+                    assert_eq!(
+                        x_after + o_after,
+                        x + o,
+                        "{x} against {o}, won by {winner:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rating_never_goes_below_zero() {
+        assert_eq!(rate_game([10, 10], Some(2)), [0, 26]);
+        assert_eq!(rate_game([10, 10], Some(1)), [26, 0]);
+    }
+
+    #[test]
+    fn draw_between_equal_ratings_changes_nothing() {
+        for rating in RATINGS {
+            assert_eq!(rate_game([rating, rating], None), [rating, rating]);
+        }
+    }
+
+    #[test]
+    fn teammates_move_by_the_points_their_team_did() {
+        let [winners, losers] = rate_team_game([[1100, 1300], [1200, 1200]], Some(1));
+        let [team_after, _] = rate_game([1200, 1200], Some(1));
+        let change = team_after - 1200;
+        assert_eq!(winners, [1100 + change, 1300 + change]);
+        assert_eq!(losers, [1200 - change, 1200 - change]);
+    }
+}
@@ -5,24 +5,151 @@ use std::{
     fs::{self, File},
     io::{self, Error, ErrorKind, Write},
     path::Path,
+    str::FromStr,
 };
 
+use avalanche_types::ids;
 use serde::{Deserialize, Serialize};
 
+/// Most leading zero bits the genesis may require of a move's proof of work, so
+/// it stays lightweight.
+pub const MAX_MOVE_WORK: u8 = 32;
+
 /// Represents the genesis data specific to the VM.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct Genesis {
     pub data: String,
+    /// Token balances the chain starts with. Omitted from the encoding when
+    /// empty, so chains created before tokens keep their genesis bytes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allocations: Vec<Allocation>,
+    /// Tokens burned from the signer's balance for each move, free if zero.
+    /// Omitted from the encoding when zero, as are allocations.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub move_fee: u64,
+    /// Cap the move fee rises to as the mempool fills up. Congestion doesn't
+    /// raise the fee if it is no more than `move_fee`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_move_fee: u64,
+    /// Tokens each address escrows when it takes a seat in a game, paid out to
+    /// the winner or refunded on a draw. Games are unwagered if zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub game_stake: u64,
+    /// Address credited with a share of each move fee, the rest being burned.
+    /// Fees are burned in full if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub treasury: Option<Treasury>,
+    /// Leading zero bits the proof of work of each move must have, see
+    /// [`block::work_hash`](crate::block::work_hash). Meant to keep feeless chains
+    /// free of spam, no work is required if zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub move_work: u8,
 }
 
 impl Default for Genesis {
     fn default() -> Self {
         Self {
             data: String::from("Hello from Rust VM!"),
+            allocations: Vec::new(),
+            move_fee: 0,
+            max_move_fee: 0,
+            game_stake: 0,
+            treasury: None,
+            move_work: 0,
         }
     }
 }
 
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+/// Tokens credited to an address when the chain starts.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Allocation {
+    pub address: ids::short::Id,
+    pub balance: u64,
+}
+
+impl FromStr for Allocation {
+    type Err = Error;
+
+    /// Parses an allocation written as `ADDRESS=BALANCE`.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("'{s}' isn't an allocation, expected ADDRESS=BALANCE"),
+            )
+        };
+        let (address, balance) = s.split_once('=').ok_or_else(invalid)?;
+        // Shorter strings make the Id decoder panic
+        if address.len() < ids::short::LEN {
+            return Err(invalid());
+        }
+        Ok(Self {
+            address: ids::short::Id::from_str(address).map_err(|_| invalid())?,
+            balance: balance.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Address credited with a share of every move fee.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Treasury {
+    pub address: ids::short::Id,
+    /// Percent of each fee credited to the address, at most 100
+    pub share: u8,
+}
+
+impl Treasury {
+    /// Returns the part of the fee credited to the treasury, rounded down.
+    #[must_use]
+    pub fn cut(&self, fee: u64) -> u64 {
+        // Multiplied wide, so large fees can't overflow
+        let cut = u128::from(fee) * u128::from(self.share.min(100)) / 100;
+        u64::try_from(cut).unwrap_or(fee)
+    }
+
+    /// Checks the share is a percentage.
+    /// # Errors
+    /// Fails if the share is above 100.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.share > 100 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("treasury share {}% is above 100%", self.share),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Treasury {
+    type Err = Error;
+
+    /// Parses a treasury written as `ADDRESS=PERCENT`.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("'{s}' isn't a treasury, expected ADDRESS=PERCENT"),
+            )
+        };
+        let (address, share) = s.split_once('=').ok_or_else(invalid)?;
+        // Shorter strings make the Id decoder panic
+        if address.len() < ids::short::LEN {
+            return Err(invalid());
+        }
+        let treasury = Self {
+            address: ids::short::Id::from_str(address).map_err(|_| invalid())?,
+            share: share.parse().map_err(|_| invalid())?,
+        };
+        treasury.validate()?;
+        Ok(treasury)
+    }
+}
+
 impl Genesis {
     /// Encodes the genesis to JSON bytes.
     /// # Errors
@@ -38,23 +165,40 @@ impl Genesis {
 
     /// Decodes the genesis from JSON bytes.
     /// # Errors
-    /// Fails if the bytes can't be deserialized
+    /// Fails if the bytes can't be deserialized, the treasury share isn't a percentage
+    /// or the move work is above [`MAX_MOVE_WORK`]
     pub fn from_slice<S>(d: S) -> io::Result<Self>
     where
         S: AsRef<[u8]>,
     {
-        serde_json::from_slice(d.as_ref())
-            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to decode {e}")))
+        let genesis: Self = serde_json::from_slice(d.as_ref())
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to decode {e}")))?;
+        if let Some(treasury) = &genesis.treasury {
+            treasury.validate()?;
+        }
+        if genesis.move_work > MAX_MOVE_WORK {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("move work of {} bits is above {MAX_MOVE_WORK}", genesis.move_work),
+            ));
+        }
+        Ok(genesis)
     }
 
     /// Persists the genesis to a file.
     /// # Errors
-    /// Fails if the file can't be created, written to, or if `self` can't be serialized
+    /// Fails if the path has no parent directory, the file can't be created, written
+    /// to, or if `self` can't be serialized
     pub fn sync(&self, file_path: &str) -> io::Result<()> {
-        log::info!("syncing genesis to '{}'", file_path);
+        log::info!("syncing genesis to '{file_path}'");
 
         let path = Path::new(file_path);
-        let parent_dir = path.parent().expect("Invalid path");
+        let parent_dir = path.parent().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("genesis path '{file_path}' has no parent directory"),
+            )
+        })?;
         fs::create_dir_all(parent_dir)?;
 
         let d = serde_json::to_vec(&self).map_err(|e| {
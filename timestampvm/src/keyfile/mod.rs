@@ -0,0 +1,204 @@
+//! Password-encrypted keyfiles holding the secp256k1 keys players sign moves with.
+
+use std::{
+    fs,
+    io::{self, Error, ErrorKind, Write},
+    path::Path,
+};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use avalanche_types::{codec::serde::hex_0x_bytes::Hex0xBytes, ids, key::secp256k1::private_key};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Version of the keyfile format written by [`Keyfile::encrypt`].
+pub const VERSION: u32 = 1;
+
+/// Default scrypt cost, as `log2(N)`.
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+/// Highest scrypt cost accepted from a keyfile, so a crafted one can't exhaust memory.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// Player key encrypted with AES-256-GCM under a scrypt-derived key.
+/// The address is authenticated along with the key, so it can't be swapped.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Keyfile {
+    pub version: u32,
+    /// Address the Vm recovers from moves signed with this key.
+    pub address: ids::short::Id,
+    pub crypto: Crypto,
+}
+
+/// Key derivation and cipher parameters of a [`Keyfile`].
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Crypto {
+    pub kdf: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    #[serde_as(as = "Hex0xBytes")]
+    pub salt: Vec<u8>,
+    #[serde_as(as = "Hex0xBytes")]
+    pub nonce: Vec<u8>,
+    #[serde_as(as = "Hex0xBytes")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl Keyfile {
+    /// Encrypts `key` under `password`.
+    /// # Errors
+    /// Fails if the address can't be derived from the key, or encryption fails.
+    pub fn encrypt(key: &private_key::Key, password: &str) -> io::Result<Self> {
+        let address = key.to_public_key().to_short_id()?;
+
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = cipher(password, &salt, SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &key.to_bytes(),
+                    aad: address.as_ref(),
+                },
+            )
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encrypt key {e}")))?;
+
+        Ok(Self {
+            version: VERSION,
+            address,
+            crypto: Crypto {
+                kdf: String::from("scrypt"),
+                log_n: SCRYPT_LOG_N,
+                r: SCRYPT_R,
+                p: SCRYPT_P,
+                salt,
+                nonce,
+                ciphertext,
+            },
+        })
+    }
+
+    /// Decrypts the key with `password`.
+    /// # Errors
+    /// Fails if the password is wrong, the keyfile was tampered with, or its parameters
+    /// aren't supported.
+    pub fn decrypt(&self, password: &str) -> io::Result<private_key::Key> {
+        if self.version != VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported keyfile version {}", self.version),
+            ));
+        }
+        let c = &self.crypto;
+        if c.kdf != "scrypt" {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported keyfile kdf '{}'", c.kdf),
+            ));
+        }
+        if c.log_n > MAX_SCRYPT_LOG_N {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("scrypt log_n {} exceeds {MAX_SCRYPT_LOG_N}", c.log_n),
+            ));
+        }
+        if c.nonce.len() != NONCE_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "expected {NONCE_LEN}-byte nonce, got {}-byte",
+                    c.nonce.len()
+                ),
+            ));
+        }
+
+        let cipher = cipher(password, &c.salt, c.log_n, c.r, c.p)?;
+        let raw = cipher
+            .decrypt(
+                Nonce::from_slice(&c.nonce),
+                Payload {
+                    msg: &c.ciphertext,
+                    aad: self.address.as_ref(),
+                },
+            )
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::PermissionDenied,
+                    "failed to decrypt key, wrong password or corrupted keyfile",
+                )
+            })?;
+
+        let key = private_key::Key::from_bytes(&raw)?;
+        let address = key.to_public_key().to_short_id()?;
+        if address != self.address {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("key belongs to {address} rather than {}", self.address),
+            ));
+        }
+        Ok(key)
+    }
+
+    /// Loads a keyfile from disk.
+    /// # Errors
+    /// Fails if the file can't be read or isn't a keyfile.
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let d = fs::read(file_path)?;
+        serde_json::from_slice(&d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to decode keyfile '{file_path}' {e}"),
+            )
+        })
+    }
+
+    /// Writes the keyfile to disk, refusing to overwrite an existing file.
+    /// # Errors
+    /// Fails if the file exists or can't be written.
+    pub fn sync(&self, file_path: &str) -> io::Result<()> {
+        if Path::new(file_path).exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("keyfile '{file_path}' already exists"),
+            ));
+        }
+        let d = serde_json::to_vec_pretty(&self).map_err(|e| {
+            Error::new(ErrorKind::Other, format!("failed to serialize keyfile {e}"))
+        })?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // readable by its owner only
+            options.mode(0o600);
+        }
+        options.open(file_path)?.write_all(&d)
+    }
+}
+
+/// Derives the AES-256-GCM cipher for `password` with scrypt.
+fn cipher(password: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> io::Result<Aes256Gcm> {
+    let params = scrypt::Params::new(log_n, r, p, KEY_LEN)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid scrypt params {e}")))?;
+    let mut derived = [0u8; KEY_LEN];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut derived)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to derive key {e}")))?;
+    Aes256Gcm::new_from_slice(&derived)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("invalid derived key {e}")))
+}
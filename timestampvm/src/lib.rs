@@ -17,8 +17,19 @@
 //! * [`bin/timestampvm`](https://github.com/ava-labs/timestampvm-rs/tree/main/timestampvm/src/bin/timestampvm): Command-line interface, and plugin server.
 //! * [`block`](https://docs.rs/timestampvm/latest/timestampvm/block): Implementation of [`snowman.Block`](https://pkg.go.dev/github.com/ava-labs/avalanchego/snow/consensus/snowman#Block) interface for timestampvm.
 //! * [`client`](https://docs.rs/timestampvm/latest/timestampvm/client): Implements client for timestampvm APIs.
+//! * [`config`](https://docs.rs/timestampvm/latest/timestampvm/config): Defines the chain configuration.
+//! * [`crosschain`](https://docs.rs/timestampvm/latest/timestampvm/crosschain): Answers game queries from other chains in the subnet.
+//! * [`game`](https://docs.rs/timestampvm/latest/timestampvm/game): Tic-Tac-Toe rules shared across the VM.
 //! * [`genesis`](https://docs.rs/timestampvm/latest/timestampvm/genesis): Defines timestampvm genesis block.
+//! * [`keyfile`](https://docs.rs/timestampvm/latest/timestampvm/keyfile): Password-encrypted keyfiles for signing moves.
+//! * [`logging`](https://docs.rs/timestampvm/latest/timestampvm/logging): Logger whose filter can be changed at runtime.
+//! * [`mempool`](https://docs.rs/timestampvm/latest/timestampvm/mempool): Holds proposed moves until their blocks are built.
+//! * [`metrics`](https://docs.rs/timestampvm/latest/timestampvm/metrics): Prometheus metrics of the VM.
+//! * [`proto`](https://docs.rs/timestampvm/latest/timestampvm/proto): Protobuf encoding of blocks and API types.
+//! * [`standalone`](https://docs.rs/timestampvm/latest/timestampvm/standalone): Runs the VM without avalanchego, for local play.
 //! * [`state`](https://docs.rs/timestampvm/latest/timestampvm/state): Manages the virtual machine states.
+//! * [`telemetry`](https://docs.rs/timestampvm/latest/timestampvm/telemetry): Exports tracing spans over OTLP (`otlp` feature).
+//! * [`webhook`](https://docs.rs/timestampvm/latest/timestampvm/webhook): Notifies webhooks of finished games.
 //! * [`vm`](https://docs.rs/timestampvm/latest/timestampvm/vm): Implementation of [`snowman.block.ChainVM`](https://pkg.go.dev/github.com/ava-labs/avalanchego/snow/engine/snowman/block#ChainVM) interface for timestampvm.
 //!
 //! ## Example
@@ -43,6 +54,23 @@
 pub mod api;
 pub mod block;
 pub mod client;
+pub mod config;
+pub mod crosschain;
+pub mod game;
 pub mod genesis;
+pub mod keyfile;
+pub mod logging;
+pub mod mempool;
+pub mod metrics;
+pub mod proto;
+pub mod standalone;
 pub mod state;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
 pub mod vm;
+pub mod webhook;
+
+/// Allocates through jemalloc, so heap profiles can be captured, see [`api::profile`].
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
@@ -0,0 +1,118 @@
+//! Logger of the VM whose filter can be changed while the node runs, e.g. to
+//! debug a misbehaving validator without restarting the plugin and losing state.
+//! Filters use the `RUST_LOG` syntax, e.g. `timestampvm=debug,info`.
+
+use std::{
+    io::{self, Error, ErrorKind},
+    str::FromStr,
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use log::{LevelFilter, Log, Metadata, Record};
+use once_cell::sync::{Lazy, OnceCell};
+
+/// Filter used when `RUST_LOG` isn't set.
+pub const DEFAULT_FILTER: &str = "info";
+
+static LOGGER: Lazy<ReloadableLogger> = Lazy::new(|| ReloadableLogger {
+    current: ArcSwap::from_pointee(FilteredLogger::new(DEFAULT_FILTER)),
+});
+
+/// Set once [`init`] installed [`LOGGER`] as the global logger.
+static INSTALLED: OnceCell<()> = OnceCell::new();
+
+/// Logger along with the filter it was built from.
+struct FilteredLogger {
+    filter: String,
+    logger: env_logger::Logger,
+}
+
+impl FilteredLogger {
+    fn new(filter: &str) -> Self {
+        Self {
+            filter: filter.to_string(),
+            logger: env_logger::Builder::new().parse_filters(filter).build(),
+        }
+    }
+}
+
+/// Forwards records to the current logger, swapped as a whole on filter changes.
+struct ReloadableLogger {
+    current: ArcSwap<FilteredLogger>,
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.current.load().logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.current.load().logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.current.load().logger.flush();
+    }
+}
+
+/// Installs the global logger, filtered by `RUST_LOG` or else [`DEFAULT_FILTER`].
+/// # Errors
+/// Fails if `RUST_LOG` is invalid or another logger is already installed.
+pub fn init() -> io::Result<()> {
+    let filter = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    check_filter(&filter)?;
+    LOGGER.current.store(Arc::new(FilteredLogger::new(&filter)));
+
+    log::set_logger(&*LOGGER)
+        .map_err(|e| Error::new(ErrorKind::AlreadyExists, format!("failed to set logger {e}")))?;
+    log::set_max_level(LOGGER.current.load().logger.filter());
+    INSTALLED.get_or_init(|| ());
+    Ok(())
+}
+
+/// Returns the current filter, if [`init`] installed the logger.
+#[must_use]
+pub fn filter() -> Option<String> {
+    INSTALLED.get().map(|()| LOGGER.current.load().filter.clone())
+}
+
+/// Replaces the filter of the logger installed by [`init`], returning the previous one.
+/// # Errors
+/// Fails if the filter is invalid or the logger wasn't installed by [`init`].
+pub fn set_filter(filter: &str) -> io::Result<String> {
+    if INSTALLED.get().is_none() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "the VM's logger isn't installed, its filter can't be changed",
+        ));
+    }
+    check_filter(filter)?;
+
+    let previous = LOGGER.current.swap(Arc::new(FilteredLogger::new(filter)));
+    log::set_max_level(LOGGER.current.load().logger.filter());
+    log::info!("log filter changed from '{}' to '{filter}'", previous.filter);
+    Ok(previous.filter.clone())
+}
+
+/// Checks each directive of a filter sets a valid level, since the logger
+/// would only skip invalid ones.
+fn check_filter(filter: &str) -> io::Result<()> {
+    // A trailing "/regex" filters messages rather than modules
+    let directives = filter.split('/').next().unwrap_or_default();
+    if directives.trim().is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "empty log filter"));
+    }
+
+    for directive in directives.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        if let Some((_, level)) = directive.split_once('=') {
+            if LevelFilter::from_str(level.trim()).is_err() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid level '{level}' in log filter directive '{directive}'"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
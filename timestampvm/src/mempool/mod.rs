@@ -0,0 +1,569 @@
+//! Holds proposed moves until blocks carrying them are built.
+//!
+//! Each move is checked against the board and turn predicted from the moves
+//! queued before it, then wrapped in a block chained on top of the last queued
+//! block, so its Id is known right away. Queued moves are keyed by what they play
+//! (see [`MoveKey`]), so resubmitting a move that is already queued returns the
+//! queued block instead of queueing a second, conflicting one.
+//!
+//! # Ordering
+//!
+//! Pending moves are kept in the order they were received, and blocks are always
+//! built for the oldest one first. Each turn goes to the first received move that
+//! can be played: a later move for a turn that is already taken is rejected on
+//! admission, or dropped on eviction if the moves ahead of it changed. Persisted
+//! moves are re-admitted in the order they were originally received.
+//!
+//! The consensus engine is told there are pending moves (`PendingTxs`) whenever
+//! a move is queued, and again after each build while moves remain, so it calls
+//! `build_block` once per pending move. With a batching window, the first move
+//! of a burst waits out the window before the engine is told, so the moves that
+//! follow it are queued (and checked against each other) before building starts.
+//!
+//! Pending moves are re-checked whenever a block is accepted (see [`Mempool::evict`]):
+//! moves the accepted state no longer allows are evicted, as are moves older than
+//! the configured age, and the moves after them are re-chained onto the block the
+//! evicted ones were built on.
+//!
+//! If configured to, the VM writes the pending moves to the database (see
+//! [`PersistedMove`]) and re-admits them on startup.
+//!
+//! Every newly admitted move is also handed to the gossip channel, so it reaches
+//! the other validators' mempools (see [`GossipedMove`]). Moves they already hold
+//! aren't admitted again, which keeps gossip from echoing back and forth.
+//!
+//! Until the node is bootstrapped (see [`Mempool::set_active`]), the engine isn't
+//! notified and nothing is gossiped, though restored moves are still queued.
+//!
+//! Moves proposed through the APIs may be rate limited per submitter (see [`rate_limit`]).
+//!
+//! Transfers, seeks, tournament, series and team actions are queued alongside
+//! moves, in the order received, but take no part in predicting the board. Each
+//! address may only have one of them pending, so it is checked against the balance
+//! and nonce it will be applied to.
+
+pub mod rate_limit;
+
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use avalanche_types::{ids, subnet::rpc::snow::engine::common::message::Message};
+use avalanche_types::codec::serde::hex_0x_bytes::Hex0xBytes;
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use tokio::sync::mpsc::{error::TrySendError, Sender, UnboundedSender};
+
+use crate::{
+    block::{AccountAction, Block, Seek, SeriesAction, TeamAction, TournamentAction, Transfer},
+    game,
+};
+
+/// Number of pending moves the mempool is initially allocated for.
+const INITIAL_CAPACITY: usize = 100;
+
+/// A block carrying a pending move, along with when the move was received.
+#[derive(Debug, Clone)]
+pub struct PendingMove {
+    pub block: Block,
+    pub received: Instant,
+}
+
+/// Identifies a queued move by what it plays, regardless of its signature.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MoveKey {
+    /// Game the move will be played in, once the moves queued before it are
+    pub game: usize,
+    /// Number of moves played in the game before this one
+    pub turn: u8,
+    pub player: u8,
+    pub cell: u8,
+}
+
+/// The transfer, seek, tournament, series or team action a pending block carries
+/// instead of a move, as gossiped and persisted alongside the move fields. At most
+/// one of them is set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<Transfer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seek: Option<Seek>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tournament: Option<TournamentAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<SeriesAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<TeamAction>,
+}
+
+impl AccountFields {
+    /// Returns the account action set, if any.
+    #[must_use]
+    pub fn into_action(self) -> Option<AccountAction> {
+        if let Some(transfer) = self.transfer {
+            Some(AccountAction::Transfer(transfer))
+        } else if let Some(seek) = self.seek {
+            Some(AccountAction::Seek(seek))
+        } else if let Some(action) = self.tournament {
+            Some(AccountAction::Tournament(action))
+        } else if let Some(action) = self.series {
+            Some(AccountAction::Series(action))
+        } else {
+            self.team.map(AccountAction::Team)
+        }
+    }
+}
+
+impl From<Option<AccountAction>> for AccountFields {
+    fn from(action: Option<AccountAction>) -> Self {
+        let mut fields = Self::default();
+        match action {
+            Some(AccountAction::Transfer(transfer)) => fields.transfer = Some(transfer),
+            Some(AccountAction::Seek(seek)) => fields.seek = Some(seek),
+            Some(AccountAction::Tournament(action)) => fields.tournament = Some(action),
+            Some(AccountAction::Series(action)) => fields.series = Some(action),
+            Some(AccountAction::Team(action)) => fields.team = Some(action),
+            None => {}
+        }
+        fields
+    }
+}
+
+/// A pending move as gossiped between validators.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GossipedMove {
+    pub action: u8,
+    #[serde_as(as = "Hex0xBytes")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+    /// Most the signer agreed to pay for the move
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<u64>,
+    /// Nonce ground for the move's proof of work
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work: Option<u64>,
+    /// Account action gossiped instead of a move
+    #[serde(flatten)]
+    pub account: AccountFields,
+}
+
+impl GossipedMove {
+    /// Encodes the move to JSON in bytes.
+    /// # Errors
+    /// Fails if the move can't be serialized.
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("failed to serialize gossiped move to JSON bytes {e}"),
+            )
+        })
+    }
+
+    /// Decodes a move from JSON bytes.
+    /// # Errors
+    /// Fails if the bytes aren't a JSON-encoded move.
+    pub fn from_slice(d: impl AsRef<[u8]>) -> io::Result<Self> {
+        serde_json::from_slice(d.as_ref()).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to deserialize gossiped move from JSON {e}"),
+            )
+        })
+    }
+}
+
+/// A pending move as written to the database.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PersistedMove {
+    pub action: u8,
+    #[serde_as(as = "Hex0xBytes")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+    /// Most the signer agreed to pay for the move
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<u64>,
+    /// Nonce ground for the move's proof of work
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work: Option<u64>,
+    /// Account action pending instead of a move
+    #[serde(flatten)]
+    pub account: AccountFields,
+    /// When the move was received, in milliseconds since the Unix epoch
+    pub received_at: i64,
+}
+
+impl PersistedMove {
+    /// Returns how long ago the move was received.
+    #[must_use]
+    pub fn age(&self) -> Duration {
+        let age = Utc::now().timestamp_millis().saturating_sub(self.received_at);
+        Duration::from_millis(u64::try_from(age).unwrap_or_default())
+    }
+}
+
+/// Number of pending moves evicted from the mempool, by reason.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+pub struct EvictionStats {
+    /// Moves the accepted state no longer allows
+    pub invalid: u64,
+    /// Moves pending for longer than the maximum age
+    pub expired: u64,
+}
+
+/// Queue of blocks carrying pending moves, in the order they will be built.
+#[derive(Debug, Default)]
+pub struct Mempool {
+    pending: VecDeque<PendingMove>,
+    /// Total evictions since the mempool was created
+    evicted: EvictionStats,
+    /// Channel to the consensus engine, notified of pending moves
+    to_engine: Option<Sender<Message>>,
+    /// How long moves are collected for before the engine is notified
+    batch_window: Duration,
+    /// Set while a notification is waiting out the batching window
+    notify_scheduled: Arc<AtomicBool>,
+    /// Channel newly admitted moves are sent to, to be gossiped
+    gossip: Option<UnboundedSender<GossipedMove>>,
+    /// Set once the node is in normal operation, when blocks may be built
+    active: bool,
+}
+
+impl Mempool {
+    #[must_use]
+    pub fn new(to_engine: Option<Sender<Message>>, batch_window: Duration) -> Self {
+        Self {
+            pending: VecDeque::with_capacity(INITIAL_CAPACITY),
+            evicted: EvictionStats::default(),
+            to_engine,
+            batch_window,
+            notify_scheduled: Arc::new(AtomicBool::new(false)),
+            gossip: None,
+            active: false,
+        }
+    }
+
+    /// Enables or disables engine notifications and gossip, as the node leaves or
+    /// enters bootstrapping. Notifies the engine right away of moves queued meanwhile.
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+        if active && !self.pending.is_empty() {
+            self.notify_engine();
+        }
+    }
+
+    /// Sends every move admitted from now on to the given channel, to be gossiped.
+    #[must_use]
+    pub fn with_gossip(mut self, gossip: UnboundedSender<GossipedMove>) -> Self {
+        self.gossip = Some(gossip);
+        self
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Returns the pending moves, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &PendingMove> {
+        self.pending.iter()
+    }
+
+    /// Returns the number of moves evicted so far.
+    #[must_use]
+    pub fn evicted(&self) -> EvictionStats {
+        self.evicted
+    }
+
+    /// Returns the pending moves in the form they are written to the database.
+    #[must_use]
+    pub fn to_persisted(&self) -> Vec<PersistedMove> {
+        let now = Utc::now().timestamp_millis();
+        self.pending
+            .iter()
+            .map(|pending| PersistedMove {
+                action: pending.block.get_player_move(),
+                signature: pending.block.signature().to_vec(),
+                max_fee: pending.block.max_fee(),
+                work: pending.block.work(),
+                account: pending.block.account_action().into(),
+                received_at: now.saturating_sub(
+                    i64::try_from(pending.received.elapsed().as_millis()).unwrap_or(i64::MAX),
+                ),
+            })
+            .collect()
+    }
+
+    /// Returns the oldest pending block, which is built next.
+    #[must_use]
+    pub fn front(&self) -> Option<&Block> {
+        self.pending.front().map(|pending| &pending.block)
+    }
+
+    /// Returns the last queued block, which the next move is chained on top of.
+    #[must_use]
+    pub fn last(&self) -> Option<&Block> {
+        self.pending.back().map(|pending| &pending.block)
+    }
+
+    /// Returns the key of every queued move, in order and leaving out other blocks,
+    /// given the index and board of the accepted game. Also returns the game the
+    /// next move will be played in.
+    #[must_use]
+    pub fn keys(&self, game: usize, board: u32) -> (Vec<MoveKey>, usize) {
+        let (mut game, mut board) = (game, board);
+        let keys = self
+            .moves()
+            .map(|block| {
+                let key = MoveKey {
+                    game,
+                    turn: game::turn(board),
+                    player: block.get_player_id(),
+                    cell: block.get_move_index(),
+                };
+                // A move that ends its game opens the next one
+                board = game::advance(board, block.get_player_move());
+                if board == 0 {
+                    game += 1;
+                }
+                key
+            })
+            .collect();
+        (keys, game)
+    }
+
+    /// Returns the queued block playing the same move as `action` would, given the
+    /// index and board of the accepted game. A cell is marked at most once per game,
+    /// so a queued move by the same player on the same cell of the game `action`
+    /// would be played in has the same key.
+    #[must_use]
+    pub fn find(&self, game: usize, board: u32, action: u8) -> Option<&Block> {
+        let (player, cell) = game::decode_action(action);
+        let (keys, next_game) = self.keys(game, board);
+        keys.iter()
+            .zip(self.moves())
+            .find(|(key, _)| key.game == next_game && key.player == player && key.cell == cell)
+            .map(|(_, block)| block)
+    }
+
+    /// Returns the queued transfer, seek, tournament, series or team action signed by
+    /// the given address, if any.
+    #[must_use]
+    pub fn find_account_action(&self, sender: &ids::short::Id) -> Option<&Block> {
+        self.blocks()
+            .find(|block| block.account_signer().ok().flatten().as_ref() == Some(sender))
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.pending.iter().map(|pending| &pending.block)
+    }
+
+    /// Returns the queued blocks carrying moves rather than account actions.
+    fn moves(&self) -> impl Iterator<Item = &Block> {
+        self.blocks().filter(|block| block.is_move())
+    }
+
+    /// Appends a block and notifies the engine. Callers check the move isn't
+    /// already queued (see [`Mempool::find`]).
+    pub fn push(&mut self, block: Block) {
+        if let (true, Some(gossip)) = (self.active, &self.gossip) {
+            let gossiped = GossipedMove {
+                action: block.get_player_move(),
+                signature: block.signature().to_vec(),
+                max_fee: block.max_fee(),
+                work: block.work(),
+                account: block.account_action().into(),
+            };
+            if gossip.send(gossiped).is_err() {
+                log::warn!("gossip channel closed, move {} won't be gossiped", block.id());
+            }
+        }
+        self.pending.push_back(PendingMove {
+            block,
+            received: Instant::now(),
+        });
+        self.schedule_notify();
+    }
+
+    /// Removes and returns the oldest pending block, notifying the engine again
+    /// if more remain.
+    pub fn pop(&mut self) -> Option<Block> {
+        let block = self.pending.pop_front().map(|pending| pending.block);
+        if !self.pending.is_empty() {
+            self.notify_engine();
+        }
+        block
+    }
+
+    /// Drops the oldest pending block as invalid, re-chaining the blocks after it
+    /// onto its parent. Returns the dropped block.
+    /// # Errors
+    /// Fails if a re-chained block can't be created.
+    pub fn discard_front(&mut self) -> io::Result<Option<Block>> {
+        let dropped = match self.pending.pop_front() {
+            Some(pending) => pending.block,
+            None => return Ok(None),
+        };
+        let mut parent = (dropped.parent_id(), dropped.height() - 1);
+        for pending in &mut self.pending {
+            pending.block = pending.block.with_parent(parent.0, parent.1 + 1)?;
+            parent = (pending.block.id(), pending.block.height());
+        }
+        self.evicted.invalid += 1;
+        Ok(Some(dropped))
+    }
+
+    /// Removes the pending block with the given Id, leaving the moves after it
+    /// chained onto it until [`Mempool::evict`] re-chains them.
+    pub fn remove(&mut self, blk_id: &ids::Id) -> Option<Block> {
+        let index = self.pending.iter().position(|pending| &pending.block.id() == blk_id)?;
+        self.pending.remove(index).map(|pending| pending.block)
+    }
+
+    /// Re-checks the pending moves against the accepted board and turn, dropping
+    /// those that can no longer be played or have been pending for longer than
+    /// `max_age`. Moves after a dropped one are re-chained onto its parent, and the
+    /// first move onto `base` (the Id and height of the block to build on) if its
+    /// parent is gone. Returns the number of moves evicted.
+    /// # Errors
+    /// Fails if a re-chained block can't be created.
+    pub fn evict(
+        &mut self,
+        board: u32,
+        next_player: u8,
+        base: Option<(ids::Id, u64)>,
+        max_age: Duration,
+    ) -> io::Result<EvictionStats> {
+        let mut evicted = EvictionStats::default();
+
+        // Block the next kept move has to be chained onto
+        let mut parent = if let Some(front) = self.pending.front() {
+            base.unwrap_or((front.block.parent_id(), front.block.height() - 1))
+        } else {
+            return Ok(evicted);
+        };
+        let mut predicted = (board, next_player);
+        let mut kept = VecDeque::with_capacity(self.pending.len());
+        for mut pending in self.pending.drain(..) {
+            let action = pending.block.get_player_move();
+            if pending.received.elapsed() > max_age {
+                log::info!("expired pending move {action} in block {}", pending.block.id());
+                evicted.expired += 1;
+                continue;
+            }
+            let is_move = pending.block.is_move();
+            if is_move {
+                if let Err(e) = game::check_move(predicted.0, predicted.1, action) {
+                    let id = pending.block.id();
+                    log::info!("evicted pending move {action} in block {id}: {e}");
+                    evicted.invalid += 1;
+                    continue;
+                }
+            }
+
+            if pending.block.parent_id() != parent.0 {
+                pending.block = pending.block.with_parent(parent.0, parent.1 + 1)?;
+            }
+            parent = (pending.block.id(), pending.block.height());
+            if is_move {
+                predicted = advance_turn(predicted.0, action);
+            }
+            kept.push_back(pending);
+        }
+
+        self.pending = kept;
+        self.evicted.invalid += evicted.invalid;
+        self.evicted.expired += evicted.expired;
+        Ok(evicted)
+    }
+
+    /// Signals the consensus engine that there are moves to build blocks for.
+    fn notify_engine(&self) {
+        if !self.active {
+            return;
+        }
+        if let Some(to_engine) = &self.to_engine {
+            notify_engine(to_engine);
+        } else {
+            log::warn!("no consensus engine channel to notify of pending moves");
+        }
+    }
+
+    /// Signals the consensus engine once the batching window has passed, unless
+    /// a signal is already waiting for it.
+    fn schedule_notify(&self) {
+        if !self.active {
+            return;
+        }
+        if self.batch_window.is_zero() {
+            self.notify_engine();
+            return;
+        }
+        if self.notify_scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(to_engine) = self.to_engine.clone() {
+            let batch_window = self.batch_window;
+            let notify_scheduled = Arc::clone(&self.notify_scheduled);
+            tokio::spawn(async move {
+                tokio::time::sleep(batch_window).await;
+                notify_scheduled.store(false, Ordering::Release);
+                notify_engine(&to_engine);
+            });
+        } else {
+            self.notify_scheduled.store(false, Ordering::Release);
+            log::warn!("no consensus engine channel to notify of pending moves");
+        }
+    }
+
+    /// Returns the board and player due to move once the moves queued before
+    /// `before` (or all of them if `None`) are applied.
+    #[must_use]
+    pub fn predict(&self, board: u32, next_player: u8, before: Option<&ids::Id>) -> (u32, u8) {
+        self.blocks()
+            .take_while(|block| Some(&block.id()) != before)
+            .filter(|block| block.is_move())
+            .fold((board, next_player), |(board, _), block| {
+                advance_turn(board, block.get_player_move())
+            })
+    }
+}
+
+fn notify_engine(to_engine: &Sender<Message>) {
+    match to_engine.try_send(Message::PendingTxs) {
+        Ok(()) => log::debug!("notified engine of pending moves"),
+        // The engine hasn't picked up the last notification yet, which covers this one
+        Err(TrySendError::Full(_)) => {}
+        Err(e) => log::warn!("dropping message to consensus engine: {e}"),
+    }
+}
+
+/// Applies an action and returns the board play continues from along with the
+/// player due to move next.
+fn advance_turn(board: u32, action: u8) -> (u32, u8) {
+    let (player, _) = game::decode_action(action);
+    let board = game::advance(board, action);
+    if board == 0 {
+        // The move ended the game, so the next one opens a new game
+        (board, game::next_player(None))
+    } else {
+        (board, game::next_player(Some(player)))
+    }
+}
@@ -0,0 +1,174 @@
+//! Limits how fast a single submitter may propose moves, so a spammy client
+//! can't flood the mempool and starve other games.
+//!
+//! Each submitter gets a token bucket holding up to `burst` moves, refilled at
+//! `movesPerMinute`. Submitters are keyed by the address that signed the move,
+//! or by source IP for unsigned moves. Calls proxied through the node's HTTP API
+//! take their source from the forwarding headers, and unsigned moves whose source
+//! still isn't known share a single bucket. Moves gossiped by other validators
+//! are limited per peer instead, see `gossipRateLimit`.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, ErrorKind},
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use avalanche_types::ids;
+use serde::{Deserialize, Serialize};
+
+/// Number of buckets kept before idle ones are dropped.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Configures the rate limit applied to each submitter.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    /// Moves a submitter may propose per minute, on average
+    pub moves_per_minute: u32,
+    /// Moves a submitter may propose at once
+    pub burst: u32,
+}
+
+/// Identifies who proposed a move.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Submitter {
+    Address(ids::short::Id),
+    Ip(IpAddr),
+    /// Validator that gossiped the move
+    Peer(ids::node::Id),
+    Unknown,
+}
+
+impl Submitter {
+    /// Keys by the signing address if there is one, otherwise by the source IP.
+    #[must_use]
+    pub fn new(address: Option<ids::short::Id>, ip: Option<IpAddr>) -> Self {
+        match (address, ip) {
+            (Some(address), _) => Self::Address(address),
+            (None, Some(ip)) => Self::Ip(ip),
+            (None, None) => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Submitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "address {address}"),
+            Self::Ip(ip) => write!(f, "IP {ip}"),
+            Self::Peer(node_id) => write!(f, "peer {node_id}"),
+            Self::Unknown => write!(f, "unknown submitter"),
+        }
+    }
+}
+
+/// Raised when a submitter proposes moves faster than allowed. Carried inside
+/// an [`io::Error`] (see [`RateLimited::from_io`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub submitter: Submitter,
+    /// Time until the submitter may propose a move again
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    /// Returns the rate limit error carried by an [`io::Error`], if any.
+    #[must_use]
+    pub fn from_io(e: &io::Error) -> Option<&Self> {
+        e.get_ref().and_then(|inner| inner.downcast_ref::<Self>())
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} proposed too many moves, retry in {}ms",
+            self.submitter,
+            self.retry_after.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+impl From<RateLimited> for io::Error {
+    fn from(e: RateLimited) -> Self {
+        Self::new(ErrorKind::WouldBlock, e)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets of every submitter seen recently.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Tokens added per second
+    rate: f64,
+    burst: f64,
+    buckets: HashMap<Submitter, Bucket>,
+}
+
+impl RateLimiter {
+    #[must_use]
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            rate: f64::from(limit.moves_per_minute) / 60.0,
+            burst: f64::from(limit.burst.max(1)),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token from the submitter's bucket.
+    /// # Errors
+    /// Fails with [`RateLimited`] if the bucket is empty.
+    pub fn check(&mut self, submitter: Submitter) -> io::Result<()> {
+        let now = Instant::now();
+        if self.buckets.len() >= MAX_BUCKETS {
+            self.prune(now);
+        }
+
+        let (rate, burst) = (self.rate, self.burst);
+        let bucket = self.buckets.entry(submitter.clone()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket, rate, burst, now);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            let retry_after = if rate > 0.0 {
+                Duration::from_secs_f64((1.0 - bucket.tokens) / rate)
+            } else {
+                Duration::MAX
+            };
+            return Err(RateLimited {
+                submitter,
+                retry_after,
+            }
+            .into());
+        }
+
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Drops the buckets that have refilled, as they're no different from new ones.
+    fn prune(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| refill(bucket, rate, burst, now) < burst);
+    }
+}
+
+/// Returns the tokens held by the bucket as of `now`.
+fn refill(bucket: &Bucket, rate: f64, burst: f64, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+    (bucket.tokens + elapsed * rate).min(burst)
+}
@@ -0,0 +1,291 @@
+//! Prometheus metrics of block processing, moves, RPC calls and the database.
+//! Exposed in the text format by the `/metrics` chain handler.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Error, ErrorKind},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use avalanche_types::ids;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{self, GameStatus};
+
+/// Prefix of every metric name.
+const NAMESPACE: &str = "tictactoevm";
+
+/// Upper bounds of the move latency buckets, in seconds.
+const MOVE_LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Upper bounds of the game duration buckets, in seconds.
+const GAME_DURATION_BUCKETS: &[f64] = &[10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+/// Upper bounds of the moves per game buckets, a game lasts 5 to 9 moves.
+const GAME_MOVES_BUCKETS: &[f64] = &[5.0, 6.0, 7.0, 8.0, 9.0];
+
+/// Number of recent move latencies percentiles are computed over.
+pub const LATENCY_WINDOW: usize = 1000;
+
+/// Maximum number of proposed moves awaiting acceptance that are tracked,
+/// the oldest is forgotten past it (e.g. moves evicted from the mempool).
+const MAX_TRACKED_PROPOSALS: usize = 10_000;
+
+/// Metrics of the VM, all registered in their own registry.
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+
+    pub blocks_built: IntCounter,
+    pub blocks_verified: IntCounter,
+    pub blocks_accepted: IntCounter,
+    pub blocks_rejected: IntCounter,
+    /// Moves rejected for breaking the rules, whether proposed or carried by a block
+    pub invalid_moves: IntCounter,
+    /// Time taken to handle RPC calls, by method
+    pub rpc_duration: HistogramVec,
+    /// Moves pending in the mempool, as of the last scrape
+    pub mempool_depth: IntGauge,
+    pub db_read_duration: Histogram,
+    pub db_write_duration: Histogram,
+    /// Time from a move being proposed to its block being accepted
+    pub move_latency: Histogram,
+
+    /// Finished games, by result ("won" or "drawn")
+    pub games_finished: IntCounterVec,
+    /// Moves played in each finished game
+    pub game_moves: Histogram,
+    /// Time from the first move of a game being accepted to its last one
+    pub game_duration: Histogram,
+
+    latency: Mutex<LatencyTracker>,
+    /// Game being played and when its first move was accepted, if seen by this node
+    game_started: Mutex<Option<(usize, Instant)>>,
+}
+
+/// Moves proposed to this node and not yet accepted, along with the latencies
+/// of the last accepted ones.
+#[derive(Debug, Default)]
+struct LatencyTracker {
+    proposed: HashMap<ids::Id, Instant>,
+    recent: VecDeque<Duration>,
+}
+
+/// Percentiles of the latency of recently accepted moves, see [`Metrics::latency_stats`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
+pub struct LatencyStats {
+    /// Number of moves the percentiles are computed over, at most [`LATENCY_WINDOW`]
+    pub samples: usize,
+    /// Proposed moves not yet accepted
+    pub pending: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Creates and registers every metric.
+    /// # Panics
+    /// Panics if a metric is invalid, which can't happen with the fixed names below.
+    #[must_use]
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let counter = |name: &str, help: &str| {
+            let counter = IntCounter::with_opts(Opts::new(name, help).namespace(NAMESPACE))
+                .expect("valid counter");
+            registry.register(Box::new(counter.clone())).expect("unique counter");
+            counter
+        };
+        let histogram_with_buckets = |name: &str, help: &str, buckets: Option<&[f64]>| {
+            let mut opts = HistogramOpts::new(name, help).namespace(NAMESPACE);
+            if let Some(buckets) = buckets {
+                opts = opts.buckets(buckets.to_vec());
+            }
+            let histogram = Histogram::with_opts(opts).expect("valid histogram");
+            registry.register(Box::new(histogram.clone())).expect("unique histogram");
+            histogram
+        };
+        let histogram = |name: &str, help: &str| histogram_with_buckets(name, help, None);
+
+        let blocks_built = counter("blocks_built", "Blocks built by this node");
+        let blocks_verified = counter("blocks_verified", "Blocks verified");
+        let blocks_accepted = counter("blocks_accepted", "Blocks accepted");
+        let blocks_rejected = counter("blocks_rejected", "Blocks rejected");
+        let invalid_moves = counter("invalid_moves", "Moves rejected for breaking the rules");
+
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_duration_seconds", "Time taken to handle RPC calls")
+                .namespace(NAMESPACE),
+            &["method"],
+        )
+        .expect("valid histogram");
+        registry.register(Box::new(rpc_duration.clone())).expect("unique histogram");
+
+        let mempool_depth = IntGauge::with_opts(
+            Opts::new("mempool_depth", "Moves pending in the mempool").namespace(NAMESPACE),
+        )
+        .expect("valid gauge");
+        registry.register(Box::new(mempool_depth.clone())).expect("unique gauge");
+
+        let db_read_duration =
+            histogram("db_read_duration_seconds", "Time taken to read blocks from the database");
+        let db_write_duration =
+            histogram("db_write_duration_seconds", "Time taken to write blocks to the database");
+
+        let move_latency = histogram_with_buckets(
+            "move_latency_seconds",
+            "Time from a move being proposed to its block being accepted",
+            Some(MOVE_LATENCY_BUCKETS),
+        );
+
+        let games_finished = IntCounterVec::new(
+            Opts::new("games_finished", "Finished games, by result").namespace(NAMESPACE),
+            &["result"],
+        )
+        .expect("valid counter");
+        registry.register(Box::new(games_finished.clone())).expect("unique counter");
+        let game_moves = histogram_with_buckets(
+            "game_moves",
+            "Moves played in each finished game",
+            Some(GAME_MOVES_BUCKETS),
+        );
+        let game_duration = histogram_with_buckets(
+            "game_duration_seconds",
+            "Time from the first move of a game being accepted to its last one",
+            Some(GAME_DURATION_BUCKETS),
+        );
+
+        Self {
+            registry,
+            blocks_built,
+            blocks_verified,
+            blocks_accepted,
+            blocks_rejected,
+            invalid_moves,
+            rpc_duration,
+            mempool_depth,
+            db_read_duration,
+            db_write_duration,
+            move_latency,
+            games_finished,
+            game_moves,
+            game_duration,
+            latency: Mutex::default(),
+            game_started: Mutex::default(),
+        }
+    }
+
+    /// Records a move accepted in `game`, given the board once it was applied and the
+    /// status of the game after it. Games that started before this node saw their
+    /// first move (e.g. before a restart) aren't timed.
+    pub fn game_move_accepted(&self, game: usize, board: u32, status: GameStatus) {
+        let mut game_started = self.game_started.lock().unwrap_or_else(PoisonError::into_inner);
+        let moves = game::turn(board);
+        if moves == 1 {
+            *game_started = Some((game, Instant::now()));
+        }
+
+        let result = match status {
+            GameStatus::InProgress { .. } => return,
+            GameStatus::Won { .. } => "won",
+            GameStatus::Drawn { .. } => "drawn",
+        };
+        self.games_finished.with_label_values(&[result]).inc();
+        self.game_moves.observe(f64::from(moves));
+        if let Some((started_game, started)) = game_started.take() {
+            if started_game == game {
+                self.game_duration.observe(started.elapsed().as_secs_f64());
+            }
+        }
+    }
+
+    /// Starts timing a move proposed in the given block, unless it already is.
+    pub fn move_proposed(&self, block_id: ids::Id) {
+        let mut latency = self.latency();
+        let full = latency.proposed.len() >= MAX_TRACKED_PROPOSALS;
+        if full && !latency.proposed.contains_key(&block_id) {
+            let oldest = latency.proposed.iter().min_by_key(|(_, at)| **at).map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                latency.proposed.remove(&oldest);
+            }
+        }
+        latency.proposed.entry(block_id).or_insert_with(Instant::now);
+    }
+
+    /// Records the latency of the move in the given block, if it was proposed to this node.
+    pub fn move_accepted(&self, block_id: &ids::Id) {
+        let mut latency = self.latency();
+        if let Some(proposed) = latency.proposed.remove(block_id) {
+            let elapsed = proposed.elapsed();
+            self.move_latency.observe(elapsed.as_secs_f64());
+            if latency.recent.len() == LATENCY_WINDOW {
+                latency.recent.pop_front();
+            }
+            latency.recent.push_back(elapsed);
+        }
+    }
+
+    /// Stops timing the move in the given block, e.g. once it is rejected.
+    pub fn move_dropped(&self, block_id: &ids::Id) {
+        self.latency().proposed.remove(block_id);
+    }
+
+    /// Locks the latency tracker, which is left consistent even if a holder panicked.
+    fn latency(&self) -> MutexGuard<'_, LatencyTracker> {
+        self.latency.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the percentiles of the last [`LATENCY_WINDOW`] move latencies.
+    #[must_use]
+    pub fn latency_stats(&self) -> LatencyStats {
+        let latency = self.latency();
+        let mut sorted: Vec<_> = latency.recent.iter().copied().collect();
+        sorted.sort_unstable();
+
+        let percentile = |p: usize| {
+            sorted
+                .len()
+                .checked_sub(1)
+                .map_or(0.0, |last| sorted[last * p / 100].as_secs_f64() * 1000.0)
+        };
+        LatencyStats {
+            samples: sorted.len(),
+            pending: latency.proposed.len(),
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: percentile(100),
+        }
+    }
+
+    /// Encodes every metric in the Prometheus text format.
+    /// # Errors
+    /// Fails if the metrics can't be encoded.
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode metrics {e}")))?;
+        Ok(buf)
+    }
+}
+
+/// Content type of the encoded metrics.
+#[must_use]
+pub fn content_type() -> String {
+    TextEncoder::new().format_type().to_string()
+}
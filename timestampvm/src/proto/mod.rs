@@ -0,0 +1,626 @@
+//! Protobuf encoding of blocks, moves and query responses, generated from
+//! `proto/tictactoe/v1/types.proto`, which is the canonical schema for bindings
+//! in other languages. Alongside the messages are conversions from (and, for
+//! blocks, transfers, moves and statuses, back to) the types used across the VM.
+//!
+//! Ids are carried CB58-encoded as strings, as they are over JSON-RPC.
+
+use std::{
+    io::{self, Error, ErrorKind},
+    str::FromStr,
+};
+
+use avalanche_types::choices;
+
+use crate::{
+    api::chain_handlers,
+    block, game,
+    state::{self, tournament::TournamentFormat, MoveRecord},
+};
+
+/// Types and service traits generated from the protobuf definitions.
+#[allow(clippy::all, clippy::pedantic)]
+pub mod v1 {
+    tonic::include_proto!("tictactoe.v1");
+}
+
+pub use v1::*;
+
+/// Length of the shortest CB58 string decoding to at least the checksum, as the
+/// Id decoders panic on anything shorter.
+const MIN_ENCODED_LEN: usize = 6;
+
+impl Board {
+    /// Returns the message of a packed board, with its cells rendered.
+    #[must_use]
+    pub fn new(board: u32) -> Self {
+        Self {
+            board,
+            cells: game::grid(board)
+                .iter()
+                .flatten()
+                .map(|cell| String::from(*cell))
+                .collect(),
+        }
+    }
+}
+
+impl GameStatus {
+    /// Returns the message of the status of the given game.
+    #[must_use]
+    pub fn new(game: usize, status: game::GameStatus) -> Self {
+        let mut msg = Self {
+            game: game as u64,
+            ..Default::default()
+        };
+        match status {
+            game::GameStatus::InProgress { next_player } => {
+                msg.set_status(game_status::Status::InProgress);
+                msg.next_player = u32::from(next_player);
+            }
+            game::GameStatus::Won { player, at_height } => {
+                msg.set_status(game_status::Status::Won);
+                msg.winner = u32::from(player);
+                msg.at_height = at_height;
+            }
+            game::GameStatus::Drawn { at_height } => {
+                msg.set_status(game_status::Status::Drawn);
+                msg.at_height = at_height;
+            }
+        }
+        msg
+    }
+}
+
+impl TryFrom<GameStatus> for game::GameStatus {
+    type Error = io::Error;
+
+    fn try_from(msg: GameStatus) -> io::Result<Self> {
+        match msg.status() {
+            game_status::Status::InProgress => Ok(Self::InProgress {
+                next_player: player(msg.next_player)?,
+            }),
+            game_status::Status::Won => Ok(Self::Won {
+                player: player(msg.winner)?,
+                at_height: msg.at_height,
+            }),
+            game_status::Status::Drawn => Ok(Self::Drawn {
+                at_height: msg.at_height,
+            }),
+            game_status::Status::Unspecified => Err(invalid("game status is unspecified")),
+        }
+    }
+}
+
+impl From<MoveRecord> for Move {
+    fn from(record: MoveRecord) -> Self {
+        Self {
+            player: u32::from(record.player),
+            cell: u32::from(record.cell),
+            block_id: record.block_id.to_string(),
+            height: record.height,
+            address: record.address.map(|a| a.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<Move> for MoveRecord {
+    type Error = io::Error;
+
+    fn try_from(msg: Move) -> io::Result<Self> {
+        let cell = u8::try_from(msg.cell)
+            .ok()
+            .filter(|cell| *cell < game::CELLS)
+            .ok_or_else(|| invalid(format!("cell {} is off the board", msg.cell)))?;
+        let address = if msg.address.is_empty() {
+            None
+        } else {
+            Some(parse_id(&msg.address)?)
+        };
+
+        Ok(Self {
+            player: player(msg.player)?,
+            cell,
+            block_id: parse_id(&msg.block_id)?,
+            height: msg.height,
+            address,
+        })
+    }
+}
+
+impl From<&block::Transfer> for Transfer {
+    fn from(transfer: &block::Transfer) -> Self {
+        Self {
+            to: transfer.to.to_string(),
+            amount: transfer.amount,
+            nonce: transfer.nonce,
+        }
+    }
+}
+
+impl TryFrom<Transfer> for block::Transfer {
+    type Error = io::Error;
+
+    fn try_from(msg: Transfer) -> io::Result<Self> {
+        Ok(Self {
+            to: parse_id(&msg.to)?,
+            amount: msg.amount,
+            nonce: msg.nonce,
+        })
+    }
+}
+
+impl From<&block::Seek> for Seek {
+    fn from(seek: &block::Seek) -> Self {
+        Self { nonce: seek.nonce }
+    }
+}
+
+impl From<&block::TournamentAction> for TournamentAction {
+    fn from(action: &block::TournamentAction) -> Self {
+        let kind = match action {
+            block::TournamentAction::Create {
+                players, rounds, ..
+            } => tournament_action::Action::Create(tournament_action::Create {
+                players: *players,
+                rounds: *rounds,
+            }),
+            block::TournamentAction::Register { tournament, .. } => {
+                tournament_action::Action::Register(tournament_action::Register {
+                    tournament: *tournament as u64,
+                })
+            }
+        };
+        Self {
+            action: Some(kind),
+            nonce: action.nonce(),
+        }
+    }
+}
+
+impl TryFrom<TournamentAction> for block::TournamentAction {
+    type Error = io::Error;
+
+    fn try_from(msg: TournamentAction) -> io::Result<Self> {
+        let nonce = msg.nonce;
+        match msg.action {
+            Some(tournament_action::Action::Create(create)) => Ok(Self::Create {
+                players: create.players,
+                rounds: create.rounds,
+                nonce,
+            }),
+            Some(tournament_action::Action::Register(register)) => Ok(Self::Register {
+                tournament: usize::try_from(register.tournament)
+                    .map_err(|_| invalid(format!("invalid tournament {}", register.tournament)))?,
+                nonce,
+            }),
+            None => Err(invalid("tournament action carries no action")),
+        }
+    }
+}
+
+impl From<&block::SeriesAction> for SeriesAction {
+    fn from(action: &block::SeriesAction) -> Self {
+        let kind = match action {
+            block::SeriesAction::Challenge {
+                opponent, games, ..
+            } => series_action::Action::Challenge(series_action::Challenge {
+                opponent: opponent.to_string(),
+                games: *games,
+            }),
+            block::SeriesAction::Accept { series, .. } => {
+                series_action::Action::Accept(series_action::Accept {
+                    series: *series as u64,
+                })
+            }
+        };
+        Self {
+            action: Some(kind),
+            nonce: action.nonce(),
+        }
+    }
+}
+
+impl TryFrom<SeriesAction> for block::SeriesAction {
+    type Error = io::Error;
+
+    fn try_from(msg: SeriesAction) -> io::Result<Self> {
+        let nonce = msg.nonce;
+        match msg.action {
+            Some(series_action::Action::Challenge(challenge)) => Ok(Self::Challenge {
+                opponent: parse_id(&challenge.opponent)?,
+                games: challenge.games,
+                nonce,
+            }),
+            Some(series_action::Action::Accept(accept)) => Ok(Self::Accept {
+                series: usize::try_from(accept.series)
+                    .map_err(|_| invalid(format!("invalid series {}", accept.series)))?,
+                nonce,
+            }),
+            None => Err(invalid("series action carries no action")),
+        }
+    }
+}
+
+impl From<&block::TeamAction> for TeamAction {
+    fn from(action: &block::TeamAction) -> Self {
+        let kind = match action {
+            block::TeamAction::Challenge {
+                partner, opponents, ..
+            } => team_action::Action::Challenge(team_action::Challenge {
+                partner: partner.to_string(),
+                opponents: opponents.iter().map(ToString::to_string).collect(),
+            }),
+            block::TeamAction::Accept { team_game, .. } => {
+                team_action::Action::Accept(team_action::Accept {
+                    team_game: *team_game as u64,
+                })
+            }
+        };
+        Self {
+            action: Some(kind),
+            nonce: action.nonce(),
+        }
+    }
+}
+
+impl TryFrom<TeamAction> for block::TeamAction {
+    type Error = io::Error;
+
+    fn try_from(msg: TeamAction) -> io::Result<Self> {
+        let nonce = msg.nonce;
+        match msg.action {
+            Some(team_action::Action::Challenge(challenge)) => {
+                let opponents = match challenge.opponents.as_slice() {
+                    [first, second] => [parse_id(first)?, parse_id(second)?],
+                    other => {
+                        return Err(invalid(format!("{} opponents instead of 2", other.len())))
+                    }
+                };
+                Ok(Self::Challenge {
+                    partner: parse_id(&challenge.partner)?,
+                    opponents,
+                    nonce,
+                })
+            }
+            Some(team_action::Action::Accept(accept)) => Ok(Self::Accept {
+                team_game: usize::try_from(accept.team_game)
+                    .map_err(|_| invalid(format!("invalid team game {}", accept.team_game)))?,
+                nonce,
+            }),
+            None => Err(invalid("team action carries no action")),
+        }
+    }
+}
+
+impl From<&block::Block> for Block {
+    fn from(block: &block::Block) -> Self {
+        Self {
+            id: block.id().to_string(),
+            parent_id: block.parent_id().to_string(),
+            height: block.height(),
+            action: u32::from(block.get_player_move()),
+            signature: block.signature().to_vec(),
+            transfer: block.transfer().map(Transfer::from),
+            fee: block.fee().unwrap_or_default(),
+            max_fee: block.max_fee(),
+            work: block.work(),
+            seek: block.seek().map(Seek::from),
+            tournament: block.tournament().map(TournamentAction::from),
+            series: block.series().map(SeriesAction::from),
+            team: block.team().map(TeamAction::from),
+        }
+    }
+}
+
+impl TryFrom<Block> for block::Block {
+    type Error = io::Error;
+
+    /// Rebuilds the block, checking it encodes to the Id carried by the message,
+    /// if any, in either format.
+    fn try_from(msg: Block) -> io::Result<Self> {
+        let parent_id = parse_id(&msg.parent_id)?;
+        let status = choices::status::Status::default();
+        let account = if let Some(transfer) = msg.transfer {
+            Some(block::AccountAction::Transfer(transfer.try_into()?))
+        } else if let Some(seek) = msg.seek {
+            Some(block::AccountAction::Seek(block::Seek { nonce: seek.nonce }))
+        } else if let Some(action) = msg.tournament {
+            Some(block::AccountAction::Tournament(action.try_into()?))
+        } else if let Some(action) = msg.series {
+            Some(block::AccountAction::Series(action.try_into()?))
+        } else if let Some(action) = msg.team {
+            Some(block::AccountAction::Team(action.try_into()?))
+        } else {
+            None
+        };
+        let block = if let Some(action) = account {
+            Self::try_new_account(parent_id, msg.height, action, msg.signature, status)?
+        } else {
+            let action = u8::try_from(msg.action)
+                .map_err(|_| invalid(format!("invalid action {}", msg.action)))?;
+            Self::try_new(parent_id, msg.height, action, msg.signature, status)?
+        };
+        let block = match msg.max_fee {
+            Some(max_fee) => block.with_max_fee(max_fee)?,
+            None => block,
+        };
+        let block = if msg.fee > 0 {
+            block.with_fee(msg.fee)?
+        } else {
+            block
+        };
+        let block = match msg.work {
+            Some(nonce) => block.with_work(nonce)?,
+            None => block,
+        };
+        if msg.id.is_empty() {
+            return Ok(block);
+        }
+
+        let id = parse_id(&msg.id)?;
+        let block = block.with_format_of(&id)?;
+        if block.id() != id {
+            return Err(invalid(format!(
+                "block encodes to Id {}, not {id}",
+                block.id()
+            )));
+        }
+        Ok(block)
+    }
+}
+
+impl From<&chain_handlers::WinnerEntry> for GameResult {
+    fn from(entry: &chain_handlers::WinnerEntry) -> Self {
+        Self {
+            game: entry.game as u64,
+            winner: entry.result.winner.map_or(0, u32::from),
+            height: entry.result.height,
+        }
+    }
+}
+
+impl From<&state::BlockEvent> for BlockEvent {
+    #[allow(clippy::too_many_lines)]
+    fn from(event: &state::BlockEvent) -> Self {
+        let event = match event {
+            state::BlockEvent::GameStarted { game } => {
+                block_event::Event::GameStarted(block_event::GameStarted { game: *game as u64 })
+            }
+            state::BlockEvent::MovePlayed { game, player, cell } => {
+                block_event::Event::MovePlayed(block_event::MovePlayed {
+                    game: *game as u64,
+                    player: u32::from(*player),
+                    cell: u32::from(*cell),
+                })
+            }
+            state::BlockEvent::GameWon { game, player } => {
+                block_event::Event::GameWon(block_event::GameWon {
+                    game: *game as u64,
+                    player: u32::from(*player),
+                })
+            }
+            state::BlockEvent::GameDrawn { game } => {
+                block_event::Event::GameDrawn(block_event::GameDrawn { game: *game as u64 })
+            }
+            state::BlockEvent::GameForfeited { game, player } => {
+                block_event::Event::GameForfeited(block_event::GameForfeited {
+                    game: *game as u64,
+                    player: u32::from(*player),
+                })
+            }
+            state::BlockEvent::ReservationLapsed { game, players } => {
+                block_event::Event::ReservationLapsed(block_event::ReservationLapsed {
+                    game: *game as u64,
+                    players: players.iter().map(ToString::to_string).collect(),
+                })
+            }
+            state::BlockEvent::Transferred { from, to, amount } => {
+                block_event::Event::Transferred(block_event::Transferred {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    amount: *amount,
+                })
+            }
+            state::BlockEvent::SeekQueued { address } => {
+                block_event::Event::SeekQueued(block_event::SeekQueued {
+                    address: address.to_string(),
+                })
+            }
+            state::BlockEvent::GameMatched { game, players } => {
+                block_event::Event::GameMatched(block_event::GameMatched {
+                    game: *game as u64,
+                    players: players.iter().map(ToString::to_string).collect(),
+                })
+            }
+            state::BlockEvent::TournamentCreated {
+                tournament,
+                creator,
+                players,
+                format,
+            } => block_event::Event::TournamentCreated(block_event::TournamentCreated {
+                tournament: *tournament as u64,
+                creator: creator.to_string(),
+                players: *players as u64,
+                rounds: match format {
+                    TournamentFormat::Elimination => None,
+                    TournamentFormat::Swiss { rounds } => Some(*rounds as u64),
+                },
+            }),
+            state::BlockEvent::TournamentRegistered {
+                tournament,
+                address,
+            } => block_event::Event::TournamentRegistered(block_event::TournamentRegistered {
+                tournament: *tournament as u64,
+                address: address.to_string(),
+            }),
+            state::BlockEvent::TournamentRoundStarted {
+                tournament,
+                round,
+                games,
+            } => block_event::Event::TournamentRoundStarted(block_event::TournamentRoundStarted {
+                tournament: *tournament as u64,
+                round: *round as u64,
+                games: games.iter().map(|game| *game as u64).collect(),
+            }),
+            state::BlockEvent::TournamentFinished {
+                tournament,
+                champion,
+            } => block_event::Event::TournamentFinished(block_event::TournamentFinished {
+                tournament: *tournament as u64,
+                champion: champion.to_string(),
+            }),
+            state::BlockEvent::SeriesChallenged {
+                series,
+                players,
+                best_of,
+            } => block_event::Event::SeriesChallenged(block_event::SeriesChallenged {
+                series: *series as u64,
+                players: players.iter().map(ToString::to_string).collect(),
+                best_of: *best_of as u64,
+            }),
+            state::BlockEvent::SeriesGameReserved {
+                series,
+                game,
+                players,
+            } => block_event::Event::SeriesGameReserved(block_event::SeriesGameReserved {
+                series: *series as u64,
+                game: *game as u64,
+                players: players.iter().map(ToString::to_string).collect(),
+            }),
+            state::BlockEvent::SeriesFinished {
+                series,
+                winner,
+                score,
+            } => block_event::Event::SeriesFinished(block_event::SeriesFinished {
+                series: *series as u64,
+                winner: winner.as_ref().map(ToString::to_string),
+                score: score.iter().map(|games| *games as u64).collect(),
+            }),
+            state::BlockEvent::TeamChallenged { team_game, teams } => {
+                let [team_x, team_o] = teams;
+                block_event::Event::TeamChallenged(block_event::TeamChallenged {
+                    team_game: *team_game as u64,
+                    team_x: team_x.iter().map(ToString::to_string).collect(),
+                    team_o: team_o.iter().map(ToString::to_string).collect(),
+                })
+            }
+            state::BlockEvent::TeamAccepted { team_game, address } => {
+                block_event::Event::TeamAccepted(block_event::TeamAccepted {
+                    team_game: *team_game as u64,
+                    address: address.to_string(),
+                })
+            }
+            state::BlockEvent::TeamGameReserved { team_game, game } => {
+                block_event::Event::TeamGameReserved(block_event::TeamGameReserved {
+                    team_game: *team_game as u64,
+                    game: *game as u64,
+                })
+            }
+        };
+        Self { event: Some(event) }
+    }
+}
+
+impl From<&state::PlayerStats> for PlayerStats {
+    fn from(stats: &state::PlayerStats) -> Self {
+        Self {
+            wins: stats.wins,
+            losses: stats.losses,
+            draws: stats.draws,
+            games_played: stats.games_played,
+        }
+    }
+}
+
+impl From<&chain_handlers::LegalMove> for LegalMove {
+    fn from(legal: &chain_handlers::LegalMove) -> Self {
+        Self {
+            row: u32::from(legal.row),
+            col: u32::from(legal.col),
+            action: u32::from(legal.action),
+        }
+    }
+}
+
+impl From<&chain_handlers::GetWinnersResponse> for GetWinnersResponse {
+    fn from(resp: &chain_handlers::GetWinnersResponse) -> Self {
+        Self {
+            total: resp.total as u64,
+            results: resp.results.iter().map(GameResult::from).collect(),
+        }
+    }
+}
+
+impl From<&chain_handlers::GetBlockEventsResponse> for GetBlockEventsResponse {
+    fn from(resp: &chain_handlers::GetBlockEventsResponse) -> Self {
+        Self {
+            id: resp.id.to_string(),
+            events: resp.events.iter().map(BlockEvent::from).collect(),
+        }
+    }
+}
+
+impl From<&chain_handlers::WhoseTurnResponse> for WhoseTurnResponse {
+    fn from(resp: &chain_handlers::WhoseTurnResponse) -> Self {
+        Self {
+            game: resp.game as u64,
+            player: u32::from(resp.player),
+        }
+    }
+}
+
+impl From<&chain_handlers::GetLegalMovesResponse> for GetLegalMovesResponse {
+    fn from(resp: &chain_handlers::GetLegalMovesResponse) -> Self {
+        Self {
+            game: resp.game as u64,
+            player: u32::from(resp.player),
+            moves: resp.moves.iter().map(LegalMove::from).collect(),
+        }
+    }
+}
+
+impl From<&chain_handlers::GetPlayerStatsResponse> for GetPlayerStatsResponse {
+    fn from(resp: &chain_handlers::GetPlayerStatsResponse) -> Self {
+        Self {
+            address: resp.address.to_string(),
+            stats: Some(PlayerStats::from(&resp.stats)),
+        }
+    }
+}
+
+impl From<&chain_handlers::GetNodeInfoResponse> for GetNodeInfoResponse {
+    fn from(resp: &chain_handlers::GetNodeInfoResponse) -> Self {
+        Self {
+            version: resp.version.clone(),
+            commit: resp.commit.clone(),
+            chain_id: resp.chain_id.to_string(),
+            height: resp.height,
+            genesis_hash: resp.genesis_hash.to_string(),
+            move_fee: resp.move_fee,
+            max_move_fee: resp.max_move_fee,
+            move_work: u32::from(resp.move_work),
+            game_stake: resp.game_stake,
+        }
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// Returns the player (1 or 2) carried by a message.
+fn player(player: u32) -> io::Result<u8> {
+    u8::try_from(player)
+        .ok()
+        .filter(|player| (1..=2).contains(player))
+        .ok_or_else(|| invalid(format!("invalid player {player}")))
+}
+
+/// Decodes a CB58-encoded Id carried by a message.
+fn parse_id<T: FromStr>(s: &str) -> io::Result<T> {
+    if s.len() < MIN_ENCODED_LEN {
+        return Err(invalid(format!("invalid Id '{s}'")));
+    }
+    T::from_str(s).map_err(|_| invalid(format!("invalid Id '{s}'")))
+}
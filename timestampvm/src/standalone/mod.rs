@@ -0,0 +1,256 @@
+//! Runs the VM without avalanchego, for local play and frontend development.
+//! A stub consensus engine builds and accepts a block as soon as a move is
+//! proposed, and the chain handlers are served over HTTP on a local address,
+//! at the same paths as through a node (e.g. `/ext/bc/[CHAIN ID]/rpc`, or just `/rpc`),
+//! along with the REST gateway (e.g. `/ext/bc/[CHAIN ID]/games/0/board`).
+//! The chain is kept in memory and lost on exit.
+
+use std::{collections::HashMap, convert::Infallible, io, net::SocketAddr, sync::Arc};
+
+use avalanche_types::{
+    ids,
+    proto::http::Element,
+    subnet::rpc::{
+        database::{
+            manager::{versioned_database::VersionedDatabase, DatabaseManager},
+            memdb,
+        },
+        http::handle::Handle,
+        snow::{
+            self,
+            engine::common::{appsender::AppSender, message::Message, vm::CommonVm},
+        },
+        snowman::block::ChainVm,
+    },
+};
+use bytes::Bytes;
+use hyper::{
+    body,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use semver::Version;
+use tokio::sync::mpsc::{self, Receiver};
+
+use crate::{
+    api::{
+        chain_handlers::ChainService,
+        rest::{RestGateway, Route},
+        static_handlers::StaticHandler,
+        ChainEndpoint,
+    },
+    vm::Vm,
+};
+
+/// Number of engine notifications the Vm can queue before it drops them.
+const ENGINE_CHANNEL_CAPACITY: usize = 100;
+
+/// Serves the Vm on `addr` until the server fails, building and accepting blocks
+/// as moves are proposed.
+/// # Errors
+/// Fails if the Vm can't be initialized from the genesis and config, or the
+/// server can't listen on the address.
+pub async fn serve(addr: SocketAddr, genesis_bytes: &[u8], config_bytes: &[u8]) -> io::Result<()> {
+    let mut vm = Vm::<NoopAppSender>::new();
+    let db_manager = DatabaseManager::from_databases(vec![VersionedDatabase::new(
+        memdb::Database::new(),
+        Version::new(0, 0, 1),
+    )]);
+    let (to_engine, from_vm) = mpsc::channel(ENGINE_CHANNEL_CAPACITY);
+    vm.initialize(
+        None,
+        db_manager,
+        genesis_bytes,
+        &[],
+        config_bytes,
+        to_engine,
+        &[],
+        NoopAppSender,
+    )
+    .await?;
+    vm.set_state(snow::State::NormalOp).await?;
+    tokio::spawn(run_engine(vm.clone(), from_vm));
+
+    let handlers = Arc::new(Handlers {
+        chain: vm
+            .create_handlers()
+            .await?
+            .into_iter()
+            .map(|(path, handler)| (path, handler.handler))
+            .collect(),
+        statics: vm
+            .create_static_handlers()
+            .await?
+            .into_iter()
+            .map(|(path, handler)| (path, handler.handler))
+            .collect(),
+        rest: RestGateway::new(ChainService::new(vm.clone())),
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let handlers = Arc::clone(&handlers);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let handlers = Arc::clone(&handlers);
+                async move { Ok::<_, Infallible>(handlers.handle(req).await) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("failed to bind standalone server to {addr}: {e}"),
+        )
+    })?;
+    log::info!("serving standalone chain on http://{addr}/ext/bc/standalone/rpc");
+    server
+        .serve(make_service)
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("standalone server failed {e}")))
+}
+
+/// Stands in for the consensus engine: builds a block for every pending move and
+/// accepts it right away, as if every validator agreed.
+async fn run_engine(vm: Vm<NoopAppSender>, mut from_vm: Receiver<Message>) {
+    while let Some(msg) = from_vm.recv().await {
+        if msg != Message::PendingTxs {
+            continue;
+        }
+
+        while !vm.mempool.read().await.is_empty() {
+            let mut block = match vm.build_block().await {
+                Ok(block) => block,
+                Err(e) => {
+                    log::warn!("failed to build block: {e}");
+                    break;
+                }
+            };
+            let blk_id = block.id();
+            let accepted = match vm.set_preference(blk_id).await {
+                Ok(()) => block.accept().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = accepted {
+                log::warn!("failed to accept block {blk_id}: {e}");
+                break;
+            }
+        }
+    }
+}
+
+/// Handlers served, by the last segment of their path (e.g. `/rpc`).
+struct Handlers {
+    chain: HashMap<String, ChainEndpoint<NoopAppSender>>,
+    statics: HashMap<String, StaticHandler>,
+    /// Serves the paths naming a REST [`Route`] instead
+    rest: RestGateway<NoopAppSender>,
+}
+
+impl Handlers {
+    /// Dispatches a request to the handler named by the last segment of its path.
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if Route::parse(req.uri().path()).is_some() {
+            return self.rest.handle(&req).await;
+        }
+        let path = req.uri().path().trim_end_matches('/');
+        let name = format!("/{}", path.rsplit('/').next().unwrap_or_default());
+        let headers: Vec<Element> = req
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| {
+                Some(Element {
+                    key: key.to_string(),
+                    values: vec![value.to_str().ok()?.to_string()],
+                })
+            })
+            .collect();
+
+        let body = match body::to_bytes(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+        let res = if let Some(handler) = self.chain.get(&name) {
+            handler.request(&body, &headers).await
+        } else if let Some(handler) = self.statics.get(&name) {
+            handler.request(&body, &headers).await
+        } else {
+            return error_response(StatusCode::NOT_FOUND, &format!("no handler for {name}"));
+        };
+
+        match res {
+            Ok((body, headers)) => {
+                let mut response = Response::builder();
+                for header in headers {
+                    for value in header.values {
+                        response = response.header(header.key.as_str(), value);
+                    }
+                }
+                response.body(Body::from(body)).unwrap_or_else(|e| {
+                    error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+                })
+            }
+            Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(Bytes::from(message.to_string())));
+    *response.status_mut() = status;
+    response
+}
+
+/// Drops every message, since there are no other validators to send them to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopAppSender;
+
+#[tonic::async_trait]
+impl AppSender for NoopAppSender {
+    async fn send_app_request(
+        &self,
+        _node_ids: ids::node::Set,
+        _request_id: u32,
+        _request: Vec<u8>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn send_app_response(
+        &self,
+        _node_id: ids::node::Id,
+        _request_id: u32,
+        _response: Vec<u8>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn send_app_gossip(&self, _msg: Vec<u8>) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn send_app_gossip_specific(
+        &self,
+        _node_ids: ids::node::Set,
+        _msg: Vec<u8>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn send_cross_chain_app_request(
+        &self,
+        _chain_id: ids::Id,
+        _request_id: u32,
+        _app_request_bytes: Vec<u8>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn send_cross_chain_app_response(
+        &self,
+        _chain_id: ids::Id,
+        _request_id: u32,
+        _app_response_bytes: Vec<u8>,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+}
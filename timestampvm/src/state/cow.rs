@@ -0,0 +1,542 @@
+//! Copy-on-write collections, so the state a verified block reaches shares all it
+//! holds with the state of the block's parent, and only copies what the block
+//! changes on top of it.
+
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+    mem,
+    ops::{Index, IndexMut},
+    sync::Arc,
+};
+
+use once_cell::sync::Lazy;
+
+/// Hashes keys into priorities, seeded randomly so no one can pick keys that
+/// unbalance the maps of every node.
+static PRIORITIES: Lazy<RandomState> = Lazy::new(RandomState::new);
+
+type Link<K, V> = Option<Arc<Node<K, V>>>;
+
+/// Entry of a [`CowMap`], along with the subtree of the entries around it.
+struct Node<K, V> {
+    key: K,
+    value: Arc<V>,
+    /// Nodes are heap-ordered by priority, which balances the tree
+    priority: u64,
+    /// Number of entries in the subtree
+    len: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K: Clone, V> Clone for Node<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            value: Arc::clone(&self.value),
+            priority: self.priority,
+            len: self.len,
+            left: self.left.clone(),
+            right: self.right.clone(),
+        }
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn update_len(&mut self) {
+        self.len = 1 + len(&self.left) + len(&self.right);
+    }
+}
+
+fn len<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+/// Returns the priority of a key, hashed so keys inserted in order still balance.
+fn priority<K: Hash>(key: &K) -> u64 {
+    let mut hasher = PRIORITIES.build_hasher();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Splits a subtree into the entries whose keys go left and the others, copying
+/// the nodes along the way that are shared with another map.
+fn split<K: Clone, V>(
+    link: Link<K, V>,
+    goes_left: &impl Fn(&K) -> bool,
+) -> (Link<K, V>, Link<K, V>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            let inner = Arc::make_mut(&mut node);
+            if goes_left(&inner.key) {
+                let (left, right) = split(inner.right.take(), goes_left);
+                inner.right = left;
+                inner.update_len();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(inner.left.take(), goes_left);
+                inner.left = right;
+                inner.update_len();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two subtrees, all keys of `left` being lower than those of `right`.
+fn merge<K: Clone, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                let inner = Arc::make_mut(&mut left);
+                inner.right = merge(inner.right.take(), Some(right));
+                inner.update_len();
+                Some(left)
+            } else {
+                let inner = Arc::make_mut(&mut right);
+                inner.left = merge(Some(left), inner.left.take());
+                inner.update_len();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Returns the value of a key in a subtree, copying the nodes leading to it and
+/// the value itself if they're shared with another map.
+fn get_mut<'a, K: Ord + Clone, V: Clone>(link: &'a mut Link<K, V>, key: &K) -> Option<&'a mut V> {
+    let inner = Arc::make_mut(link.as_mut()?);
+    match key.cmp(&inner.key) {
+        Ordering::Less => get_mut(&mut inner.left, key),
+        Ordering::Greater => get_mut(&mut inner.right, key),
+        Ordering::Equal => Some(Arc::make_mut(&mut inner.value)),
+    }
+}
+
+/// Returns the value of a shared node, copying it only if another map still
+/// holds it.
+fn into_value<V: Clone>(value: Arc<V>) -> V {
+    Arc::try_unwrap(value).unwrap_or_else(|shared| shared.as_ref().clone())
+}
+
+/// Map ordered by key, whose clones share their entries. Changing an entry in
+/// one copies the entry and the nodes leading to it, O(log n) of them, leaving
+/// the others shared.
+pub struct CowMap<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K, V> Default for CowMap<K, V> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<K, V> Clone for CowMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K, V> CowMap<K, V> {
+    /// Creates an empty map.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    /// Returns whether the map has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the entries in key order.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+
+    /// Returns the keys in order.
+    #[must_use]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Returns the values in key order.
+    #[must_use]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Returns the entry with the highest key, if any.
+    #[must_use]
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K: Ord, V> CowMap<K, V> {
+    /// Returns the value of a key, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            match key.cmp(&node.key) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => link = &node.right,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        None
+    }
+
+    /// Returns whether the map holds the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the entries from `from` on, in key order.
+    pub fn range_from(&self, from: &K) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            if node.key < *from {
+                link = node.right.as_deref();
+            } else {
+                iter.stack.push(node);
+                link = node.left.as_deref();
+            }
+        }
+        iter
+    }
+}
+
+impl<K: Ord + Clone + Hash, V: Clone> CowMap<K, V> {
+    /// Returns the value of a key to change, if any.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        // Leaves the nodes shared when there's nothing to change
+        if !self.contains_key(key) {
+            return None;
+        }
+        get_mut(&mut self.root, key)
+    }
+
+    /// Returns the value of a key to change, inserting the default value first if
+    /// the map doesn't hold it.
+    pub fn get_or_insert_default(&mut self, key: &K) -> &mut V
+    where
+        V: Default,
+    {
+        if !self.contains_key(key) {
+            self.insert(key.clone(), V::default());
+        }
+        match get_mut(&mut self.root, key) {
+            Some(value) => value,
+            None => unreachable!("the key was inserted above"),
+        }
+    }
+
+    /// Sets the value of a key, returning the one it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(current) = self.get_mut(&key) {
+            return Some(mem::replace(current, value));
+        }
+        let node = Node {
+            priority: priority(&key),
+            key,
+            value: Arc::new(value),
+            len: 1,
+            left: None,
+            right: None,
+        };
+        let (left, right) = split(self.root.take(), &|k: &K| *k < node.key);
+        self.root = merge(merge(left, Some(Arc::new(node))), right);
+        None
+    }
+
+    /// Removes a key, returning its value, if any.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let (left, rest) = split(self.root.take(), &|k: &K| k < key);
+        let (removed, right) = split(rest, &|k: &K| k <= key);
+        self.root = merge(left, right);
+        removed.map(|node| into_value(into_value(node).value))
+    }
+}
+
+impl<K: Ord + Clone + Hash, V: Clone> FromIterator<(K, V)> for CowMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for CowMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for CowMap<K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for CowMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a CowMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`CowMap`] in key order.
+pub struct Iter<'a, K, V> {
+    /// Nodes left to visit, along with their right subtree, the next one last
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut link: Option<&'a Node<K, V>>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+/// Iterator over the keys of a [`CowMap`] in order.
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+/// Iterator over the values of a [`CowMap`] in key order.
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+/// Vector whose clones share their elements, see [`CowMap`]. Elements are only
+/// ever added at the end.
+pub struct CowVec<T> {
+    elements: CowMap<usize, T>,
+}
+
+impl<T> Default for CowVec<T> {
+    fn default() -> Self {
+        Self {
+            elements: CowMap::new(),
+        }
+    }
+}
+
+impl<T> Clone for CowVec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            elements: self.elements.clone(),
+        }
+    }
+}
+
+impl<T> CowVec<T> {
+    /// Creates an empty vector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns whether the vector has no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the element at an index, if any.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.elements.get(&index)
+    }
+
+    /// Returns the last element, if any.
+    #[must_use]
+    pub fn last(&self) -> Option<&T> {
+        self.elements.last_key_value().map(|(_, element)| element)
+    }
+
+    /// Returns the elements in order.
+    #[must_use]
+    pub fn iter(&self) -> Values<'_, usize, T> {
+        self.elements.values()
+    }
+}
+
+impl<T: Clone> CowVec<T> {
+    /// Returns the element at an index to change, if any.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.elements.get_mut(&index)
+    }
+
+    /// Returns the last element to change, if any.
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        let last = self.len().checked_sub(1)?;
+        self.get_mut(last)
+    }
+
+    /// Appends an element.
+    pub fn push(&mut self, element: T) {
+        self.elements.insert(self.len(), element);
+    }
+}
+
+impl<T: Clone> FromIterator<T> for CowVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(elements: I) -> Self {
+        let mut vec = Self::new();
+        for element in elements {
+            vec.push(element);
+        }
+        vec
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CowVec<T> {
+    type Item = &'a T;
+    type IntoIter = Values<'a, usize, T>;
+
+    fn into_iter(self) -> Values<'a, usize, T> {
+        self.iter()
+    }
+}
+
+impl<T> Index<usize> for CowVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(element) => element,
+            None => panic!("index {index} is out of bounds of {} elements", self.len()),
+        }
+    }
+}
+
+impl<T: Clone> IndexMut<usize> for CowVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(element) => element,
+            None => panic!("index {index} is out of bounds of {len} elements"),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for CowVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.elements == other.elements
+    }
+}
+
+impl<T: Eq> Eq for CowVec<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for CowVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::{CowMap, CowVec};
+
+    #[test]
+    fn map_keeps_keys_ordered() {
+        let mut map: CowMap<u32, u32> = (0..1_000).rev().map(|key| (key * 2, key)).collect();
+        assert_eq!(map.len(), 1_000);
+        assert!(map.keys().copied().eq((0..1_000).map(|key| key * 2)));
+        assert_eq!(map.insert(10, 0), Some(5));
+        assert_eq!(map.remove(&12), Some(6));
+        assert_eq!(map.remove(&13), None);
+        assert_eq!(map.len(), 999);
+        assert!(map
+            .range_from(&9)
+            .map(|(key, _)| *key)
+            .take(3)
+            .eq([10, 14, 16]));
+        assert_eq!(map.last_key_value(), Some((&1_998, &999)));
+    }
+
+    #[test]
+    fn clones_share_what_neither_changed() {
+        let mut parent: CowVec<Vec<u32>> = (0..100).map(|n| vec![n]).collect();
+        let mut child = parent.clone();
+        child.last_mut().unwrap().push(100);
+        child.push(vec![101]);
+        parent.get_mut(0).unwrap().push(1);
+
+        assert_eq!(parent.len(), 100);
+        assert_eq!(parent.last(), Some(&vec![99]));
+        assert_eq!(child.get(0), Some(&vec![0]));
+        assert_eq!(child.get(99), Some(&vec![99, 100]));
+        assert_eq!(child.last(), Some(&vec![101]));
+
+        // Elements neither of them changed are still the same allocation
+        assert!(ptr::eq(parent.get(50).unwrap(), child.get(50).unwrap()));
+        assert!(!ptr::eq(parent.get(0).unwrap(), child.get(0).unwrap()));
+    }
+}
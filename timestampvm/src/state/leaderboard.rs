@@ -0,0 +1,84 @@
+//! Ranks the addresses seated in finished games, by rating and by wins. Both
+//! rankings are kept sorted as games finish, so a page of the leaderboard is read
+//! without sorting every address.
+
+use std::cmp::Reverse;
+
+use avalanche_types::ids;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::cow::CowMap;
+
+/// What the leaderboard ranks addresses by. Ties are broken by address.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardOrder {
+    /// Highest rating first, only counting addresses with a rated game
+    #[default]
+    Rating,
+    /// Most wins first
+    Wins,
+}
+
+/// Addresses sorted by rating and by wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Leaderboard {
+    /// Rating and wins each address is ranked under
+    entries: CowMap<ids::short::Id, (Option<u32>, u64)>,
+    by_rating: CowMap<(Reverse<u32>, ids::short::Id), ()>,
+    by_wins: CowMap<(Reverse<u64>, ids::short::Id), ()>,
+}
+
+impl Leaderboard {
+    /// Ranks the address under its new rating, if it has one, and wins.
+    pub fn update(&mut self, address: &ids::short::Id, rating: Option<u32>, wins: u64) {
+        let previous = self.entries.insert(address.clone(), (rating, wins));
+        if let Some((rating, wins)) = previous {
+            if let Some(rating) = rating {
+                self.by_rating.remove(&(Reverse(rating), address.clone()));
+            }
+            self.by_wins.remove(&(Reverse(wins), address.clone()));
+        }
+        if let Some(rating) = rating {
+            self.by_rating
+                .insert((Reverse(rating), address.clone()), ());
+        }
+        self.by_wins.insert((Reverse(wins), address.clone()), ());
+    }
+
+    /// Returns the addresses ranked from `offset` on, at most `limit` of them.
+    #[must_use]
+    pub fn page(
+        &self,
+        order: LeaderboardOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Vec<ids::short::Id> {
+        match order {
+            LeaderboardOrder::Rating => self
+                .by_rating
+                .keys()
+                .skip(offset)
+                .take(limit)
+                .map(|(_, address)| address.clone())
+                .collect(),
+            LeaderboardOrder::Wins => self
+                .by_wins
+                .keys()
+                .skip(offset)
+                .take(limit)
+                .map(|(_, address)| address.clone())
+                .collect(),
+        }
+    }
+
+    /// Returns the number of addresses ranked.
+    #[must_use]
+    pub fn len(&self, order: LeaderboardOrder) -> usize {
+        match order {
+            LeaderboardOrder::Rating => self.by_rating.len(),
+            LeaderboardOrder::Wins => self.by_wins.len(),
+        }
+    }
+}
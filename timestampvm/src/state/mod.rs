@@ -1,49 +1,556 @@
 //! Manages the virtual machine states.
 
+pub mod cow;
+pub mod leaderboard;
+pub mod series;
+pub mod sharded;
+pub mod team;
+pub mod tournament;
+
 use std::{
     collections::HashMap,
     io::{self, Error, ErrorKind},
+    num::NonZeroUsize,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
-use crate::block::Block;
-use avalanche_types::{choices, ids, subnet};
+use crate::{
+    block::{self, AccountAction, Block, SeriesAction, TeamAction, TournamentAction, Transfer},
+    game::{self, rating, GameError, GameResult, GameStatus},
+    genesis::{Allocation, Genesis, Treasury},
+    mempool::PersistedMove,
+    metrics::Metrics,
+};
+use avalanche_types::{
+    choices, ids,
+    subnet::rpc::{
+        database::{memdb, BoxedDatabase},
+        errors,
+    },
+};
+use arc_swap::ArcSwap;
+use lru::LruCache;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, RwLock};
+
+use self::{
+    cow::{CowMap, CowVec},
+    leaderboard::{Leaderboard, LeaderboardOrder},
+    series::{Series, SeriesAdvance, SeriesStatus},
+    sharded::{Sharded, DEFAULT_SHARDS},
+    team::TeamGame,
+    tournament::{Advance, Tournament, TournamentFormat, TournamentStatus},
+};
+
+/// Number of accepted moves kept for subscribers that fall behind.
+const ACCEPTED_CHANNEL_CAPACITY: usize = 256;
+
+/// Number of accepted blocks kept decoded in memory, split evenly across shards.
+const BLOCK_CACHE_SIZE: usize = 1_024;
+
+/// zstd level accepted blocks are compressed at, if configured.
+const BLOCK_COMPRESSION_LEVEL: i32 = 3;
+
+/// Magic number every zstd frame starts with, which JSON-encoded blocks never do.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Number of pending blocks each doubling the move fee, see [`State::congestion_fee`].
+pub const FEE_DOUBLING_DEPTH: usize = 8;
+
+/// Number of blocks the seat to move in a reserved game is awaited for before it
+/// forfeits, see [`State::awaited_since`]. Blocks of any kind count, and the
+/// deadline is enforced before each of them is applied.
+pub const RESERVATION_DEADLINE: u64 = 100;
+
+/// Number of blocks replayed between progress logs.
+const REPLAY_LOG_INTERVAL: u64 = 1_000;
+
+/// Number of blocks decoded in parallel while replaying.
+const REPLAY_CHUNK_SIZE: u64 = 256;
+
+/// Number of decoded chunks waiting to be replayed, at most.
+const REPLAY_CHUNKS_AHEAD: usize = 4;
 
 /// Manages block and chain states for this VM, both in-memory and persistent
 #[derive(Clone)]
 pub struct State {
+    /// Persistent key-value store of the chain
+    pub db: Arc<RwLock<BoxedDatabase>>,
 
     /// Unsigned 32-bit integer representing the Tic-Tac-Toe state
     pub curr_game: Arc<RwLock<u32>>,
 
-    /// Vector storing the winner of each Tic-Tac-Toe game
-    pub winners: Arc<RwLock<Vec<u32>>>,
+    /// Vector storing the result of each finished Tic-Tac-Toe game (draws included)
+    pub winners: Arc<RwLock<CowVec<GameResult>>>,
+
+    /// Board and results as of the last accepted block, swapped as a whole on accept
+    /// so readers never wait on the locks above
+    pub latest: Arc<ArcSwap<LatestGame>>,
 
     /// Maps block Id to Block
     /// Each element represents a valid player move
     /// Each element is verified but not yet accepted/rejected (e.g. preferred)
-    pub verified_blocks: Arc<RwLock<HashMap<ids::Id, Block>>>,
+    pub verified_blocks: Arc<Sharded<HashMap<ids::Id, Block>>>,
+
+    /// Maps the Id of each verified block to the state it reaches, which its
+    /// children are verified against before it's accepted. Each shares all the
+    /// block didn't change with the state its parent reaches, see [`CowMap`]
+    pub verified_states: Arc<Sharded<HashMap<ids::Id, State>>>,
+
+    /// Most recently used accepted blocks, so they aren't decoded from the database again
+    pub block_cache: Arc<Sharded<LruCache<ids::Id, Block>>>,
+
+    /// Maps the height of each accepted block to its block Id
+    pub height_index: Arc<RwLock<HashMap<u64, ids::Id>>>,
+
+    /// Height of the last accepted block, updated as accepted blocks are indexed
+    pub last_accepted_height: Arc<RwLock<u64>>,
+
+    /// Maps block Id to the board as it stood once that block was accepted
+    pub board_snapshots: Arc<RwLock<CowMap<ids::Id, u32>>>,
+
+    /// Ordered moves of each game, indexed by game (draws included)
+    /// The last element is the game currently being played
+    pub move_history: Arc<RwLock<CowVec<Vec<MoveRecord>>>>,
+
+    /// Maps player address to its results, updated as games finish
+    pub player_stats: Arc<RwLock<CowMap<ids::short::Id, PlayerStats>>>,
+
+    /// Maps address to the seats it took, in the order it took them
+    pub account_games: Arc<RwLock<CowMap<ids::short::Id, Vec<AccountGame>>>>,
+
+    /// Maps address to the changes of its rating, one per rated game in the order
+    /// they finished
+    pub ratings: Arc<RwLock<CowMap<ids::short::Id, Vec<RatingChange>>>>,
+
+    /// Addresses seated in finished games, ranked by rating and by wins
+    pub leaderboard: Arc<RwLock<Leaderboard>>,
+
+    /// Maps address to its token balance, seeded from the genesis allocations
+    pub balances: Arc<RwLock<CowMap<ids::short::Id, u64>>>,
+
+    /// Genesis allocations the balances were seeded from, so replays start from them
+    pub allocations: Arc<RwLock<Vec<Allocation>>>,
+
+    /// Maps address to the number of transfers, seeks, tournament, series and team
+    /// actions it made
+    pub nonces: Arc<RwLock<CowMap<ids::short::Id, u64>>>,
+
+    /// Tokens burned from the signer's balance for each move, set from the genesis
+    pub move_fee: u64,
+
+    /// Cap congestion raises the move fee to, set from the genesis
+    pub max_move_fee: u64,
+
+    /// Tokens escrowed from each address taking a seat in a game, set from the genesis
+    pub game_stake: u64,
+
+    /// Addresses waiting to be paired into a game, keyed by the height they were
+    /// queued at, so oldest first
+    pub seekers: Arc<RwLock<CowMap<u64, Seeker>>>,
 
-    pub blk_map: Arc<RwLock<HashMap<ids::Id, Block>>>
+    /// Maps game index to the addresses matchmaking paired into it, holding seats 1
+    /// and 2 in order
+    pub matches: Arc<RwLock<CowMap<usize, [ids::short::Id; 2]>>>,
+
+    /// Tournaments, played as brackets or over Swiss rounds, indexed by the order
+    /// they were created in
+    pub tournaments: Arc<RwLock<CowVec<Tournament>>>,
+
+    /// Best-of-N series, indexed by the order they were challenged in
+    pub series: Arc<RwLock<CowVec<Series>>>,
+
+    /// 2v2 team games, indexed by the order they were challenged in
+    pub team_games: Arc<RwLock<CowVec<TeamGame>>>,
+
+    /// Height of the last block a move was played in, or the current game wasn't
+    /// reserved at. Once [`RESERVATION_DEADLINE`] blocks passed since, the seat to
+    /// move in the reserved game forfeits it
+    pub awaited_since: Arc<RwLock<u64>>,
+
+    /// Address credited with a share of each move fee, set from the genesis
+    pub treasury: Option<Treasury>,
+
+    /// Leading zero bits each move's proof of work must have, set from the genesis
+    pub move_work: u8,
+
+    /// Maps accepted block Id to the events its move emitted
+    pub block_events: Arc<RwLock<CowMap<ids::Id, Vec<BlockEvent>>>>,
+
+    /// Broadcasts every move once its block is accepted
+    pub accepted: broadcast::Sender<AcceptedMove>,
+
+    /// When this node last accepted a block
+    pub last_accepted_at: Arc<RwLock<Option<Instant>>>,
+
+    /// Number of most recent blocks whose board snapshots and events are kept,
+    /// all of them if `None`
+    pub history_blocks: Option<u64>,
+
+    /// Whether accepted blocks are compressed before being written to the database
+    pub compress_blocks: bool,
+
+    pub metrics: Arc<Metrics>,
 }
 
 impl Default for State {
     fn default() -> State {
         Self {
+            db: Arc::new(RwLock::new(memdb::Database::new())),
             curr_game: Arc::new(RwLock::new(0)),
-            winners: Arc::new(RwLock::new(Vec::new())),
-            verified_blocks: Arc::new(RwLock::new(HashMap::new())),
-            blk_map: Arc::new(RwLock::new(HashMap::new()))
+            winners: Arc::new(RwLock::new(CowVec::new())),
+            latest: Arc::new(ArcSwap::from_pointee(LatestGame::default())),
+            verified_blocks: Arc::new(Sharded::new(DEFAULT_SHARDS, HashMap::new)),
+            verified_states: Arc::new(Sharded::new(DEFAULT_SHARDS, HashMap::new)),
+            block_cache: Arc::new(Sharded::new(DEFAULT_SHARDS, || {
+                LruCache::new(NonZeroUsize::new(BLOCK_CACHE_SIZE / DEFAULT_SHARDS).unwrap())
+            })),
+            height_index: Arc::new(RwLock::new(HashMap::new())),
+            last_accepted_height: Arc::new(RwLock::new(0)),
+            board_snapshots: Arc::new(RwLock::new(CowMap::new())),
+            move_history: Arc::new(RwLock::new(CowVec::from_iter([Vec::new()]))),
+            player_stats: Arc::new(RwLock::new(CowMap::new())),
+            account_games: Arc::new(RwLock::new(CowMap::new())),
+            ratings: Arc::new(RwLock::new(CowMap::new())),
+            leaderboard: Arc::new(RwLock::new(Leaderboard::default())),
+            balances: Arc::new(RwLock::new(CowMap::new())),
+            allocations: Arc::new(RwLock::new(Vec::new())),
+            nonces: Arc::new(RwLock::new(CowMap::new())),
+            move_fee: 0,
+            max_move_fee: 0,
+            game_stake: 0,
+            seekers: Arc::new(RwLock::new(CowMap::new())),
+            matches: Arc::new(RwLock::new(CowMap::new())),
+            tournaments: Arc::new(RwLock::new(CowVec::new())),
+            series: Arc::new(RwLock::new(CowVec::new())),
+            team_games: Arc::new(RwLock::new(CowVec::new())),
+            awaited_since: Arc::new(RwLock::new(0)),
+            treasury: None,
+            move_work: 0,
+            block_events: Arc::new(RwLock::new(CowMap::new())),
+            accepted: broadcast::channel(ACCEPTED_CHANNEL_CAPACITY).0,
+            last_accepted_at: Arc::new(RwLock::new(None)),
+            history_blocks: None,
+            compress_blocks: false,
+            metrics: Arc::new(Metrics::new()),
+        }
+    }
+}
+
+/// Immutable view of the game as of the last accepted block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatestGame {
+    /// Board of the game currently being played
+    pub board: u32,
+    /// Result of each finished game (draws included)
+    pub winners: CowVec<GameResult>,
+}
+
+/// A single accepted move within a game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct MoveRecord {
+    /// Player that made the move (1 or 2)
+    pub player: u8,
+    /// Board cell the move was placed in (0-8)
+    pub cell: u8,
+    /// Id of the block carrying the move
+    #[schemars(with = "String")]
+    pub block_id: ids::Id,
+    /// Height of the block carrying the move
+    pub height: u64,
+    /// Address that signed the move, if it was signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub address: Option<ids::short::Id>,
+}
+
+/// A move whose block was just accepted, along with the game it was played in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedMove {
+    pub game: usize,
+    #[serde(flatten)]
+    pub record: MoveRecord,
+    /// Board of the game once the move was applied
+    pub board: u32,
+    /// Status of the game once the move was applied
+    pub status: GameStatus,
+}
+
+/// Event emitted by an accepted block, in the order listed by [`State::get_block_events`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "type")]
+pub enum BlockEvent {
+    /// The block carried the first move of a game
+    GameStarted { game: usize },
+    MovePlayed { game: usize, player: u8, cell: u8 },
+    GameWon { game: usize, player: u8 },
+    GameDrawn { game: usize },
+    /// The seat to move in a reserved game didn't move before the deadline,
+    /// forfeiting the game to the other seat
+    GameForfeited { game: usize, player: u8 },
+    /// No move was played in a game matchmaking reserved before the deadline, so
+    /// anyone may play it
+    ReservationLapsed {
+        game: usize,
+        /// Addresses that held seats 1 and 2
+        #[schemars(with = "[String; 2]")]
+        players: [ids::short::Id; 2],
+    },
+    /// The block carried a transfer rather than a move
+    Transferred {
+        #[schemars(with = "String")]
+        from: ids::short::Id,
+        #[schemars(with = "String")]
+        to: ids::short::Id,
+        amount: u64,
+    },
+    /// The block carried a seek, queueing the seeker
+    SeekQueued {
+        #[schemars(with = "String")]
+        address: ids::short::Id,
+    },
+    /// The block carried a seek, pairing the seeker with a waiting one in a game
+    GameMatched {
+        game: usize,
+        /// Addresses holding seats 1 and 2
+        #[schemars(with = "[String; 2]")]
+        players: [ids::short::Id; 2],
+    },
+    /// The block carried a tournament action, creating a tournament
+    TournamentCreated {
+        tournament: usize,
+        #[schemars(with = "String")]
+        creator: ids::short::Id,
+        players: usize,
+        format: TournamentFormat,
+    },
+    /// The block carried a tournament action, registering its signer
+    TournamentRegistered {
+        tournament: usize,
+        #[schemars(with = "String")]
+        address: ids::short::Id,
+    },
+    /// A round of a tournament was seeded, reserving a game for each of its matches
+    TournamentRoundStarted {
+        tournament: usize,
+        round: usize,
+        games: Vec<usize>,
+    },
+    /// The final of a tournament was won
+    TournamentFinished {
+        tournament: usize,
+        #[schemars(with = "String")]
+        champion: ids::short::Id,
+    },
+    /// The block carried a series action, challenging an address to a series
+    SeriesChallenged {
+        series: usize,
+        /// Challenger and opponent, in order
+        #[schemars(with = "[String; 2]")]
+        players: [ids::short::Id; 2],
+        best_of: usize,
+    },
+    /// A game of a series was reserved, on its acceptance or once the previous one
+    /// finished
+    SeriesGameReserved {
+        series: usize,
+        game: usize,
+        /// Addresses holding seats 1 and 2
+        #[schemars(with = "[String; 2]")]
+        players: [ids::short::Id; 2],
+    },
+    /// A series was decided, or all of its games were played
+    SeriesFinished {
+        series: usize,
+        /// Unset if the series was drawn
+        #[schemars(with = "Option<String>")]
+        winner: Option<ids::short::Id>,
+        /// Games won by the challenger and the opponent, in order
+        score: [usize; 2],
+    },
+    /// The block carried a team action, challenging two addresses to a team game
+    TeamChallenged {
+        team_game: usize,
+        /// Teams holding seats 1 and 2, each listing its teammates in the order
+        /// they move
+        #[schemars(with = "[[String; 2]; 2]")]
+        teams: [[ids::short::Id; 2]; 2],
+    },
+    /// The block carried a team action, accepting a team game for its signer
+    TeamAccepted {
+        team_game: usize,
+        #[schemars(with = "String")]
+        address: ids::short::Id,
+    },
+    /// Everyone invited to a team game accepted, reserving a game for the teams
+    TeamGameReserved {
+        team_game: usize,
+        game: usize,
+    },
+}
+
+/// Results of all finished games an address took part in.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, JsonSchema)]
+pub struct PlayerStats {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    pub games_played: u64,
+}
+
+/// Change of an address's rating brought by a finished game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct RatingChange {
+    pub game: usize,
+    #[schemars(with = "String")]
+    pub opponent: ids::short::Id,
+    /// Rating once the game was rated
+    pub rating: u32,
+    /// Points the game gained, or lost if negative
+    pub change: i64,
+}
+
+/// Address waiting in the matchmaking queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seeker {
+    pub address: ids::short::Id,
+    /// Height of the block that queued it
+    pub height: u64,
+}
+
+/// Seat an address took in a game.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct AccountGame {
+    pub game: usize,
+    /// Player (1 or 2) whose seat the address took
+    pub player: u8,
+}
+
+/// Differences between the in-memory state and the one replayed from the database,
+/// see [`State::check_consistency`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    /// "true" if nothing differs
+    pub consistent: bool,
+    /// Height of the last accepted block stored in the database
+    pub height: u64,
+    /// What differs, one entry per difference
+    pub mismatches: Vec<String>,
+}
+
+/// Version of the [`StateArchive`] format written by this build.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Accepted chain data of a node as a single archive, to migrate it to another
+/// node or seed a test environment, see [`State::export_archive`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateArchive {
+    /// Format of the archive, see [`ARCHIVE_VERSION`]
+    pub version: u32,
+    /// Accepted blocks in height order, starting with the genesis block
+    pub blocks: Vec<ArchivedBlock>,
+    /// Board as it stood once each block was accepted, for the blocks whose
+    /// history is kept
+    pub snapshots: Vec<BoardSnapshot>,
+    /// Result of each finished game (draws included)
+    pub winners: Vec<GameResult>,
+}
+
+/// Accepted block of a [`StateArchive`], along with the Id it was accepted under.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchivedBlock {
+    pub id: ids::Id,
+    pub block: Block,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoardSnapshot {
+    pub block_id: ids::Id,
+    pub board: u32,
+}
+
+impl StateArchive {
+    /// Replays the archived blocks into a fresh in-memory state, checking that each
+    /// encodes to its Id and that the state reached matches the snapshots and results.
+    /// Returns the blocks in height order, ready to be accepted.
+    /// Balances start from the allocations of the given genesis, and its move fee
+    /// and game stake apply.
+    /// # Errors
+    /// Fails if the archive is of another version or doesn't replay to its snapshots.
+    pub async fn verify(&self, genesis: &Genesis) -> io::Result<Vec<Block>> {
+        if self.version != ARCHIVE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "archive version {} isn't supported, expected {ARCHIVE_VERSION}",
+                    self.version
+                ),
+            ));
+        }
+
+        let replayed = State {
+            move_fee: genesis.move_fee,
+            max_move_fee: genesis.max_move_fee,
+            game_stake: genesis.game_stake,
+            treasury: genesis.treasury.clone(),
+            move_work: genesis.move_work,
+            ..State::default()
+        };
+        replayed.seed_balances(&genesis.allocations).await?;
+        let mut blocks = Vec::with_capacity(self.blocks.len());
+        let mut parent_id = ids::Id::empty();
+        for ArchivedBlock { id, block } in &self.blocks {
+            // Re-encodes the block, so its Id is recomputed from its contents
+            let block = block
+                .with_parent(block.parent_id(), block.height())?
+                .with_format_of(id)?;
+            if block.id() != *id || block.height() != blocks.len() as u64 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "archived block {id} at height {} encodes to {}",
+                        block.height(),
+                        block.id()
+                    ),
+                ));
+            }
+            replayed.replay_block(&block, &parent_id).await?;
+            parent_id = *id;
+            blocks.push(block);
+        }
+
+        for snapshot in &self.snapshots {
+            let board = replayed.get_board_at_block(&snapshot.block_id).await?;
+            if board != snapshot.board {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "board after block {} is {} in the archive and {board} replayed",
+                        snapshot.block_id, snapshot.board
+                    ),
+                ));
+            }
+        }
+        if !replayed.winners.read().await.iter().eq(&self.winners) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "game results differ from the replayed ones",
+            ));
         }
+        Ok(blocks)
     }
 }
 
 const LAST_ACCEPTED_BLOCK_KEY: &[u8] = b"last_accepted_block";
 
+const PENDING_MOVES_KEY: &[u8] = b"pending_moves";
+
 const STATUS_PREFIX: u8 = 0x0;
 
+const HEIGHT_PREFIX: u8 = 0x1;
+
 const DELIMITER: u8 = b'/';
 
 /// Returns a vec of bytes used as a key for identifying blocks in state.
@@ -56,6 +563,192 @@ fn block_with_status_key(blk_id: &ids::Id) -> Vec<u8> {
     k
 }
 
+/// Returns a vec of bytes used as a key for identifying the accepted block at a height.
+/// '`HEIGHT_PREFIX`' + '`BYTE_DELIMITER`' + [`height`]
+fn height_key(height: u64) -> Vec<u8> {
+    let mut k: Vec<u8> = Vec::with_capacity(10);
+    k.push(HEIGHT_PREFIX);
+    k.push(DELIMITER);
+    k.extend_from_slice(&height.to_be_bytes());
+    k
+}
+
+/// Returns the player expected to move next in the last (current) game of `move_history`.
+fn next_player_of(move_history: &CowVec<Vec<MoveRecord>>) -> u8 {
+    game::next_player(
+        move_history
+            .last()
+            .and_then(|moves| moves.last())
+            .map(|last_move| last_move.player),
+    )
+}
+
+/// Returns the address holding the given player's seat in `moves`, if any
+/// A seat is taken by the first signed move made for that player.
+#[must_use]
+pub fn seat_of(moves: &[MoveRecord], player: u8) -> Option<ids::short::Id> {
+    moves
+        .iter()
+        .find(|m| m.player == player && m.address.is_some())
+        .and_then(|m| m.address.clone())
+}
+
+/// Returns the addresses that played each side of a finished game: the teams of a
+/// team game, otherwise the addresses seated in it.
+fn sides_of(
+    moves: &[MoveRecord],
+    teams: Option<&[[ids::short::Id; 2]; 2]>,
+) -> [Vec<ids::short::Id>; 2] {
+    match teams {
+        Some(teams) => teams.clone().map(Vec::from),
+        None => [1, 2].map(|player| seat_of(moves, player).into_iter().collect()),
+    }
+}
+
+/// Credits the addresses that played each side of a finished game with its result.
+fn record_player_stats(
+    player_stats: &mut CowMap<ids::short::Id, PlayerStats>,
+    sides: &[Vec<ids::short::Id>; 2],
+    winner: Option<u8>,
+) {
+    for (player, side) in (1..=2).zip(sides) {
+        for address in side {
+            let stats = player_stats.get_or_insert_default(address);
+            stats.games_played += 1;
+            match winner {
+                Some(w) if w == player => stats.wins += 1,
+                Some(_) => stats.losses += 1,
+                None => stats.draws += 1,
+            }
+        }
+    }
+}
+
+/// Returns the rating an address reached given its rating changes.
+fn current_rating(changes: Option<&Vec<RatingChange>>) -> u32 {
+    changes
+        .and_then(|changes| changes.last())
+        .map_or(rating::INITIAL_RATING, |last| last.rating)
+}
+
+/// Rates the addresses seated in a finished game, unless a seat is empty or both
+/// are held by the same address.
+fn record_ratings(
+    ratings: &mut CowMap<ids::short::Id, Vec<RatingChange>>,
+    game: usize,
+    moves: &[MoveRecord],
+    winner: Option<u8>,
+) {
+    if let (Some(x), Some(o)) = (seat_of(moves, 1), seat_of(moves, 2)) {
+        if x == o {
+            return;
+        }
+        let before = [current_rating(ratings.get(&x)), current_rating(ratings.get(&o))];
+        let after = rating::rate_game(before, winner);
+        let seats = [(x.clone(), o.clone()), (o, x)];
+        for (((address, opponent), before), after) in seats.into_iter().zip(before).zip(after) {
+            ratings.get_or_insert_default(&address).push(RatingChange {
+                game,
+                opponent,
+                rating: after,
+                change: i64::from(after) - i64::from(before),
+            });
+        }
+    }
+}
+
+/// Rates the teammates of a finished team game, each against the opponent moving
+/// at the same turn for the other side.
+fn record_team_ratings(
+    ratings: &mut CowMap<ids::short::Id, Vec<RatingChange>>,
+    game: usize,
+    teams: &[[ids::short::Id; 2]; 2],
+    winner: Option<u8>,
+) {
+    let before = teams
+        .clone()
+        .map(|team| team.map(|address| current_rating(ratings.get(&address))));
+    let after = rating::rate_team_game(before, winner);
+    let opponents = [&teams[1], &teams[0]];
+    let results = before.into_iter().zip(after);
+    for ((team, opponents), (before, after)) in teams.iter().zip(opponents).zip(results) {
+        let rated = team.iter().zip(opponents).zip(before).zip(after);
+        for (((address, opponent), before), after) in rated {
+            let changes = ratings.get_or_insert_default(address);
+            changes.push(RatingChange {
+                game,
+                opponent: opponent.clone(),
+                rating: after,
+                change: i64::from(after) - i64::from(before),
+            });
+        }
+    }
+}
+
+/// Returns the height the waiting seeker a new one rated `rating` is paired with
+/// at `height` was queued at: the closest rated of those whose match window it
+/// falls in, the oldest of them on a tie.
+fn pick_opponent(
+    seekers: &CowMap<u64, Seeker>,
+    ratings: &CowMap<ids::short::Id, Vec<RatingChange>>,
+    rating: u32,
+    height: u64,
+) -> Option<u64> {
+    seekers
+        .values()
+        .map(|seeker| {
+            let diff = rating.abs_diff(current_rating(ratings.get(&seeker.address)));
+            (seeker.height, diff, rating::match_window(height.saturating_sub(seeker.height)))
+        })
+        .filter(|(_, diff, window)| diff <= window)
+        .min_by_key(|(_, diff, _)| *diff)
+        .map(|(queued, _, _)| queued)
+}
+
+/// Returns the first game from `from` on that no seat is reserved in, nor in any
+/// game after it.
+fn next_open_game(matches: &CowMap<usize, [ids::short::Id; 2]>, from: usize) -> usize {
+    matches
+        .last_key_value()
+        .map_or(from, |(last, _)| from.max(last + 1))
+}
+
+/// Reserves the next open game from `from` on for the players, holding seats 1 and
+/// 2 in order, and returns it.
+fn reserve_game(
+    matches: &mut CowMap<usize, [ids::short::Id; 2]>,
+    from: usize,
+    players: [ids::short::Id; 2],
+) -> usize {
+    let game = next_open_game(matches, from);
+    matches.insert(game, players);
+    game
+}
+
+/// Pays out the stakes escrowed by the addresses seated in a finished game: the
+/// winner takes the pot, and a draw refunds each seat.
+fn pay_out_stakes(
+    balances: &mut CowMap<ids::short::Id, u64>,
+    moves: &[MoveRecord],
+    winner: Option<u8>,
+    stake: u64,
+) {
+    let seats = [seat_of(moves, 1), seat_of(moves, 2)];
+    let pot = stake * seats.iter().flatten().count() as u64;
+    for (player, seat) in (1..=2).zip(seats) {
+        if let Some(address) = seat {
+            let payout = match winner {
+                Some(w) if w == player => pot,
+                Some(_) => 0,
+                None => stake,
+            };
+            // Stakes were escrowed from balances, so no balance can overflow
+            let balance = balances.get_or_insert_default(&address);
+            *balance = balance.saturating_add(payout);
+        }
+    }
+}
+
 /// Wraps a [`Block`](crate::block::Block) and its status.
 /// This is the data format that [`State`](State) uses to persist blocks.
 #[derive(Serialize, Deserialize, Clone)]
@@ -89,105 +782,2091 @@ impl State {
 
     /// Returns integer representing the current state of the Tic-Tac-Toe game
     pub async fn get_curr_game(&self) -> u32 {
-        let ttt = self.curr_game.read().await;
-        return ttt.clone();
+        *self.curr_game.read().await
     }
 
 
-    pub async fn get_winner(&self, i: usize) -> Option<u32> {
+    /// Returns the result of the ith game, if it has finished
+    pub async fn get_winner(&self, i: usize) -> Option<GameResult> {
         let winner_list = self.winners.read().await;
         winner_list.get(i).copied()
     }
 
+    /// Returns up to `limit` results of finished games starting at game `offset`,
+    /// along with the total number of finished games
+    pub async fn get_winners(&self, offset: usize, limit: usize) -> (Vec<GameResult>, usize) {
+        let winner_list = self.winners.read().await;
+        let page = winner_list.iter().skip(offset).take(limit).copied().collect();
+        (page, winner_list.len())
+    }
+
+    /// Returns the status of the given game
+    /// # Errors
+    /// Fails if the game doesn't exist yet.
+    pub async fn get_game_status(&self, game: usize) -> io::Result<GameStatus> {
+        // same lock order as `update_board`
+        let winners = self.winners.read().await;
+        let move_history = self.move_history.read().await;
+
+        if let Some(result) = winners.get(game) {
+            return Ok(GameStatus::from(*result));
+        }
+        if game + 1 == move_history.len() {
+            return Ok(GameStatus::InProgress {
+                next_player: next_player_of(&move_history),
+            });
+        }
+
+        Err(GameError::UnknownGame(game).into())
+    }
+
     /// Returns an already published block
+    /// # Errors
+    /// Fails if the block is neither verified nor accepted.
     pub async fn get_block(&self, blk_id: &ids::Id) -> io::Result<Block> {
         // check if the block exists in memory as previously verified.
-        let verified_blocks = self.verified_blocks.read().await;
+        let verified_blocks = self.verified_blocks.shard(blk_id).read().await;
         if let Some(b) = verified_blocks.get(blk_id) {
             return Ok(b.clone());
         }
 
+        drop(verified_blocks);
+
         // Check if block already applied to state
-        let blk_map = self.blk_map.read().await;
+        let mut block_cache = self.block_cache.shard(blk_id).write().await;
+        if let Some(b) = block_cache.get(blk_id) {
+            return Ok(b.clone());
+        }
+        drop(block_cache);
 
-        let blk = blk_map.get(blk_id);
+        let block = match self.read_block(blk_id).await {
+            Ok(block) => block,
+            Err(e) if errors::is_not_found(&e) => {
+                return Err(GameError::UnknownBlock(*blk_id).into())
+            }
+            Err(e) => return Err(e),
+        };
+        self.block_cache
+            .shard(blk_id)
+            .write()
+            .await
+            .put(*blk_id, block.clone());
+        Ok(block)
+    }
 
-        match blk {
-            Some(t) => Ok(t.clone()),
-            None => Err(Error::new(ErrorKind::Other, "Block doesn't exist!"))
+    /// Returns the Id of the accepted block at the given height
+    /// # Errors
+    /// Fails if no block was accepted at the height.
+    pub async fn get_block_id_at_height(&self, height: u64) -> io::Result<ids::Id> {
+        let height_index = self.height_index.read().await;
+        match height_index.get(&height) {
+            Some(blk_id) => Ok(*blk_id),
+            None => Err(GameError::UnknownHeight(height).into()),
         }
     }
 
-    // Adds a block to "verified blocks"
-    pub async fn add_verified(&mut self, block: &Block) {
-        let blk_id = block.id();
-        log::info!("verified added {blk_id}");
+    /// Returns the height of the last accepted block
+    pub async fn get_height(&self) -> u64 {
+        *self.last_accepted_height.read().await
+    }
 
-        let mut verified_blocks = self.verified_blocks.write().await;
-        verified_blocks.insert(blk_id, block.clone());
+    /// Returns the accepted block at the given height
+    /// # Errors
+    /// Fails if no block was accepted at the height or it can't be read.
+    pub async fn get_block_by_height(&self, height: u64) -> io::Result<Block> {
+        let blk_id = self.get_block_id_at_height(height).await?;
+
+        self.get_block(&blk_id).await
     }
 
-    /// Removes a block from "`verified_blocks`".
-    pub async fn remove_verified(&mut self, blk_id: &ids::Id) {
-        let mut verified_blocks = self.verified_blocks.write().await;
-        verified_blocks.remove(blk_id);
+    /// Returns up to `limit` accepted blocks starting at `from_height`, in height order
+    pub async fn get_blocks_from(&self, from_height: u64, limit: usize) -> Vec<Block> {
+        let height_index = self.height_index.read().await;
+        let blk_ids: Vec<ids::Id> = (from_height..)
+            .map_while(|height| height_index.get(&height).copied())
+            .take(limit)
+            .collect();
+        drop(height_index);
+
+        let mut blocks = Vec::with_capacity(blk_ids.len());
+        for blk_id in blk_ids {
+            if let Ok(block) = self.get_block(&blk_id).await {
+                blocks.push(block);
+            }
+        }
+        blocks
     }
 
-    /// Returns "true" if the block Id has been already verified.
-    pub async fn has_verified(&self, blk_id: &ids::Id) -> bool {
-        let verified_blocks = self.verified_blocks.read().await;
-        verified_blocks.contains_key(blk_id)
+    /// Returns the board recorded after the given block was accepted
+    /// # Errors
+    /// Fails if the block is unknown or its history was pruned.
+    pub async fn get_board_at_block(&self, blk_id: &ids::Id) -> io::Result<u32> {
+        let board = self.board_snapshots.read().await.get(blk_id).copied();
+        match board {
+            Some(board) => Ok(board),
+            None => Err(self.missing_history(blk_id).await),
+        }
     }
-    /// Updates game board/resets game board if no win is possible (i.e. checks
-    /// all possible combinations)
-    pub async fn update_board(&self, block: &Block) -> io::Result<()> {
-        /// First update game board
-        let mut curr_board = self.curr_game.write().await;
 
-        // Bitmasking to get board index player wants to modify
-        let intended_position =  block.get_move_index();
-        // Bitmasking to get id of player (1 or 2)
-        let player_id = block.get_player_id() as u32;
+    /// Returns the error for a block without history: pruned if it was accepted,
+    /// unknown otherwise.
+    async fn missing_history(&self, blk_id: &ids::Id) -> io::Error {
+        let db = self.db.read().await;
+        if let Ok(true) = db.has(&block_with_status_key(blk_id)).await {
+            return GameError::PrunedBlock(*blk_id).into();
+        }
+        GameError::UnknownBlock(*blk_id).into()
+    }
 
-        // Erase current index value
-        *curr_board = *curr_board & !(0b11 << (2 * intended_position));
-        // Board is now updated!
-        *curr_board = *curr_board | (player_id << (2 * intended_position));
-
-        // Now check if someone won:
-        let legal_moves = [
-            [0, 1, 2], [3, 4, 5], [6, 7, 8],
-            [0, 3, 6], [1, 4, 7], [2, 5, 8],
-            [0, 4, 8], [6, 4, 2] 
-        ];
-
-        let mut seen_zero = 0;
-
-        for possible_win in legal_moves.iter() {
-            // Clone board
-            let val = curr_board.clone();
-            let val_1 = 0b11 & (val >> (2 * possible_win[0]));
-            let val_2 = 0b11 & (val >> (2 * possible_win[1]));
-            let val_3 = 0b11 & (val >> (2 * possible_win[2]));
-            // Checking player X has three in a row while ignoring the zero row
-            if val_1 == val_2 && val_2 == val_3 && val_1 != 0 {
-                // Add winner to winner vec
-                let mut win_vec = self.winners.write().await;
-                win_vec.push(player_id);
-                // Reset the state of the game
-                *curr_board = 0;
-            } else if val_1 == 0 || val_2 == 0 || val_3 == 0 {
-                seen_zero = 1;
-            }
-        }
-        if seen_zero == 0 {
-            // Board is completely full with no possible winner
-            // Add winner to winner vec
-            *curr_board = 0;
+    /// Returns the board recorded after the block at the given height was accepted
+    /// # Errors
+    /// Fails if no block was accepted at the height or its history was pruned.
+    pub async fn get_board_at_height(&self, height: u64) -> io::Result<u32> {
+        let blk_id = self.get_block_id_at_height(height).await?;
+
+        self.get_board_at_block(&blk_id).await
+    }
+
+    /// Returns the index of the game currently being played
+    pub async fn get_curr_game_index(&self) -> usize {
+        let move_history = self.move_history.read().await;
+        move_history.len() - 1
+    }
+
+    /// Returns the player (1 or 2) expected to make the next move of the current game
+    pub async fn get_next_player(&self) -> u8 {
+        let move_history = self.move_history.read().await;
+        next_player_of(&move_history)
+    }
+
+    /// Returns the number of moves the given player made in the current game
+    pub async fn get_moves_made(&self, player: u8) -> usize {
+        let move_history = self.move_history.read().await;
+        move_history.last().map_or(0, |moves| {
+            moves.iter().filter(|m| m.player == player).count()
+        })
+    }
+
+    /// Returns the address holding the given player's seat in the current game, if any
+    pub async fn get_seat(&self, player: u8) -> Option<ids::short::Id> {
+        let move_history = self.move_history.read().await;
+        move_history.last().and_then(|moves| seat_of(moves, player))
+    }
+
+    /// Returns the results recorded for the given address
+    pub async fn get_player_stats(&self, address: &ids::short::Id) -> PlayerStats {
+        let player_stats = self.player_stats.read().await;
+        player_stats.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Returns the seats the given address took, oldest first
+    pub async fn get_account_games(&self, address: &ids::short::Id) -> Vec<AccountGame> {
+        let account_games = self.account_games.read().await;
+        account_games.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Returns the rating of the given address
+    pub async fn get_rating(&self, address: &ids::short::Id) -> u32 {
+        let ratings = self.ratings.read().await;
+        current_rating(ratings.get(address))
+    }
+
+    /// Returns the changes of the given address's rating, oldest first
+    pub async fn get_rating_history(&self, address: &ids::short::Id) -> Vec<RatingChange> {
+        let ratings = self.ratings.read().await;
+        ratings.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Returns a page of the addresses ranked by `order`, from `offset` on and at
+    /// most `limit` of them, along with the number of addresses ranked.
+    pub async fn get_leaderboard(
+        &self,
+        order: LeaderboardOrder,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<ids::short::Id>, usize) {
+        let leaderboard = self.leaderboard.read().await;
+        (leaderboard.page(order, offset, limit), leaderboard.len(order))
+    }
+
+    /// Records the seat a move of the current game takes for its signer, unless it
+    /// already moved for that player, as teammates both do.
+    async fn record_seat(&self, player: u8, signer: Option<&ids::short::Id>) {
+        if let Some(signer) = signer {
+            let move_history = self.move_history.read().await;
+            let moved = move_history.last().map_or(false, |moves| {
+                let by_signer = |m: &MoveRecord| m.address.as_ref() == Some(signer);
+                moves.iter().any(|m| m.player == player && by_signer(m))
+            });
+            drop(move_history);
+            if !moved {
+                let game = self.get_curr_game_index().await;
+                let mut account_games = self.account_games.write().await;
+                let games = account_games.get_or_insert_default(signer);
+                games.push(AccountGame { game, player });
+            }
+        }
+    }
+
+    /// Returns the addresses matchmaking paired into the given game, holding seats
+    /// 1 and 2 in order
+    pub async fn get_match(&self, game: usize) -> Option<[ids::short::Id; 2]> {
+        let matches = self.matches.read().await;
+        matches.get(&game).cloned()
+    }
+
+    /// Returns the games from `from` on matchmaking paired the given address into
+    pub async fn get_matched_games(&self, address: &ids::short::Id, from: usize) -> Vec<usize> {
+        let matches = self.matches.read().await;
+        matches
+            .range_from(&from)
+            .filter(|(_, players)| players.contains(address))
+            .map(|(game, _)| *game)
+            .collect()
+    }
+
+    /// Returns how many seekers wait ahead of the given address, or `None` if it
+    /// isn't seeking a game
+    pub async fn get_seek_position(&self, address: &ids::short::Id) -> Option<usize> {
+        let seekers = self.seekers.read().await;
+        seekers.values().position(|seeker| seeker.address == *address)
+    }
+
+    /// Returns the number of addresses waiting to be paired into a game
+    pub async fn get_seekers_len(&self) -> usize {
+        self.seekers.read().await.len()
+    }
+
+    /// Returns the tournament with the given index, if it was created
+    pub async fn get_tournament(&self, tournament: usize) -> Option<Tournament> {
+        self.tournaments.read().await.get(tournament).cloned()
+    }
+
+    /// Returns the number of tournaments created
+    pub async fn get_tournaments_len(&self) -> usize {
+        self.tournaments.read().await.len()
+    }
+
+    /// Returns the series with the given index, if it was challenged
+    pub async fn get_series(&self, series: usize) -> Option<Series> {
+        self.series.read().await.get(series).cloned()
+    }
+
+    /// Returns the team game with the given index, if it was challenged
+    pub async fn get_team_game(&self, team_game: usize) -> Option<TeamGame> {
+        self.team_games.read().await.get(team_game).cloned()
+    }
+
+    /// Returns the team game being played in the given game, if any
+    async fn get_playing_team_game(&self, game: usize) -> Option<TeamGame> {
+        let team_games = self.team_games.read().await;
+        team_games.iter().find(|t| t.is_playing(game)).cloned()
+    }
+
+    /// Returns the token balance of the given address
+    pub async fn get_balance(&self, address: &ids::short::Id) -> u64 {
+        let balances = self.balances.read().await;
+        balances.get(address).copied().unwrap_or_default()
+    }
+
+    /// Credits the genesis allocations, before any block is accepted or replayed.
+    /// # Errors
+    /// Fails if the balance of an address listed more than once overflows.
+    pub async fn seed_balances(&self, allocations: &[Allocation]) -> io::Result<()> {
+        *self.allocations.write().await = allocations.to_vec();
+        let mut balances = self.balances.write().await;
+        for Allocation { address, balance } in allocations {
+            let credited = balances.get_or_insert_default(address);
+            *credited = credited.checked_add(*balance).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("genesis balance of {address} overflows"),
+                )
+            })?;
+        }
+        log::info!("seeded {} balances from genesis", balances.len());
+        Ok(())
+    }
+
+    /// Returns the number of transfers the given address made, which is the nonce
+    /// its next transfer must carry
+    pub async fn get_nonce(&self, address: &ids::short::Id) -> u64 {
+        let nonces = self.nonces.read().await;
+        nonces.get(address).copied().unwrap_or_default()
+    }
+
+    /// Checks that a transfer, seek, tournament, series or team action can be applied
+    /// next: it must be signed by the address taking it with that address's next
+    /// nonce, and pass the checks of its kind.
+    /// Returns the signer.
+    /// # Errors
+    /// Fails with the reason the action is invalid.
+    pub async fn validate_account_action(
+        &self,
+        action: &AccountAction,
+        signature: &[u8],
+    ) -> io::Result<ids::short::Id> {
+        let signer = action.recover_signer(signature)?;
+        let nonce = self.get_nonce(&signer).await;
+        if action.nonce() != nonce {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} nonce {} of {signer} isn't {nonce}", action.kind(), action.nonce()),
+            ));
+        }
+        match action {
+            AccountAction::Transfer(transfer) => self.check_transfer(&signer, transfer).await?,
+            AccountAction::Seek(_) => self.check_seek(&signer).await?,
+            AccountAction::Tournament(action) => {
+                self.check_tournament_action(&signer, action).await?;
+            }
+            AccountAction::Series(action) => self.check_series_action(&signer, action).await?,
+            AccountAction::Team(action) => self.check_team_action(&signer, action).await?,
+        }
+        Ok(signer)
+    }
+
+    /// Checks that a transfer moves tokens, no more than its sender's balance.
+    async fn check_transfer(&self, sender: &ids::short::Id, transfer: &Transfer) -> io::Result<()> {
+        if transfer.amount == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "transfer amount is zero"));
+        }
+        let balance = self.get_balance(sender).await;
+        if balance < transfer.amount {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "balance {balance} of {sender} can't cover transfer of {}",
+                    transfer.amount
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that the seeker isn't waiting already.
+    async fn check_seek(&self, seeker: &ids::short::Id) -> io::Result<()> {
+        if self.get_seek_position(seeker).await.is_some() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{seeker} is already seeking a game"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that a registration is for a tournament still open that the entrant
+    /// isn't registered in yet.
+    async fn check_tournament_action(
+        &self,
+        entrant: &ids::short::Id,
+        action: &TournamentAction,
+    ) -> io::Result<()> {
+        match action {
+            TournamentAction::Create {
+                players, rounds, ..
+            } => {
+                tournament::check_capacity(*players, *rounds)?;
+            }
+            TournamentAction::Register { tournament, .. } => {
+                let tournaments = self.tournaments.read().await;
+                let registered = tournaments.get(*tournament).ok_or_else(|| {
+                    Error::new(ErrorKind::NotFound, format!("tournament {tournament} not found"))
+                })?;
+                if registered.status() != TournamentStatus::Registering {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("tournament {tournament} is closed for registration"),
+                    ));
+                }
+                if registered.players.contains(entrant) {
+                    return Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!("{entrant} is already registered in tournament {tournament}"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a challenge is to another address over an odd number of games,
+    /// and that an acceptance is by the opponent of a series still waiting for it.
+    async fn check_series_action(
+        &self,
+        signer: &ids::short::Id,
+        action: &SeriesAction,
+    ) -> io::Result<()> {
+        match action {
+            SeriesAction::Challenge {
+                opponent, games, ..
+            } => {
+                series::check_best_of(*games)?;
+                if opponent == signer {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{signer} can't challenge itself to a series"),
+                    ));
+                }
+            }
+            SeriesAction::Accept { series, .. } => {
+                let all = self.series.read().await;
+                let challenged = all.get(*series).ok_or_else(|| {
+                    Error::new(ErrorKind::NotFound, format!("series {series} not found"))
+                })?;
+                if challenged.players[1] != *signer {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("series {series} wasn't challenged to {signer}"),
+                    ));
+                }
+                if challenged.status() != SeriesStatus::Challenged {
+                    return Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!("series {series} was already accepted"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a challenge is of two other addresses with a third, and that an
+    /// acceptance is by the partner or an opponent of a team game still waiting for
+    /// it.
+    async fn check_team_action(&self, signer: &ids::short::Id, action: &TeamAction) -> io::Result<()> {
+        match action {
+            TeamAction::Challenge {
+                partner, opponents, ..
+            } => {
+                team::check_teams(&[[signer.clone(), partner.clone()], opponents.clone()])?;
+            }
+            TeamAction::Accept { team_game, .. } => {
+                let team_games = self.team_games.read().await;
+                let challenged = team_games.get(*team_game).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("team game {team_game} not found"),
+                    )
+                })?;
+                let invitee = challenged.invitee(signer).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("team game {team_game} didn't invite {signer}"),
+                    )
+                })?;
+                if challenged.accepted[invitee] {
+                    return Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!("{signer} already accepted team game {team_game}"),
+                    ));
+                }
+            }
         }
+        Ok(())
+    }
 
+    /// Applies the transfer, seek, tournament, series or team action a block carries
+    /// instead of a move once its signer's nonce is taken, recording the board and
+    /// the events of the block.
+    async fn apply_account_action(&self, block: &Block, action: &AccountAction) -> io::Result<()> {
+        let signer = action.recover_signer(block.signature())?;
+        self.take_nonce(&signer, action.nonce(), action.kind()).await?;
+        let events = match action {
+            AccountAction::Transfer(transfer) => self.apply_transfer(signer, transfer).await?,
+            AccountAction::Seek(_) => self.apply_seek(signer, block.height()).await,
+            AccountAction::Tournament(action) => {
+                self.apply_tournament_action(signer, action).await?
+            }
+            AccountAction::Series(action) => self.apply_series_action(signer, action).await?,
+            AccountAction::Team(action) => self.apply_team_action(signer, action).await?,
+        };
 
+        let board = *self.curr_game.read().await;
+        self.board_snapshots.write().await.insert(block.id(), board);
+        let mut block_events = self.block_events.write().await;
+        block_events.get_or_insert_default(&block.id()).extend(events);
         Ok(())
     }
 
+    /// Challenges the opponent of a validated action to a series, or accepts one,
+    /// reserving its first game for the challenger to open. Returns the event that
+    /// emits.
+    async fn apply_series_action(
+        &self,
+        signer: ids::short::Id,
+        action: &SeriesAction,
+    ) -> io::Result<Vec<BlockEvent>> {
+        // Read before taking `series`, as accepting a move holds `move_history` while
+        // it advances a series
+        let from = self.first_unplayed_game().await;
+        let mut all = self.series.write().await;
+        let event = match action {
+            SeriesAction::Challenge {
+                opponent, games, ..
+            } => {
+                let best_of = series::check_best_of(*games)?;
+                let players = [signer, opponent.clone()];
+                let event = BlockEvent::SeriesChallenged {
+                    series: all.len(),
+                    players: players.clone(),
+                    best_of,
+                };
+                all.push(Series::new(players, best_of));
+                event
+            }
+            SeriesAction::Accept { series, .. } => {
+                let accepted = all.get_mut(*series).ok_or_else(|| {
+                    Error::new(ErrorKind::NotFound, format!("series {series} not found"))
+                })?;
+                let players = accepted.next_seats();
+                let game = reserve_game(&mut *self.matches.write().await, from, players.clone());
+                accepted.games.push(game);
+                BlockEvent::SeriesGameReserved {
+                    series: *series,
+                    game,
+                    players,
+                }
+            }
+        };
+        Ok(vec![event])
+    }
+
+    /// Challenges the partner and opponents of a validated action to a team game, or
+    /// accepts one, reserving its game once everyone invited accepted. Returns the
+    /// events that emits.
+    async fn apply_team_action(
+        &self,
+        signer: ids::short::Id,
+        action: &TeamAction,
+    ) -> io::Result<Vec<BlockEvent>> {
+        // Read before taking `team_games`, as accepting a move holds `move_history`
+        // while it settles a team game
+        let from = self.first_unplayed_game().await;
+        let mut team_games = self.team_games.write().await;
+        let mut events = Vec::new();
+        match action {
+            TeamAction::Challenge {
+                partner, opponents, ..
+            } => {
+                let teams = [[signer, partner.clone()], opponents.clone()];
+                events.push(BlockEvent::TeamChallenged {
+                    team_game: team_games.len(),
+                    teams: teams.clone(),
+                });
+                team_games.push(TeamGame::new(teams));
+            }
+            TeamAction::Accept { team_game, .. } => {
+                let accepted = team_games.get_mut(*team_game).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("team game {team_game} not found"),
+                    )
+                })?;
+                let invitee = accepted.invitee(&signer).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("team game {team_game} didn't invite {signer}"),
+                    )
+                })?;
+                accepted.accepted[invitee] = true;
+                events.push(BlockEvent::TeamAccepted {
+                    team_game: *team_game,
+                    address: signer,
+                });
+                if accepted.is_ready() {
+                    let mut matches = self.matches.write().await;
+                    let game = reserve_game(&mut matches, from, accepted.openers());
+                    accepted.game = Some(game);
+                    events.push(BlockEvent::TeamGameReserved {
+                        team_game: *team_game,
+                        game,
+                    });
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Creates a tournament for the entrant of a validated action, or registers it
+    /// in one, seeding its players and reserving the first round's games once it's
+    /// full. Returns the events that emits.
+    async fn apply_tournament_action(
+        &self,
+        entrant: ids::short::Id,
+        action: &TournamentAction,
+    ) -> io::Result<Vec<BlockEvent>> {
+        // Read before taking `tournaments`, as accepting a move holds `move_history`
+        // and `ratings` while it advances a tournament
+        let from = self.first_unplayed_game().await;
+        let ratings = self.ratings.read().await;
+        let mut tournaments = self.tournaments.write().await;
+        let mut events = Vec::new();
+        match action {
+            TournamentAction::Create {
+                players, rounds, ..
+            } => {
+                let (format, capacity) = tournament::check_capacity(*players, *rounds)?;
+                events.push(BlockEvent::TournamentCreated {
+                    tournament: tournaments.len(),
+                    creator: entrant.clone(),
+                    players: capacity,
+                    format,
+                });
+                tournaments.push(Tournament::new(entrant, format, capacity));
+            }
+            TournamentAction::Register { tournament, .. } => {
+                let registered = tournaments.get_mut(*tournament).ok_or_else(|| {
+                    Error::new(ErrorKind::NotFound, format!("tournament {tournament} not found"))
+                })?;
+                registered.players.push(entrant.clone());
+                events.push(BlockEvent::TournamentRegistered {
+                    tournament: *tournament,
+                    address: entrant,
+                });
+                if registered.is_full() {
+                    let pairs = registered.seed(|address| current_rating(ratings.get(address)));
+                    let mut matches = self.matches.write().await;
+                    let round: Vec<_> = pairs
+                        .into_iter()
+                        .map(|players| (players.clone(), reserve_game(&mut matches, from, players)))
+                        .collect();
+                    drop(matches);
+                    let games = round.iter().map(|(_, game)| *game).collect();
+                    registered.start_round(round);
+                    events.push(BlockEvent::TournamentRoundStarted {
+                        tournament: *tournament,
+                        round: 0,
+                        games,
+                    });
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Pairs the seeker of a validated seek with a similarly rated waiting seeker
+    /// (see [`rating::match_window`]) in the next open game, or else queues it as of
+    /// the given height. Returns the event that emits.
+    async fn apply_seek(&self, seeker: ids::short::Id, height: u64) -> Vec<BlockEvent> {
+        let mut seekers = self.seekers.write().await;
+        let opponent = {
+            let ratings = self.ratings.read().await;
+            let rating = current_rating(ratings.get(&seeker));
+            pick_opponent(&seekers, &ratings, rating, height)
+        };
+        let event = if let Some(waited) = opponent.and_then(|queued| seekers.remove(&queued)) {
+            // The seeker that waited opens the game
+            let players = [waited.address, seeker];
+            let game = self.next_match_game().await;
+            self.matches.write().await.insert(game, players.clone());
+            BlockEvent::GameMatched { game, players }
+        } else {
+            let queued = Seeker {
+                address: seeker.clone(),
+                height,
+            };
+            seekers.insert(height, queued);
+            BlockEvent::SeekQueued { address: seeker }
+        };
+        vec![event]
+    }
+
+    /// Returns the game the next pair of seekers is matched into: the current one if
+    /// no move was played nor seat reserved in it yet, else the one after the last
+    /// game played or reserved.
+    async fn next_match_game(&self) -> usize {
+        let from = self.first_unplayed_game().await;
+        let matches = self.matches.read().await;
+        next_open_game(&matches, from)
+    }
+
+    /// Returns the current game if no move was played in it yet, else the next one.
+    async fn first_unplayed_game(&self) -> usize {
+        let curr = self.get_curr_game_index().await;
+        let started = self
+            .move_history
+            .read()
+            .await
+            .last()
+            .map_or(false, |moves| !moves.is_empty());
+        if started {
+            curr + 1
+        } else {
+            curr
+        }
+    }
+
+    /// Takes the nonce an account action carries, which must be its signer's next,
+    /// so a signed action is applied at most once.
+    /// # Errors
+    /// Fails if the nonce isn't the signer's next.
+    async fn take_nonce(
+        &self,
+        signer: &ids::short::Id,
+        nonce: u64,
+        action: &str,
+    ) -> io::Result<()> {
+        let mut nonces = self.nonces.write().await;
+        let next = nonces.get_or_insert_default(signer);
+        if nonce != *next {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{action} nonce {nonce} of {signer} isn't {next}"),
+            ));
+        }
+        *next += 1;
+        Ok(())
+    }
+
+    /// Moves the tokens of a transfer from its sender. Returns the event that emits.
+    async fn apply_transfer(
+        &self,
+        sender: ids::short::Id,
+        transfer: &Transfer,
+    ) -> io::Result<Vec<BlockEvent>> {
+        let unbalanced = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("transfer of {} from {sender} is unbalanced", transfer.amount),
+            )
+        };
+
+        let mut balances = self.balances.write().await;
+        let balance = balances.get(&sender).copied().unwrap_or_default();
+        if transfer.to == sender {
+            // Leaves the balance as is, once checked to cover the amount
+            balance.checked_sub(transfer.amount).ok_or_else(unbalanced)?;
+        } else {
+            let sent = balance.checked_sub(transfer.amount);
+            let received = balances
+                .get(&transfer.to)
+                .copied()
+                .unwrap_or_default()
+                .checked_add(transfer.amount);
+            if let (Some(sent), Some(received)) = (sent, received) {
+                balances.insert(sender.clone(), sent);
+                balances.insert(transfer.to.clone(), received);
+            } else {
+                return Err(unbalanced());
+            }
+        }
+        Ok(vec![BlockEvent::Transferred {
+            from: sender,
+            to: transfer.to.clone(),
+            amount: transfer.amount,
+        }])
+    }
+
+    /// Checks that a move can be played next in the current game: the cell must be
+    /// on the board and empty, it must be the player's turn, once a seat is held
+    /// by an address only that address may move for it, unless the teammates of a
+    /// team game take turns making its moves, and the signer must be able to pay the
+    /// move fee and, when taking a seat, the game stake. The fee is the one the
+    /// block carries, within the maximum its signer agreed to, see
+    /// [`State::check_fee`]. The move must also carry enough proof of work, see
+    /// [`State::check_work`].
+    /// # Errors
+    /// Fails with the reason the move is illegal.
+    pub async fn validate_move(
+        &self,
+        action: u8,
+        signature: &[u8],
+        fee: Option<u64>,
+        max_fee: Option<u64>,
+        work: Option<u64>,
+    ) -> io::Result<()> {
+        let result = self
+            .check_move_rules(action, signature, fee, max_fee, work)
+            .await;
+        if result.is_err() {
+            self.metrics.invalid_moves.inc();
+        }
+        result
+    }
+
+    /// Checks the move against the rules, see [`State::validate_move`].
+    async fn check_move_rules(
+        &self,
+        action: u8,
+        signature: &[u8],
+        fee: Option<u64>,
+        max_fee: Option<u64>,
+        work: Option<u64>,
+    ) -> io::Result<()> {
+        // Get the current game
+        let curr_game = self.get_curr_game().await;
+        let game_index = self.get_curr_game_index().await;
+        self.check_work(game_index, action, signature, work)?;
+
+        let next_player = self.get_next_player().await;
+        game::check_move(curr_game, next_player, action)?;
+
+        let (player_id, _) = game::decode_action(action);
+        let signer = block::recover_signer(game_index, action, max_fee, signature)?;
+        if let Some(team_game) = self.get_playing_team_game(game_index).await {
+            // Teammates take turns making their side's moves
+            let made = self.get_moves_made(player_id).await;
+            let mover = team_game.mover(player_id, made);
+            if signer.as_ref() != Some(mover) {
+                return Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!("next move of player {player_id} in game {game_index} is {mover}'s"),
+                ));
+            }
+        } else {
+            // A game reserved by matchmaking, a tournament or a series is only played
+            // by the addresses it's reserved for
+            if let Some(players) = self.get_match(game_index).await {
+                let reserved = players.get(usize::from(player_id).saturating_sub(1));
+                if signer.as_ref() != reserved {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!(
+                            "player {player_id} seat of game {game_index} is matched to \
+                             another address"
+                        ),
+                    ));
+                }
+            }
+
+            // Once a seat is taken by an address, only that address may move for it
+            if let Some(seat) = self.get_seat(player_id).await {
+                if signer.as_ref() != Some(&seat) {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("player {player_id} seat is held by {seat}"),
+                    ));
+                }
+            }
+        }
+
+        let fee = self.check_fee(fee, max_fee)?;
+        self.check_move_cost(player_id, signer.as_ref(), fee).await
+    }
+
+    /// Checks a move of the given game carries a proof of work with as many leading
+    /// zero bits as the genesis requires, ground over the move's signature.
+    /// # Errors
+    /// Fails if work is required but the move is unsigned, carries none, or too
+    /// little.
+    pub fn check_work(
+        &self,
+        game: usize,
+        action: u8,
+        signature: &[u8],
+        work: Option<u64>,
+    ) -> io::Result<()> {
+        if self.move_work == 0 {
+            return Ok(());
+        }
+        if signature.is_empty() {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "moves must be signed to carry a proof of work",
+            ));
+        }
+        let nonce = work.ok_or_else(|| {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                format!("moves must carry a proof of work of {} bits", self.move_work),
+            )
+        })?;
+        let bits = block::work_bits(game, action, signature, nonce);
+        if bits < u32::from(self.move_work) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("proof of work of {bits} bits is below the {} required", self.move_work),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the fee of a move proposed while `pending` blocks wait in the mempool:
+    /// the base fee, doubled every [`FEE_DOUBLING_DEPTH`] pending blocks up to the
+    /// cap set in the genesis.
+    #[must_use]
+    pub fn congestion_fee(&self, pending: usize) -> u64 {
+        let doublings = u32::try_from(pending / FEE_DOUBLING_DEPTH).unwrap_or(u32::MAX);
+        let fee = self.move_fee.saturating_mul(2_u64.saturating_pow(doublings));
+        fee.min(self.max_move_fee.max(self.move_fee))
+    }
+
+    /// Returns the fee a move block pays: the one its proposer set, or the base fee
+    /// if it carries none. Its signer agreed to pay at most `max_fee`, or only the
+    /// base fee if it signed no maximum.
+    /// # Errors
+    /// Fails if the fee is below the base fee, above the cap or above what the
+    /// signer agreed to pay.
+    pub fn check_fee(&self, fee: Option<u64>, max_fee: Option<u64>) -> io::Result<u64> {
+        let max = self.max_move_fee.max(self.move_fee);
+        let fee = match fee {
+            None => self.move_fee,
+            Some(fee) if fee < self.move_fee || fee > max => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("move fee {fee} isn't within {}..={max}", self.move_fee),
+                ))
+            }
+            Some(fee) => fee,
+        };
+        let agreed = max_fee.unwrap_or(self.move_fee);
+        if fee > agreed {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("move fee {fee} is above the {agreed} its signer agreed to pay"),
+            ));
+        }
+        Ok(fee)
+    }
+
+    /// Checks the signer of a move for the given player can pay what it costs: the
+    /// fee, plus the game stake if the move takes the player's seat.
+    /// # Errors
+    /// Fails if the move costs tokens but is unsigned, so no one can pay, or the
+    /// signer's balance can't cover the cost.
+    pub async fn check_move_cost(
+        &self,
+        player: u8,
+        signer: Option<&ids::short::Id>,
+        fee: u64,
+    ) -> io::Result<()> {
+        self.balance_after_cost(player, signer, fee).await.map(|_| ())
+    }
+
+    /// Takes the fee the block carries, or the base fee, from the balance of the
+    /// move's signer, and escrows the game stake if the move takes the player's seat.
+    /// The treasury's share of the fee is credited to it, the rest is burned.
+    async fn charge_move(
+        &self,
+        player: u8,
+        signer: Option<&ids::short::Id>,
+        fee: Option<u64>,
+        max_fee: Option<u64>,
+    ) -> io::Result<()> {
+        let fee = self.check_fee(fee, max_fee)?;
+        if let Some((signer, balance)) = self.balance_after_cost(player, signer, fee).await? {
+            let mut balances = self.balances.write().await;
+            balances.insert(signer, balance);
+            if let Some(treasury) = &self.treasury {
+                // Fees were taken from balances, so no balance can overflow
+                let credited = balances.get_or_insert_default(&treasury.address);
+                *credited = credited.saturating_add(treasury.cut(fee));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the signer of a move for the given player along with their balance
+    /// once its cost is paid, or `None` if the move is free.
+    async fn balance_after_cost(
+        &self,
+        player: u8,
+        signer: Option<&ids::short::Id>,
+        fee: u64,
+    ) -> io::Result<Option<(ids::short::Id, u64)>> {
+        let stake = if self.get_seat(player).await.is_none() {
+            self.game_stake
+        } else {
+            0
+        };
+        let cost = fee.saturating_add(stake);
+        if cost == 0 {
+            return Ok(None);
+        }
+        let signer = signer.ok_or_else(|| {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                format!("moves must be signed to pay their cost of {cost}"),
+            )
+        })?;
+
+        let balance = self.get_balance(signer).await;
+        let paid = balance.checked_sub(cost).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("balance {balance} of {signer} can't cover the move's cost of {cost}"),
+            )
+        })?;
+        Ok(Some((signer.clone(), paid)))
+    }
+
+    /// Notifies subscribers that a move was accepted.
+    pub fn publish_accepted(&self, accepted: AcceptedMove) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.accepted.send(accepted);
+    }
+
+    /// Returns a receiver of every move accepted from now on.
+    #[must_use]
+    pub fn subscribe_accepted(&self) -> broadcast::Receiver<AcceptedMove> {
+        self.accepted.subscribe()
+    }
+
+    /// Waits until a move of the given game above `after_height` is accepted, or the
+    /// timeout elapses. Returns the earliest such move, or `None` on timeout.
+    /// # Errors
+    /// Fails if the game does not exist.
+    pub async fn wait_for_move(
+        &self,
+        game: usize,
+        after_height: u64,
+        timeout: Duration,
+    ) -> io::Result<Option<MoveRecord>> {
+        // Subscribe before looking at the history so no move accepted in between is missed
+        let mut accepted = self.subscribe_accepted();
+        if let Some(record) = self.first_move_after(game, after_height).await? {
+            return Ok(Some(record));
+        }
+
+        let wait = async {
+            loop {
+                match accepted.recv().await {
+                    Ok(accepted) if accepted.game == game => return Ok(Some(accepted.record)),
+                    Ok(_) => {}
+                    // Moves were skipped, the history has them all
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(record) = self.first_move_after(game, after_height).await? {
+                            return Ok(Some(record));
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(None),
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Ok(None))
+    }
+
+    /// Returns the earliest move of the given game above `after_height`, if any
+    async fn first_move_after(
+        &self,
+        game: usize,
+        after_height: u64,
+    ) -> io::Result<Option<MoveRecord>> {
+        let moves = self.get_move_history(game).await?;
+        Ok(moves.into_iter().find(|record| record.height > after_height))
+    }
+
+    /// Returns the events emitted by the given accepted block
+    /// # Errors
+    /// Fails if the block is unknown or its history was pruned.
+    pub async fn get_block_events(&self, blk_id: &ids::Id) -> io::Result<Vec<BlockEvent>> {
+        let events = self.block_events.read().await.get(blk_id).cloned();
+        match events {
+            Some(events) => Ok(events),
+            None => Err(self.missing_history(blk_id).await),
+        }
+    }
+
+    /// Returns the ordered moves of the given game
+    /// # Errors
+    /// Fails if the game doesn't exist yet.
+    pub async fn get_move_history(&self, game: usize) -> io::Result<Vec<MoveRecord>> {
+        let move_history = self.move_history.read().await;
+        match move_history.get(game) {
+            Some(moves) => Ok(moves.clone()),
+            None => Err(GameError::UnknownGame(game).into()),
+        }
+    }
+
+    /// Returns the ordered moves of the given game along with its status, read
+    /// together so a finished game's moves always end with its final move
+    /// # Errors
+    /// Fails if the game doesn't exist yet.
+    pub async fn get_game_record(&self, game: usize) -> io::Result<(Vec<MoveRecord>, GameStatus)> {
+        // same lock order as `update_board`
+        let winners = self.winners.read().await;
+        let move_history = self.move_history.read().await;
+
+        let moves = match move_history.get(game) {
+            Some(moves) => moves.clone(),
+            None => return Err(GameError::UnknownGame(game).into()),
+        };
+        let status = match winners.get(game) {
+            Some(result) => GameStatus::from(*result),
+            None => GameStatus::InProgress {
+                next_player: next_player_of(&move_history),
+            },
+        };
+        Ok((moves, status))
+    }
+
+    /// Returns up to `limit` moves accepted above `after_height`, in order
+    pub async fn get_accepted_after(&self, after_height: u64, limit: usize) -> Vec<AcceptedMove> {
+        let board_snapshots = self.board_snapshots.read().await;
+        let winners = self.winners.read().await;
+        let move_history = self.move_history.read().await;
+
+        let mut accepted = Vec::new();
+        for (game, moves) in move_history.iter().enumerate() {
+            // Games are played in order, so skip those that ended before the height
+            if moves.last().map_or(true, |last| last.height <= after_height) {
+                continue;
+            }
+
+            for record in moves.iter().filter(|record| record.height > after_height) {
+                if accepted.len() == limit {
+                    return accepted;
+                }
+
+                let status = match winners.get(game) {
+                    Some(result) if result.height == record.height => GameStatus::from(*result),
+                    _ => GameStatus::InProgress {
+                        next_player: game::next_player(Some(record.player)),
+                    },
+                };
+                accepted.push(AcceptedMove {
+                    game,
+                    record: record.clone(),
+                    board: board_snapshots.get(&record.block_id).copied().unwrap_or_default(),
+                    status,
+                });
+            }
+        }
+
+        accepted
+    }
+
+    /// Replaces the pending moves stored in the database
+    /// # Errors
+    /// Fails if the moves can't be serialized or written.
+    pub async fn write_pending_moves(&self, moves: &[PersistedMove]) -> io::Result<()> {
+        let d = serde_json::to_vec(moves).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize pending moves to JSON bytes: {e}"),
+            )
+        })?;
+
+        let mut db = self.db.write().await;
+        db.put(PENDING_MOVES_KEY, &d).await
+    }
+
+    /// Returns the pending moves stored in the database, if any
+    /// # Errors
+    /// Fails if the moves can't be read or deserialized.
+    pub async fn read_pending_moves(&self) -> io::Result<Vec<PersistedMove>> {
+        let db = self.db.read().await;
+        let d = match db.get(PENDING_MOVES_KEY).await {
+            Ok(d) => d,
+            Err(e) if errors::is_not_found(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        serde_json::from_slice(&d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to deserialize pending moves from JSON: {e}"),
+            )
+        })
+    }
+
+    /// Closes the database, after which it can't be read or written.
+    /// # Errors
+    /// Fails if the database is already closed.
+    pub async fn close(&self) -> io::Result<()> {
+        let db = self.db.read().await;
+        db.close().await
+    }
+
+    /// Persists an accepted block, then indexes it by height
+    /// # Errors
+    /// Fails if the block can't be written to the database.
+    pub async fn write_block(&self, block: &Block) -> io::Result<()> {
+        let blk_id = block.id();
+        let blk_status = BlockWithStatus {
+            block_bytes: block.bytes().to_vec(),
+            status: block.status(),
+        };
+
+        let mut value = blk_status.encode()?;
+        if self.compress_blocks {
+            value = zstd::bulk::compress(&value, BLOCK_COMPRESSION_LEVEL)?;
+        }
+
+        // Written as a single batch so a crash never leaves the block half persisted
+        let db = self.db.read().await;
+        let timer = self.metrics.db_write_duration.start_timer();
+        let mut batch = db.new_batch().await?;
+        batch.put(&block_with_status_key(&blk_id), &value).await?;
+        batch.put(&height_key(block.height()), &blk_id.to_vec()).await?;
+        batch.put(LAST_ACCEPTED_BLOCK_KEY, &blk_id.to_vec()).await?;
+        batch.write().await?;
+        timer.observe_duration();
+        drop(db);
+
+        self.index_block(block).await;
+
+        let mut last_accepted_at = self.last_accepted_at.write().await;
+        *last_accepted_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Caches an accepted block and indexes it by height
+    async fn index_block(&self, block: &Block) {
+        let blk_id = block.id();
+
+        let mut block_cache = self.block_cache.shard(&blk_id).write().await;
+        block_cache.put(blk_id, block.clone());
+        drop(block_cache);
+
+        let mut height_index = self.height_index.write().await;
+        height_index.insert(block.height(), blk_id);
+        let mut last_accepted_height = self.last_accepted_height.write().await;
+        *last_accepted_height = block.height().max(*last_accepted_height);
+        drop(last_accepted_height);
+
+        // Only the history of the most recent blocks is kept, if configured
+        let pruned = self
+            .history_blocks
+            .and_then(|keep| block.height().checked_sub(keep.max(1)))
+            .and_then(|height| height_index.get(&height).copied());
+        drop(height_index);
+        if let Some(pruned) = pruned {
+            self.board_snapshots.write().await.remove(&pruned);
+            self.block_events.write().await.remove(&pruned);
+        }
+    }
+
+    /// Returns the Id of the last accepted block stored in the database
+    /// # Errors
+    /// Fails if no block was ever accepted or the database can't be read.
+    pub async fn get_last_accepted_block_id(&self) -> io::Result<ids::Id> {
+        let db = self.db.read().await;
+        let d = db.get(LAST_ACCEPTED_BLOCK_KEY).await?;
+        Ok(ids::Id::from_slice(&d))
+    }
+
+    /// Returns the accepted block with the given Id stored in the database
+    async fn read_block(&self, blk_id: &ids::Id) -> io::Result<Block> {
+        let db = self.db.read().await;
+        let timer = self.metrics.db_read_duration.start_timer();
+        let mut d = db.get(&block_with_status_key(blk_id)).await?;
+        timer.observe_duration();
+        drop(db);
+
+        // Blocks may have been stored compressed or not, whatever the current config
+        if d.starts_with(&ZSTD_MAGIC) {
+            d = zstd::stream::decode_all(d.as_slice())?;
+        }
+        let blk_status = BlockWithStatus::from_slice(d)?;
+        let mut block = Block::from_slice(&blk_status.block_bytes)?;
+        block.set_status(blk_status.status);
+        block.set_state(self.clone());
+        Ok(block)
+    }
+
+    /// Rebuilds the in-memory state by replaying the accepted blocks stored in the
+    /// database through the game rules, in height order.
+    /// Blocks are read and decoded ahead in parallel, chunk by chunk, while earlier
+    /// ones are applied.
+    /// Returns the last accepted block Id, or `None` if no block was ever accepted.
+    /// # Errors
+    /// Fails if a block is missing or doesn't follow the rules.
+    pub async fn replay(&self) -> io::Result<Option<ids::Id>> {
+        let last_accepted = match self.get_last_accepted_block_id().await {
+            Ok(blk_id) => blk_id,
+            Err(e) if errors::is_not_found(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let last_height = self.read_block(&last_accepted).await?.height();
+        log::info!("replaying {} accepted blocks", last_height + 1);
+
+        let (decoded, mut chunks) = mpsc::channel(REPLAY_CHUNKS_AHEAD);
+        tokio::spawn(self.clone().decode_chunks(last_height, decoded));
+
+        let mut parent_id = ids::Id::empty();
+        while let Some(chunk) = chunks.recv().await {
+            for block in chunk? {
+                let height = block.height();
+                self.replay_block(&block, &parent_id).await?;
+                parent_id = block.id();
+
+                if height % REPLAY_LOG_INTERVAL == 0 && height > 0 {
+                    log::info!("replayed {height}/{last_height} blocks");
+                }
+            }
+        }
+
+        // The decoder stops early only on errors, which were returned above
+        if parent_id != last_accepted {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("replay stopped before reaching block {last_accepted}"),
+            ));
+        }
+        log::info!("replayed chain up to block {last_accepted} at height {last_height}");
+        Ok(Some(last_accepted))
+    }
+
+    /// Applies an accepted block through the game rules, once checked to extend
+    /// `parent_id`, which is empty for the genesis block.
+    /// # Errors
+    /// Fails if the block doesn't extend `parent_id` or doesn't follow the rules.
+    pub async fn replay_block(&self, block: &Block, parent_id: &ids::Id) -> io::Result<()> {
+        let blk_id = block.id();
+        if block.parent_id() != *parent_id {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("replayed block {blk_id} doesn't extend block {parent_id}"),
+            ));
+        }
+        if block.height() > 0 {
+            self.enforce_reservation_deadline(block).await;
+            let res = if let Some(action) = block.account_action() {
+                self.validate_account_action(&action, block.signature())
+                    .await
+                    .map(|_| ())
+            } else {
+                let (action, signature) = (block.get_player_move(), block.signature());
+                let (fee, max_fee) = (block.fee(), block.max_fee());
+                self.validate_move(action, signature, fee, max_fee, block.work())
+                    .await
+            };
+            res.map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("replayed block {blk_id} breaks the rules: {e}"),
+                )
+            })?;
+        }
+        self.update_board(block).await?;
+        self.index_block(block).await;
+        Ok(())
+    }
+
+    /// Reads and decodes the accepted blocks up to `last_height`, sending them in
+    /// height order one chunk at a time. Stops at the first chunk that fails.
+    async fn decode_chunks(self, last_height: u64, decoded: mpsc::Sender<io::Result<Vec<Block>>>) {
+        let mut start = 0;
+        while start <= last_height {
+            let end = last_height.min(start + REPLAY_CHUNK_SIZE - 1);
+            let chunk = self.decode_chunk(start, end).await;
+            let failed = chunk.is_err();
+            if decoded.send(chunk).await.is_err() || failed {
+                return;
+            }
+            start = end + 1;
+        }
+    }
+
+    /// Reads and decodes the accepted blocks from `start` to `end` (inclusive) in parallel.
+    async fn decode_chunk(&self, start: u64, end: u64) -> io::Result<Vec<Block>> {
+        let tasks: Vec<_> = (start..=end)
+            .map(|height| {
+                let state = self.clone();
+                tokio::spawn(async move { state.read_block_at_height(height).await })
+            })
+            .collect();
+
+        let mut blocks = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let block = task.await.map_err(|e| {
+                Error::new(ErrorKind::Other, format!("failed to decode replayed block: {e}"))
+            })??;
+            blocks.push(block);
+        }
+        Ok(blocks)
+    }
+
+    /// Returns the accepted block stored at the given height, after checking it was
+    /// stored under its own Id and height.
+    async fn read_block_at_height(&self, height: u64) -> io::Result<Block> {
+        let db = self.db.read().await;
+        let blk_id = ids::Id::from_slice(&db.get(&height_key(height)).await?);
+        drop(db);
+
+        let block = self.read_block(&blk_id).await?;
+        if block.id() != blk_id || block.height() != height {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "block {} at height {} is stored as block {blk_id} at height {height}",
+                    block.id(),
+                    block.height()
+                ),
+            ));
+        }
+        Ok(block)
+    }
+
+    /// Returns the time since this node last accepted a block, if it has.
+    pub async fn get_last_accepted_age(&self) -> Option<Duration> {
+        let last_accepted_at = self.last_accepted_at.read().await;
+        last_accepted_at.map(|at| at.elapsed())
+    }
+
+    // Adds a block to "verified blocks", along with the state it reaches
+    pub async fn add_verified(&mut self, block: Block, reached: State) {
+        let blk_id = block.id();
+        let mut verified_blocks = self.verified_blocks.shard(&blk_id).write().await;
+        verified_blocks.insert(blk_id, block);
+        drop(verified_blocks);
+        let mut verified_states = self.verified_states.shard(&blk_id).write().await;
+        verified_states.insert(blk_id, reached);
+    }
+
+    /// Removes a block from "`verified_blocks`", along with the state it reaches.
+    pub async fn remove_verified(&mut self, blk_id: &ids::Id) {
+        let mut verified_blocks = self.verified_blocks.shard(blk_id).write().await;
+        verified_blocks.remove(blk_id);
+        drop(verified_blocks);
+        let mut verified_states = self.verified_states.shard(blk_id).write().await;
+        verified_states.remove(blk_id);
+    }
+
+    /// Returns the game the move of the given verified block was played in, or
+    /// `None` if it isn't a verified move block.
+    pub async fn get_verified_game(&self, blk_id: &ids::Id) -> Option<usize> {
+        let verified_states = self.verified_states.shard(blk_id).read().await;
+        let move_history = verified_states.get(blk_id)?.move_history.read().await;
+        // The game the move ended is the one before the last
+        let last = move_history.len() - 1;
+        [last, last.saturating_sub(1)].into_iter().find(|game| {
+            move_history
+                .get(*game)
+                .map_or(false, |moves| moves.iter().any(|m| m.block_id == *blk_id))
+        })
+    }
+
+    /// Returns a copy of the state reached by the given block, which a child of it
+    /// is verified and applied against: the state of a verified block, or this one
+    /// if it's the last accepted block.
+    /// # Errors
+    /// Fails if the block is neither verified nor the last accepted block.
+    pub async fn state_after(&self, blk_id: &ids::Id) -> io::Result<State> {
+        let verified_states = self.verified_states.shard(blk_id).read().await;
+        if let Some(reached) = verified_states.get(blk_id) {
+            return Ok(reached.fork().await);
+        }
+        drop(verified_states);
+
+        let height = self.get_height().await;
+        if self.height_index.read().await.get(&height) == Some(blk_id) {
+            return Ok(self.fork().await);
+        }
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("block {blk_id} is neither verified nor the last accepted block"),
+        ))
+    }
+
+    /// Returns a copy of this state that blocks can be applied to without changing
+    /// it. The copy shares everything with this state, and only copies what blocks
+    /// applied to it change, see [`CowMap`].
+    async fn fork(&self) -> State {
+        State {
+            curr_game: Arc::new(RwLock::new(*self.curr_game.read().await)),
+            winners: Arc::new(RwLock::new(self.winners.read().await.clone())),
+            latest: Arc::new(ArcSwap::new(self.latest.load_full())),
+            verified_states: Arc::new(Sharded::new(DEFAULT_SHARDS, HashMap::new)),
+            board_snapshots: Arc::new(RwLock::new(self.board_snapshots.read().await.clone())),
+            move_history: Arc::new(RwLock::new(self.move_history.read().await.clone())),
+            player_stats: Arc::new(RwLock::new(self.player_stats.read().await.clone())),
+            account_games: Arc::new(RwLock::new(self.account_games.read().await.clone())),
+            ratings: Arc::new(RwLock::new(self.ratings.read().await.clone())),
+            leaderboard: Arc::new(RwLock::new(self.leaderboard.read().await.clone())),
+            balances: Arc::new(RwLock::new(self.balances.read().await.clone())),
+            nonces: Arc::new(RwLock::new(self.nonces.read().await.clone())),
+            seekers: Arc::new(RwLock::new(self.seekers.read().await.clone())),
+            matches: Arc::new(RwLock::new(self.matches.read().await.clone())),
+            tournaments: Arc::new(RwLock::new(self.tournaments.read().await.clone())),
+            series: Arc::new(RwLock::new(self.series.read().await.clone())),
+            team_games: Arc::new(RwLock::new(self.team_games.read().await.clone())),
+            awaited_since: Arc::new(RwLock::new(*self.awaited_since.read().await)),
+            block_events: Arc::new(RwLock::new(self.block_events.read().await.clone())),
+            accepted: broadcast::channel(1).0,
+            last_accepted_at: Arc::new(RwLock::new(None)),
+            ..self.clone()
+        }
+    }
+
+    /// Returns "true" if the block Id has been already verified.
+    pub async fn has_verified(&self, blk_id: &ids::Id) -> bool {
+        let verified_blocks = self.verified_blocks.shard(blk_id).read().await;
+        verified_blocks.contains_key(blk_id)
+    }
+
+    /// Returns every verified block not yet accepted or rejected, by height.
+    pub async fn get_verified_blocks(&self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        for shard in self.verified_blocks.shards() {
+            blocks.extend(shard.read().await.values().cloned());
+        }
+        blocks.sort_by_key(Block::height);
+        blocks
+    }
+
+    /// Replays the accepted blocks stored in the database into a fresh state, and
+    /// compares it to this one. Replays the whole chain, so is only meant for debugging.
+    /// # Errors
+    /// Fails if the database can't be replayed.
+    pub async fn check_consistency(&self) -> io::Result<ConsistencyReport> {
+        let replayed = State {
+            db: Arc::clone(&self.db),
+            history_blocks: self.history_blocks,
+            move_fee: self.move_fee,
+            max_move_fee: self.max_move_fee,
+            game_stake: self.game_stake,
+            treasury: self.treasury.clone(),
+            move_work: self.move_work,
+            ..State::default()
+        };
+        replayed.seed_balances(&self.allocations.read().await).await?;
+        let last_accepted = replayed.replay().await?;
+
+        let mut mismatches = Vec::new();
+        let (height, in_memory_height) = (replayed.get_height().await, self.get_height().await);
+        if height != in_memory_height {
+            mismatches.push(format!(
+                "height is {in_memory_height} in memory and {height} in the database"
+            ));
+        }
+        if let Some(last_accepted) = last_accepted {
+            let in_memory = self.height_index.read().await.get(&height).copied();
+            if in_memory != Some(last_accepted) {
+                mismatches.push(format!(
+                    "last accepted block is {last_accepted} in the database and {in_memory:?} \
+                     in memory"
+                ));
+            }
+        }
+        let (board, replayed_board) = (self.get_curr_game().await, replayed.get_curr_game().await);
+        if board != replayed_board {
+            mismatches.push(format!("board is {board} in memory and {replayed_board} replayed"));
+        }
+        let (game, replayed_game) =
+            (self.get_curr_game_index().await, replayed.get_curr_game_index().await);
+        if game != replayed_game {
+            mismatches.push(format!("game is {game} in memory and {replayed_game} replayed"));
+        }
+        if *self.winners.read().await != *replayed.winners.read().await {
+            mismatches.push(String::from("game results differ from the replayed ones"));
+        }
+        if *self.move_history.read().await != *replayed.move_history.read().await {
+            mismatches.push(String::from("move history differs from the replayed one"));
+        }
+        if *self.balances.read().await != *replayed.balances.read().await {
+            mismatches.push(String::from("balances differ from the replayed ones"));
+        }
+        if *self.matches.read().await != *replayed.matches.read().await {
+            mismatches.push(String::from("matched games differ from the replayed ones"));
+        }
+        if *self.ratings.read().await != *replayed.ratings.read().await {
+            mismatches.push(String::from("ratings differ from the replayed ones"));
+        }
+        if *self.leaderboard.read().await != *replayed.leaderboard.read().await {
+            mismatches.push(String::from("leaderboard differs from the replayed one"));
+        }
+        if *self.tournaments.read().await != *replayed.tournaments.read().await {
+            mismatches.push(String::from("tournaments differ from the replayed ones"));
+        }
+        if *self.series.read().await != *replayed.series.read().await {
+            mismatches.push(String::from("series differ from the replayed ones"));
+        }
+        if *self.team_games.read().await != *replayed.team_games.read().await {
+            mismatches.push(String::from("team games differ from the replayed ones"));
+        }
+        if self.latest.load().board != board {
+            mismatches.push(String::from("published board differs from the current one"));
+        }
+
+        Ok(ConsistencyReport {
+            consistent: mismatches.is_empty(),
+            height,
+            mismatches,
+        })
+    }
+
+    /// Collects the accepted blocks, the board snapshots kept and the game results
+    /// into a single archive. Blocks accepted meanwhile are left out.
+    /// # Errors
+    /// Fails if an accepted block can't be read from the database.
+    pub async fn export_archive(&self) -> io::Result<StateArchive> {
+        let height = self.get_height().await;
+        let mut blocks = Vec::new();
+        for height in 0..=height {
+            // Read past the cache, so exporting doesn't evict the blocks in use
+            let block = self.read_block_at_height(height).await?;
+            blocks.push(ArchivedBlock {
+                id: block.id(),
+                block,
+            });
+        }
+
+        let board_snapshots = self.board_snapshots.read().await;
+        let snapshots = blocks
+            .iter()
+            .filter_map(|archived| {
+                board_snapshots.get(&archived.id).map(|board| BoardSnapshot {
+                    block_id: archived.id,
+                    board: *board,
+                })
+            })
+            .collect();
+        drop(board_snapshots);
+
+        let winners = self
+            .winners
+            .read()
+            .await
+            .iter()
+            .filter(|result| result.height <= height)
+            .copied()
+            .collect();
+        Ok(StateArchive {
+            version: ARCHIVE_VERSION,
+            blocks,
+            snapshots,
+            winners,
+        })
+    }
+    /// Swaps in the board after a move, adding the result if the move ended the game.
+    fn publish_latest(&self, board: u32, result: Option<GameResult>) {
+        let mut winners = self.latest.load().winners.clone();
+        if let Some(result) = result {
+            winners.push(result);
+        }
+        self.latest.store(Arc::new(LatestGame { board, winners }));
+    }
+
+    /// Settles a finished game for the addresses that played it: records its result,
+    /// pays out the stakes of those seated, rates them, ranks them anew and advances
+    /// the tournament, series or team game it was reserved for, adding the events
+    /// that emits. Both teammates of a team game are credited with its result.
+    async fn settle_game(
+        &self,
+        game: usize,
+        moves: &[MoveRecord],
+        winner: Option<u8>,
+        events: &mut Vec<BlockEvent>,
+    ) {
+        let teams = self.get_playing_team_game(game).await.map(|t| t.teams);
+        let sides = sides_of(moves, teams.as_ref());
+        let mut player_stats = self.player_stats.write().await;
+        record_player_stats(&mut player_stats, &sides, winner);
+        let mut balances = self.balances.write().await;
+        pay_out_stakes(&mut balances, moves, winner, self.game_stake);
+        let mut ratings = self.ratings.write().await;
+        match &teams {
+            Some(teams) => record_team_ratings(&mut ratings, game, teams, winner),
+            None => record_ratings(&mut ratings, game, moves, winner),
+        }
+
+        let mut leaderboard = self.leaderboard.write().await;
+        for address in sides.iter().flatten() {
+            let rating = ratings.get(address).map(|changes| current_rating(Some(changes)));
+            let wins = player_stats.get(address).map_or(0, |stats| stats.wins);
+            leaderboard.update(address, rating, wins);
+        }
+        drop(leaderboard);
+        self.advance_tournament(game, winner, events).await;
+        self.advance_series(game, winner, events).await;
+        self.finish_team_game(game).await;
+    }
+
+    /// Returns whether a game is reserved for a tournament match, a series or a team
+    /// game, whose results must be settled for them to go on.
+    async fn is_competition_game(&self, game: usize) -> bool {
+        let tournaments = self.tournaments.read().await;
+        let series = self.series.read().await;
+        tournaments.iter().any(|t| t.find_match(game).is_some())
+            || series.iter().any(|s| s.is_playing(game))
+            || self.get_playing_team_game(game).await.is_some()
+    }
+
+    /// Ends the reserved current game once [`RESERVATION_DEADLINE`] blocks passed
+    /// since the block at [`State::awaited_since`] without a move, before the given
+    /// block is checked and applied. The seat to move forfeits the game, which is
+    /// settled as won by the other seat, so the tournament, series or team game it
+    /// was reserved for goes on. A game matchmaking reserved that no move was played
+    /// in instead lapses, opening it to anyone. The events that emits are added to
+    /// the block's. Enforcing it again for the same block does nothing.
+    pub async fn enforce_reservation_deadline(&self, block: &Block) {
+        let height = block.height();
+        let game = self.get_curr_game_index().await;
+        let reserved = self.get_match(game).await;
+        let mut awaited_since = self.awaited_since.write().await;
+        if reserved.is_none() {
+            *awaited_since = height;
+            return;
+        }
+        if height.saturating_sub(*awaited_since) < RESERVATION_DEADLINE {
+            return;
+        }
+        *awaited_since = height;
+        drop(awaited_since);
+
+        let mut events = Vec::new();
+        let competition = self.is_competition_game(game).await;
+        let mut curr_board = self.curr_game.write().await;
+        let mut winners = self.winners.write().await;
+        let mut move_history = self.move_history.write().await;
+        let moves = move_history.last().cloned().unwrap_or_default();
+        if moves.is_empty() && !competition {
+            if let Some(players) = self.matches.write().await.remove(&game) {
+                events.push(BlockEvent::ReservationLapsed { game, players });
+            }
+        } else {
+            let absent = next_player_of(&move_history);
+            let winner = game::next_player(Some(absent));
+            let result = GameResult {
+                winner: Some(winner),
+                height,
+            };
+            winners.push(result);
+            events.push(BlockEvent::GameForfeited {
+                game,
+                player: absent,
+            });
+            events.push(BlockEvent::GameWon {
+                game,
+                player: winner,
+            });
+            self.settle_game(game, &moves, Some(winner), &mut events).await;
+            move_history.push(Vec::new());
+            *curr_board = 0;
+            self.publish_latest(*curr_board, Some(result));
+        }
+        let mut block_events = self.block_events.write().await;
+        block_events.get_or_insert_default(&block.id()).extend(events);
+    }
+
+    /// Marks the team game a finished game was reserved for as finished, if any.
+    async fn finish_team_game(&self, game: usize) {
+        let mut team_games = self.team_games.write().await;
+        let playing = team_games.iter().position(|t| t.is_playing(game));
+        if let Some(team_game) = playing.and_then(|index| team_games.get_mut(index)) {
+            team_game.finished = true;
+        }
+    }
+
+    /// Advances the series a finished game was reserved for, if any: its score is
+    /// updated, and unless that decides it, the next game is reserved with the seats
+    /// swapped.
+    async fn advance_series(&self, game: usize, winner: Option<u8>, events: &mut Vec<BlockEvent>) {
+        let mut all = self.series.write().await;
+        let found = all.iter().position(|s| s.is_playing(game));
+        let mut matches = self.matches.write().await;
+        let seats = matches.get(&game).cloned();
+        if let (Some(id), Some(seats)) = (found, seats) {
+            let s = &mut all[id];
+            match s.finish_game(&seats, winner) {
+                SeriesAdvance::NextGame(players) => {
+                    // Games are reserved from the one after the finished game on
+                    let next = reserve_game(&mut matches, game + 1, players.clone());
+                    s.games.push(next);
+                    events.push(BlockEvent::SeriesGameReserved {
+                        series: id,
+                        game: next,
+                        players,
+                    });
+                }
+                SeriesAdvance::Finished(winner) => {
+                    events.push(BlockEvent::SeriesFinished {
+                        series: id,
+                        winner,
+                        score: s.score,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Advances the tournament a finished game was reserved for, if any: a drawn
+    /// bracket match is replayed in the next open game with the seats swapped, and
+    /// once its round is decided the next one is paired from its results.
+    async fn advance_tournament(
+        &self,
+        game: usize,
+        winner: Option<u8>,
+        events: &mut Vec<BlockEvent>,
+    ) {
+        let mut tournaments = self.tournaments.write().await;
+        let found = tournaments
+            .iter()
+            .enumerate()
+            .find_map(|(id, t)| t.find_match(game).map(|index| (id, index)));
+        let mut matches = self.matches.write().await;
+        let seats = matches.get(&game).cloned();
+        if let (Some((id, index)), Some(seats)) = (found, seats) {
+            let t = &mut tournaments[id];
+            // Games are reserved from the one after the finished game on
+            match t.finish_game(index, seats, winner) {
+                Advance::Replay(players) => {
+                    let replay = reserve_game(&mut matches, game + 1, players.clone());
+                    t.replay(index, replay);
+                    events.push(BlockEvent::GameMatched {
+                        game: replay,
+                        players,
+                    });
+                }
+                Advance::NextRound(pairs) => {
+                    let round: Vec<_> = pairs
+                        .into_iter()
+                        .map(|players| {
+                            (players.clone(), reserve_game(&mut matches, game + 1, players))
+                        })
+                        .collect();
+                    let games = round.iter().map(|(_, game)| *game).collect();
+                    t.start_round(round);
+                    events.push(BlockEvent::TournamentRoundStarted {
+                        tournament: id,
+                        round: t.rounds.len() - 1,
+                        games,
+                    });
+                }
+                Advance::Finished(champion) => {
+                    events.push(BlockEvent::TournamentFinished {
+                        tournament: id,
+                        champion,
+                    });
+                }
+                Advance::Pending => {}
+            }
+        }
+    }
+
+    /// Updates game board/resets game board if no win is possible (i.e. checks
+    /// all possible combinations)
+    /// Returns the move the block carried, or `None` for the genesis block,
+    /// transfers, seeks, tournament, series and team actions, which don't play a move
+    /// # Errors
+    /// Fails if the move or action can't be applied, or its signer can't pay for it.
+    pub async fn update_board(&self, block: &Block) -> io::Result<Option<AcceptedMove>> {
+        self.enforce_reservation_deadline(block).await;
+        if let Some(action) = block.account_action() {
+            self.apply_account_action(block, &action).await?;
+            return Ok(None);
+        }
+
+        // Resolve who signed the move before taking any lock
+        let address = if block.height() == 0 {
+            None
+        } else {
+            let address = block.signer(self.get_curr_game_index().await)?;
+            let (fee, max_fee) = (block.fee(), block.max_fee());
+            self.charge_move(block.get_player_id(), address.as_ref(), fee, max_fee)
+                .await?;
+            self.record_seat(block.get_player_id(), address.as_ref()).await;
+            address
+        };
+
+        // First update game board
+        let mut curr_board = self.curr_game.write().await;
+
+        // The genesis block carries no move
+        if block.height() == 0 {
+            let mut board_snapshots = self.board_snapshots.write().await;
+            board_snapshots.insert(block.id(), *curr_board);
+            let mut block_events = self.block_events.write().await;
+            block_events.insert(block.id(), Vec::new());
+            return Ok(None);
+        }
+
+        // Bitmasking to get board index player wants to modify
+        let intended_position = block.get_move_index();
+        // Bitmasking to get id of player (1 or 2)
+        let player_id = block.get_player_id();
+
+        // Board is now updated!
+        *curr_board = game::place(*curr_board, player_id, intended_position);
+        let board = *curr_board;
+
+        // Snapshot the board with this move applied, before any reset below
+        let mut board_snapshots = self.board_snapshots.write().await;
+        board_snapshots.insert(block.id(), *curr_board);
+
+        // Now check if someone won, or if the board is completely full with no possible winner
+        let result = if let Some(winner) = game::winner(*curr_board) {
+            Some(GameResult {
+                winner: Some(winner),
+                height: block.height(),
+            })
+        } else if game::is_full(*curr_board) {
+            Some(GameResult {
+                winner: None,
+                height: block.height(),
+            })
+        } else {
+            None
+        };
+
+        // Reset the state of the game
+        if result.is_some() {
+            *curr_board = 0;
+        }
+
+        // Add result to winner vec
+        if let Some(result) = result {
+            self.winners.write().await.push(result);
+        }
+
+        // Record the move against the current game, then open the next one if it ended
+        let record = MoveRecord {
+            player: block.get_player_id(),
+            cell: intended_position,
+            block_id: block.id(),
+            height: block.height(),
+            address,
+        };
+        let mut move_history = self.move_history.write().await;
+        let game = move_history.len() - 1;
+        let mut events = Vec::new();
+        if let Some(moves) = move_history.last_mut() {
+            moves.push(record.clone());
+            if moves.len() == 1 {
+                events.push(BlockEvent::GameStarted { game });
+            }
+        }
+        events.push(BlockEvent::MovePlayed {
+            game,
+            player: player_id,
+            cell: intended_position,
+        });
+        match result {
+            Some(GameResult {
+                winner: Some(player),
+                ..
+            }) => events.push(BlockEvent::GameWon { game, player }),
+            Some(GameResult { winner: None, .. }) => events.push(BlockEvent::GameDrawn { game }),
+            None => {}
+        }
+        if let Some(result) = result {
+            if let Some(moves) = move_history.last() {
+                self.settle_game(game, moves, result.winner, &mut events).await;
+            }
+            move_history.push(Vec::new());
+        }
+        self.block_events.write().await.get_or_insert_default(&block.id()).extend(events);
+
+        self.publish_latest(*curr_board, result);
+        *self.awaited_since.write().await = block.height();
+
+        let status = match result {
+            Some(result) => GameStatus::from(result),
+            None => GameStatus::InProgress {
+                next_player: game::next_player(Some(player_id)),
+            },
+        };
+
+        Ok(Some(AcceptedMove {
+            game,
+            record,
+            board,
+            status,
+        }))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use avalanche_types::{choices::status::Status, ids};
+
+    use super::{BlockEvent, State, RESERVATION_DEADLINE};
+    use crate::{block::Block, game};
+
+    fn address(n: u8) -> ids::short::Id {
+        ids::short::Id::from_slice(&[n])
+    }
+
+    /// Builds an unsigned block at the given height playing a cell for a player.
+    fn move_block(height: u64, player: u8, cell: u8) -> Block {
+        let action = game::encode_action(player, cell);
+        Block::try_new(ids::Id::empty(), height, action, Vec::new(), Status::Processing)
+            .expect("failed to build block")
+    }
+
+    /// Builds a block on top of `parent` playing a cell for a player, checked and
+    /// applied against `state`.
+    fn child_block(state: &State, parent: &Block, player: u8, cell: u8) -> Block {
+        let action = game::encode_action(player, cell);
+        let height = parent.height() + 1;
+        let mut block = Block::try_new(parent.id(), height, action, Vec::new(), Status::Processing)
+            .expect("failed to build block");
+        block.set_state(state.clone());
+        block
+    }
+
+    #[tokio::test]
+    async fn child_verifies_against_its_processing_parent() {
+        let state = State::default();
+        let mut genesis = move_block(0, 1, 0);
+        genesis.set_state(state.clone());
+        genesis.accept().await.expect("failed to accept genesis");
+
+        let mut parent = child_block(&state, &genesis, 1, 0);
+        parent.verify().await.expect("failed to verify parent");
+
+        // The parent's move isn't accepted yet, but children are checked against it
+        let mut taken = child_block(&state, &parent, 2, 0);
+        assert!(taken.verify().await.is_err());
+        let mut child = child_block(&state, &parent, 2, 4);
+        child.verify().await.expect("failed to verify child");
+        let reached = state.state_after(&child.id()).await.unwrap();
+        assert_eq!(reached.get_move_history(0).await.unwrap().len(), 2);
+        assert!(state.get_move_history(0).await.unwrap().is_empty());
+        assert_eq!(state.get_curr_game().await, 0);
+
+        parent.accept().await.expect("failed to accept parent");
+        child.accept().await.expect("failed to accept child");
+        assert_eq!(state.get_curr_game().await, reached.get_curr_game().await);
+        assert_eq!(
+            state.get_move_history(0).await.unwrap(),
+            reached.get_move_history(0).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn unplayed_reservation_lapses_past_the_deadline() {
+        let state = State::default();
+        let players = [address(1), address(2)];
+        state.matches.write().await.insert(0, players.clone());
+
+        let early = move_block(RESERVATION_DEADLINE - 1, 1, 0);
+        state.enforce_reservation_deadline(&early).await;
+        assert_eq!(state.get_match(0).await, Some(players.clone()));
+
+        // The first block past the deadline, of any kind, lapses it before being applied
+        let late = move_block(RESERVATION_DEADLINE, 1, 4);
+        state.update_board(&late).await.expect("failed to apply move");
+        assert_eq!(state.get_match(0).await, None);
+        let events = state.get_block_events(&late.id()).await.unwrap();
+        assert_eq!(events[0], BlockEvent::ReservationLapsed { game: 0, players });
+        assert!(events.contains(&BlockEvent::MovePlayed {
+            game: 0,
+            player: 1,
+            cell: 4
+        }));
+    }
+
+    #[tokio::test]
+    async fn absent_seat_forfeits_past_the_deadline() {
+        let state = State::default();
+        state.matches.write().await.insert(0, [address(1), address(2)]);
+        let opening = move_block(1, 1, 0);
+        state.update_board(&opening).await.expect("failed to apply move");
+
+        // Seat 2 never answers, so the first block past the deadline forfeits it
+        let late = move_block(1 + RESERVATION_DEADLINE, 1, 4);
+        state.update_board(&late).await.expect("failed to apply move");
+        let events = state.get_block_events(&late.id()).await.unwrap();
+        assert_eq!(
+            events[..2],
+            [
+                BlockEvent::GameForfeited { game: 0, player: 2 },
+                BlockEvent::GameWon { game: 0, player: 1 },
+            ]
+        );
+        assert_eq!(state.get_winner(0).await.and_then(|r| r.winner), Some(1));
+        assert!(events.contains(&BlockEvent::MovePlayed {
+            game: 1,
+            player: 1,
+            cell: 4
+        }));
+    }
 }
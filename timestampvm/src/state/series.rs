@@ -0,0 +1,232 @@
+//! Best-of-N match series. An address challenges another to a series of games,
+//! and once the opponent accepts, a game is reserved for them at a time, the first
+//! move alternating between them, until one of them won more than half the games
+//! or all of them were played.
+
+use std::{
+    cmp::Ordering,
+    io::{self, Error, ErrorKind},
+};
+
+use avalanche_types::ids;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Most games a series may be played over.
+pub const MAX_SERIES_GAMES: u32 = 9;
+
+/// Where a series stands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SeriesStatus {
+    /// Waiting for the opponent to accept
+    Challenged,
+    /// Its games are being played
+    InProgress,
+    /// Decided, or all of its games were played
+    Finished,
+}
+
+/// Series of games between two addresses, the challenger moving first in the first
+/// game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Series {
+    /// Challenger and opponent, in order
+    #[schemars(with = "[String; 2]")]
+    pub players: [ids::short::Id; 2],
+    /// Number of games the series is played over, an odd number
+    pub best_of: usize,
+    /// Games reserved for the series, in the order they're played
+    pub games: Vec<usize>,
+    /// Games won by the challenger and the opponent, in order
+    pub score: [usize; 2],
+    /// Games drawn
+    pub draws: usize,
+    /// Whether the series was decided or all of its games were played
+    pub finished: bool,
+    /// Address that won the series, unset while it's played or if it was drawn
+    #[schemars(with = "Option<String>")]
+    pub winner: Option<ids::short::Id>,
+}
+
+/// What a finished series game leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeriesAdvance {
+    /// The next game is played by these seats
+    NextGame([ids::short::Id; 2]),
+    /// The series was decided, for this address unless it was drawn
+    Finished(Option<ids::short::Id>),
+}
+
+impl Series {
+    #[must_use]
+    pub fn new(players: [ids::short::Id; 2], best_of: usize) -> Self {
+        Self {
+            players,
+            best_of,
+            games: Vec::with_capacity(best_of),
+            score: [0, 0],
+            draws: 0,
+            finished: false,
+            winner: None,
+        }
+    }
+
+    #[must_use]
+    pub fn status(&self) -> SeriesStatus {
+        if self.finished {
+            SeriesStatus::Finished
+        } else if self.games.is_empty() {
+            SeriesStatus::Challenged
+        } else {
+            SeriesStatus::InProgress
+        }
+    }
+
+    /// Returns the seats of the series' next game: the challenger opens the first
+    /// one, and the players swap seats every game after it.
+    #[must_use]
+    pub fn next_seats(&self) -> [ids::short::Id; 2] {
+        let [challenger, opponent] = self.players.clone();
+        if self.games.len() % 2 == 0 {
+            [challenger, opponent]
+        } else {
+            [opponent, challenger]
+        }
+    }
+
+    /// Returns whether the game is the one the undecided series is being played in.
+    #[must_use]
+    pub fn is_playing(&self, game: usize) -> bool {
+        !self.finished && self.games.last() == Some(&game)
+    }
+
+    /// Records the result of the series' current game, given who held seats 1 and 2
+    /// in it, and returns what it leads to.
+    pub fn finish_game(
+        &mut self,
+        seats: &[ids::short::Id; 2],
+        winner: Option<u8>,
+    ) -> SeriesAdvance {
+        let won_by = match winner {
+            Some(1) => Some(&seats[0]),
+            Some(_) => Some(&seats[1]),
+            None => None,
+        };
+        match won_by.and_then(|address| self.players.iter().position(|p| p == address)) {
+            Some(index) => self.score[index] += 1,
+            None => self.draws += 1,
+        }
+
+        let needed = self.best_of / 2 + 1;
+        let played = self.score[0] + self.score[1] + self.draws;
+        if self.score[0] < needed && self.score[1] < needed && played < self.best_of {
+            return SeriesAdvance::NextGame(self.next_seats());
+        }
+        self.finished = true;
+        self.winner = match self.score[0].cmp(&self.score[1]) {
+            Ordering::Greater => Some(self.players[0].clone()),
+            Ordering::Less => Some(self.players[1].clone()),
+            Ordering::Equal => None,
+        };
+        SeriesAdvance::Finished(self.winner.clone())
+    }
+}
+
+/// Checks a series may be created over the given number of games.
+/// # Errors
+/// Fails unless it's an odd number from 1 to [`MAX_SERIES_GAMES`].
+pub fn check_best_of(games: u32) -> io::Result<usize> {
+    if !(1..=MAX_SERIES_GAMES).contains(&games) || games % 2 == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "series are played over an odd number of games from 1 to \
+                 {MAX_SERIES_GAMES}, not {games}"
+            ),
+        ));
+    }
+    usize::try_from(games).map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use avalanche_types::ids;
+
+    use super::{check_best_of, Series, SeriesAdvance, SeriesStatus};
+
+    fn address(n: u8) -> ids::short::Id {
+        ids::short::Id::from_slice(&[n])
+    }
+
+    /// Plays the series' next game, won by the given address or drawn, and returns
+    /// what it leads to.
+    fn play(series: &mut Series, game: usize, won_by: Option<&ids::short::Id>) -> SeriesAdvance {
+        let seats = series.next_seats();
+        series.games.push(game);
+        let winner = won_by.map(|address| if *address == seats[0] { 1 } else { 2 });
+        series.finish_game(&seats, winner)
+    }
+
+    #[test]
+    fn seats_swap_every_game() {
+        let (challenger, opponent) = (address(1), address(2));
+        let mut series = Series::new([challenger.clone(), opponent.clone()], 5);
+        assert_eq!(series.status(), SeriesStatus::Challenged);
+        assert_eq!(series.next_seats(), [challenger.clone(), opponent.clone()]);
+
+        assert_eq!(
+            play(&mut series, 0, None),
+            SeriesAdvance::NextGame([opponent.clone(), challenger.clone()])
+        );
+        assert_eq!(series.status(), SeriesStatus::InProgress);
+        assert!(series.is_playing(0));
+        assert_eq!(
+            play(&mut series, 1, Some(&challenger)),
+            SeriesAdvance::NextGame([challenger, opponent])
+        );
+    }
+
+    #[test]
+    fn series_ends_once_a_player_won_most_games() {
+        let (challenger, opponent) = (address(1), address(2));
+        let mut series = Series::new([challenger.clone(), opponent.clone()], 5);
+        for (game, won_by) in [&opponent, &challenger, &opponent].into_iter().enumerate() {
+            assert!(matches!(
+                play(&mut series, game, Some(won_by)),
+                SeriesAdvance::NextGame(_)
+            ));
+        }
+        assert_eq!(
+            play(&mut series, 3, Some(&opponent)),
+            SeriesAdvance::Finished(Some(opponent.clone()))
+        );
+        assert_eq!(series.score, [1, 3]);
+        assert_eq!(series.winner, Some(opponent));
+        assert_eq!(series.status(), SeriesStatus::Finished);
+        assert!(!series.is_playing(3));
+    }
+
+    #[test]
+    fn series_level_after_all_games_is_drawn() {
+        let (challenger, opponent) = (address(1), address(2));
+        let mut series = Series::new([challenger.clone(), opponent.clone()], 3);
+        play(&mut series, 0, Some(&challenger));
+        play(&mut series, 1, None);
+        assert_eq!(
+            play(&mut series, 2, Some(&opponent)),
+            SeriesAdvance::Finished(None)
+        );
+        assert_eq!((series.score, series.draws), ([1, 1], 1));
+    }
+
+    #[test]
+    fn best_of_is_odd_and_capped() {
+        for games in [1, 3, 9] {
+            assert_eq!(check_best_of(games).unwrap(), games as usize);
+        }
+        for games in [0, 2, 11] {
+            assert!(check_best_of(games).is_err(), "best of {games}");
+        }
+    }
+}
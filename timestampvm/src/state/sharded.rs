@@ -0,0 +1,167 @@
+//! Splits a collection across independently locked shards, so the engine verifying
+//! several blocks at once and queries reading other blocks don't all wait on a
+//! single lock.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+};
+
+use tokio::sync::RwLock;
+
+/// Number of shards collections are split into, unless specified otherwise.
+pub const DEFAULT_SHARDS: usize = 16;
+
+/// Collection split into shards, each behind its own lock. Keys are assigned
+/// to a shard by hash, so all operations on a key go through the same shard.
+#[derive(Debug)]
+pub struct Sharded<T> {
+    shards: Vec<RwLock<T>>,
+    hasher: RandomState,
+}
+
+impl<T> Sharded<T> {
+    /// Creates `count` shards (at least one), each built by `new_shard`.
+    #[must_use]
+    pub fn new(count: usize, mut new_shard: impl FnMut() -> T) -> Self {
+        Self {
+            shards: (0..count.max(1)).map(|_| RwLock::new(new_shard())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns the shard holding the given key.
+    pub fn shard<K: Hash + ?Sized>(&self, key: &K) -> &RwLock<T> {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        // Truncating the hash on 32-bit targets still spreads keys evenly
+        #[allow(clippy::cast_possible_truncation)]
+        let index = hasher.finish() as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    /// Returns every shard, e.g. to visit all entries.
+    #[must_use]
+    pub fn shards(&self) -> &[RwLock<T>] {
+        &self.shards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use tokio::sync::RwLock;
+
+    use super::{Sharded, DEFAULT_SHARDS};
+
+    const TASKS: u64 = 8;
+    const KEYS_PER_TASK: u64 = 20_000;
+
+    /// Inserts, reads back and removes every other key from `TASKS` concurrent tasks,
+    /// the way blocks go through `verified_blocks`.
+    async fn stress<M>(map: Arc<M>) -> Arc<M>
+    where
+        M: VerifiedMap + Send + Sync + 'static,
+    {
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|task| {
+                let map = Arc::clone(&map);
+                tokio::spawn(async move {
+                    for key in task * KEYS_PER_TASK..(task + 1) * KEYS_PER_TASK {
+                        map.insert(key).await;
+                        assert!(map.contains(key).await);
+                        if key % 2 == 0 {
+                            map.remove(key).await;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        map
+    }
+
+    #[tonic::async_trait]
+    trait VerifiedMap {
+        async fn insert(&self, key: u64);
+        async fn contains(&self, key: u64) -> bool;
+        async fn remove(&self, key: u64);
+        async fn len(&self) -> usize;
+    }
+
+    #[tonic::async_trait]
+    impl VerifiedMap for RwLock<HashMap<u64, u64>> {
+        async fn insert(&self, key: u64) {
+            self.write().await.insert(key, key);
+        }
+        async fn contains(&self, key: u64) -> bool {
+            self.read().await.contains_key(&key)
+        }
+        async fn remove(&self, key: u64) {
+            self.write().await.remove(&key);
+        }
+        async fn len(&self) -> usize {
+            self.read().await.len()
+        }
+    }
+
+    #[tonic::async_trait]
+    impl VerifiedMap for Sharded<HashMap<u64, u64>> {
+        async fn insert(&self, key: u64) {
+            self.shard(&key).write().await.insert(key, key);
+        }
+        async fn contains(&self, key: u64) -> bool {
+            self.shard(&key).read().await.contains_key(&key)
+        }
+        async fn remove(&self, key: u64) {
+            self.shard(&key).write().await.remove(&key);
+        }
+        async fn len(&self) -> usize {
+            let mut len = 0;
+            for shard in self.shards() {
+                len += shard.read().await.len();
+            }
+            len
+        }
+    }
+
+    /// Returns the time `TASKS` concurrent tasks take to stress the map.
+    async fn time_stress<M>(map: M) -> Duration
+    where
+        M: VerifiedMap + Send + Sync + 'static,
+    {
+        let started = Instant::now();
+        stress(Arc::new(map)).await;
+        started.elapsed()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn concurrent_verification_stress() {
+        let single = stress(Arc::new(RwLock::new(HashMap::new()))).await;
+        let sharded = stress(Arc::new(Sharded::new(DEFAULT_SHARDS, HashMap::new))).await;
+
+        let expected = usize::try_from(TASKS * KEYS_PER_TASK / 2).unwrap();
+        assert_eq!(single.len().await, expected);
+        assert_eq!(sharded.len().await, expected);
+    }
+
+    /// Passes if the sharded map gets through the stress faster than a single lock.
+    /// Timing depends on the machine, so run it with `--ignored` on an idle one.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "measures throughput"]
+    async fn sharded_map_outpaces_single_lock() {
+        let single = time_stress(RwLock::new(HashMap::new())).await;
+        let sharded = time_stress(Sharded::new(DEFAULT_SHARDS, HashMap::new)).await;
+        assert!(
+            sharded < single,
+            "{DEFAULT_SHARDS} shards took {sharded:?}, a single lock {single:?}"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! 2v2 team games. An address challenges two others to a game with a partner, and
+//! once the partner and both opponents accepted, a game is reserved for the two
+//! teams. Teammates take turns making their side's moves, the challenger and the
+//! first opponent opening for theirs, and the result counts for both of them.
+
+use std::io::{self, Error, ErrorKind};
+
+use avalanche_types::ids;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Where a team game stands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TeamGameStatus {
+    /// Waiting for the partner or the opponents to accept
+    Challenged,
+    /// Its game is reserved or being played
+    InProgress,
+    /// Its game was won or drawn
+    Finished,
+}
+
+/// Game between two teams of two addresses, the challenger's team holding seat 1.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct TeamGame {
+    /// Teams holding seats 1 and 2, each listing its teammates in the order they
+    /// move: the challenger and its partner, then the two opponents
+    #[schemars(with = "[[String; 2]; 2]")]
+    pub teams: [[ids::short::Id; 2]; 2],
+    /// Whether the partner, the first and the second opponent accepted, in order
+    pub accepted: [bool; 3],
+    /// Game reserved for the teams once everyone accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<usize>,
+    /// Whether its game was won or drawn
+    pub finished: bool,
+}
+
+impl TeamGame {
+    #[must_use]
+    pub fn new(teams: [[ids::short::Id; 2]; 2]) -> Self {
+        Self {
+            teams,
+            accepted: [false; 3],
+            game: None,
+            finished: false,
+        }
+    }
+
+    #[must_use]
+    pub fn status(&self) -> TeamGameStatus {
+        if self.finished {
+            TeamGameStatus::Finished
+        } else if self.game.is_none() {
+            TeamGameStatus::Challenged
+        } else {
+            TeamGameStatus::InProgress
+        }
+    }
+
+    /// Returns the index into [`TeamGame::accepted`] of the given address, unless
+    /// it's the challenger or wasn't invited.
+    #[must_use]
+    pub fn invitee(&self, address: &ids::short::Id) -> Option<usize> {
+        [&self.teams[0][1], &self.teams[1][0], &self.teams[1][1]]
+            .iter()
+            .position(|invited| *invited == address)
+    }
+
+    /// Returns whether the partner and both opponents accepted.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.accepted.iter().all(|accepted| *accepted)
+    }
+
+    /// Returns the addresses opening each side, which hold seats 1 and 2 of the
+    /// reserved game.
+    #[must_use]
+    pub fn openers(&self) -> [ids::short::Id; 2] {
+        [self.teams[0][0].clone(), self.teams[1][0].clone()]
+    }
+
+    /// Returns the teammate making the next move for the given player (1 or 2),
+    /// once the side made `made` moves.
+    #[must_use]
+    pub fn mover(&self, player: u8, made: usize) -> &ids::short::Id {
+        let side = usize::from(player).saturating_sub(1).min(1);
+        &self.teams[side][made % 2]
+    }
+
+    /// Returns whether the game is the one the team game is being played in.
+    #[must_use]
+    pub fn is_playing(&self, game: usize) -> bool {
+        !self.finished && self.game == Some(game)
+    }
+}
+
+/// Checks a team game may be challenged by the given addresses.
+/// # Errors
+/// Fails unless the challenger, its partner and both opponents are distinct.
+pub fn check_teams(teams: &[[ids::short::Id; 2]; 2]) -> io::Result<()> {
+    let players: Vec<_> = teams.iter().flatten().collect();
+    for (i, player) in players.iter().enumerate() {
+        if players[..i].contains(player) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{player} can't play twice in a team game"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use avalanche_types::ids;
+
+    use super::{check_teams, TeamGame, TeamGameStatus};
+
+    fn address(n: u8) -> ids::short::Id {
+        ids::short::Id::from_slice(&[n])
+    }
+
+    /// Returns a team game challenged by 1 with 2 as its partner against 3 and 4.
+    fn challenged() -> TeamGame {
+        TeamGame::new([[address(1), address(2)], [address(3), address(4)]])
+    }
+
+    #[test]
+    fn game_is_ready_once_everyone_invited_accepted() {
+        let mut team_game = challenged();
+        assert_eq!(team_game.invitee(&address(1)), None);
+        assert_eq!(team_game.invitee(&address(5)), None);
+        for (invitee, address) in [address(2), address(3), address(4)].iter().enumerate() {
+            assert!(!team_game.is_ready());
+            assert_eq!(team_game.invitee(address), Some(invitee));
+            team_game.accepted[invitee] = true;
+        }
+        assert!(team_game.is_ready());
+        assert_eq!(team_game.status(), TeamGameStatus::Challenged);
+
+        team_game.game = Some(7);
+        assert_eq!(team_game.status(), TeamGameStatus::InProgress);
+        assert!(team_game.is_playing(7));
+        team_game.finished = true;
+        assert_eq!(team_game.status(), TeamGameStatus::Finished);
+        assert!(!team_game.is_playing(7));
+    }
+
+    #[test]
+    fn teammates_take_turns_moving() {
+        let team_game = challenged();
+        assert_eq!(team_game.openers(), [address(1), address(3)]);
+        let movers: Vec<_> = (0..4)
+            .map(|made| team_game.mover(1, made).clone())
+            .collect();
+        assert_eq!(movers, [address(1), address(2), address(1), address(2)]);
+        let movers: Vec<_> = (0..4)
+            .map(|made| team_game.mover(2, made).clone())
+            .collect();
+        assert_eq!(movers, [address(3), address(4), address(3), address(4)]);
+    }
+
+    #[test]
+    fn players_are_distinct() {
+        assert!(check_teams(&challenged().teams).is_ok());
+        assert!(check_teams(&[[address(1), address(2)], [address(3), address(1)]]).is_err());
+        assert!(check_teams(&[[address(1), address(1)], [address(3), address(4)]]).is_err());
+    }
+}
@@ -0,0 +1,523 @@
+//! Tournaments, played as a single-elimination bracket or over Swiss rounds.
+//! Addresses register until a tournament is full, then its players are seeded by
+//! rating and each round's games are reserved once the previous round is decided.
+//!
+//! In a bracket, a drawn game is replayed with the seats swapped until one of the
+//! players wins it. In Swiss rounds, a draw stands and scores a point for each
+//! player (a win scores two), and each round pairs players with the closest
+//! standings who haven't met yet, the leader after the last round winning.
+
+use std::{
+    cmp::Reverse,
+    io::{self, Error, ErrorKind},
+};
+
+use avalanche_types::ids;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Most players a tournament may be created for.
+pub const MAX_TOURNAMENT_PLAYERS: u32 = 64;
+
+/// Where a tournament stands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TournamentStatus {
+    /// Waiting for players to register
+    Registering,
+    /// Its rounds are being played
+    InProgress,
+    /// Its final or last round was decided
+    Finished,
+}
+
+/// How a tournament's rounds are paired.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TournamentFormat {
+    /// Single-elimination bracket, the winners of a round meeting in the next
+    #[default]
+    Elimination,
+    /// Given number of rounds, each pairing players by their standings
+    Swiss { rounds: usize },
+}
+
+/// Match of a tournament round between two players.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct BracketMatch {
+    /// Players of the match, the higher seed first
+    #[schemars(with = "[String; 2]")]
+    pub players: [ids::short::Id; 2],
+    /// Games reserved for the match, all drawn but the last
+    pub games: Vec<usize>,
+    #[schemars(with = "Option<String>")]
+    pub winner: Option<ids::short::Id>,
+    /// Whether the match was drawn, which only stands in Swiss rounds
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub drawn: bool,
+}
+
+impl BracketMatch {
+    /// Returns whether the match was won or drawn.
+    #[must_use]
+    pub fn is_decided(&self) -> bool {
+        self.winner.is_some() || self.drawn
+    }
+}
+
+/// Results of a tournament's player in the matches decided so far.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Standing {
+    #[schemars(with = "String")]
+    pub address: ids::short::Id,
+    /// Two for each match won and one for each drawn
+    pub points: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// Tournament created by an address.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Tournament {
+    #[schemars(with = "String")]
+    pub creator: ids::short::Id,
+    pub format: TournamentFormat,
+    /// Number of players the tournament is seeded with, a power of two for a
+    /// bracket and an even number for Swiss rounds
+    pub capacity: usize,
+    /// Registered addresses, in the order they registered
+    #[schemars(with = "Vec<String>")]
+    pub players: Vec<ids::short::Id>,
+    /// Registered addresses by seed, the top seed first, once the tournament is full
+    #[schemars(with = "Vec<String>")]
+    pub seeds: Vec<ids::short::Id>,
+    /// Rounds scheduled so far, the last being played until the tournament finishes
+    pub rounds: Vec<Vec<BracketMatch>>,
+    #[schemars(with = "Option<String>")]
+    pub champion: Option<ids::short::Id>,
+}
+
+/// What a decided tournament game leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Advance {
+    /// The game was drawn, so the match is replayed by these seats
+    Replay([ids::short::Id; 2]),
+    /// The round is decided, so the next one pairs these players
+    NextRound(Vec<[ids::short::Id; 2]>),
+    /// The tournament was won by this address
+    Finished(ids::short::Id),
+    /// Other matches of the round are still being played
+    Pending,
+}
+
+impl Tournament {
+    #[must_use]
+    pub fn new(creator: ids::short::Id, format: TournamentFormat, capacity: usize) -> Self {
+        Self {
+            creator,
+            format,
+            capacity,
+            players: Vec::with_capacity(capacity),
+            seeds: Vec::new(),
+            rounds: Vec::new(),
+            champion: None,
+        }
+    }
+
+    #[must_use]
+    pub fn status(&self) -> TournamentStatus {
+        if self.champion.is_some() {
+            TournamentStatus::Finished
+        } else if self.rounds.is_empty() {
+            TournamentStatus::Registering
+        } else {
+            TournamentStatus::InProgress
+        }
+    }
+
+    /// Returns whether as many players registered as the tournament is seeded with.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.capacity
+    }
+
+    /// Seeds the registered players by rating, and those rated alike by the order
+    /// they registered in, then returns the first round's pairings. A bracket keeps
+    /// the top seeds apart until the last rounds, and a Swiss round pairs the top
+    /// half of the seeds with the bottom half.
+    pub fn seed(&mut self, rating: impl Fn(&ids::short::Id) -> u32) -> Vec<[ids::short::Id; 2]> {
+        let mut seeds = self.players.clone();
+        seeds.sort_by_key(|address| Reverse(rating(address)));
+        self.seeds = seeds;
+        match self.format {
+            TournamentFormat::Elimination => bracket_order(self.seeds.len())
+                .chunks(2)
+                .map(|pair| [self.seeds[pair[0]].clone(), self.seeds[pair[1]].clone()])
+                .collect(),
+            TournamentFormat::Swiss { .. } => {
+                let (top, bottom) = self.seeds.split_at(self.seeds.len() / 2);
+                top.iter()
+                    .zip(bottom)
+                    .map(|(x, o)| [x.clone(), o.clone()])
+                    .collect()
+            }
+        }
+    }
+
+    /// Schedules the next round, each match with the game reserved for it.
+    pub fn start_round(&mut self, matches: Vec<([ids::short::Id; 2], usize)>) {
+        let round = matches
+            .into_iter()
+            .map(|(players, game)| BracketMatch {
+                players,
+                games: vec![game],
+                winner: None,
+                drawn: false,
+            })
+            .collect();
+        self.rounds.push(round);
+    }
+
+    /// Returns the index of the current round's match the game is reserved for,
+    /// if it's still undecided.
+    #[must_use]
+    pub fn find_match(&self, game: usize) -> Option<usize> {
+        if self.champion.is_some() {
+            return None;
+        }
+        self.rounds
+            .last()?
+            .iter()
+            .position(|m| !m.is_decided() && m.games.last() == Some(&game))
+    }
+
+    /// Records the game reserved to replay a drawn match.
+    pub fn replay(&mut self, index: usize, game: usize) {
+        if let Some(m) = self
+            .rounds
+            .last_mut()
+            .and_then(|round| round.get_mut(index))
+        {
+            m.games.push(game);
+        }
+    }
+
+    /// Records the result of a game of the current round's match, given who held
+    /// seats 1 and 2 in it, and returns what it leads to.
+    pub fn finish_game(
+        &mut self,
+        index: usize,
+        seats: [ids::short::Id; 2],
+        winner: Option<u8>,
+    ) -> Advance {
+        let [x, o] = seats;
+        let winner = match (winner, self.format) {
+            (Some(1), _) => Some(x),
+            (Some(_), _) => Some(o),
+            (None, TournamentFormat::Elimination) => return Advance::Replay([o, x]),
+            (None, TournamentFormat::Swiss { .. }) => None,
+        };
+        if let Some(m) = self
+            .rounds
+            .last_mut()
+            .and_then(|round| round.get_mut(index))
+        {
+            m.drawn = winner.is_none();
+            m.winner = winner;
+        }
+
+        let round = self.rounds.last().map_or(&[][..], Vec::as_slice);
+        if !round.iter().all(BracketMatch::is_decided) {
+            return Advance::Pending;
+        }
+        match self.format {
+            TournamentFormat::Elimination => {
+                let winners: Vec<_> = round.iter().filter_map(|m| m.winner.clone()).collect();
+                if let [champion] = winners.as_slice() {
+                    self.champion = Some(champion.clone());
+                    return Advance::Finished(champion.clone());
+                }
+                Advance::NextRound(
+                    winners
+                        .chunks(2)
+                        .map(|pair| [pair[0].clone(), pair[1].clone()])
+                        .collect(),
+                )
+            }
+            TournamentFormat::Swiss { rounds } if self.rounds.len() >= rounds => {
+                let leader = self.standings().swap_remove(0).address;
+                self.champion = Some(leader.clone());
+                Advance::Finished(leader)
+            }
+            TournamentFormat::Swiss { .. } => Advance::NextRound(self.swiss_pairs()),
+        }
+    }
+
+    /// Returns the seeded players' results in the matches decided so far, by points
+    /// and then by seed.
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<_> = self
+            .seeds
+            .iter()
+            .map(|address| Standing {
+                address: address.clone(),
+                points: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            })
+            .collect();
+        for m in self.rounds.iter().flatten().filter(|m| m.is_decided()) {
+            for standing in &mut standings {
+                if !m.players.contains(&standing.address) {
+                    continue;
+                }
+                match &m.winner {
+                    Some(winner) if *winner == standing.address => {
+                        standing.wins += 1;
+                        standing.points += 2;
+                    }
+                    Some(_) => standing.losses += 1,
+                    None => {
+                        standing.draws += 1;
+                        standing.points += 1;
+                    }
+                }
+            }
+        }
+        // Stable, so players level on points stay in seed order
+        standings.sort_by_key(|standing| Reverse(standing.points));
+        standings
+    }
+
+    /// Returns whether the two players met in a round scheduled so far.
+    fn have_met(&self, a: &ids::short::Id, b: &ids::short::Id) -> bool {
+        self.rounds
+            .iter()
+            .flatten()
+            .any(|m| m.players.contains(a) && m.players.contains(b))
+    }
+
+    /// Returns the next Swiss round's pairings: going down the standings, each
+    /// unpaired player meets the highest standing one it hasn't met yet, or the
+    /// highest standing one if it met them all.
+    fn swiss_pairs(&self) -> Vec<[ids::short::Id; 2]> {
+        let mut unpaired: Vec<_> = self
+            .standings()
+            .into_iter()
+            .map(|standing| standing.address)
+            .collect();
+        let mut pairs = Vec::with_capacity(unpaired.len() / 2);
+        while unpaired.len() >= 2 {
+            let top = unpaired.remove(0);
+            let opponent = unpaired
+                .iter()
+                .position(|other| !self.have_met(&top, other))
+                .unwrap_or(0);
+            let opponent = unpaired.remove(opponent);
+            pairs.push([top, opponent]);
+        }
+        pairs
+    }
+}
+
+/// Checks a tournament may be created for the given number of players and, for
+/// Swiss rounds, rounds. Returns its format and capacity.
+/// # Errors
+/// Fails unless the players are a power of two from 2 to [`MAX_TOURNAMENT_PLAYERS`]
+/// for a bracket, or an even number in that range for Swiss rounds, which must be
+/// at least one and fewer than the players.
+pub fn check_capacity(players: u32, rounds: Option<u32>) -> io::Result<(TournamentFormat, usize)> {
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidInput, msg);
+    if !(2..=MAX_TOURNAMENT_PLAYERS).contains(&players) {
+        return Err(invalid(format!(
+            "tournaments are for 2 to {MAX_TOURNAMENT_PLAYERS} players, not {players}"
+        )));
+    }
+    let capacity = usize::try_from(players).map_err(|e| invalid(e.to_string()))?;
+    let format = match rounds {
+        None if players.is_power_of_two() => TournamentFormat::Elimination,
+        None => {
+            return Err(invalid(format!(
+                "brackets are for a power of two of players, not {players}"
+            )))
+        }
+        Some(_) if players % 2 != 0 => {
+            return Err(invalid(format!(
+                "Swiss rounds are for an even number of players, not {players}"
+            )))
+        }
+        Some(rounds) if rounds == 0 || rounds >= players => {
+            return Err(invalid(format!(
+                "Swiss rounds for {players} players are 1 to {}, not {rounds}",
+                players - 1
+            )))
+        }
+        Some(rounds) => TournamentFormat::Swiss {
+            rounds: usize::try_from(rounds).map_err(|e| invalid(e.to_string()))?,
+        },
+    };
+    Ok((format, capacity))
+}
+
+/// Returns the seeds of a bracket of `size` players, a power of two, in the order
+/// they are paired in: the top seed (0) first, then its opponent, and so on. The
+/// top seeds only meet in the last rounds.
+#[must_use]
+pub fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let len = order.len() * 2;
+        order = order
+            .iter()
+            .flat_map(|&seed| [seed, len - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use avalanche_types::ids;
+
+    use super::{bracket_order, Advance, Tournament, TournamentFormat, TournamentStatus};
+
+    fn address(n: usize) -> ids::short::Id {
+        ids::short::Id::from_slice(&n.to_be_bytes())
+    }
+
+    /// Returns a full tournament of `size` players rated alike, so they're seeded
+    /// in the order they registered, along with its first round's pairings.
+    fn seeded(format: TournamentFormat, size: usize) -> (Tournament, Vec<[ids::short::Id; 2]>) {
+        let mut tournament = Tournament::new(address(0), format, size);
+        tournament.players = (1..=size).map(address).collect();
+        let pairs = tournament.seed(|_| 1200);
+        (tournament, pairs)
+    }
+
+    /// Starts a round of the given pairings, reserving them games from `first` on.
+    fn start(tournament: &mut Tournament, pairs: Vec<[ids::short::Id; 2]>, first: usize) {
+        tournament.start_round(pairs.into_iter().zip(first..).collect());
+    }
+
+    #[test]
+    fn bracket_keeps_top_seeds_apart() {
+        for size in (1..=6).map(|exp| 1 << exp) {
+            let order = bracket_order(size);
+            let mut sorted = order.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..size).collect::<Vec<_>>(), "bracket of {size}");
+
+            // The top seed of each pair is paired with the bottom one
+            for pair in order.chunks(2) {
+                assert_eq!(pair[0] + pair[1], size - 1, "bracket of {size}");
+            }
+            // The top `sections` seeds are each in their own section of the bracket,
+            // so they only meet once the sections are merged
+            let mut sections = 2;
+            while sections <= size {
+                for (section, seeds) in order.chunks(size / sections).enumerate() {
+                    let top = seeds.iter().filter(|seed| **seed < sections).count();
+                    assert_eq!(top, 1, "section {section}/{sections} of bracket of {size}");
+                }
+                sections *= 2;
+            }
+        }
+    }
+
+    #[test]
+    fn drawn_bracket_match_is_replayed_with_seats_swapped() {
+        let (mut tournament, pairs) = seeded(TournamentFormat::Elimination, 2);
+        let [x, o] = pairs[0].clone();
+        start(&mut tournament, pairs, 0);
+
+        assert_eq!(
+            tournament.finish_game(0, [x.clone(), o.clone()], None),
+            Advance::Replay([o.clone(), x.clone()])
+        );
+        tournament.replay(0, 1);
+        assert_eq!(tournament.find_match(0), None);
+        assert_eq!(tournament.find_match(1), Some(0));
+
+        assert_eq!(
+            tournament.finish_game(0, [o.clone(), x], Some(1)),
+            Advance::Finished(o.clone())
+        );
+        assert_eq!(tournament.rounds[0][0].games, [0, 1]);
+        assert_eq!(tournament.champion, Some(o));
+        assert_eq!(tournament.status(), TournamentStatus::Finished);
+    }
+
+    #[test]
+    fn bracket_advances_round_by_round_until_finished() {
+        let (mut tournament, pairs) = seeded(TournamentFormat::Elimination, 4);
+        assert_eq!(pairs, [[address(1), address(4)], [address(2), address(3)]]);
+        start(&mut tournament, pairs, 0);
+        assert_eq!(tournament.status(), TournamentStatus::InProgress);
+
+        assert_eq!(
+            tournament.finish_game(0, [address(1), address(4)], Some(2)),
+            Advance::Pending
+        );
+        let next = tournament.finish_game(1, [address(2), address(3)], Some(1));
+        assert_eq!(next, Advance::NextRound(vec![[address(4), address(2)]]));
+        if let Advance::NextRound(pairs) = next {
+            start(&mut tournament, pairs, 2);
+        }
+
+        assert_eq!(tournament.find_match(2), Some(0));
+        assert_eq!(
+            tournament.finish_game(0, [address(4), address(2)], Some(1)),
+            Advance::Finished(address(4))
+        );
+        assert_eq!(tournament.find_match(2), None);
+        assert_eq!(tournament.champion, Some(address(4)));
+    }
+
+    #[test]
+    fn swiss_rounds_avoid_rematches() {
+        let (mut tournament, pairs) = seeded(TournamentFormat::Swiss { rounds: 3 }, 4);
+        assert_eq!(pairs, [[address(1), address(3)], [address(2), address(4)]]);
+        start(&mut tournament, pairs, 0);
+
+        // Round 1: the top seeds win
+        tournament.finish_game(0, [address(1), address(3)], Some(1));
+        let next = tournament.finish_game(1, [address(2), address(4)], Some(1));
+        let pairs = vec![[address(1), address(2)], [address(3), address(4)]];
+        assert_eq!(next, Advance::NextRound(pairs.clone()));
+        start(&mut tournament, pairs, 2);
+
+        // Round 2: the leader wins and the draw stands. 2 and 3 stand above 4 but
+        // already met the leader, so 4 meets it next
+        tournament.finish_game(0, [address(1), address(2)], Some(1));
+        let next = tournament.finish_game(1, [address(3), address(4)], None);
+        let pairs = vec![[address(1), address(4)], [address(2), address(3)]];
+        assert_eq!(next, Advance::NextRound(pairs.clone()));
+        start(&mut tournament, pairs, 4);
+
+        let mut met: Vec<_> = tournament
+            .rounds
+            .iter()
+            .flatten()
+            .map(|m| {
+                let mut players = m.players.clone();
+                players.sort();
+                players
+            })
+            .collect();
+        met.sort();
+        met.dedup();
+        assert_eq!(met.len(), 6);
+
+        tournament.finish_game(0, [address(1), address(4)], None);
+        assert_eq!(
+            tournament.finish_game(1, [address(2), address(3)], Some(2)),
+            Advance::Finished(address(1))
+        );
+        let standings = tournament.standings();
+        assert_eq!(standings[0].points, 5);
+        assert_eq!(standings[1].address, address(3));
+    }
+}
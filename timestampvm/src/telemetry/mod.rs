@@ -0,0 +1,35 @@
+//! Exports the tracing spans of block processing, proposed moves and RPC calls
+//! to an OpenTelemetry collector over OTLP. Only built with the `otlp` feature.
+
+use std::io::{self, Error, ErrorKind};
+
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::{layer::SubscriberExt, EnvFilter};
+
+/// Installs a global subscriber exporting spans, filtered by `RUST_LOG`, to the
+/// OTLP collector listening at `endpoint` (e.g., `http://localhost:4317`).
+/// Must be called from within a Tokio runtime.
+/// # Errors
+/// Fails if the exporter can't be set up or a subscriber is already installed.
+pub fn init_otlp(endpoint: &str) -> io::Result<()> {
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to set up OTLP {e}")))?;
+
+    let subscriber = tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to install subscriber {e}")))
+}
+
+/// Exports the spans that haven't been yet, to be called before exiting.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -1,20 +1,41 @@
 //! Implementation of [`snowman.block.ChainVM`](https://pkg.go.dev/github.com/ava-labs/avalanchego/snow/engine/snowman/block#ChainVM) interface for timestampvm.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     io::{self, Error, ErrorKind},
+    net::IpAddr,
     sync::Arc,
+    time::Duration,
 };
 
 use crate::{
     api::{
-        chain_handlers::{ChainHandler, ChainService},
-        // static_handlers::{StaticHandler, StaticService},
+        admin::{AdminHandler, AdminService},
+        faucet::{FaucetHandler, FaucetService},
+        chain_handlers::{self, ChainHandler, ChainService},
+        event_stream::EventStreamHandler,
+        graphql::GraphQlHandler,
+        grpc::{self, ChainGrpc},
+        metrics::MetricsHandler,
+        rest::{self, RestGateway},
+        schema::SchemaHandler,
+        static_handlers::{StaticHandler, StaticService},
+        ChainEndpoint,
     },
-    block::Block,
+    block::{self, AccountAction, Block},
+    config::Config,
+    crosschain::{self, CrossChainRequest, CrossChainResponse},
+    game,
     genesis::Genesis,
-    state,
+    mempool::{
+        rate_limit::{RateLimiter, Submitter},
+        EvictionStats, GossipedMove, Mempool,
+    },
+    metrics::Metrics,
+    state::{self, LatestGame, StateArchive},
+    webhook,
 };
+use arc_swap::ArcSwap;
 use avalanche_types::{
     choices, ids,
     subnet::{
@@ -39,10 +60,269 @@ use avalanche_types::{
 };
 use chrono::{DateTime, Utc};
 use semver::Version;
-use tokio::sync::{mpsc::Sender, RwLock};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, Sender, UnboundedReceiver},
+    oneshot, RwLock,
+};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// How often pending moves are checked for expiry when no block is accepted.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Git commit the VM was built from ("unknown" if built outside a checkout).
+pub const BUILD_COMMIT: &str = env!("TIMESTAMPVM_BUILD_COMMIT");
+
+/// Move proposed as part of a batch: the action, its signature, the most its
+/// signer agreed to pay and the nonce ground for its proof of work.
+pub type ProposedMove = (u8, Vec<u8>, Option<u64>, Option<u64>);
+
+/// Checks the move against the board and turn predicted from the queued moves,
+/// then creates a block for it on top of the last queued block (or the preferred
+/// block) and appends it to the mempool. The block carries the fee congestion
+/// raised the move to, if above the base fee, which must be within the maximum the
+/// move was signed for, and the move's proof of work if the
+/// chain requires one. A move that is already queued for the
+/// same game and turn isn't queued again, its pending block is returned instead.
+/// Returns the block along with the board predicted once it is accepted.
+async fn queue_block(
+    state: &state::State,
+    preferred: &ids::Id,
+    mempool: &mut Mempool,
+    d: u8,
+    signature: Vec<u8>,
+    max_fee: Option<u64>,
+    work: Option<u64>,
+) -> io::Result<(Block, u32)> {
+    let game = state.get_curr_game_index().await;
+    let board = state.get_curr_game().await;
+    let next_player = state.get_next_player().await;
+
+    if let Some(queued) = mempool.find(game, board, d) {
+        let (board, _) = mempool.predict(board, next_player, Some(&queued.id()));
+        let board = game::place(board, queued.get_player_id(), queued.get_move_index());
+        log::info!("move {d} is already pending in block {}", queued.id());
+        return Ok((queued.clone(), board));
+    }
+
+    let (board, next_player) = mempool.predict(board, next_player, None);
+    game::check_move(board, next_player, d)?;
+    state.check_work(game, d, &signature, work)?;
+    let signer = block::recover_signer(game, d, max_fee, &signature)?;
+    let (player, _) = game::decode_action(d);
+    let fee = state.congestion_fee(mempool.len());
+    state.check_fee(Some(fee), max_fee)?;
+    state.check_move_cost(player, signer.as_ref(), fee).await?;
+
+    let (parent_id, parent_height) = if let Some(last) = mempool.last() {
+        (last.id(), last.height())
+    } else {
+        let prnt_blk = state.get_block(preferred).await?;
+        (prnt_blk.id(), prnt_blk.height())
+    };
+
+    let block = Block::try_new(
+        parent_id,
+        parent_height + 1,
+        d,
+        signature,
+        choices::status::Status::Processing,
+    )?;
+    let block = match max_fee {
+        Some(max_fee) => block.with_max_fee(max_fee)?,
+        None => block,
+    };
+    let block = if fee > state.move_fee {
+        block.with_fee(fee)?
+    } else {
+        block
+    };
+    let block = match work {
+        Some(nonce) if state.move_work > 0 => block.with_work(nonce)?,
+        _ => block,
+    };
+    let board = game::place(board, block.get_player_id(), block.get_move_index());
+
+    mempool.push(block.clone());
+    state.metrics.move_proposed(block.id());
+    log::info!("proposed move {d} in block {}", block.id());
+
+    Ok((block, board))
+}
+
+/// Checks the transfer, seek, tournament, series or team action against the
+/// accepted nonce of its signer and the state its kind reads, then creates a block
+/// for it on top of the last queued block (or the preferred block) and appends it
+/// to the mempool. An action that is already queued isn't queued again, its
+/// pending block is returned instead.
+async fn queue_account_action(
+    state: &state::State,
+    preferred: &ids::Id,
+    mempool: &mut Mempool,
+    action: AccountAction,
+    signature: Vec<u8>,
+) -> io::Result<Block> {
+    let signer = state.validate_account_action(&action, &signature).await?;
+    let kind = action.kind();
+    if let Some(queued) = mempool.find_account_action(&signer) {
+        if queued.account_action().as_ref() == Some(&action) {
+            log::info!("{kind} from {signer} is already pending in block {}", queued.id());
+            return Ok(queued.clone());
+        }
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("an account action from {signer} is already pending"),
+        ));
+    }
+
+    let (parent_id, parent_height) = if let Some(last) = mempool.last() {
+        (last.id(), last.height())
+    } else {
+        let prnt_blk = state.get_block(preferred).await?;
+        (prnt_blk.id(), prnt_blk.height())
+    };
+
+    let block = Block::try_new_account(
+        parent_id,
+        parent_height + 1,
+        action,
+        signature,
+        choices::status::Status::Processing,
+    )?;
+
+    mempool.push(block.clone());
+    log::info!("proposed {kind} from {signer} in block {}", block.id());
+
+    Ok(block)
+}
+
+/// Checks who proposed a move against the allowlist and rate limit, if configured.
+/// The submitter is the address that signed the move as part of the current game,
+/// or else the source IP.
+async fn check_submitter(
+    vm_state: &State,
+    rate_limiter: Option<&mut RateLimiter>,
+    d: u8,
+    signature: &[u8],
+    max_fee: Option<u64>,
+    source: Option<IpAddr>,
+) -> io::Result<()> {
+    if let Some(state) = &vm_state.state {
+        let game = state.get_curr_game_index().await;
+        let address = block::recover_signer(game, d, max_fee, signature).ok().flatten();
+        check_sender(vm_state, rate_limiter, address, source)?;
+    }
+    Ok(())
+}
+
+/// Checks a submitter, by address if known or else by source IP, against the
+/// allowlist and rate limit, if configured.
+fn check_sender(
+    vm_state: &State,
+    rate_limiter: Option<&mut RateLimiter>,
+    address: Option<ids::short::Id>,
+    source: Option<IpAddr>,
+) -> io::Result<()> {
+    vm_state.config.check_allowed(address.as_ref())?;
+    if let Some(limiter) = rate_limiter {
+        limiter.check(Submitter::new(address, source))?;
+    }
+    Ok(())
+}
+
+/// Rejects moves and block builds until the node is in normal operation.
+fn check_bootstrapped(vm_state: &State) -> io::Result<()> {
+    if vm_state.bootstrapped {
+        return Ok(());
+    }
+    Err(Error::new(ErrorKind::Other, "node is still bootstrapping"))
+}
+
+/// Evicts the queued moves the accepted state no longer allows or that expired,
+/// see [`Mempool::evict`].
+async fn evict_queued(
+    vm_state: &State,
+    state: &state::State,
+    mempool: &mut Mempool,
+) -> io::Result<EvictionStats> {
+    let board = state.get_curr_game().await;
+    let next_player = state.get_next_player().await;
+
+    // Moves queued on a block that is gone (e.g. rejected) move onto the preferred block
+    let mut base = None;
+    if let Some(front) = mempool.iter().next() {
+        if state.get_block(&front.block.parent_id()).await.is_err() {
+            let preferred = state.get_block(&vm_state.preferred).await?;
+            base = Some((preferred.id(), preferred.height()));
+        }
+    }
+
+    mempool.evict(board, next_player, base, vm_state.config.pending_move_ttl())
+}
+
+/// Writes the pending moves to the database, if the mempool is configured to persist.
+async fn persist_mempool(vm_state: &State, mempool: &Mempool) {
+    if !vm_state.config.persist_mempool {
+        return;
+    }
+    if let Some(state) = &vm_state.state {
+        if let Err(e) = state.write_pending_moves(&mempool.to_persisted()).await {
+            log::warn!("failed to persist pending moves: {e}");
+        }
+    }
+}
+
+/// Re-admits the pending moves persisted before a restart, dropping those that
+/// expired or can't be played on the current state.
+async fn restore_mempool(vm_state: &State, mempool: &mut Mempool) -> io::Result<()> {
+    if let Some(state) = &vm_state.state {
+        let ttl = vm_state.config.pending_move_ttl();
+        let mut persisted = state.read_pending_moves().await?;
+        // Keep first-received order, so the same move wins each turn as before the restart
+        persisted.sort_by_key(|pending| pending.received_at);
+        let total = persisted.len();
+
+        for pending in persisted.into_iter().filter(|pending| pending.age() <= ttl) {
+            let (d, signature, work) = (pending.action, pending.signature, pending.work);
+            let max_fee = pending.max_fee;
+            let preferred = &vm_state.preferred;
+            if let Some(action) = pending.account.into_action() {
+                let kind = action.kind();
+                let res = queue_account_action(state, preferred, mempool, action, signature);
+                if let Err(e) = res.await {
+                    log::info!("dropped persisted pending {kind}: {e}");
+                }
+                continue;
+            }
+            let res = queue_block(state, preferred, mempool, d, signature, max_fee, work);
+            if let Err(e) = res.await {
+                log::info!("dropped persisted pending move {d}: {e}");
+            }
+        }
+        log::info!("re-admitted {} of {total} persisted pending moves", mempool.len());
+
+        persist_mempool(vm_state, mempool).await;
+    }
+    Ok(())
+}
+
+/// Gossips the moves admitted to the mempool to the other validators, until the
+/// mempool is dropped.
+async fn gossip_moves<A: AppSender>(app_sender: A, mut admitted: UnboundedReceiver<GossipedMove>) {
+    while let Some(gossiped) = admitted.recv().await {
+        let res = match gossiped.to_vec() {
+            Ok(msg) => app_sender.send_app_gossip(msg).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            log::warn!("failed to gossip move {}: {e}", gossiped.action);
+        }
+    }
+}
+
 /// Represents VM-specific states.
 /// Defined in a separate struct, for interior mutability in [`Vm`](Vm).
 /// To be protected with `Arc` and `RwLock`.
@@ -50,6 +330,9 @@ pub struct State {
     pub ctx: Option<Context<ValidatorStateClient>>,
     pub version: Version,
     pub genesis: Genesis,
+    pub config: Config,
+    /// Hash of the genesis bytes the chain was initialized with.
+    pub genesis_hash: ids::Id,
 
     /// Represents persistent Vm state.
     pub state: Option<state::State>,
@@ -68,6 +351,8 @@ impl Default for State {
             ctx: None,
             version: Version::new(0, 0, 0),
             genesis: Genesis::default(),
+            config: Config::default(),
+            genesis_hash: ids::Id::empty(),
 
             state: None,
             preferred: ids::Id::empty(),
@@ -77,6 +362,26 @@ impl Default for State {
     }
 }
 
+/// Health of the Vm, as reported by the node's health checks and the `health` RPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Health {
+    /// "true" if the database and engine channel are both reachable
+    pub healthy: bool,
+    pub bootstrapped: bool,
+    /// Error returned by the database health check, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_error: Option<String>,
+    /// Whether the channel to the consensus engine is still open
+    pub engine_connected: bool,
+    /// Height of the last accepted block
+    pub height: u64,
+    /// Time since this node last accepted a block, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_accepted_age_ms: Option<u64>,
+    /// Number of moves waiting for blocks to be built
+    pub mempool_depth: usize,
+}
+
 /// Implements [`snowman.block.ChainVM`](https://pkg.go.dev/github.com/ava-labs/avalanchego/snow/engine/snowman/block#ChainVM) interface.
 #[derive(Clone)]
 pub struct Vm<A> {
@@ -84,9 +389,31 @@ pub struct Vm<A> {
     pub state: Arc<RwLock<State>>,
     pub app_sender: Option<A>,
 
-    /// A queue of data that have not been put into a block and proposed yet.
+    /// Proposed moves whose blocks have not been built and verified yet.
     /// Mempool is not persistent, so just keep in memory via Vm.
-    pub mempool: Arc<RwLock<VecDeque<u8>>>,
+    pub mempool: Arc<RwLock<Mempool>>,
+
+    /// Limits how fast each submitter may propose moves, if configured.
+    pub rate_limiter: Arc<RwLock<Option<RateLimiter>>>,
+
+    /// Limits how fast each peer may gossip moves, if configured.
+    pub gossip_limiter: Arc<RwLock<Option<RateLimiter>>>,
+
+    /// Serves the chain RPC over WebSocket, if configured.
+    pub ws_server: Arc<RwLock<Option<jsonrpc_ws_server::Server>>>,
+
+    /// Stops the gRPC chain service once used, if it is served.
+    pub grpc_server: Arc<RwLock<Option<oneshot::Sender<()>>>>,
+
+    /// Stops the REST gateway once used, if it is served.
+    pub rest_server: Arc<RwLock<Option<oneshot::Sender<()>>>>,
+
+    /// Board and results as of the last accepted block, shared with the state
+    /// manager so queries can read them without taking any lock.
+    pub latest: Arc<ArcSwap<LatestGame>>,
+
+    /// Prometheus metrics, shared with the state manager.
+    pub metrics: Arc<Metrics>,
 }
 
 impl<A> Default for Vm<A>
@@ -107,7 +434,14 @@ where
         Self {
             state: Arc::new(RwLock::new(State::default())),
             app_sender: None,
-            mempool: Arc::new(RwLock::new(VecDeque::with_capacity(100))),
+            mempool: Arc::new(RwLock::new(Mempool::default())),
+            rate_limiter: Arc::new(RwLock::new(None)),
+            gossip_limiter: Arc::new(RwLock::new(None)),
+            ws_server: Arc::new(RwLock::new(None)),
+            grpc_server: Arc::new(RwLock::new(None)),
+            rest_server: Arc::new(RwLock::new(None)),
+            latest: Arc::new(ArcSwap::from_pointee(LatestGame::default())),
+            metrics: Arc::new(Metrics::new()),
         }
     }
 
@@ -116,36 +450,318 @@ where
         vm_state.bootstrapped
     }
 
-    /// Signals the consensus engine that a new block is ready to be created.
-    pub async fn notify_block_ready(&self) {
+    /// Checks the database and engine channel, and reports chain and mempool progress.
+    /// # Errors
+    /// Fails if there's no state.
+    pub async fn health(&self) -> io::Result<Health> {
         let vm_state = self.state.read().await;
-        if let Some(to_engine) = &vm_state.to_engine {
-            to_engine
-                .send(snow::engine::common::message::Message::PendingTxs)
-                .await
-                .unwrap_or_else(|e| log::warn!("dropping message to consensus engine: {}", e));
+        if let Some(state) = &vm_state.state {
+            let db_error = state.db.read().await.health_check().await.err();
+            let engine_connected = vm_state
+                .to_engine
+                .as_ref()
+                .map_or(false, |to_engine| !to_engine.is_closed());
+
+            return Ok(Health {
+                healthy: db_error.is_none() && engine_connected,
+                bootstrapped: vm_state.bootstrapped,
+                db_error: db_error.map(|e| e.to_string()),
+                engine_connected,
+                height: state.get_height().await,
+                last_accepted_age_ms: state
+                    .get_last_accepted_age()
+                    .await
+                    .map(|age| u64::try_from(age.as_millis()).unwrap_or(u64::MAX)),
+                mempool_depth: self.mempool.read().await.len(),
+            });
+        }
 
-            log::info!("notified block ready!");
-        } else {
-            log::error!("consensus engine channel failed to initialized");
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
+    /// Re-checks the mempool against the accepted state, evicting the moves that
+    /// can no longer be played or have been pending for too long. Nothing is evicted
+    /// while bootstrapping, as the accepted state is still catching up.
+    /// # Errors
+    /// Fails if there's no state or a pending block can't be re-chained.
+    pub async fn evict_pending(&self) -> io::Result<EvictionStats> {
+        let vm_state = self.state.read().await;
+        if !vm_state.bootstrapped {
+            return Ok(EvictionStats::default());
+        }
+        if let Some(state) = &vm_state.state {
+            let mut mempool = self.mempool.write().await;
+            let evicted = evict_queued(&vm_state, state, &mut mempool).await?;
+            persist_mempool(&vm_state, &mempool).await;
+            return Ok(evicted);
         }
+
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
     }
 
-    /// Proposes arbitrary data to mempool and notifies that a block is ready for builds.
-    /// Other VMs may optimize mempool with more complicated batching mechanisms.
+    /// Drops the pending block with the given Id whatever its move, then re-chains
+    /// the moves after it and evicts those it was holding up as with
+    /// [`Vm::evict_pending`].
     /// # Errors
-    /// Can fail if the data size exceeds `PROPOSE_LIMIT_BYTES`.
-    pub async fn propose_block(&self, d: u8) -> io::Result<()> {
+    /// Fails if there's no state, the block isn't pending or the pending moves can't
+    /// be re-chained.
+    pub async fn evict_pending_block(&self, blk_id: &ids::Id) -> io::Result<EvictionStats> {
+        let vm_state = self.state.read().await;
+        if let Some(state) = &vm_state.state {
+            let mut mempool = self.mempool.write().await;
+            let block = mempool.remove(blk_id).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("block {blk_id} isn't pending"))
+            })?;
+            self.metrics.move_dropped(blk_id);
+            log::warn!("force evicted pending move {} in block {blk_id}", block.get_player_move());
+
+            let evicted = evict_queued(&vm_state, state, &mut mempool).await?;
+            persist_mempool(&vm_state, &mempool).await;
+            return Ok(evicted);
+        }
 
-        let mut mempool = self.mempool.write().await;
-        mempool.push_back(d);
-        log::info!("proposed {d} bytes of data for a block");
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
+    /// Accepts the blocks of an archive exported by another node, once it replays to
+    /// its own snapshots. As the blocks bypass consensus, only a node that accepted
+    /// nothing but the archive's genesis block and has no pending moves may import.
+    /// Returns the Id and height of the last accepted block.
+    /// # Errors
+    /// Fails if there's no state, the node already accepted blocks or has pending
+    /// ones, or the archive is invalid or starts from another genesis block.
+    pub async fn import_state(&self, archive: &StateArchive) -> io::Result<(ids::Id, u64)> {
+        // Held throughout, so no move is proposed on top of the blocks being imported
+        let mut vm_state = self.state.write().await;
+        let blocks = archive.verify(&vm_state.genesis).await?;
+        if let Some(state) = vm_state.state.clone() {
+            let mempool = self.mempool.read().await;
+            if state.get_height().await > 0
+                || !mempool.is_empty()
+                || !state.get_verified_blocks().await.is_empty()
+            {
+                return Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    "only a node that accepted nothing but its genesis block may import",
+                ));
+            }
+            let genesis_id = state.get_block_id_at_height(0).await?;
+            if blocks.first().map(Block::id) != Some(genesis_id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("archive doesn't start from genesis block {genesis_id}"),
+                ));
+            }
 
-        self.notify_block_ready().await;
+            let (mut last_accepted, mut height) = (genesis_id, 0);
+            for mut block in blocks.into_iter().skip(1) {
+                block.set_state(state.clone());
+                block.accept().await?;
+                (last_accepted, height) = (block.id(), block.height());
+            }
+            vm_state.preferred = last_accepted;
+            log::info!("imported {height} blocks on top of genesis block {genesis_id}");
+            return Ok((last_accepted, height));
+        }
+
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
+    /// Serves the transports listening on their own address, if configured and not
+    /// served yet.
+    async fn serve_transports(&self, vm_state: &State) -> io::Result<()> {
+        // The WebSocket transport dispatches to the same service on its own address,
+        // since requests proxied through the node can't be upgraded
+        if let (Some(addr), Some(state)) = (vm_state.config.ws_addr, &vm_state.state) {
+            let mut ws_server = self.ws_server.write().await;
+            if ws_server.is_none() {
+                let service = ChainService::new(self.clone());
+                let accepted = state.subscribe_accepted();
+                *ws_server = Some(chain_handlers::serve_ws(service, accepted, &addr)?);
+                log::info!("serving chain RPC over WebSocket on {addr}");
+            }
+        }
+        if let Some(addr) = vm_state.config.grpc_addr {
+            let mut grpc_server = self.grpc_server.write().await;
+            if grpc_server.is_none() {
+                *grpc_server = Some(grpc::serve_grpc(ChainGrpc::new(self.clone()), addr)?);
+                log::info!("serving gRPC chain service on {addr}");
+            }
+        }
+        if let Some(addr) = vm_state.config.rest_addr {
+            let mut rest_server = self.rest_server.write().await;
+            if rest_server.is_none() {
+                let gateway = RestGateway::new(ChainService::new(self.clone()));
+                *rest_server = Some(rest::serve_rest(gateway, addr)?);
+                log::info!("serving REST gateway on {addr}");
+            }
+        }
         Ok(())
     }
 
+    /// Evicts pending moves whenever a block is accepted, and periodically so stale
+    /// moves expire even when no block is. Stops once the accepted-move channel closes.
+    async fn evict_pending_on_accept(self, mut accepted: broadcast::Receiver<state::AcceptedMove>) {
+        loop {
+            let res = tokio::time::timeout(EVICTION_INTERVAL, accepted.recv()).await;
+            if let Ok(Err(broadcast::error::RecvError::Closed)) = res {
+                return;
+            }
+
+            match self.evict_pending().await {
+                Ok(evicted) if evicted != EvictionStats::default() => log::info!(
+                    "evicted {} invalid and {} expired pending moves",
+                    evicted.invalid,
+                    evicted.expired
+                ),
+                Ok(_) => {}
+                Err(e) => log::warn!("failed to evict pending moves: {e}"),
+            }
+        }
+    }
+
+    /// Proposes a move to mempool, which notifies the engine a block is ready for builds.
+    /// The block is created right away, on top of the last queued block (or the
+    /// preferred block), so its Id is known before it is built.
+    /// Other VMs may optimize mempool with more complicated batching mechanisms.
+    /// Moves are checked against the allowlist, and rate limited per submitter
+    /// (keyed by `source` for unsigned moves).
+    /// Returns the pending block along with the board predicted once it is accepted.
+    /// # Errors
+    /// Fails if there's no state, the node is still bootstrapping, the submitter isn't
+    /// allowed or is rate limited, the move can't be played after the queued moves or the parent
+    /// block can't be found.
+    #[tracing::instrument(name = "vm.propose_block", skip(self, signature))]
+    pub async fn propose_block(
+        &self,
+        d: u8,
+        signature: Vec<u8>,
+        max_fee: Option<u64>,
+        work: Option<u64>,
+        source: Option<IpAddr>,
+    ) -> io::Result<(Block, u32)> {
+        {
+            let vm_state = self.state.read().await;
+            let mut limiter = self.rate_limiter.write().await;
+            check_submitter(&vm_state, limiter.as_mut(), d, &signature, max_fee, source).await?;
+        }
+        self.admit_block(d, signature, max_fee, work).await
+    }
+
+    /// Queues a move as with [`Vm::propose_block`], without checking its submitter.
+    async fn admit_block(
+        &self,
+        d: u8,
+        signature: Vec<u8>,
+        max_fee: Option<u64>,
+        work: Option<u64>,
+    ) -> io::Result<(Block, u32)> {
+        let vm_state = self.state.read().await;
+        check_bootstrapped(&vm_state)?;
+        if let Some(state) = &vm_state.state {
+            let mut mempool = self.mempool.write().await;
+            let preferred = &vm_state.preferred;
+            let res =
+                queue_block(state, preferred, &mut mempool, d, signature, max_fee, work).await;
+            persist_mempool(&vm_state, &mempool).await;
+            return res;
+        }
+
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
+    /// Proposes a transfer, seek, tournament, series or team action signed by the
+    /// address taking it to mempool, checked against its accepted nonce and the
+    /// state its kind reads. Only one account action per address may be pending at
+    /// a time. The signer is checked as the submitter of a move is.
+    /// # Errors
+    /// Fails if there's no state, the node is still bootstrapping, the signer isn't
+    /// allowed or is rate limited, already has a pending account action, or the
+    /// action can't be taken.
+    #[tracing::instrument(
+        name = "vm.propose_account_action",
+        skip(self, signature),
+        fields(kind = action.kind())
+    )]
+    pub async fn propose_account_action(
+        &self,
+        action: AccountAction,
+        signature: Vec<u8>,
+        source: Option<IpAddr>,
+    ) -> io::Result<Block> {
+        {
+            let vm_state = self.state.read().await;
+            let mut rate_limiter = self.rate_limiter.write().await;
+            let signer = action.recover_signer(&signature)?;
+            check_sender(&vm_state, rate_limiter.as_mut(), Some(signer), source)?;
+        }
+        self.admit_account_action(action, signature).await
+    }
+
+    /// Queues an account action as with [`Vm::propose_account_action`], without
+    /// checking its signer.
+    pub(crate) async fn admit_account_action(
+        &self,
+        action: AccountAction,
+        signature: Vec<u8>,
+    ) -> io::Result<Block> {
+        let vm_state = self.state.read().await;
+        check_bootstrapped(&vm_state)?;
+        if let Some(state) = &vm_state.state {
+            let mut mempool = self.mempool.write().await;
+            let preferred = &vm_state.preferred;
+            let res = queue_account_action(state, preferred, &mut mempool, action, signature).await;
+            persist_mempool(&vm_state, &mempool).await;
+            return res;
+        }
+
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
+    /// Proposes an ordered list of moves to mempool, as with [`Vm::propose_block`].
+    /// Moves failing the check are not queued and don't affect the ones after them.
+    /// Seats are only checked once the blocks are verified.
+    /// Each move's submitter is checked as with [`Vm::propose_block`].
+    /// Returns a result per move, in order.
+    /// # Errors
+    /// Fails if there's no state or the node is still bootstrapping.
+    #[tracing::instrument(name = "vm.propose_blocks", skip_all, fields(moves = moves.len()))]
+    pub async fn propose_blocks(
+        &self,
+        moves: Vec<ProposedMove>,
+        source: Option<IpAddr>,
+    ) -> io::Result<Vec<io::Result<(Block, u32)>>> {
+        let vm_state = self.state.read().await;
+        check_bootstrapped(&vm_state)?;
+        if let Some(state) = &vm_state.state {
+            let mut rate_limiter = self.rate_limiter.write().await;
+            let mut mempool = self.mempool.write().await;
+
+            let mut results = Vec::with_capacity(moves.len());
+            for (d, signature, max_fee, work) in moves {
+                let limiter = rate_limiter.as_mut();
+                let checked =
+                    check_submitter(&vm_state, limiter, d, &signature, max_fee, source).await;
+                if let Err(e) = checked {
+                    results.push(Err(e));
+                    continue;
+                }
+                let preferred = &vm_state.preferred;
+                let mempool = &mut mempool;
+                results.push(
+                    queue_block(state, preferred, mempool, d, signature, max_fee, work).await,
+                );
+            }
+            persist_mempool(&vm_state, &mempool).await;
+
+            return Ok(results);
+        }
+
+        Err(Error::new(ErrorKind::NotFound, "state manager not found"))
+    }
+
     /// Sets the state of the Vm.
+    /// Moves are only proposed, gossiped and built into blocks in normal operation.
     /// # Errors
     /// Will fail if the `snow::State` is syncing
     pub async fn set_state(&self, snow_state: snow::State) -> io::Result<()> {
@@ -155,6 +771,7 @@ where
             snow::State::Initializing => {
                 log::info!("set_state: initializing");
                 vm_state.bootstrapped = false;
+                self.mempool.write().await.set_active(false);
                 Ok(())
             }
 
@@ -167,6 +784,7 @@ where
             snow::State::Bootstrapping => {
                 log::info!("set_state: bootstrapping");
                 vm_state.bootstrapped = false;
+                self.mempool.write().await.set_active(false);
                 Ok(())
             }
 
@@ -174,6 +792,7 @@ where
             snow::State::NormalOp => {
                 log::info!("set_state: normal op");
                 vm_state.bootstrapped = true;
+                self.mempool.write().await.set_active(true);
                 Ok(())
             }
         }
@@ -182,14 +801,14 @@ where
     /// Returns the last accepted block Id.
     /// # Errors
     /// Will fail if there's no state or if the db can't be accessed
-    // pub async fn last_accepted(&self) -> io::Result<ids::Id> {
-    //     let vm_state = self.state.read().await;
-
-    //     match &vm_state.state {
-    //         Some(state) => state.get_last_accepted_block_id().await,
-    //         None => Err(Error::new(ErrorKind::NotFound, "state manager not found")),
-    //     }
-    // }
+    pub async fn last_accepted(&self) -> io::Result<ids::Id> {
+        let vm_state = self.state.read().await;
+
+        match &vm_state.state {
+            Some(state) => state.get_last_accepted_block_id().await,
+            None => Err(Error::new(ErrorKind::NotFound, "state manager not found")),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -199,8 +818,8 @@ where
 {
     type DatabaseManager = DatabaseManager;
     type AppSender = A;
-    type ChainHandler = ChainHandler<ChainService<A>>;
-    // type StaticHandler = StaticHandler;
+    type ChainHandler = ChainEndpoint<A>;
+    type StaticHandler = StaticHandler;
     type ValidatorState = ValidatorStateClient;
 
     async fn initialize(
@@ -209,7 +828,7 @@ where
         db_manager: Self::DatabaseManager,
         genesis_bytes: &[u8],
         _upgrade_bytes: &[u8],
-        _config_bytes: &[u8],
+        config_bytes: &[u8],
         to_engine: Sender<snow::engine::common::message::Message>,
         _fxs: &[snow::engine::common::vm::Fx],
         app_sender: Self::AppSender,
@@ -225,48 +844,98 @@ where
 
         let genesis = Genesis::from_slice(genesis_bytes)?;
         vm_state.genesis = genesis;
+        vm_state.genesis_hash = ids::Id::sha256(genesis_bytes);
+
+        vm_state.config = Config::from_slice(config_bytes)?;
 
         let current = db_manager.current().await?;
-        // let state = state::State {
-        //     db: Arc::new(RwLock::new(current.db)),
-        //     verified_blocks: Arc::new(RwLock::new(HashMap::new())),
-        // };
         let state = state::State {
-            curr_game: Arc::new(RwLock::new(0)),
-            winners: Arc::new(RwLock::new(Vec::new())),
-            verified_blocks: Arc::new(RwLock::new(HashMap::new())),
-            blk_map: Arc::new(RwLock::new(HashMap::new()))
+            db: Arc::new(RwLock::new(current.db)),
+            history_blocks: vm_state.config.history_blocks,
+            compress_blocks: vm_state.config.compress_blocks,
+            move_fee: vm_state.genesis.move_fee,
+            max_move_fee: vm_state.genesis.max_move_fee,
+            game_stake: vm_state.genesis.game_stake,
+            treasury: vm_state.genesis.treasury.clone(),
+            move_work: vm_state.genesis.move_work,
+            latest: Arc::clone(&self.latest),
+            metrics: Arc::clone(&self.metrics),
+            ..Default::default()
         };
 
+        state.seed_balances(&vm_state.genesis.allocations).await?;
         vm_state.state = Some(state.clone());
 
-        vm_state.to_engine = Some(to_engine);
+        if !vm_state.config.webhooks.is_empty() {
+            webhook::spawn(vm_state.config.webhooks.clone(), state.clone());
+        }
+
+        vm_state.to_engine = Some(to_engine.clone());
 
+        let (gossip, admitted) = mpsc::unbounded_channel();
+        tokio::spawn(gossip_moves(app_sender.clone(), admitted));
         self.app_sender = Some(app_sender);
 
-        let mut genesis_block = Block::try_new(
-            ids::Id::empty(),
-            0,
-            0,
-            vm_state.genesis.data.as_bytes().to_vec(),
-            choices::status::Status::default(),
-        )?;
-        genesis_block.set_state(state.clone());
-        genesis_block.accept().await?;
+        // Blocks accepted before a restart are replayed, otherwise the chain starts at genesis
+        if let Some(last_accepted) = state.replay().await? {
+            vm_state.preferred = last_accepted;
+            log::info!("initialized Vm with last accepted block {last_accepted}");
+        } else {
+            let mut genesis_block = Block::try_new(
+                ids::Id::empty(),
+                0,
+                0,
+                Vec::new(),
+                choices::status::Status::default(),
+            )?;
+            genesis_block.set_state(state.clone());
+            genesis_block.accept().await?;
 
-        let genesis_blk_id = genesis_block.id();
-        vm_state.preferred = genesis_blk_id;
-        log::info!("initialized Vm with genesis block {genesis_blk_id}");
+            let genesis_blk_id = genesis_block.id();
+            vm_state.preferred = genesis_blk_id;
+            log::info!("initialized Vm with genesis block {genesis_blk_id}");
+        }
 
-        self.mempool = Arc::new(RwLock::new(VecDeque::with_capacity(100)));
+        let mut mempool =
+            Mempool::new(Some(to_engine), vm_state.config.batch_window()).with_gossip(gossip);
+        if vm_state.config.persist_mempool {
+            restore_mempool(&vm_state, &mut mempool).await?;
+        }
+        self.mempool = Arc::new(RwLock::new(mempool));
+        self.rate_limiter = Arc::new(RwLock::new(vm_state.config.rate_limit.map(RateLimiter::new)));
+        let gossip_limiter = vm_state.config.gossip_rate_limit.map(RateLimiter::new);
+        self.gossip_limiter = Arc::new(RwLock::new(gossip_limiter));
+        tokio::spawn(self.clone().evict_pending_on_accept(state.subscribe_accepted()));
 
         log::info!("successfully initialized Vm");
         Ok(())
     }
     /// Called when the node is shutting down.
+    /// Stops taking moves, writes the pending ones to the database if configured to
+    /// persist them, then closes the database.
     async fn shutdown(&self) -> io::Result<()> {
         // grpc servers are shutdown via broadcast channel
         // if additional shutdown is required we can extend.
+        if let Some(ws_server) = self.ws_server.write().await.take() {
+            ws_server.close();
+        }
+        if let Some(grpc_server) = self.grpc_server.write().await.take() {
+            let _ = grpc_server.send(());
+        }
+        if let Some(rest_server) = self.rest_server.write().await.take() {
+            let _ = rest_server.send(());
+        }
+
+        let mut vm_state = self.state.write().await;
+        vm_state.bootstrapped = false;
+        let mut mempool = self.mempool.write().await;
+        mempool.set_active(false);
+        persist_mempool(&vm_state, &mempool).await;
+
+        if let Some(state) = &vm_state.state {
+            state.close().await?;
+        }
+        log::info!("shut down Vm with {} pending moves", mempool.len());
         Ok(())
     }
 
@@ -278,38 +947,129 @@ where
         Ok(String::from(VERSION))
     }
 
-    // /// Creates static handlers.
-    // async fn create_static_handlers(
-    //     &mut self,
-    // ) -> io::Result<HashMap<String, HttpHandler<Self::StaticHandler>>> {
-    //     let handler = StaticHandler::new(StaticService::new());
-    //     let mut handlers = HashMap::new();
-    //     handlers.insert(
-    //         "/static".to_string(),
-    //         HttpHandler {
-    //             lock_option: LockOptions::WriteLock,
-    //             handler,
-    //             server_addr: None,
-    //         },
-    //     );
-
-    //     Ok(handlers)
-    // }
+    /// Creates static handlers.
+    async fn create_static_handlers(
+        &mut self,
+    ) -> io::Result<HashMap<String, HttpHandler<Self::StaticHandler>>> {
+        let handler = StaticHandler::new(StaticService::new());
+        let mut handlers = HashMap::new();
+        handlers.insert(
+            "/static".to_string(),
+            HttpHandler {
+                lock_option: LockOptions::WriteLock,
+                handler,
+                server_addr: None,
+            },
+        );
+
+        Ok(handlers)
+    }
 
     /// Creates VM-specific handlers.
     async fn create_handlers(
         &mut self,
     ) -> io::Result<HashMap<String, HttpHandler<Self::ChainHandler>>> {
-        let handler = ChainHandler::new(ChainService::new(self.clone()));
+        let mut handler = ChainHandler::new(ChainService::new(self.clone()))
+            .with_metrics(Arc::clone(&self.metrics));
+        let events = EventStreamHandler::new(self.clone());
+        let graphql = GraphQlHandler::new(self.clone());
+        let metrics = MetricsHandler::new(self.clone());
+
+        let vm_state = self.state.read().await;
+        self.serve_transports(&vm_state).await?;
+        let admin = vm_state
+            .config
+            .admin_api
+            .then(|| AdminHandler::new(AdminService::new(self.clone())));
+        let faucet = match &vm_state.config.faucet {
+            Some(faucet) => Some(FaucetHandler::new(FaucetService::new(self.clone(), faucet)?)),
+            None => None,
+        };
+        let profiling = vm_state.config.profiling;
+        if let Some(threshold) = vm_state.config.slow_rpc_threshold() {
+            handler = handler.with_slow_call_threshold(threshold);
+        }
+        drop(vm_state);
+
         let mut handlers = HashMap::new();
         handlers.insert(
             "/rpc".to_string(),
             HttpHandler {
-                lock_option: LockOptions::WriteLock,
-                handler,
+                // Handlers synchronize on Vm state themselves, and long-polling
+                // calls must not hold up consensus while they wait
+                lock_option: LockOptions::NoLock,
+                handler: ChainEndpoint::Rpc(handler),
+                server_addr: None,
+            },
+        );
+        handlers.insert(
+            "/events".to_string(),
+            HttpHandler {
+                lock_option: LockOptions::NoLock,
+                handler: ChainEndpoint::Events(events),
                 server_addr: None,
             },
         );
+        handlers.insert(
+            "/graphql".to_string(),
+            HttpHandler {
+                lock_option: LockOptions::NoLock,
+                handler: ChainEndpoint::GraphQl(graphql),
+                server_addr: None,
+            },
+        );
+        handlers.insert(
+            "/metrics".to_string(),
+            HttpHandler {
+                lock_option: LockOptions::NoLock,
+                handler: ChainEndpoint::Metrics(metrics),
+                server_addr: None,
+            },
+        );
+        handlers.insert(
+            "/schema".to_string(),
+            HttpHandler {
+                lock_option: LockOptions::NoLock,
+                handler: ChainEndpoint::Schema(SchemaHandler::new()),
+                server_addr: None,
+            },
+        );
+        if let Some(admin) = admin {
+            log::warn!("serving the admin RPC on /admin");
+            handlers.insert(
+                "/admin".to_string(),
+                HttpHandler {
+                    lock_option: LockOptions::NoLock,
+                    handler: ChainEndpoint::Admin(admin),
+                    server_addr: None,
+                },
+            );
+        }
+        if let Some(faucet) = faucet {
+            log::warn!("serving the faucet RPC on /faucet");
+            handlers.insert(
+                "/faucet".to_string(),
+                HttpHandler {
+                    lock_option: LockOptions::NoLock,
+                    handler: ChainEndpoint::Faucet(faucet),
+                    server_addr: None,
+                },
+            );
+        }
+        if profiling {
+            #[cfg(feature = "profiling")]
+            handlers.insert(
+                "/pprof".to_string(),
+                HttpHandler {
+                    // Profiles take seconds to capture, which must not hold up consensus
+                    lock_option: LockOptions::NoLock,
+                    handler: ChainEndpoint::Profile(crate::api::profile::ProfileHandler::new()),
+                    server_addr: None,
+                },
+            );
+            #[cfg(not(feature = "profiling"))]
+            log::warn!("profiling is enabled but the VM was built without the profiling feature");
+        }
 
         Ok(handlers)
     }
@@ -322,8 +1082,10 @@ where
 {
     type Block = Block;
 
-    /// Builds a block from mempool data.
+    /// Builds a block from mempool data, once the node is bootstrapped.
     async fn build_block(&self) -> io::Result<<Self as ChainVm>::Block> {
+        let vm_state = self.state.read().await;
+        check_bootstrapped(&vm_state)?;
         let mut mempool = self.mempool.write().await;
 
         log::info!("build_block called for {} mempool", mempool.len());
@@ -331,30 +1093,26 @@ where
             return Err(Error::new(ErrorKind::Other, "no pending block"));
         }
 
-        let vm_state = self.state.read().await;
         if let Some(state) = &vm_state.state {
-            self.notify_block_ready().await;
-
-            // "state" must have preferred block in cache/verified_block
-            // otherwise, not found error from rpcchainvm database
-            let prnt_blk = state.get_block(&vm_state.preferred).await?;
-            let unix_now = Utc::now()
-                .timestamp()
-                .try_into()
-                .expect("timestamp to convert from i64 to u64");
-
-            let first = mempool.pop_front().unwrap();
-            let mut block = Block::try_new(
-                prnt_blk.id(),
-                prnt_blk.height() + 1,
-                first,
-                choices::status::Status::Processing,
-            )?;
-            block.set_state(state.clone());
-            block.verify().await?;
-
-            log::info!("successfully built block");
-            return Ok(block);
+            // "state" must have the parent block in cache/verified_block
+            // otherwise, verification fails with a not found error
+            if let Some(front) = mempool.front() {
+                let mut block = front.clone();
+                block.set_state(state.clone());
+                if let Err(e) = block.verify().await {
+                    // Drops the block and the moves it leaves unplayable, chaining
+                    // the rest onto its parent
+                    mempool.discard_front()?;
+                    evict_queued(&vm_state, state, &mut mempool).await?;
+                    persist_mempool(&vm_state, &mempool).await;
+                    return Err(e);
+                }
+                mempool.pop();
+                persist_mempool(&vm_state, &mempool).await;
+                self.metrics.blocks_built.inc();
+                block.log_event("built", None, None);
+                return Ok(block);
+            }
         }
 
         Err(Error::new(ErrorKind::NotFound, "state manager not found"))
@@ -367,9 +1125,9 @@ where
         Ok(())
     }
 
-    // async fn last_accepted(&self) -> io::Result<ids::Id> {
-    //     self.last_accepted().await
-    // }
+    async fn last_accepted(&self) -> io::Result<ids::Id> {
+        self.last_accepted().await
+    }
 
     async fn issue_tx(&self) -> io::Result<<Self as ChainVm>::Block> {
         Err(Error::new(
@@ -377,6 +1135,11 @@ where
             "issue_tx not implemented",
         ))
     }
+
+    // Batched ancestor fetch (GetAncestors) and BatchedParseBlock are blocked on
+    // avalanche-types: its plugin server answers both with Unimplemented and has no
+    // hook to dispatch them to the VM, so bootstrapping peers fetch and parse this
+    // VM's blocks one at a time until it does.
 }
 
 #[tonic::async_trait]
@@ -414,8 +1177,48 @@ where
         Ok(())
     }
 
-    /// Currently, no app-specific messages, so returning Ok.
-    async fn app_gossip(&self, _node_id: &ids::node::Id, _msg: &[u8]) -> io::Result<()> {
+    /// Admits moves gossiped by other validators to the mempool, as if proposed here.
+    /// Their submitters aren't checked again, the validator they were proposed to did so,
+    /// but each peer may only gossip as fast as `gossipRateLimit` allows.
+    /// Malformed or unplayable moves are dropped, as gossip errors are fatal to the node.
+    async fn app_gossip(&self, node_id: &ids::node::Id, msg: &[u8]) -> io::Result<()> {
+        if !self.is_bootstrapped().await {
+            log::debug!("dropping gossip from {node_id} while bootstrapping");
+            return Ok(());
+        }
+        if let Some(limiter) = self.gossip_limiter.write().await.as_mut() {
+            if let Err(e) = limiter.check(Submitter::Peer(*node_id)) {
+                log::debug!("dropping gossip from {node_id}: {e}");
+                return Ok(());
+            }
+        }
+        let gossiped = match GossipedMove::from_slice(msg) {
+            Ok(gossiped) => gossiped,
+            Err(e) => {
+                log::warn!("dropping gossip from {node_id}: {e}");
+                return Ok(());
+            }
+        };
+
+        if let Some(action) = gossiped.account.into_action() {
+            let kind = action.kind();
+            match self.admit_account_action(action, gossiped.signature).await {
+                Ok(block) => {
+                    log::debug!("admitted {kind} gossiped by {node_id} as block {}", block.id());
+                }
+                Err(e) => log::debug!("dropping {kind} gossiped by {node_id}: {e}"),
+            }
+            return Ok(());
+        }
+
+        let action = gossiped.action;
+        let (signature, max_fee) = (gossiped.signature, gossiped.max_fee);
+        match self.admit_block(action, signature, max_fee, gossiped.work).await {
+            Ok((block, _)) => {
+                log::debug!("admitted move {action} gossiped by {node_id} as block {}", block.id());
+            }
+            Err(e) => log::debug!("dropping move {action} gossiped by {node_id}: {e}"),
+        }
         Ok(())
     }
 }
@@ -425,33 +1228,66 @@ impl<A> CrossChainAppHandler for Vm<A>
 where
     A: AppSender + Send + Sync + Clone + 'static,
 {
-    /// Currently, no cross chain specific messages, so returning Ok.
+    /// Answers game queries from other chains (see [`crosschain`]).
+    /// Malformed requests are answered with an error rather than left to time out.
     async fn cross_chain_app_request(
         &self,
-        _chain_id: &ids::Id,
-        _request_id: u32,
-        _deadline: DateTime<Utc>,
-        _request: &[u8],
+        chain_id: &ids::Id,
+        request_id: u32,
+        deadline: DateTime<Utc>,
+        request: &[u8],
     ) -> io::Result<()> {
+        if deadline < Utc::now() {
+            log::debug!("dropping expired cross-chain request {request_id} from {chain_id}");
+            return Ok(());
+        }
+
+        let response = match crosschain::from_slice::<CrossChainRequest>(request) {
+            Ok(request) => {
+                let vm_state = self.state.read().await;
+                if let Some(state) = &vm_state.state {
+                    request.respond(state).await
+                } else {
+                    CrossChainResponse::Error {
+                        message: String::from("state manager not found"),
+                    }
+                }
+            }
+            Err(e) => CrossChainResponse::Error {
+                message: e.to_string(),
+            },
+        };
+
+        if let Some(app_sender) = &self.app_sender {
+            let response = crosschain::to_vec(&response)?;
+            if let Err(e) = app_sender
+                .send_cross_chain_app_response(*chain_id, request_id, response)
+                .await
+            {
+                log::warn!("failed to answer cross-chain request {request_id} of {chain_id}: {e}");
+            }
+        }
         Ok(())
     }
 
-    /// Currently, no cross chain specific messages, so returning Ok.
+    /// This chain sends no cross-chain requests, so there is nothing to fail.
     async fn cross_chain_app_request_failed(
         &self,
-        _chain_id: &ids::Id,
-        _request_id: u32,
+        chain_id: &ids::Id,
+        request_id: u32,
     ) -> io::Result<()> {
+        log::debug!("unexpected failure of cross-chain request {request_id} to {chain_id}");
         Ok(())
     }
 
-    /// Currently, no cross chain specific messages, so returning Ok.
+    /// This chain sends no cross-chain requests, so responses are dropped.
     async fn cross_chain_app_response(
         &self,
-        _chain_id: &ids::Id,
-        _request_id: u32,
+        chain_id: &ids::Id,
+        request_id: u32,
         _response: &[u8],
     ) -> io::Result<()> {
+        log::debug!("dropping unexpected cross-chain response {request_id} from {chain_id}");
         Ok(())
     }
 }
@@ -479,8 +1315,22 @@ impl<A> Checkable for Vm<A>
 where
     A: AppSender + Send + Sync + Clone + 'static,
 {
+    /// Reports the [`Health`] of the Vm as JSON, failing if it is unhealthy.
     async fn health_check(&self) -> io::Result<Vec<u8>> {
-        Ok("200".as_bytes().to_vec())
+        let health = self.health().await?;
+        let d = serde_json::to_vec(&health).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize health to JSON bytes {e}"),
+            )
+        })?;
+        if !health.healthy {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("unhealthy: {}", String::from_utf8_lossy(&d)),
+            ));
+        }
+        Ok(d)
     }
 }
 
@@ -0,0 +1,182 @@
+//! Notifies operator-configured webhooks whenever a game finishes.
+//!
+//! Deliveries run in the background, off the accept path, and are retried with
+//! exponential backoff. Payloads are JSON, signed with HMAC-SHA256 when the
+//! webhook has a secret (see [`SIGNATURE_HEADER`]).
+
+use std::{
+    fmt::Write,
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use avalanche_types::ids;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+
+use crate::{
+    game::{self, GameStatus},
+    state::{AcceptedMove, State},
+};
+
+/// Header carrying `sha256=<hex HMAC of the body>`, for webhooks with a secret.
+pub const SIGNATURE_HEADER: &str = "X-TicTacToe-Signature";
+
+/// Number of times a delivery is attempted before it is dropped.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry, doubled after every failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How long a single delivery attempt may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A webhook to notify of finished games.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Webhook {
+    pub url: String,
+    /// Key the payload is signed with, unsigned if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// Payload delivered once a game finishes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameFinished {
+    pub game: usize,
+    /// Block whose move ended the game
+    pub block_id: ids::Id,
+    pub height: u64,
+    #[serde(flatten)]
+    pub status: GameStatus,
+    /// Final board, packed and decoded
+    pub board: u32,
+    pub grid: [[String; 3]; 3],
+}
+
+impl GameFinished {
+    /// Returns the payload for the accepted move if it ended its game.
+    #[must_use]
+    pub fn from_accepted(accepted: &AcceptedMove) -> Option<Self> {
+        if let GameStatus::InProgress { .. } = accepted.status {
+            return None;
+        }
+
+        Some(Self {
+            game: accepted.game,
+            block_id: accepted.record.block_id,
+            height: accepted.record.height,
+            status: accepted.status,
+            board: accepted.board,
+            grid: game::grid(accepted.board).map(|row| row.map(String::from)),
+        })
+    }
+}
+
+/// Delivers every game finished from now on to the webhooks, in the background.
+/// Games finished while it fell behind the accepted-move channel are read back
+/// from the state. Stops once the channel closes.
+pub fn spawn(webhooks: Vec<Webhook>, state: State) {
+    tokio::spawn(async move {
+        let mut delivered = state.get_height().await;
+        let mut accepted = state.subscribe_accepted();
+        loop {
+            let moves = match accepted.recv().await {
+                Ok(accepted) => vec![accepted],
+                // Moves were skipped, the history has them all
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("webhooks fell behind, reading {skipped} skipped moves from state");
+                    state.get_accepted_after(delivered, usize::MAX).await
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+
+            for accepted in moves {
+                // Moves read back from the state may still be queued in the channel
+                if accepted.record.height <= delivered {
+                    continue;
+                }
+                delivered = accepted.record.height;
+                notify(&webhooks, &accepted);
+            }
+        }
+    });
+}
+
+/// Delivers the accepted move to the webhooks if it ended its game.
+fn notify(webhooks: &[Webhook], accepted: &AcceptedMove) {
+    if let Some(finished) = GameFinished::from_accepted(accepted) {
+        let body = match serde_json::to_string(&finished) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("failed to serialize game {} result: {e}", finished.game);
+                return;
+            }
+        };
+
+        for webhook in webhooks {
+            tokio::spawn(deliver(webhook.clone(), body.clone()));
+        }
+    }
+}
+
+/// Posts the body to the webhook, retrying with backoff until it succeeds or
+/// runs out of attempts.
+async fn deliver(webhook: Webhook, body: String) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        match post(&webhook, &body).await {
+            Ok(()) => {
+                log::debug!("delivered webhook to {}", webhook.url);
+                return;
+            }
+            Err(e) => {
+                log::warn!(
+                    "failed to deliver webhook to {} (attempt {attempt}/{MAX_ATTEMPTS}): {e}",
+                    webhook.url
+                );
+            }
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+/// Makes a single delivery attempt.
+async fn post(webhook: &Webhook, body: &str) -> io::Result<()> {
+    let mut req = http_manager::create_json_post(&webhook.url, "", body)?;
+    if let Some(secret) = &webhook.secret {
+        let signature = format!("sha256={}", sign(secret, body));
+        let value = signature
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid signature {e}")))?;
+        req.headers_mut().insert(SIGNATURE_HEADER, value);
+    }
+
+    let is_https = webhook.url.starts_with("https");
+    http_manager::read_bytes(req, REQUEST_TIMEOUT, is_https, true).await?;
+    Ok(())
+}
+
+/// Returns the hex-encoded HMAC-SHA256 of the body.
+/// # Panics
+/// Never, as HMAC accepts keys of any length.
+#[must_use]
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
@@ -0,0 +1,347 @@
+//! Checks blocks against the golden corpus in `testdata/blocks.json`, so changes
+//! to the codec can't silently alter the bytes (and so the Ids) historic nodes
+//! produced. Each vector lists the fields of a block along with its expected
+//! legacy JSON and binary encodings and their Ids, its protobuf encoding, and
+//! for signed blocks the signer. Blocks carrying anything but a move, and moves
+//! carrying a fee or proof of work, have no binary encoding, so their vectors
+//! list none. Blocks carrying more than one kind of action are checked to be
+//! rejected, as each signed action must have a single encoding.
+//!
+//! After an intended change to the encoding, or to add vectors, regenerate the
+//! expected values from the fields with:
+//!
+//! `TIMESTAMPVM_BLESS=1 cargo test -p timestampvm --test golden`
+
+use std::{collections::HashMap, env, fmt::Write, fs, path::PathBuf, str::FromStr};
+
+use avalanche_types::{choices::status::Status, ids, key::secp256k1::private_key};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use timestampvm::{
+    block::{
+        self, AccountAction, Block, Format, Seek, SeriesAction, TeamAction, TournamentAction,
+        Transfer,
+    },
+    proto,
+};
+
+/// Set to regenerate the expected values of the corpus.
+const BLESS_VAR: &str = "TIMESTAMPVM_BLESS";
+
+#[derive(Serialize, Deserialize)]
+struct Corpus {
+    /// Hex-encoded private key signing the signed vectors
+    key: String,
+    vectors: Vec<Vector>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Vector {
+    name: String,
+    /// Name of the vector whose block is the parent, the empty Id if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    height: u64,
+    action: u8,
+    /// Game the move is signed for, unsigned if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    game: Option<usize>,
+    /// Transfer the block carries instead of the move, always signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transfer: Option<Transfer>,
+    /// Seek the block carries instead of the move, always signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seek: Option<Seek>,
+    /// Tournament action the block carries instead of the move, always signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tournament: Option<TournamentAction>,
+    /// Series action the block carries instead of the move, always signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    series: Option<SeriesAction>,
+    /// Team action the block carries instead of the move, always signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    team: Option<TeamAction>,
+    /// Fee the proposer set for the move
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fee: Option<u64>,
+    /// Most the move is signed to pay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_fee: Option<u64>,
+    /// Nonce ground for the move's proof of work
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    work: Option<u64>,
+
+    /// Hex-encoded block bytes, in the legacy JSON format
+    encoded: String,
+    id: String,
+    /// Hex-encoded block bytes, in the binary format, if the block has one
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    binary: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    binary_id: String,
+    /// Hex-encoded protobuf `Block` message
+    protobuf: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signer: Option<String>,
+}
+
+fn corpus_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/blocks.json")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+fn from_hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("invalid hex in corpus"))
+        .collect()
+}
+
+/// Builds the block a vector describes, signing it if it carries anything but a
+/// move or names a game.
+fn build(vector: &Vector, key: &private_key::Key, parent_id: ids::Id) -> Block {
+    let (height, status) = (vector.height, Status::Processing);
+    let account = if let Some(transfer) = &vector.transfer {
+        let signature = block::sign_transfer(key, transfer).expect("failed to sign transfer");
+        Some((AccountAction::Transfer(transfer.clone()), signature))
+    } else if let Some(seek) = &vector.seek {
+        let signature = block::sign_seek(key, seek).expect("failed to sign seek");
+        Some((AccountAction::Seek(seek.clone()), signature))
+    } else if let Some(action) = &vector.tournament {
+        let signature =
+            block::sign_tournament_action(key, action).expect("failed to sign tournament action");
+        Some((AccountAction::Tournament(action.clone()), signature))
+    } else if let Some(action) = &vector.series {
+        let signature =
+            block::sign_series_action(key, action).expect("failed to sign series action");
+        Some((AccountAction::Series(action.clone()), signature))
+    } else if let Some(action) = &vector.team {
+        let signature = block::sign_team_action(key, action).expect("failed to sign team action");
+        Some((AccountAction::Team(action.clone()), signature))
+    } else {
+        None
+    };
+    let block = if let Some((action, signature)) = account {
+        Block::try_new_account(parent_id, height, action, signature, status)
+    } else {
+        let signature = vector.game.map_or_else(Vec::new, |game| {
+            block::sign_move(key, game, vector.action, vector.max_fee).expect("failed to sign move")
+        });
+        Block::try_new(parent_id, height, vector.action, signature, status)
+    };
+    let block = block.expect("failed to build block");
+
+    let block = match vector.max_fee {
+        Some(max_fee) => block
+            .with_max_fee(max_fee)
+            .expect("failed to set maximum fee"),
+        None => block,
+    };
+    let block = match vector.fee {
+        Some(fee) => block.with_fee(fee).expect("failed to set fee"),
+        None => block,
+    };
+    match vector.work {
+        Some(nonce) => block.with_work(nonce).expect("failed to set work"),
+        None => block,
+    }
+}
+
+/// Recovers the address that signed the block a vector describes, if any.
+fn signer(vector: &Vector, block: &Block) -> Option<String> {
+    let signer = if block.is_move() {
+        block.signer(vector.game.unwrap_or_default())
+    } else {
+        block.account_signer()
+    };
+    signer
+        .expect("failed to recover signer")
+        .map(|signer| signer.to_string())
+}
+
+/// Checks the golden bytes decode to the block, keeping their Id and format.
+fn assert_decodes(name: &str, encoded: &str, id: &str, block: &Block) {
+    let decoded = Block::from_slice(from_hex(encoded)).expect("failed to decode");
+    assert_eq!(decoded.format(), block.format(), "{name}: format differs");
+    assert_eq!(
+        decoded.parent_id(),
+        block.parent_id(),
+        "{name}: parent Id differs"
+    );
+    assert_eq!(decoded.height(), block.height(), "{name}: height differs");
+    assert_eq!(decoded.get_player_move(), block.get_player_move(), "{name}");
+    assert_eq!(
+        decoded.transfer(),
+        block.transfer(),
+        "{name}: transfer differs"
+    );
+    assert_eq!(decoded.seek(), block.seek(), "{name}: seek differs");
+    assert_eq!(
+        decoded.tournament(),
+        block.tournament(),
+        "{name}: tournament action differs"
+    );
+    assert_eq!(
+        decoded.series(),
+        block.series(),
+        "{name}: series action differs"
+    );
+    assert_eq!(decoded.team(), block.team(), "{name}: team action differs");
+    assert_eq!(decoded.fee(), block.fee(), "{name}: fee differs");
+    assert_eq!(decoded.work(), block.work(), "{name}: work differs");
+    assert_eq!(
+        decoded.max_fee(),
+        block.max_fee(),
+        "{name}: maximum fee differs"
+    );
+    assert_eq!(
+        decoded.signature(),
+        block.signature(),
+        "{name}: signature differs"
+    );
+    assert_eq!(decoded.id(), ids::Id::from_str(id).unwrap(), "{name}");
+}
+
+#[test]
+fn golden_blocks() {
+    let path = corpus_path();
+    let mut corpus: Corpus =
+        serde_json::from_slice(&fs::read(&path).expect("failed to read corpus"))
+            .expect("failed to parse corpus");
+    let key = private_key::Key::from_hex(&corpus.key).expect("invalid corpus key");
+    let bless = env::var_os(BLESS_VAR).is_some();
+
+    let mut ids = HashMap::new();
+    for vector in &mut corpus.vectors {
+        let parent_id = match &vector.parent {
+            Some(parent) => *ids.get(parent).expect("parents must be listed first"),
+            None => ids::Id::empty(),
+        };
+        let block = build(vector, &key, parent_id);
+        let message = proto::Block::from(&block);
+        let signer = signer(vector, &block);
+        // Only moves without a fee or proof of work have a binary encoding
+        let binary = block.clone().with_format(Format::Binary).ok();
+        ids.insert(vector.name.clone(), block.id());
+
+        if bless {
+            vector.encoded = to_hex(block.bytes());
+            vector.id = block.id().to_string();
+            vector.binary = binary
+                .as_ref()
+                .map(|b| to_hex(b.bytes()))
+                .unwrap_or_default();
+            vector.binary_id = binary
+                .as_ref()
+                .map(|b| b.id().to_string())
+                .unwrap_or_default();
+            vector.protobuf = to_hex(&message.encode_to_vec());
+            vector.signer = signer;
+            continue;
+        }
+
+        let name = &vector.name;
+        assert_eq!(
+            to_hex(block.bytes()),
+            vector.encoded,
+            "{name}: encoding changed"
+        );
+        assert_eq!(block.id().to_string(), vector.id, "{name}: Id changed");
+        assert_eq!(signer, vector.signer, "{name}: signer changed");
+
+        // Decoding the golden bytes must give back the same block
+        assert_decodes(name, &vector.encoded, &vector.id, &block);
+        if let Some(binary) = &binary {
+            assert_eq!(
+                to_hex(binary.bytes()),
+                vector.binary,
+                "{name}: binary changed"
+            );
+            assert_eq!(
+                binary.id().to_string(),
+                vector.binary_id,
+                "{name}: binary Id changed"
+            );
+            assert_decodes(name, &vector.binary, &vector.binary_id, binary);
+        } else {
+            assert!(vector.binary.is_empty(), "{name}: binary encoding lost");
+        }
+
+        let encoded = to_hex(&message.encode_to_vec());
+        assert_eq!(
+            encoded, vector.protobuf,
+            "{name}: protobuf encoding changed"
+        );
+        let message = proto::Block::decode(from_hex(&vector.protobuf).as_slice())
+            .expect("failed to decode protobuf");
+        let rebuilt = Block::try_from(message).expect("failed to rebuild block");
+        assert_eq!(
+            rebuilt.id(),
+            block.id(),
+            "{name}: protobuf round trip changed the Id"
+        );
+        if let Some(binary) = &binary {
+            let rebuilt = Block::try_from(proto::Block::from(binary)).expect("failed to rebuild");
+            assert_eq!(
+                rebuilt.format(),
+                Format::Binary,
+                "{name}: protobuf lost the format"
+            );
+        }
+    }
+
+    if bless {
+        let mut out = serde_json::to_string_pretty(&corpus).expect("failed to encode corpus");
+        out.push('\n');
+        fs::write(&path, out).expect("failed to write corpus");
+    }
+}
+
+#[test]
+fn ambiguous_account_blocks() {
+    let transfer = Transfer {
+        to: ids::short::Id::empty(),
+        amount: 1,
+        nonce: 0,
+    };
+    let action = AccountAction::Transfer(transfer);
+    let block = Block::try_new_account(ids::Id::empty(), 1, action, Vec::new(), Status::Processing)
+        .expect("failed to build block");
+    let encoded: serde_json::Value =
+        serde_json::from_slice(block.bytes()).expect("failed to parse block");
+    Block::from_slice(block.bytes()).expect("a single account action must decode");
+
+    // Each extra field would give the same signed transfer another Id
+    let extras = [
+        ("seek", serde_json::json!({ "nonce": 0 })),
+        (
+            "team",
+            serde_json::json!({ "kind": "accept", "team_game": 0, "nonce": 0 }),
+        ),
+        ("player_move", serde_json::json!(18)),
+        ("fee", serde_json::json!(1)),
+        ("max_fee", serde_json::json!(1)),
+        ("work", serde_json::json!(7)),
+    ];
+    for (field, value) in extras {
+        let mut ambiguous = encoded.clone();
+        ambiguous[field] = value;
+        let bytes = serde_json::to_vec(&ambiguous).expect("failed to encode block");
+        assert!(
+            Block::from_slice(bytes).is_err(),
+            "a transfer carrying {field} must be rejected"
+        );
+    }
+
+    assert!(
+        block.clone().with_fee(1).is_err(),
+        "a transfer can't pay a fee"
+    );
+    assert!(block.with_work(7).is_err(), "a transfer can't carry work");
+}